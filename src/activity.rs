@@ -0,0 +1,383 @@
+//! # Activity Module
+//!
+//! Posture, activity level, steps and movement intensity from the
+//! accelerometer, as context for heart rate and HRV: a heart rate while
+//! walking is not comparable to one at rest.
+//!
+//! ## Epochs
+//! ACC samples are summarised in epochs of `EPOCH_NS`. Each epoch yields:
+//! - **ENMO** (Euclidean norm minus one): mean of max(|a| - 1 g, 0) in mg,
+//!   the movement intensity measure common in accelerometry research
+//! - **Activity level**: still below `STILL_MAX_ENMO_MG`, running from
+//!   `RUNNING_MIN_ENMO_MG`, walking in between. Movement without steps, such
+//!   as cycling, also counts as walking.
+//! - **Posture**: the tilt of the epoch's mean acceleration (gravity) from
+//!   the upright axis. Lying beyond `LYING_MIN_TILT_DEG`, sitting beyond
+//!   `SITTING_MIN_TILT_DEG`, which catches the recline of a chair, and
+//!   standing otherwise, as well as whenever walking or running. A chest
+//!   sensor cannot tell an upright seat from standing.
+//! - **Steps**: cumulative count since the classifier started
+//!
+//! ## Posture and Placement
+//! Chest straps are worn upright, with the sensor's x axis pointing down
+//! when the wearer stands. Arm-worn sensors (Verity Sense, OH1) turn with the
+//! arm, so no posture is given for them.
+//!
+//! ## Step Detection
+//! The acceleration magnitude minus its slow running mean (`GRAVITY_TAU_S`)
+//! is smoothed over `SMOOTHING_TAU_S`. Each rise above `STEP_THRESHOLD_MG`
+//! is a step, once the signal fell below zero since the last one and at
+//! least `MIN_STEP_INTERVAL_S` passed, which caps the cadence at 4 steps/s.
+//!
+//! ## Gaps
+//! A step between samples longer than `MAX_SAMPLE_STEP_NS` drops the epoch
+//! in progress and restarts the filters; the step count carries on.
+
+use std::fmt;
+
+// Length of a classification epoch
+const EPOCH_NS: u64 = 1_000_000_000;
+
+// ENMO limits of the activity levels in mg
+const STILL_MAX_ENMO_MG: f64 = 30.0;
+const RUNNING_MIN_ENMO_MG: f64 = 300.0;
+
+// Tilt from upright beyond which the wearer sits or lies
+const SITTING_MIN_TILT_DEG: f64 = 20.0;
+const LYING_MIN_TILT_DEG: f64 = 60.0;
+
+// Time constants of the gravity estimate and the step signal smoothing
+const GRAVITY_TAU_S: f64 = 1.0;
+const SMOOTHING_TAU_S: f64 = 0.04;
+
+// Rise of the acceleration magnitude over gravity that counts as a step
+const STEP_THRESHOLD_MG: f64 = 150.0;
+
+// No two steps are closer than this
+const MIN_STEP_INTERVAL_S: f64 = 0.25;
+
+// Longer steps between samples restart the classifier
+const MAX_SAMPLE_STEP_NS: u64 = 1_000_000_000;
+
+// One g in mg
+const GRAVITY_MG: f64 = 1000.0;
+
+const NANOS_PER_SECOND: f64 = 1_000_000_000.0;
+
+/// Body position of the wearer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Posture {
+    Lying,
+    Sitting,
+    Standing,
+}
+
+impl Posture {
+    /// Value stored in the posture channel
+    pub fn code(&self) -> i32 {
+        match self {
+            Posture::Lying => 0,
+            Posture::Sitting => 1,
+            Posture::Standing => 2,
+        }
+    }
+
+    pub fn from_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(Posture::Lying),
+            1 => Some(Posture::Sitting),
+            2 => Some(Posture::Standing),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Posture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Posture::Lying => "lying",
+            Posture::Sitting => "sitting",
+            Posture::Standing => "standing",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How much the wearer moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActivityLevel {
+    Still,
+    Walking,
+    Running,
+}
+
+impl ActivityLevel {
+    pub fn from_enmo(enmo_mg: f64) -> Self {
+        if enmo_mg < STILL_MAX_ENMO_MG {
+            ActivityLevel::Still
+        } else if enmo_mg < RUNNING_MIN_ENMO_MG {
+            ActivityLevel::Walking
+        } else {
+            ActivityLevel::Running
+        }
+    }
+
+    /// Value stored in the activity channel
+    pub fn code(&self) -> i32 {
+        match self {
+            ActivityLevel::Still => 0,
+            ActivityLevel::Walking => 1,
+            ActivityLevel::Running => 2,
+        }
+    }
+
+    pub fn from_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(ActivityLevel::Still),
+            1 => Some(ActivityLevel::Walking),
+            2 => Some(ActivityLevel::Running),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ActivityLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ActivityLevel::Still => "still",
+            ActivityLevel::Walking => "walking",
+            ActivityLevel::Running => "running",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Classification of one epoch
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ActivityEpoch {
+    /// End of the epoch in nanoseconds
+    pub time: u64,
+    pub level: ActivityLevel,
+    /// Posture, for chest-worn sensors
+    pub posture: Option<Posture>,
+    /// Movement intensity in mg
+    pub enmo_mg: f64,
+    /// Steps since the classifier started
+    pub steps: u32,
+}
+
+/// Streaming activity classifier for one accelerometer
+#[derive(Debug, Clone)]
+pub struct ActivityClassifier {
+    chest_worn: bool,
+    epoch_start: Option<u64>,
+    /// Sums of the axes and of ENMO within the epoch, and the sample count
+    axis_sums: [f64; 3],
+    enmo_sum: f64,
+    samples: usize,
+    /// Running mean of the magnitude, and the smoothed magnitude above it
+    gravity: f64,
+    step_signal: f64,
+    /// Whether the step signal fell below zero since the last step
+    armed: bool,
+    last_step: Option<u64>,
+    steps: u32,
+    last_time: Option<u64>,
+}
+
+impl ActivityClassifier {
+    /// Classifier for a sensor worn on the chest, giving posture, or elsewhere
+    pub fn new(chest_worn: bool) -> Self {
+        Self {
+            chest_worn,
+            epoch_start: None,
+            axis_sums: [0.0; 3],
+            enmo_sum: 0.0,
+            samples: 0,
+            gravity: GRAVITY_MG,
+            step_signal: 0.0,
+            armed: false,
+            last_step: None,
+            steps: 0,
+            last_time: None,
+        }
+    }
+
+    pub fn steps(&self) -> u32 {
+        self.steps
+    }
+
+    /// Feed one sample in mg, returning the classification of the epoch it ends
+    pub fn push(&mut self, t: u64, x: i32, y: i32, z: i32) -> Option<ActivityEpoch> {
+        let dt = match self.last_time {
+            Some(last) if t > last && t - last <= MAX_SAMPLE_STEP_NS => (t - last) as f64 / NANOS_PER_SECOND,
+            _ => {
+                self.restart();
+                0.0
+            }
+        };
+        self.last_time = Some(t);
+
+        let axes = [x as f64, y as f64, z as f64];
+        let magnitude = axes.iter().map(|a| a * a).sum::<f64>().sqrt();
+        self.detect_step(t, magnitude, dt);
+
+        let start = *self.epoch_start.get_or_insert(t);
+        let epoch = if t >= start + EPOCH_NS { self.finish_epoch(t) } else { None };
+
+        for (sum, a) in self.axis_sums.iter_mut().zip(axes) {
+            *sum += a;
+        }
+        self.enmo_sum += (magnitude - GRAVITY_MG).max(0.0);
+        self.samples += 1;
+        epoch
+    }
+
+    /// Drop the epoch in progress and restart the step filters
+    fn restart(&mut self) {
+        self.epoch_start = None;
+        self.axis_sums = [0.0; 3];
+        self.enmo_sum = 0.0;
+        self.samples = 0;
+        self.gravity = GRAVITY_MG;
+        self.step_signal = 0.0;
+        self.armed = false;
+    }
+
+    fn detect_step(&mut self, t: u64, magnitude: f64, dt: f64) {
+        if dt == 0.0 {
+            // Start from the first magnitude so an offset is no step
+            self.gravity = magnitude;
+            return;
+        }
+        self.gravity += (magnitude - self.gravity) * (1.0 - (-dt / GRAVITY_TAU_S).exp());
+        self.step_signal += (magnitude - self.gravity - self.step_signal) * (1.0 - (-dt / SMOOTHING_TAU_S).exp());
+
+        if self.step_signal < 0.0 {
+            self.armed = true;
+        }
+        let rested = !self
+            .last_step
+            .is_some_and(|last| (t.saturating_sub(last) as f64 / NANOS_PER_SECOND) < MIN_STEP_INTERVAL_S);
+        if self.armed && rested && self.step_signal > STEP_THRESHOLD_MG {
+            self.steps += 1;
+            self.last_step = Some(t);
+            self.armed = false;
+        }
+    }
+
+    /// Classify the epoch ending at `t` and start the next one
+    fn finish_epoch(&mut self, t: u64) -> Option<ActivityEpoch> {
+        let samples = std::mem::take(&mut self.samples);
+        let sums = std::mem::take(&mut self.axis_sums);
+        let enmo_mg = std::mem::take(&mut self.enmo_sum) / samples.max(1) as f64;
+        self.epoch_start = Some(t);
+        if samples == 0 {
+            return None;
+        }
+
+        let level = ActivityLevel::from_enmo(enmo_mg);
+        let posture = self.chest_worn.then(|| {
+            let mean = sums.map(|sum| sum / samples as f64);
+            if level == ActivityLevel::Still {
+                Self::posture(mean)
+            } else {
+                Posture::Standing
+            }
+        });
+        Some(ActivityEpoch { time: t, level, posture, enmo_mg, steps: self.steps })
+    }
+
+    /// Posture from the mean acceleration of a chest-worn sensor
+    fn posture(mean: [f64; 3]) -> Posture {
+        let norm = mean.iter().map(|a| a * a).sum::<f64>().sqrt();
+        if norm == 0.0 {
+            return Posture::Lying;
+        }
+        // Upright, gravity pulls along -x
+        let tilt = (-mean[0] / norm).clamp(-1.0, 1.0).acos().to_degrees();
+        if tilt >= LYING_MIN_TILT_DEG {
+            Posture::Lying
+        } else if tilt >= SITTING_MIN_TILT_DEG {
+            Posture::Sitting
+        } else {
+            Posture::Standing
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u64 = 100;
+
+    /// Epochs of `seconds` of 100 Hz samples from `sample(t)` with t in seconds
+    fn classify(
+        classifier: &mut ActivityClassifier,
+        seconds: u64,
+        sample: impl Fn(f64) -> (f64, f64, f64),
+    ) -> Vec<ActivityEpoch> {
+        (0..seconds * RATE)
+            .filter_map(|i| {
+                let t = i as f64 / RATE as f64;
+                let (x, y, z) = sample(t);
+                classifier.push(i * 1_000_000_000 / RATE, x.round() as i32, y.round() as i32, z.round() as i32)
+            })
+            .collect()
+    }
+
+    /// Gravity tilted from upright by `degrees` towards z, with a little breathing
+    fn at_rest(degrees: f64) -> impl Fn(f64) -> (f64, f64, f64) {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        move |t| {
+            let breath = 10.0 * (2.0 * std::f64::consts::PI * 0.25 * t).sin();
+            (-GRAVITY_MG * cos + breath, 20.0, GRAVITY_MG * sin + breath)
+        }
+    }
+
+    /// Upright with a vertical bounce of `amplitude` mg at `cadence` steps/s
+    fn stepping(cadence: f64, amplitude: f64) -> impl Fn(f64) -> (f64, f64, f64) {
+        move |t| (-GRAVITY_MG - amplitude * (2.0 * std::f64::consts::PI * cadence * t).sin(), 50.0, 100.0)
+    }
+
+    #[test]
+    fn test_posture_at_rest() {
+        for (degrees, posture) in [(5.0, Posture::Standing), (40.0, Posture::Sitting), (85.0, Posture::Lying)] {
+            let epochs = classify(&mut ActivityClassifier::new(true), 10, at_rest(degrees));
+            assert_eq!(epochs.len(), 9);
+            assert!(epochs.iter().all(|e| e.level == ActivityLevel::Still && e.posture == Some(posture)), "{:?}", epochs);
+            assert!(epochs.iter().all(|e| e.steps == 0));
+        }
+
+        // Arm-worn sensors give no posture
+        let epochs = classify(&mut ActivityClassifier::new(false), 3, at_rest(85.0));
+        assert!(epochs.iter().all(|e| e.posture.is_none()));
+    }
+
+    #[test]
+    fn test_walking_and_running() {
+        let mut classifier = ActivityClassifier::new(true);
+        let walking = classify(&mut classifier, 20, stepping(1.8, 300.0));
+        assert!(walking.iter().all(|e| e.level == ActivityLevel::Walking), "{:?}", walking);
+        assert!(walking.iter().all(|e| e.posture == Some(Posture::Standing)));
+        assert!((classifier.steps() as i32 - 36).abs() <= 1, "{} steps", classifier.steps());
+
+        let mut classifier = ActivityClassifier::new(true);
+        let running = classify(&mut classifier, 20, stepping(2.8, 1200.0));
+        assert!(running.iter().all(|e| e.level == ActivityLevel::Running), "{:?}", running);
+        assert!((classifier.steps() as i32 - 56).abs() <= 1, "{} steps", classifier.steps());
+    }
+
+    #[test]
+    fn test_gap_restarts_epoch() {
+        let mut classifier = ActivityClassifier::new(true);
+        assert_eq!(classifier.push(0, -1000, 0, 0), None);
+        assert_eq!(classifier.push(500_000_000, -1000, 0, 0), None);
+        // The epoch in progress is dropped instead of ending after the gap
+        assert_eq!(classifier.push(5_000_000_000, -1000, 0, 0), None);
+        let epoch = classifier.push(6_000_000_000, -1000, 0, 0).unwrap();
+        assert_eq!(epoch.time, 6_000_000_000);
+        assert_eq!(epoch.level, ActivityLevel::Still);
+    }
+}
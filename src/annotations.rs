@@ -0,0 +1,89 @@
+//! # Annotations Module
+//!
+//! Event markers such as "stimulus started" or "participant coughed", placed
+//! during a live session or while reviewing a recording.
+//!
+//! ## Time
+//! Annotations carry recording time (nanoseconds since the Unix epoch, like
+//! recorded points), so they line up with the data in the file and in
+//! exports. A marker is a single moment; a duration turns it into a span.
+//!
+//! ## Storage
+//! Recordings keep annotations in `/annotations` (see `recorder`), and the
+//! journal keeps those added while recording for crash recovery.
+
+/// Label of markers added without one
+pub const DEFAULT_LABEL: &str = "Marker";
+
+/// Labelled moment or span of a session
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    /// Start in nanoseconds since the Unix epoch
+    pub time: u64,
+    /// Length of the annotated span, 0 for a single moment
+    pub duration_ns: u64,
+    pub label: String,
+    /// Optional free text
+    pub note: String,
+}
+
+impl Annotation {
+    /// Marker at a moment, labelled `DEFAULT_LABEL` if the label is blank
+    pub fn new(time: u64, label: &str, note: &str) -> Self {
+        let label = match label.trim() {
+            "" => DEFAULT_LABEL,
+            label => label,
+        };
+        Self {
+            time,
+            duration_ns: 0,
+            label: label.to_string(),
+            note: note.trim().to_string(),
+        }
+    }
+
+    pub fn end(&self) -> u64 {
+        self.time + self.duration_ns
+    }
+
+    /// Make the annotation last until `end`; an end before the start makes it a moment
+    pub fn set_end(&mut self, end: u64) {
+        self.duration_ns = end.saturating_sub(self.time);
+    }
+}
+
+/// Insert an annotation into a list sorted by time, returning its index
+pub fn insert_sorted(annotations: &mut Vec<Annotation>, annotation: Annotation) -> usize {
+    let index = annotations.partition_point(|a| a.time <= annotation.time);
+    annotations.insert(index, annotation);
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_and_span() {
+        let mut annotation = Annotation::new(1_000, "  ", " coughed ");
+        assert_eq!(annotation.label, DEFAULT_LABEL);
+        assert_eq!(annotation.note, "coughed");
+        assert_eq!(annotation.end(), 1_000);
+
+        annotation.set_end(3_500);
+        assert_eq!(annotation.duration_ns, 2_500);
+        annotation.set_end(500);
+        assert_eq!(annotation.duration_ns, 0);
+    }
+
+    #[test]
+    fn test_insert_sorted() {
+        let mut annotations = Vec::new();
+        assert_eq!(insert_sorted(&mut annotations, Annotation::new(20, "b", "")), 0);
+        assert_eq!(insert_sorted(&mut annotations, Annotation::new(10, "a", "")), 0);
+        // Equal times keep the order they were added in
+        assert_eq!(insert_sorted(&mut annotations, Annotation::new(20, "c", "")), 2);
+        let labels: Vec<&str> = annotations.iter().map(|a| a.label.as_str()).collect();
+        assert_eq!(labels, vec!["a", "b", "c"]);
+    }
+}
@@ -9,16 +9,23 @@
 //! - `Message`: User interactions and events
 //! - `update()`: State transitions
 //! - `view()`: UI rendering
-//! - `subscription()`: Time-based updates
+//! - `subscription()`: Time-based updates and the marker hotkey
 //!
 //! ## Key State
-//! - Connection state (disconnected, scanning, connecting, connected)
-//! - Available and selected Bluetooth devices
-//! - Sensor data channels (ECG, HR, RR, HRV, ACC)
+//! - Scanning flag and available/selected Bluetooth devices
+//! - One `DeviceSession` per connecting or connected device, each with its
+//!   own connection state and data channels (ECG, HR, RR, HRV, ACC)
+//! - Active device whose charts are shown
+//! - Recording playback state and the pausable display clock
+//! - Chart view: live or frozen and scrolled back, with recorded history
+//!   loaded for windows older than the live buffers
+//! - Event markers of the live session, or of the recording under review
+//! - Stress baseline calibration in progress
 //! - Configuration settings
 //!
 //! ## Data Flow
-//! Sensor data arrives via mpsc channel from connection thread.
+//! Sensor data arrives via mpsc channel from connection thread, tagged with
+//! the device ID so it is routed to the matching session.
 //! Connection commands are sent via separate mpsc channel.
 //! UI updates at 60Hz (16ms intervals) to process pending sensor data.
 //!
@@ -26,41 +33,97 @@
 //! Prevents autoconnect from immediately reconnecting after user explicitly
 //! disconnects. Reset when autoconnect is re-enabled or manually connecting.
 
-use crate::charts::{AccChartType, EcgChartType, HrChartType, HrvChartType, RrChartType};
+use crate::charts::{
+    self, AccChartType, EcgChartType, GyroChartType, HrChartType, HrvChartType, MagChartType, PpgChartType,
+    RrChartType, RespirationChartType, StressChartType,
+};
 use crate::config::Config;
 use crate::connection::ConnectionCommand;
+use crate::device_profile::{DeviceModel, SensorStream};
 use crate::device_scanner::{scan_devices, BluetoothDevice};
 use crate::error::ScanError;
-use crate::sensor::SensorUpdate;
+use crate::export::{self, ExportFormat};
+use crate::activity::{ActivityLevel, Posture};
+use crate::annotations::{self, Annotation};
+use crate::artifacts::ArtifactCorrection;
+use crate::filters::MainsFrequency;
+use crate::hrv::{self, WindowLength};
 use crate::polar_data::Channels;
-use crate::recorder::PolarDataManager;
+use crate::sensor::{DeviceUpdate, SensorUpdate};
+use crate::recorder::{ChannelId, PolarDataManager, RecordingInfo};
+use crate::session::{ConnectionState, DeviceSession};
+use crate::replay::{PlaybackCommand, PlaybackStatus, RecordedDevice, PLAYBACK_DEVICE_ID, PLAYBACK_SPEEDS};
+use crate::stress::{Calibration, StressBaseline, CALIBRATION_SECONDS};
+use crate::synthetic::{SyntheticConfig, SIMULATED_DEVICE_ID};
+use crate::timeseries::TimeSeries;
 use crate::ui::styles;
-use iced::widget::{button, checkbox, column, container, row, scrollable, text, vertical_space};
-use iced::{Element, Length, Subscription, Task};
+use crate::visualization::{current_display_time, ChartKind, ChartView, ChartWindow, DisplayClock, YScaleMode};
+use iced::widget::{
+    button, checkbox, column, container, horizontal_space, pick_list, progress_bar, row, scrollable, slider, text,
+    text_input, vertical_space,
+};
+use iced::{keyboard, Element, Length, Subscription, Task};
 use plotters_iced::ChartWidget;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::PathBuf;
 use std::sync::mpsc::Receiver;
-
-#[derive(Debug, Clone, PartialEq)]
-pub enum ConnectionState {
-    Disconnected,
-    Scanning,
-    Connecting,
-    Connected,
-}
+use std::time::{Duration, Instant};
 
 // Iced Application State
 pub struct ZenSignal {
-    pub channels: Channels,
-    receiver: Receiver<SensorUpdate>,
-    pub connection_state: ConnectionState,
+    receiver: Receiver<DeviceUpdate>,
+    pub scanning: bool,
+    /// Devices streaming or being connected, in connection order
+    pub sessions: Vec<DeviceSession>,
+    /// Device whose charts are shown
+    active_device: Option<String>,
     pub available_devices: Vec<BluetoothDevice>,
     pub selected_device: Option<BluetoothDevice>,
     connect_sender: std::sync::mpsc::Sender<ConnectionCommand>,
     pub config: Config,
     manual_disconnect: bool, // Track if user manually disconnected
     recorder: PolarDataManager,
+    pub display_clock: DisplayClock,
+    recordings: Vec<PathBuf>,
+    selected_recording: Option<PathBuf>,
+    /// Devices of the selected recording; playback offers a choice if there are several
+    recording_devices: Vec<RecordedDevice>,
+    playback_device: Option<RecordedDevice>,
+    /// Journals of recordings interrupted by a crash, offered for recovery
+    unfinished_recordings: Vec<PathBuf>,
+    export_format: ExportFormat,
+    /// Outcome of the last export, shown below the recordings; `None` while idle
+    export_status: Option<String>,
+    exporting: bool,
+    playback: Option<PlaybackStatus>, // Set while a recording is being played back
+    /// Resting baseline being recorded
+    calibration: Option<Calibration>,
+    /// Outcome of the last calibration; `None` while idle
+    calibration_status: Option<String>,
+    seek_preview: Option<f64>, // Seek slider position (seconds) while dragging
+    /// Display time at which the latest playback status arrived
+    playback_status_time: u64,
+    /// Markers of the live session, or of the recording being played back, sorted by time
+    annotations: Vec<Annotation>,
+    /// Label and note given to the next marker
+    annotation_label: String,
+    annotation_note: String,
+    /// Whether the markers of the recording being played back changed since they were saved
+    annotations_modified: bool,
+    /// Whether the charts follow live data or are frozen and scrolled back
+    chart_view: ChartView,
+    /// Recorded data of the active device for frozen chart windows older than its live buffers
+    chart_history: HashMap<ChannelId, TimeSeries>,
+    /// When wheel scrolling will have settled enough to load chart history
+    chart_history_due: Option<Instant>,
+    /// When to save config changes made by repeated wheel input
+    config_save_due: Option<Instant>,
 }
 
+// Wheel scrolling or zooming counts as stopped this long after its last event
+const WHEEL_SETTLE: Duration = Duration::from_millis(300);
+
 #[derive(Debug, Clone)]
 pub enum Message {
     Tick,
@@ -68,16 +131,66 @@ pub enum Message {
     DevicesScanned(Result<Vec<BluetoothDevice>, ScanError>),
     SelectDevice(BluetoothDevice),
     ConnectDevice,
-    DisconnectDevice,
+    DisconnectDevice(String),
+    SelectActiveDevice(String),
     ToggleAutoconnect(bool),
     ToggleSmoothStreaming(bool),
+    ToggleSimulatedDevice(bool),
+    SelectSimulatedModel(DeviceModel),
+    SelectHrvWindow(WindowLength),
+    SelectArtifactCorrection(ArtifactCorrection),
+    ToggleHrvStillOnly(bool),
+    ToggleFilteredView(bool),
+    ToggleRecordFiltered(bool),
+    SelectMainsFrequency(MainsFrequency),
+    SubjectIdChanged(String),
+    DescriptionChanged(String),
+    /// Save the subject ID and description once editing is done
+    SaveRecordingInfo,
     StartRecording,
     StopRecording,
+    RefreshRecordings,
+    SelectRecording(PathBuf),
+    SelectPlaybackDevice(RecordedDevice),
+    PlayRecording,
+    RecoverRecording(PathBuf),
+    RecordingRecovered(Result<PathBuf, String>),
+    DiscardUnfinishedRecording(PathBuf),
+    SelectExportFormat(ExportFormat),
+    ExportRecording,
+    RecordingExported(Result<Vec<PathBuf>, String>),
+    PlaybackTogglePause,
+    PlaybackSetSpeed(f64),
+    PlaybackToggleLoop(bool),
+    PlaybackSeekPreview(f64),
+    PlaybackSeekRelease,
+    StartCalibration(String),
+    CancelCalibration,
+    ClearStressBaseline,
+    AnnotationLabelChanged(String),
+    AnnotationNoteChanged(String),
+    AddAnnotation,
+    EditAnnotationLabel(usize, String),
+    EditAnnotationNote(usize, String),
+    EndAnnotationHere(usize),
+    DeleteAnnotation(usize),
+    SeekToAnnotation(usize),
+    SaveAnnotations,
+    SelectChartWindow(ChartKind, ChartWindow),
+    SelectChartScaleMode(ChartKind, YScaleMode),
+    /// Show a chart's values from the first to the second, switching it to manual scale
+    SetChartValueRange(ChartKind, i32, i32),
+    ToggleFreezeCharts,
+    /// Scroll the charts back (positive) or forward by nanoseconds
+    ScrollCharts(i64),
+    /// Scroll the frozen charts to seconds before the frozen moment
+    ScrollChartsTo(f64),
+    LoadChartHistory,
 }
 
 impl ZenSignal {
     pub fn new(
-        receiver: Receiver<SensorUpdate>,
+        receiver: Receiver<DeviceUpdate>,
         connect_sender: std::sync::mpsc::Sender<ConnectionCommand>,
     ) -> (Self, Task<Message>) {
         let config = Config::load().unwrap_or_else(|e| {
@@ -85,6 +198,7 @@ impl ZenSignal {
             Config::default()
         });
         let should_autoconnect = config.enable_autoconnect;
+        let simulated_model = config.enable_simulated_device.then_some(config.simulated_device_model);
         
         // Initialize data manager - always exists for live buffering
         let recorder = PolarDataManager::new(
@@ -97,20 +211,49 @@ impl ZenSignal {
             PolarDataManager::new(30, 10).expect("Failed to create fallback data manager")
         });
         
+        let recordings = PolarDataManager::list_recordings(&config.recording_directory);
+        let unfinished_recordings = PolarDataManager::unfinished_recordings(&config.recording_directory);
+        if !unfinished_recordings.is_empty() {
+            log::warn!("Found {} unfinished recording(s)", unfinished_recordings.len());
+        }
+
         (
             ZenSignal {
-                channels: Channels::new(),
                 receiver,
-                connection_state: if should_autoconnect { ConnectionState::Scanning } else { ConnectionState::Disconnected },
+                scanning: should_autoconnect,
+                sessions: Vec::new(),
+                active_device: None,
                 available_devices: Vec::new(),
                 selected_device: None,
                 connect_sender,
                 config,
                 manual_disconnect: false,
                 recorder,
+                display_clock: DisplayClock::new(),
+                recordings,
+                selected_recording: None,
+                recording_devices: Vec::new(),
+                playback_device: None,
+                unfinished_recordings,
+                export_format: ExportFormat::default(),
+                export_status: None,
+                exporting: false,
+                playback: None,
+                seek_preview: None,
+                playback_status_time: 0,
+                calibration: None,
+                calibration_status: None,
+                annotations: Vec::new(),
+                annotation_label: String::new(),
+                annotation_note: String::new(),
+                annotations_modified: false,
+                chart_view: ChartView::default(),
+                chart_history: HashMap::new(),
+                chart_history_due: None,
+                config_save_due: None,
             },
             if should_autoconnect {
-                Task::perform(scan_devices(), Message::DevicesScanned)
+                Task::perform(scan_devices(simulated_model), Message::DevicesScanned)
             } else {
                 Task::none()
             },
@@ -121,68 +264,28 @@ impl ZenSignal {
         match message {
             Message::Tick => {
                 // Process all pending messages without blocking
-                loop {
-                    match self.receiver.try_recv() {
-                        Ok(update) => {
-                            match update {
-                                SensorUpdate::ConnectionStatus(status) => {
-                                    use crate::sensor::ConnectionStatus;
-                                    match status {
-                                        ConnectionStatus::Connecting => {
-                                            self.connection_state = ConnectionState::Connecting;
-                                        }
-                                        ConnectionStatus::Connected => {
-                                            self.connection_state = ConnectionState::Connected;
-                                        }
-                                        ConnectionStatus::Disconnected => {
-                                            self.connection_state = ConnectionState::Disconnected;
-                                            self.channels = Channels::new();
-                                        }
-                                        ConnectionStatus::Error(e) => {
-                                            log::error!("Connection error: {}", e);
-                                            self.connection_state = ConnectionState::Disconnected;
-                                        }
-                                    }
-                                }
-                                SensorUpdate::HeartRate(hr) => {
-                                    self.channels.handle_heart_rate(hr, Some(&self.recorder));
-                                }
-                                SensorUpdate::MeasurementData(data) => {
-                                    self.channels.handle_measurement_data(data, Some(&self.recorder));
-                                }
-                                SensorUpdate::SampleRateConfig { ecg_rate, acc_rate } => {
-                                    log::info!("Updating sample rates: ECG={} Hz, ACC={} Hz", ecg_rate, acc_rate);
-                                    self.channels.set_ecg_sample_rate(ecg_rate);
-                                    self.channels.set_acc_sample_rate(acc_rate);
-                                }
-                            }
-                        }
-                        Err(_) => {
-                            // No more messages
-                            break;
-                        }
-                    }
+                while let Ok(DeviceUpdate { device_id, update }) = self.receiver.try_recv() {
+                    self.handle_device_update(device_id, update);
                 }
+                self.check_calibration();
+                self.apply_settled_wheel_input();
                 Task::none()
             }
             Message::ScanDevices => {
-                self.connection_state = ConnectionState::Scanning;
+                self.scanning = true;
                 self.available_devices.clear();
-                Task::perform(scan_devices(), Message::DevicesScanned)
+                Task::perform(scan_devices(self.simulated_model()), Message::DevicesScanned)
             }
             Message::DevicesScanned(result) => {
-                self.connection_state = ConnectionState::Disconnected;
+                self.scanning = false;
                 match result {
                     Ok(devices) => {
-                        // Auto-connect to first Polar device if enabled and not manually disconnected
-                        if self.config.enable_autoconnect && !self.manual_disconnect && !devices.is_empty() {
+                        // Auto-connect to first Polar device if enabled, nothing is connected yet
+                        // and the user has not manually disconnected
+                        if self.config.enable_autoconnect && !self.manual_disconnect && self.sessions.is_empty() {
                             if let Some(polar_device) = devices.iter().find(|d| d.name.to_lowercase().contains("polar")) {
                                 self.selected_device = Some(polar_device.clone());
-                                self.connection_state = ConnectionState::Connecting;
-                                if let Err(e) = self.connect_sender.send(ConnectionCommand::Connect(polar_device.id.clone())) {
-                                    log::error!("Failed to send autoconnect request: {}", e);
-                                    self.connection_state = ConnectionState::Disconnected;
-                                }
+                                self.connect_device(polar_device.clone());
                             }
                         }
                         
@@ -199,24 +302,25 @@ impl ZenSignal {
                 Task::none()
             }
             Message::ConnectDevice => {
-                if let Some(device) = &self.selected_device {
-                    self.connection_state = ConnectionState::Connecting;
-                    if let Err(e) = self.connect_sender.send(ConnectionCommand::Connect(device.id.clone())) {
-                        log::error!("Failed to send connection request: {}", e);
-                        self.connection_state = ConnectionState::Disconnected;
-                    }
+                if let Some(device) = self.selected_device.clone() {
+                    self.connect_device(device);
                 }
                 Task::none()
             }
-            Message::DisconnectDevice => {
-                log::info!("Sending disconnect command");
+            Message::DisconnectDevice(device_id) => {
+                log::info!("Sending disconnect command for {}", device_id);
                 self.manual_disconnect = true; // Mark as manual disconnect
-                if let Err(e) = self.connect_sender.send(ConnectionCommand::Disconnect) {
+                if let Err(e) = self.connect_sender.send(ConnectionCommand::Disconnect(device_id)) {
                     log::error!("Failed to send disconnect request: {}", e);
                 } else {
                     log::debug!("Disconnect command sent successfully");
                 }
-                // State will be updated when we receive ConnectionStatus::Disconnected
+                // Session is removed when we receive ConnectionStatus::Disconnected
+                Task::none()
+            }
+            Message::SelectActiveDevice(device_id) => {
+                self.active_device = Some(device_id);
+                self.refresh_chart_history();
                 Task::none()
             }
             Message::ToggleAutoconnect(enabled) => {
@@ -226,11 +330,11 @@ impl ZenSignal {
                 }
                 
                 // If enabling autoconnect, reset manual disconnect flag and scan
-                if enabled && self.connection_state == ConnectionState::Disconnected {
+                if enabled && !self.scanning && self.sessions.is_empty() {
                     self.manual_disconnect = false; // Reset manual disconnect flag
-                    self.connection_state = ConnectionState::Scanning;
+                    self.scanning = true;
                     self.available_devices.clear();
-                    return Task::perform(scan_devices(), Message::DevicesScanned);
+                    return Task::perform(scan_devices(self.simulated_model()), Message::DevicesScanned);
                 }
                 
                 Task::none()
@@ -242,13 +346,123 @@ impl ZenSignal {
                 }
                 Task::none()
             }
+            Message::SelectHrvWindow(WindowLength(seconds)) => {
+                self.config.hrv_window_seconds = seconds;
+                if let Err(e) = self.config.save() {
+                    log::error!("Failed to save config: {}", e);
+                }
+                for session in &mut self.sessions {
+                    session.set_hrv_window(seconds);
+                }
+                Task::none()
+            }
+            Message::SelectArtifactCorrection(correction) => {
+                self.config.rr_artifact_correction = correction;
+                if let Err(e) = self.config.save() {
+                    log::error!("Failed to save config: {}", e);
+                }
+                for session in &mut self.sessions {
+                    session.set_artifact_correction(correction);
+                }
+                Task::none()
+            }
+            Message::ToggleHrvStillOnly(enabled) => {
+                self.config.hrv_still_only = enabled;
+                if let Err(e) = self.config.save() {
+                    log::error!("Failed to save config: {}", e);
+                }
+                for session in &mut self.sessions {
+                    session.set_hrv_still_only(enabled);
+                }
+                Task::none()
+            }
+            Message::ToggleFilteredView(enabled) => {
+                self.config.show_filtered_signals = enabled;
+                if let Err(e) = self.config.save() {
+                    log::error!("Failed to save config: {}", e);
+                }
+                self.refresh_chart_history();
+                Task::none()
+            }
+            Message::ToggleRecordFiltered(enabled) => {
+                self.config.signal_filters.record = enabled;
+                self.apply_filters();
+                Task::none()
+            }
+            Message::SelectMainsFrequency(mains) => {
+                self.config.signal_filters.ecg.notch = mains;
+                self.apply_filters();
+                Task::none()
+            }
+            Message::ToggleSimulatedDevice(enabled) => {
+                self.config.enable_simulated_device = enabled;
+                if let Err(e) = self.config.save() {
+                    log::error!("Failed to save config: {}", e);
+                }
+
+                // Update the device list right away instead of requiring a rescan
+                if enabled {
+                    if !self.available_devices.iter().any(|d| d.is_simulated()) {
+                        self.available_devices.push(BluetoothDevice::simulated(self.config.simulated_device_model));
+                    }
+                } else if self.session(SIMULATED_DEVICE_ID).is_none() {
+                    self.available_devices.retain(|d| !d.is_simulated());
+                    if self.selected_device.as_ref().is_some_and(|d| d.is_simulated()) {
+                        self.selected_device = None;
+                    }
+                }
+                Task::none()
+            }
+            Message::SelectSimulatedModel(model) => {
+                self.config.simulated_device_model = model;
+                if let Err(e) = self.config.save() {
+                    log::error!("Failed to save config: {}", e);
+                }
+
+                // Replace the listed simulated device so the next connect uses the new model
+                let simulated = BluetoothDevice::simulated(model);
+                for device in self.available_devices.iter_mut().filter(|d| d.is_simulated()) {
+                    *device = simulated.clone();
+                }
+                if self.selected_device.as_ref().is_some_and(|d| d.is_simulated()) {
+                    self.selected_device = Some(simulated);
+                }
+                Task::none()
+            }
+            // Saved on submit or when recording starts, not on every keystroke
+            Message::SubjectIdChanged(subject_id) => {
+                self.config.subject_id = subject_id;
+                Task::none()
+            }
+            Message::DescriptionChanged(description) => {
+                self.config.recording_description = description;
+                Task::none()
+            }
+            Message::SaveRecordingInfo => {
+                if let Err(e) = self.config.save() {
+                    log::error!("Failed to save config: {}", e);
+                }
+                Task::none()
+            }
             Message::StartRecording => {
                 if !self.recorder.is_recording() {
+                    if let Err(e) = self.config.save() {
+                        log::error!("Failed to save config: {}", e);
+                    }
+                    let info = RecordingInfo {
+                        subject_id: self.config.subject_id.trim().to_string(),
+                        description: self.config.recording_description.trim().to_string(),
+                    };
                     match self.recorder.start_recording(
                         &self.config.recording_directory,
+                        info,
                     ) {
                         Ok(()) => {
                             log::info!("Recording started");
+                            // Quality attributes describe the recorded span only
+                            for session in self.sessions.iter_mut() {
+                                session.channels.reset_quality();
+                            }
                         }
                         Err(e) => {
                             log::error!("Failed to start recording: {}", e);
@@ -263,22 +477,675 @@ impl ZenSignal {
                 } else {
                     log::info!("Recording stopped");
                 }
+                self.recordings = PolarDataManager::list_recordings(&self.config.recording_directory);
+                self.refresh_chart_history();
+                Task::none()
+            }
+            Message::RefreshRecordings => {
+                self.recordings = PolarDataManager::list_recordings(&self.config.recording_directory);
+                Task::none()
+            }
+            Message::SelectRecording(path) => {
+                self.select_recording(path);
+                Task::none()
+            }
+            Message::SelectPlaybackDevice(device) => {
+                self.playback_device = Some(device);
+                Task::none()
+            }
+            Message::PlayRecording => {
+                if let Some(path) = self.selected_recording.clone() {
+                    let mut name = path
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    if let Some(device) = self.playback_device.as_ref().filter(|_| self.recording_devices.len() > 1) {
+                        name = format!("{} ({})", name, device);
+                    }
+                    let device_id = self.playback_device.as_ref().map(|device| device.id.clone());
+                    let session = self.new_session(PLAYBACK_DEVICE_ID.to_string(), name);
+                    self.sessions.push(session);
+                    self.annotations = PolarDataManager::read_annotations_from_file(&path).unwrap_or_else(|e| {
+                        log::error!("Failed to read annotations: {}", e);
+                        Vec::new()
+                    });
+                    self.annotations_modified = false;
+                    if let Err(e) = self.connect_sender.send(ConnectionCommand::Replay(path, device_id)) {
+                        log::error!("Failed to send playback request: {}", e);
+                        self.remove_session(PLAYBACK_DEVICE_ID);
+                    }
+                }
+                Task::none()
+            }
+            Message::RecoverRecording(journal) => {
+                self.unfinished_recordings.retain(|path| path != &journal);
+                // Recovery replays the whole journal into a new file, so it must not hold up an async worker
+                Task::perform(
+                    async move {
+                        tokio::task::spawn_blocking(move || PolarDataManager::recover_recording(&journal))
+                            .await
+                            .unwrap_or_else(|e| Err(e.to_string()))
+                    },
+                    Message::RecordingRecovered,
+                )
+            }
+            Message::RecordingRecovered(result) => {
+                match result {
+                    Ok(path) => {
+                        log::info!("Recovered recording {}", path.display());
+                        self.recordings = PolarDataManager::list_recordings(&self.config.recording_directory);
+                        self.select_recording(path);
+                    }
+                    Err(e) => log::error!("Failed to recover recording: {}", e),
+                }
+                Task::none()
+            }
+            Message::DiscardUnfinishedRecording(journal) => {
+                if let Err(e) = PolarDataManager::discard_journal(&journal) {
+                    log::error!("{}", e);
+                }
+                self.unfinished_recordings.retain(|path| path != &journal);
+                Task::none()
+            }
+            Message::SelectExportFormat(format) => {
+                self.export_format = format;
+                Task::none()
+            }
+            Message::ExportRecording => {
+                let Some(recording) = self.selected_recording.clone() else {
+                    return Task::none();
+                };
+                let format = self.export_format;
+                self.exporting = true;
+                self.export_status = Some(format!("Exporting as {}...", format));
+                // Export reads and writes whole channels, so it must not hold up an async worker
+                Task::perform(
+                    async move {
+                        let output_dir = export::default_output_dir(&recording);
+                        tokio::task::spawn_blocking(move || export::export_recording(&recording, format, &output_dir))
+                            .await
+                            .unwrap_or_else(|e| Err(e.to_string()))
+                    },
+                    Message::RecordingExported,
+                )
+            }
+            Message::RecordingExported(result) => {
+                self.exporting = false;
+                self.export_status = Some(match result {
+                    Ok(files) => match files.first().and_then(|f| f.parent()) {
+                        Some(dir) => format!("Exported {} file(s) to {}", files.len(), dir.display()),
+                        None => "Nothing exported".to_string(),
+                    },
+                    Err(e) => {
+                        log::error!("Failed to export recording: {}", e);
+                        format!("Export failed: {}", e)
+                    }
+                });
+                Task::none()
+            }
+            Message::PlaybackTogglePause => {
+                if let Some(status) = &self.playback {
+                    let command = if status.paused { PlaybackCommand::Play } else { PlaybackCommand::Pause };
+                    self.send_playback_command(command);
+                }
+                Task::none()
+            }
+            Message::PlaybackSetSpeed(speed) => {
+                self.send_playback_command(PlaybackCommand::SetSpeed(speed));
+                Task::none()
+            }
+            Message::PlaybackToggleLoop(looping) => {
+                self.send_playback_command(PlaybackCommand::SetLoop(looping));
+                Task::none()
+            }
+            Message::PlaybackSeekPreview(seconds) => {
+                self.seek_preview = Some(seconds);
+                Task::none()
+            }
+            Message::PlaybackSeekRelease => {
+                if let Some(seconds) = self.seek_preview.take() {
+                    self.seek_playback((seconds * 1e9) as u64);
+                }
+                Task::none()
+            }
+            Message::StartCalibration(device_id) => {
+                log::info!("Calibrating stress baseline on {}", device_id);
+                self.calibration = Some(Calibration::new(device_id, self.display_clock.now()));
+                self.calibration_status = None;
+                Task::none()
+            }
+            Message::CancelCalibration => {
+                self.calibration = None;
+                self.calibration_status = Some("Calibration cancelled".to_string());
+                Task::none()
+            }
+            Message::ClearStressBaseline => {
+                self.set_stress_baseline(None);
+                self.calibration_status = None;
+                Task::none()
+            }
+            Message::AnnotationLabelChanged(label) => {
+                self.annotation_label = label;
+                Task::none()
+            }
+            Message::AnnotationNoteChanged(note) => {
+                self.annotation_note = note;
+                Task::none()
+            }
+            Message::AddAnnotation => {
+                // Markers belong to a running session or a recording under review
+                if !self.has_connected_device() {
+                    return Task::none();
+                }
+                let annotation = Annotation::new(self.annotation_time_now(), &self.annotation_label, &self.annotation_note);
+                log::info!("Marker \"{}\" at {}", annotation.label, annotation.time);
+                if self.playback.is_some() {
+                    self.annotations_modified = true;
+                } else if self.recorder.is_recording() {
+                    if let Err(e) = self.recorder.add_annotation(annotation.clone()) {
+                        log::error!("Failed to record marker: {}", e);
+                    }
+                }
+                annotations::insert_sorted(&mut self.annotations, annotation);
+                self.annotation_note.clear();
+                Task::none()
+            }
+            Message::EditAnnotationLabel(index, label) => {
+                if let Some(annotation) = self.annotations.get_mut(index) {
+                    annotation.label = label;
+                    self.annotations_modified = true;
+                }
+                Task::none()
+            }
+            Message::EditAnnotationNote(index, note) => {
+                if let Some(annotation) = self.annotations.get_mut(index) {
+                    annotation.note = note;
+                    self.annotations_modified = true;
+                }
+                Task::none()
+            }
+            Message::EndAnnotationHere(index) => {
+                let now = self.annotation_time_now();
+                if let Some(annotation) = self.annotations.get_mut(index) {
+                    annotation.set_end(now);
+                    self.annotations_modified = true;
+                }
+                Task::none()
+            }
+            Message::DeleteAnnotation(index) => {
+                if index < self.annotations.len() {
+                    self.annotations.remove(index);
+                    self.annotations_modified = true;
+                }
+                Task::none()
+            }
+            Message::SeekToAnnotation(index) => {
+                let start_time = self.playback.as_ref().map(|status| status.start_time_ns);
+                if let (Some(start_time), Some(annotation)) = (start_time, self.annotations.get(index)) {
+                    self.seek_playback(annotation.time.saturating_sub(start_time));
+                }
+                Task::none()
+            }
+            Message::SaveAnnotations => {
+                self.save_annotations();
+                Task::none()
+            }
+            Message::SelectChartWindow(kind, window) => {
+                self.config.chart_windows.set(kind, window);
+                if let Err(e) = self.config.save() {
+                    log::error!("Failed to save config: {}", e);
+                }
+                self.refresh_chart_history();
+                Task::none()
+            }
+            Message::SelectChartScaleMode(kind, mode) => {
+                self.config.chart_scales.get_mut(kind).mode = mode;
+                if let Err(e) = self.config.save() {
+                    log::error!("Failed to save config: {}", e);
+                }
+                Task::none()
+            }
+            Message::SetChartValueRange(kind, low, high) => {
+                let scale = self.config.chart_scales.get_mut(kind);
+                scale.mode = YScaleMode::Manual;
+                scale.manual_range = Some((low, high));
+                // Saved once zooming stops, not on every wheel tick
+                self.config_save_due = Some(Instant::now() + WHEEL_SETTLE);
+                Task::none()
+            }
+            Message::ToggleFreezeCharts => {
+                if self.chart_view.is_frozen() {
+                    self.chart_view.follow_live();
+                } else {
+                    self.chart_view.freeze(self.live_display_time());
+                }
+                self.refresh_chart_history();
+                Task::none()
+            }
+            Message::ScrollCharts(delta_ns) => {
+                // Scrolling freezes the charts first, so the live edge does not move underneath
+                self.chart_view.freeze(self.live_display_time());
+                let back_ns = self.chart_view.scroll_back_ns().saturating_add_signed(delta_ns);
+                self.chart_view.scroll_to(back_ns, self.history_start());
+                // History is loaded once scrolling stops, not on every wheel tick
+                self.chart_history_due = Some(Instant::now() + WHEEL_SETTLE);
+                Task::none()
+            }
+            Message::ScrollChartsTo(seconds) => {
+                // History is loaded once the slider is released
+                self.chart_view.scroll_to((seconds * 1e9) as u64, self.history_start());
+                Task::none()
+            }
+            Message::LoadChartHistory => {
+                self.refresh_chart_history();
                 Task::none()
             }
         }
     }
 
+    /// Load chart history and save the config once wheel scrolling or zooming has stopped
+    fn apply_settled_wheel_input(&mut self) {
+        let now = Instant::now();
+        if self.chart_history_due.is_some_and(|due| now >= due) {
+            self.refresh_chart_history();
+        }
+        if self.config_save_due.is_some_and(|due| now >= due) {
+            self.config_save_due = None;
+            if let Err(e) = self.config.save() {
+                log::error!("Failed to save config: {}", e);
+            }
+        }
+    }
+
+    /// Live edge of the charts, delayed when smooth streaming is on
+    fn live_display_time(&self) -> u64 {
+        current_display_time(&self.display_clock, self.config.smooth_data_streaming)
+    }
+
+    /// Time the charts end at: the live edge, or where they were frozen and scrolled to
+    pub fn chart_display_time(&self) -> u64 {
+        self.chart_view.end_time(self.live_display_time())
+    }
+
+    /// Series to chart for a channel: its live buffer, or recorded history reaching further back
+    pub fn chart_series<'a>(&'a self, channel: ChannelId, channels: &'a Channels) -> &'a TimeSeries {
+        self.chart_history.get(&channel).unwrap_or_else(|| channels.series(channel))
+    }
+
+    /// Earliest time the charts can scroll back to: the oldest buffered point or
+    /// summary of the active device, or the start of the running recording
+    fn history_start(&self) -> u64 {
+        let buffered = self.active_session().and_then(|session| {
+            let channels = &session.channels;
+            [&channels.ecg, &channels.ppg, &channels.hr, &channels.rr, &channels.acc_x]
+                .into_iter()
+                .filter_map(|series| series.oldest_time())
+                .min()
+        });
+        let recorded = self.playback.is_none().then(|| self.recorder.recording_start_time()).flatten();
+        buffered.into_iter().chain(recorded).min().unwrap_or(u64::MAX)
+    }
+
+    /// Recorded channels drawn by a chart
+    fn chart_channels(&self, kind: ChartKind, channels: &Channels) -> Vec<ChannelId> {
+        let filtered = |stream| charts::show_filtered(self, channels, stream);
+        match kind {
+            ChartKind::Ecg if filtered(SensorStream::Ecg) => vec![ChannelId::EcgFiltered, ChannelId::RPeak],
+            ChartKind::Ecg => vec![ChannelId::Ecg, ChannelId::RPeak],
+            ChartKind::Ppg if filtered(SensorStream::Ppg) => vec![ChannelId::PpgFiltered],
+            ChartKind::Ppg => vec![ChannelId::Ppg],
+            ChartKind::Hr => vec![ChannelId::Hr],
+            ChartKind::Rr if channels.profile.supports(SensorStream::Ppi) => vec![ChannelId::Ppi, ChannelId::RrArtifact],
+            ChartKind::Rr => vec![ChannelId::Rr, ChannelId::EcgRr, ChannelId::RrArtifact],
+            ChartKind::Hrv => vec![ChannelId::Hrv],
+            ChartKind::Stress => Vec::new(),
+            ChartKind::Respiration => vec![ChannelId::Respiration],
+            ChartKind::Acc => ChannelId::for_stream(SensorStream::Acc).to_vec(),
+            ChartKind::Gyro => ChannelId::for_stream(SensorStream::Gyro).to_vec(),
+            ChartKind::Mag => ChannelId::for_stream(SensorStream::Mag).to_vec(),
+        }
+    }
+
+    /// Read the running recording for frozen chart windows reaching back past the live buffers
+    ///
+    /// Recorded points older than the buffer are joined with the buffered ones.
+    /// Recording playback has no separate history, its data is all replayed.
+    fn refresh_chart_history(&mut self) {
+        self.chart_history_due = None;
+        self.chart_history.clear();
+        if !self.chart_view.is_frozen() || self.playback.is_some() || !self.recorder.is_recording() {
+            return;
+        }
+        let Some(session) = self.active_session() else {
+            return;
+        };
+
+        let end = self.chart_display_time();
+        let mut history = HashMap::new();
+        for kind in ChartKind::all() {
+            let window = self.config.chart_windows.get(kind).as_nanos();
+            let start = end.saturating_sub(window);
+            for channel in self.chart_channels(kind, &session.channels) {
+                let live = session.channels.series(channel);
+                let buffered_from = live.first_point().map_or(u64::MAX, |p| p.time);
+                if buffered_from <= start {
+                    continue;
+                }
+                match self.recorder.read_range(&session.id, channel, start, end) {
+                    Ok(recorded) => {
+                        let mut series = TimeSeries::new(live.sample_rate());
+                        let older = recorded.iter().filter(|p| p.time < buffered_from);
+                        for point in older.chain(live.range_from_time(end, window)) {
+                            series.add_point(point.time, point.value);
+                        }
+                        history.insert(channel, series);
+                    }
+                    Err(e) => log::debug!("No recorded history for {:?}: {}", channel, e),
+                }
+            }
+        }
+        self.chart_history = history;
+    }
+
+    /// Select a recording for playback and export, starting with its first device
+    fn select_recording(&mut self, path: PathBuf) {
+        self.recording_devices = RecordedDevice::list(&path).unwrap_or_else(|e| {
+            log::error!("Failed to list recorded devices: {}", e);
+            Vec::new()
+        });
+        self.playback_device = self.recording_devices.first().cloned();
+        self.selected_recording = Some(path);
+    }
+
+    /// Jump to an offset of the recording being played back
+    fn seek_playback(&mut self, position_ns: u64) {
+        self.chart_view.follow_live();
+        // Data before the seek point no longer belongs on the charts
+        if let Some(session) = self.sessions.iter_mut().find(|s| s.id == PLAYBACK_DEVICE_ID) {
+            session.clear_data();
+        }
+        self.send_playback_command(PlaybackCommand::Seek(position_ns));
+    }
+
+    /// Recording time of the current moment: now when live, the playback position when reviewing
+    fn annotation_time_now(&self) -> u64 {
+        match &self.playback {
+            Some(status) => {
+                let since_status = self.display_clock.now().saturating_sub(self.playback_status_time);
+                let position = status.position_ns + since_status;
+                status.start_time_ns + position.min(status.duration_ns)
+            }
+            None => self.display_clock.now(),
+        }
+    }
+
+    /// Annotations with their span in display time, the time base of chart data
+    pub fn chart_annotations(&self) -> impl Iterator<Item = (Range<u64>, &Annotation)> + '_ {
+        self.annotations.iter().map(move |annotation| {
+            let span = match &self.playback {
+                // Replayed data keeps its recorded spacing, shifted to when the position was reported
+                Some(status) => {
+                    let to_display = |time: u64| {
+                        let offset = time as i64 - (status.start_time_ns + status.position_ns) as i64;
+                        (self.playback_status_time as i64 + offset).max(0) as u64
+                    };
+                    to_display(annotation.time)..to_display(annotation.end())
+                }
+                None => annotation.time..annotation.end(),
+            };
+            (span, annotation)
+        })
+    }
+
+    /// Write the reviewed markers back into the recording being played back
+    fn save_annotations(&mut self) {
+        let Some(status) = &self.playback else {
+            return;
+        };
+        match PolarDataManager::write_annotations_to_file(&status.file_path, &self.annotations) {
+            Ok(()) => {
+                log::info!("Saved {} marker(s) to {}", self.annotations.len(), status.file_path.display());
+                self.annotations_modified = false;
+            }
+            Err(e) => log::error!("Failed to save markers: {}", e),
+        }
+    }
+
+    /// Finish the calibration in progress once its period is over
+    fn check_calibration(&mut self) {
+        let Some(calibration) = &self.calibration else {
+            return;
+        };
+        let Some(session) = self.session(&calibration.device_id).filter(|s| s.is_connected()) else {
+            self.calibration = None;
+            self.calibration_status = Some("Calibration stopped: the device disconnected".to_string());
+            return;
+        };
+        if !calibration.is_complete(self.display_clock.now()) {
+            return;
+        }
+
+        let nn = session.channels.nn.last_duration(u64::MAX);
+        let result = calibration.finish(nn, chrono::Local::now().to_rfc3339());
+        self.calibration = None;
+        match result {
+            Ok(baseline) => {
+                log::info!("Recorded stress baseline: {:?}", baseline);
+                self.calibration_status = Some(format!(
+                    "Baseline recorded: RMSSD {:.1} ms at {:.0} bpm",
+                    baseline.rmssd, baseline.mean_hr
+                ));
+                self.set_stress_baseline(Some(baseline));
+            }
+            Err(e) => {
+                log::warn!("Calibration failed: {}", e);
+                self.calibration_status = Some(format!("Calibration failed: {}", e));
+            }
+        }
+    }
+
+    /// Save a new resting baseline and score every device against it
+    fn set_stress_baseline(&mut self, baseline: Option<StressBaseline>) {
+        self.config.stress_baseline = baseline;
+        if let Err(e) = self.config.save() {
+            log::error!("Failed to save config: {}", e);
+        }
+        for session in &mut self.sessions {
+            session.set_stress_baseline(self.config.stress_baseline.clone());
+        }
+    }
+
+    /// Route an update from the connection thread to its device session
+    fn handle_device_update(&mut self, device_id: String, update: SensorUpdate) {
+        use crate::sensor::ConnectionStatus;
+
+        if let SensorUpdate::ConnectionStatus(status) = update {
+            let now = self.display_clock.now();
+            match status {
+                ConnectionStatus::Connecting => {
+                    self.ensure_session(&device_id).state = ConnectionState::Connecting;
+                }
+                ConnectionStatus::Connected => {
+                    self.ensure_session(&device_id);
+                    let session = self.sessions.iter_mut().find(|s| s.id == device_id).unwrap();
+                    session.mark_connected(now, &self.recorder.for_device(&device_id));
+                    if self.active_device.is_none() {
+                        self.active_device = Some(device_id);
+                    }
+                }
+                ConnectionStatus::Reconnecting { attempt } => {
+                    log::warn!("{} lost its link, reconnecting (attempt {})", device_id, attempt);
+                    self.ensure_session(&device_id).mark_reconnecting(attempt, now);
+                }
+                ConnectionStatus::Disconnected => {
+                    self.remove_session(&device_id);
+                }
+                ConnectionStatus::Error(e) => {
+                    log::error!("Connection error ({}): {}", device_id, e);
+                    self.remove_session(&device_id);
+                }
+            }
+            return;
+        }
+
+        if let SensorUpdate::PlaybackStatus(status) = update {
+            // Charts stand still while playback is paused and run at the playback speed
+            if status.paused {
+                self.display_clock.pause();
+            } else {
+                self.display_clock.resume();
+            }
+            self.display_clock.set_rate(status.speed);
+            self.playback_status_time = self.display_clock.now();
+            self.playback = Some(status);
+            return;
+        }
+
+        let Some(session) = self.sessions.iter_mut().find(|s| s.id == device_id) else {
+            log::debug!("Dropping update for unknown device {}", device_id);
+            return;
+        };
+
+        // Replayed data must never end up in a new recording
+        let device_recorder = self.recorder.for_device(&device_id);
+        let recorder = self.playback.is_none().then_some(&device_recorder);
+        session.handle_update(update, self.display_clock.now(), recorder);
+    }
+
+    fn session(&self, device_id: &str) -> Option<&DeviceSession> {
+        self.sessions.iter().find(|s| s.id == device_id)
+    }
+
+    /// Session for a new device, using the configured HRV window, filters and baseline
+    fn new_session(&self, id: String, name: String) -> DeviceSession {
+        DeviceSession::new(id, name)
+            .with_hrv_window(self.config.hrv_window_seconds)
+            .with_artifact_correction(self.config.rr_artifact_correction)
+            .with_hrv_still_only(self.config.hrv_still_only)
+            .with_filters(self.config.signal_filters)
+            .with_stress_baseline(self.config.stress_baseline.clone())
+    }
+
+    /// Save changed filter settings and use them for every device
+    fn apply_filters(&mut self) {
+        if let Err(e) = self.config.save() {
+            log::error!("Failed to save config: {}", e);
+        }
+        for session in &mut self.sessions {
+            session.set_filters(self.config.signal_filters);
+        }
+    }
+
+    /// Session for a device, created if a source reports a device we did not connect
+    fn ensure_session(&mut self, device_id: &str) -> &mut DeviceSession {
+        if let Some(index) = self.sessions.iter().position(|s| s.id == device_id) {
+            return &mut self.sessions[index];
+        }
+        let name = self
+            .available_devices
+            .iter()
+            .find(|d| d.id == device_id)
+            .map(|d| d.name.clone())
+            .unwrap_or_else(|| device_id.to_string());
+        let session = self.new_session(device_id.to_string(), name);
+        self.sessions.push(session);
+        self.sessions.last_mut().unwrap()
+    }
+
+    /// Drop a device's session and its data once it has disconnected
+    fn remove_session(&mut self, device_id: &str) {
+        self.sessions.retain(|s| s.id != device_id);
+
+        if device_id == PLAYBACK_DEVICE_ID {
+            // Edits are kept even if playback stops before they were saved
+            if self.annotations_modified {
+                self.save_annotations();
+            }
+            self.annotations.clear();
+            self.annotations_modified = false;
+            self.playback = None;
+            self.display_clock = DisplayClock::new();
+            self.chart_view.follow_live();
+        }
+        if self.active_device.as_deref() == Some(device_id) {
+            self.active_device = self.sessions.iter().find(|s| s.is_connected()).map(|s| s.id.clone());
+        }
+        self.refresh_chart_history();
+    }
+
+    /// Session whose charts are shown: the selected device, else the first connected one
+    fn active_session(&self) -> Option<&DeviceSession> {
+        self.active_device
+            .as_deref()
+            .and_then(|id| self.session(id))
+            .filter(|s| s.is_connected())
+            .or_else(|| self.sessions.iter().find(|s| s.is_connected()))
+    }
+
+    fn has_connected_device(&self) -> bool {
+        self.sessions.iter().any(|s| s.is_connected())
+    }
+
+    /// Start a session for a device and ask the connection thread to connect it
+    fn connect_device(&mut self, device: BluetoothDevice) {
+        if self.session(&device.id).is_some() {
+            log::info!("{} is already connected", device.name);
+            return;
+        }
+
+        let command = self.connect_command(&device);
+        let session = self.new_session(device.id.clone(), device.name);
+        self.sessions.push(session);
+        if let Err(e) = self.connect_sender.send(command) {
+            log::error!("Failed to send connection request: {}", e);
+            self.remove_session(&device.id);
+        }
+    }
+
+    fn send_playback_command(&self, command: PlaybackCommand) {
+        if let Err(e) = self.connect_sender.send(ConnectionCommand::Playback(command)) {
+            log::error!("Failed to send playback command: {}", e);
+        }
+    }
+
+    /// Build the connect command for a device, routing the simulated device
+    /// to the synthetic data source
+    fn connect_command(&self, device: &BluetoothDevice) -> ConnectionCommand {
+        if device.is_simulated() {
+            ConnectionCommand::ConnectSimulated(SyntheticConfig {
+                heart_rate_bpm: self.config.simulated_heart_rate_bpm as f64,
+                model: device.model,
+            })
+        } else {
+            ConnectionCommand::Connect(device.id.clone(), device.model)
+        }
+    }
+
+    /// Model of the simulated device to list, if it is enabled
+    fn simulated_model(&self) -> Option<DeviceModel> {
+        self.config
+            .enable_simulated_device
+            .then_some(self.config.simulated_device_model)
+    }
+
     pub fn subscription(&self) -> Subscription<Message> {
-        iced::time::every(std::time::Duration::from_millis(16)).map(|_| Message::Tick)
+        // Key presses typed into a text field do not reach the hotkey
+        let marker_hotkey = keyboard::on_key_press(|key, modifiers| match key.as_ref() {
+            keyboard::Key::Character("m") if modifiers.is_empty() => Some(Message::AddAnnotation),
+            _ => None,
+        });
+        Subscription::batch([
+            iced::time::every(std::time::Duration::from_millis(16)).map(|_| Message::Tick),
+            marker_hotkey,
+        ])
     }
 
     pub fn view(&'_ self) -> Element<'_, Message> {
         let sidebar = self.create_sidebar();
         
-        let main_content = if self.connection_state == ConnectionState::Connected {
-            self.create_main_view()
-        } else {
-            self.create_disconnected_view()
+        let main_content = match self.active_session() {
+            Some(session) => self.create_main_view(session),
+            None => self.create_disconnected_view(),
         };
 
         let content = row![sidebar, main_content].spacing(0);
@@ -293,14 +1160,14 @@ impl ZenSignal {
         let title = text("Bluetooth Devices").size(20);
 
         let scan_button = button(
-            text(if self.connection_state == ConnectionState::Scanning {
+            text(if self.scanning {
                 "Scanning..."
             } else {
                 "Scan for Devices"
             })
         )
         .on_press_maybe(
-            if self.connection_state == ConnectionState::Disconnected {
+            if !self.scanning && self.playback.is_none() {
                 Some(Message::ScanDevices)
             } else {
                 None
@@ -309,21 +1176,21 @@ impl ZenSignal {
         .padding(10);
 
         let device_list: Element<'_, Message> = if self.available_devices.is_empty() {
-            let message = if self.connection_state == ConnectionState::Scanning {
+            let message = if self.scanning {
                 "Scanning for devices..."
             } else {
                 "No devices found. Click 'Scan for Devices' to start."
             };
             text(message).into()
         } else {
-            let is_connected = self.connection_state == ConnectionState::Connected;
+            let is_playing_back = self.playback.is_some();
             let devices = self.available_devices.iter().map(|device| {
                 let is_selected = self.selected_device.as_ref() == Some(device);
                 let device_text = format!("{}\n{}", device.name, device.id);
                 
                 button(text(device_text).size(14))
                     .on_press_maybe(
-                        if is_connected {
+                        if is_playing_back {
                             None
                         } else {
                             Some(Message::SelectDevice(device.clone()))
@@ -338,46 +1205,118 @@ impl ZenSignal {
             scrollable(column(devices).spacing(5)).into()
         };
 
-        let connect_button = match self.connection_state {
-            ConnectionState::Connected => {
-                button(text("Disconnect"))
-                    .on_press(Message::DisconnectDevice)
+        // Selected device: connect it unless it already has a session
+        let selected_session = self
+            .selected_device
+            .as_ref()
+            .and_then(|device| self.session(&device.id));
+        let connect_button = match (&self.selected_device, selected_session) {
+            (Some(_), Some(session)) => {
+                button(text(session.state.label()))
                     .padding(10)
                     .width(Length::Fill)
-                    .style(styles::disconnect_button_style())
             }
-            ConnectionState::Connecting => {
-                button(text("Connecting..."))
+            (Some(_), None) if self.playback.is_none() => {
+                button(text("Connect"))
+                    .on_press(Message::ConnectDevice)
                     .padding(10)
                     .width(Length::Fill)
+                    .style(styles::connect_button_style())
             }
             _ => {
-                if let Some(_) = &self.selected_device {
-                    button(text("Connect"))
-                        .on_press(Message::ConnectDevice)
-                        .padding(10)
-                        .width(Length::Fill)
-                        .style(styles::connect_button_style())
-                } else {
-                    button(text("Select a device"))
-                        .padding(10)
-                        .width(Length::Fill)
-                }
+                button(text("Select a device"))
+                    .padding(10)
+                    .width(Length::Fill)
             }
         };
 
+        let sessions_section = self.create_sessions_section();
+
         let smooth_streaming_checkbox = checkbox(
             "Smooth and Delay Streaming Data",
             self.config.smooth_data_streaming
         )
         .on_toggle(Message::ToggleSmoothStreaming);
 
+        let hrv_still_only_checkbox = checkbox(
+            "HRV Only When Still",
+            self.config.hrv_still_only
+        )
+        .on_toggle(Message::ToggleHrvStillOnly);
+
+        let filtered_view_checkbox = checkbox(
+            "Show Filtered ECG/PPG",
+            self.config.show_filtered_signals
+        )
+        .on_toggle(Message::ToggleFilteredView);
+
+        // Changing mid-recording would leave the derived channels incomplete
+        let record_filtered_checkbox = checkbox(
+            "Record Filtered Signals",
+            self.config.signal_filters.record
+        )
+        .on_toggle_maybe((!self.recorder.is_recording()).then_some(Message::ToggleRecordFiltered));
+
+        let mains_picker = row![
+            text("Mains Notch").size(14),
+            pick_list(
+                MainsFrequency::all(),
+                Some(self.config.signal_filters.ecg.notch),
+                Message::SelectMainsFrequency,
+            )
+            .width(Length::Fill),
+        ]
+        .spacing(10)
+        .align_y(iced::alignment::Vertical::Center);
+
         let autoconnect_checkbox = checkbox(
             "Enable Autoconnect",
             self.config.enable_autoconnect
         )
         .on_toggle(Message::ToggleAutoconnect);
 
+        let simulated_device_checkbox = checkbox(
+            "Show Simulated Device",
+            self.config.enable_simulated_device
+        )
+        .on_toggle(Message::ToggleSimulatedDevice);
+
+        let simulated_model_picker: Element<'_, Message> = if self.config.enable_simulated_device {
+            pick_list(
+                DeviceModel::all(),
+                Some(self.config.simulated_device_model),
+                Message::SelectSimulatedModel,
+            )
+            .width(Length::Fill)
+            .into()
+        } else {
+            column![].into()
+        };
+
+        let hrv_window_picker = row![
+            text("HRV Window").size(14),
+            pick_list(
+                hrv::WINDOW_CHOICES,
+                Some(WindowLength(self.config.hrv_window_seconds)),
+                Message::SelectHrvWindow,
+            )
+            .width(Length::Fill),
+        ]
+        .spacing(10)
+        .align_y(iced::alignment::Vertical::Center);
+
+        let artifact_correction_picker = row![
+            text("RR Correction").size(14),
+            pick_list(
+                ArtifactCorrection::all(),
+                Some(self.config.rr_artifact_correction),
+                Message::SelectArtifactCorrection,
+            )
+            .width(Length::Fill),
+        ]
+        .spacing(10)
+        .align_y(iced::alignment::Vertical::Center);
+
         // Recording controls
         let recording_status = if self.recorder.is_recording() {
             format!(
@@ -389,13 +1328,33 @@ impl ZenSignal {
             "Not recording".to_string()
         };
 
+        // Stored with the next recording, so locked while one is running
+        let recording_info = if self.recorder.is_recording() {
+            column![
+                text_input("Subject ID", &self.config.subject_id).size(12),
+                text_input("Description", &self.config.recording_description).size(12),
+            ]
+        } else {
+            column![
+                text_input("Subject ID", &self.config.subject_id)
+                    .on_input(Message::SubjectIdChanged)
+                    .on_submit(Message::SaveRecordingInfo)
+                    .size(12),
+                text_input("Description", &self.config.recording_description)
+                    .on_input(Message::DescriptionChanged)
+                    .on_submit(Message::SaveRecordingInfo)
+                    .size(12),
+            ]
+        }
+        .spacing(5);
+
         let recording_button = if self.recorder.is_recording() {
             button(text("⏹ Stop Recording"))
                 .on_press(Message::StopRecording)
                 .padding(10)
                 .width(Length::Fill)
                 .style(styles::disconnect_button_style())
-        } else if self.connection_state == ConnectionState::Connected {
+        } else if self.has_connected_device() && self.playback.is_none() {
             button(text("⏺ Start Recording"))
                 .on_press(Message::StartRecording)
                 .padding(10)
@@ -407,16 +1366,29 @@ impl ZenSignal {
                 .width(Length::Fill)
         };
 
+        let recordings_section = self.create_recordings_section();
+
         let sidebar_content = column![
             title,
             scan_button,
             device_list,
             connect_button,
+            sessions_section,
+            recordings_section,
             vertical_space(), // Push settings to bottom
             text(recording_status).size(12),
+            recording_info,
             recording_button,
             smooth_streaming_checkbox,
+            filtered_view_checkbox,
+            record_filtered_checkbox,
             autoconnect_checkbox,
+            simulated_device_checkbox,
+            simulated_model_picker,
+            hrv_window_picker,
+            artifact_correction_picker,
+            hrv_still_only_checkbox,
+            mains_picker,
         ]
         .padding(20)
         .spacing(10)
@@ -429,11 +1401,186 @@ impl ZenSignal {
             .into()
     }
 
+    /// Connected devices, each with its own disconnect button
+    fn create_sessions_section(&self) -> Element<'_, Message> {
+        if self.sessions.is_empty() {
+            return column![].into();
+        }
+
+        let items = self.sessions.iter().map(|session| {
+            let status = session.state.label();
+            let label = if session.id == PLAYBACK_DEVICE_ID { "Stop Playback" } else { "Disconnect" };
+
+            row![
+                text(format!("{}\n{}", session.name, status)).size(12).width(Length::Fill),
+                button(text(label).size(12))
+                    .on_press(Message::DisconnectDevice(session.id.clone()))
+                    .padding(5)
+                    .style(styles::disconnect_button_style()),
+            ]
+            .spacing(5)
+            .align_y(iced::alignment::Vertical::Center)
+            .into()
+        });
+
+        column![text("Active Devices").size(16), column(items).spacing(5)]
+            .spacing(5)
+            .into()
+    }
+
+    /// Recording file list with playback button
+    fn create_recordings_section(&self) -> Element<'_, Message> {
+        let is_disconnected = self.sessions.is_empty();
+
+        let header = row![
+            text("Recordings").size(16).width(Length::Fill),
+            button(text("Refresh").size(12))
+                .on_press(Message::RefreshRecordings)
+                .padding(5),
+        ]
+        .align_y(iced::alignment::Vertical::Center);
+
+        let list: Element<'_, Message> = if self.recordings.is_empty() {
+            text("No recordings found").size(12).into()
+        } else {
+            let items = self.recordings.iter().map(|path| {
+                let is_selected = self.selected_recording.as_ref() == Some(path);
+                let name = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+
+                button(text(name).size(12))
+                    .on_press_maybe(is_disconnected.then(|| Message::SelectRecording(path.clone())))
+                    .width(Length::Fill)
+                    .padding(5)
+                    .style(styles::device_button_style(is_selected))
+                    .into()
+            });
+            scrollable(column(items).spacing(3)).height(Length::Fixed(120.0)).into()
+        };
+
+        // Multi-device recordings play one device at a time
+        let device_picker = (self.recording_devices.len() > 1).then(|| {
+            pick_list(
+                self.recording_devices.as_slice(),
+                self.playback_device.clone(),
+                Message::SelectPlaybackDevice,
+            )
+            .text_size(12)
+            .width(Length::Fill)
+        });
+
+        let play_button = button(text("▶ Play Recording"))
+            .on_press_maybe(
+                (is_disconnected && self.selected_recording.is_some()).then_some(Message::PlayRecording),
+            )
+            .padding(10)
+            .width(Length::Fill);
+
+        // Recordings cut short by a crash, recovered from their journals on request
+        let unfinished = self.unfinished_recordings.iter().map(|journal| {
+            let name = journal
+                .file_stem()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            column![
+                text(format!("Unfinished: {}", name)).size(12),
+                row![
+                    button(text("Recover").size(12))
+                        .on_press(Message::RecoverRecording(journal.clone()))
+                        .padding(5),
+                    button(text("Discard").size(12))
+                        .on_press(Message::DiscardUnfinishedRecording(journal.clone()))
+                        .padding(5),
+                ]
+                .spacing(5),
+            ]
+            .spacing(3)
+            .into()
+        });
+
+        let export_row = row![
+            pick_list(ExportFormat::all(), Some(self.export_format), Message::SelectExportFormat)
+                .text_size(12)
+                .width(Length::Fill),
+            button(text("Export").size(12))
+                .on_press_maybe(
+                    (self.selected_recording.is_some() && !self.exporting).then_some(Message::ExportRecording),
+                )
+                .padding(5),
+        ]
+        .spacing(5)
+        .align_y(iced::alignment::Vertical::Center);
+        let export_status = self.export_status.as_deref().map(|status| text(status).size(12));
+
+        column![header, column(unfinished).spacing(5), list]
+            .push_maybe(device_picker)
+            .push(play_button)
+            .push(export_row)
+            .push_maybe(export_status)
+            .spacing(5)
+            .into()
+    }
+
+    /// Playback transport: pause/play, speed, loop and seek slider
+    fn create_playback_controls<'a>(&self, status: &'a PlaybackStatus) -> Element<'a, Message> {
+        let duration_secs = status.duration_ns as f64 / 1e9;
+        let position_secs = self
+            .seek_preview
+            .unwrap_or(status.position_ns as f64 / 1e9)
+            .min(duration_secs);
+
+        let pause_button = button(text(if status.paused { "▶ Play" } else { "⏸ Pause" }))
+            .on_press(Message::PlaybackTogglePause)
+            .padding(8);
+
+        let speed_buttons = PLAYBACK_SPEEDS.iter().map(|&speed| {
+            let is_selected = (status.speed - speed).abs() < f64::EPSILON;
+            button(text(format!("{}x", speed)).size(14))
+                .on_press(Message::PlaybackSetSpeed(speed))
+                .padding(8)
+                .style(styles::device_button_style(is_selected))
+                .into()
+        });
+
+        let loop_checkbox = checkbox("Loop", status.looping).on_toggle(Message::PlaybackToggleLoop);
+
+        let seek_slider = slider(0.0..=duration_secs.max(0.001), position_secs, Message::PlaybackSeekPreview)
+            .step(0.1)
+            .on_release(Message::PlaybackSeekRelease)
+            .width(Length::Fill);
+
+        let position_text = text(format!(
+            "{} / {}",
+            format_duration(position_secs),
+            format_duration(duration_secs)
+        ))
+        .size(14);
+
+        let file_name = status
+            .file_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        column![
+            text(format!("Playing {}", file_name)).size(14),
+            row![pause_button, row(speed_buttons).spacing(5), loop_checkbox, seek_slider, position_text]
+                .spacing(10)
+                .align_y(iced::alignment::Vertical::Center),
+        ]
+        .spacing(5)
+        .into()
+    }
+
     fn create_disconnected_view(&self) -> Element<'_, Message> {
-        let message = match self.connection_state {
-            ConnectionState::Scanning => "Scanning for devices...",
-            ConnectionState::Connecting => "Connecting to device...",
-            _ => "Select a Polar device from the sidebar to begin",
+        let message = if self.scanning {
+            "Scanning for devices..."
+        } else if !self.sessions.is_empty() {
+            "Connecting to device..."
+        } else {
+            "Select a Polar device from the sidebar to begin"
         };
 
         container(
@@ -447,57 +1594,415 @@ impl ZenSignal {
         .into()
     }
 
-    fn create_main_view(&self) -> Element<'_, Message> {
-        let hr_series = &self.channels.hr;
-        let last_hr_point = hr_series.last_points(1);
-        let hr = last_hr_point.last().map(|point| point.value).unwrap_or(0);
-
-        // Calculate RMSSD from last 30 seconds of RR data
-        use crate::timeseries::PointSliceExt;
-        const THIRTY_SECONDS_NS: u64 = 30_000_000_000;
-        let recent_rr = self.channels.rr.last_duration(THIRTY_SECONDS_NS);
-        let rmssd = if recent_rr.len() >= 2 {
-            recent_rr.rmssd()
-        } else {
-            0.0
-        };
-
-        let ecg_chart = ChartWidget::new(EcgChartType { state: self })
-            .width(Length::Fill)
-            .height(Length::Fill);
-
-        let hr_chart = ChartWidget::new(HrChartType { state: self })
-            .width(Length::Fill)
-            .height(Length::Fill);
+    fn create_main_view<'a>(&'a self, session: &'a DeviceSession) -> Element<'a, Message> {
+        // Only show charts for streams the active device provides
+        let channels = &session.channels;
+        let profile = &channels.profile;
+        let mut charts: Vec<Element<'_, Message>> = Vec::new();
 
-        let rr_chart = ChartWidget::new(RrChartType { state: self })
-            .width(Length::Fill)
-            .height(Length::Fill);
+        if profile.supports(SensorStream::Ecg) {
+            charts.push(self.chart_with_window(
+                ChartKind::Ecg,
+                ChartWidget::new(EcgChartType { state: self, channels }).width(Length::Fill).height(Length::Fill),
+            ));
+        }
+        if profile.supports(SensorStream::Ppg) {
+            charts.push(self.chart_with_window(
+                ChartKind::Ppg,
+                ChartWidget::new(PpgChartType { state: self, channels }).width(Length::Fill).height(Length::Fill),
+            ));
+        }
 
-        let hrv_chart = ChartWidget::new(HrvChartType { state: self })
-            .width(Length::Fill)
-            .height(Length::Fill);
+        charts.push(self.chart_with_window(
+            ChartKind::Hr,
+            ChartWidget::new(HrChartType { state: self, channels }).width(Length::Fill).height(Length::Fill),
+        ));
+        charts.push(self.chart_with_window(
+            ChartKind::Rr,
+            ChartWidget::new(RrChartType { state: self, channels }).width(Length::Fill).height(Length::Fill),
+        ));
+        charts.push(self.chart_with_window(
+            ChartKind::Hrv,
+            ChartWidget::new(HrvChartType { state: self, channels }).width(Length::Fill).height(Length::Fill),
+        ));
+        charts.push(self.chart_with_window(
+            ChartKind::Stress,
+            ChartWidget::new(StressChartType { state: self, session }).width(Length::Fill).height(Length::Fill),
+        ));
+        charts.push(self.chart_with_window(
+            ChartKind::Respiration,
+            ChartWidget::new(RespirationChartType { state: self, channels }).width(Length::Fill).height(Length::Fill),
+        ));
 
-        let acc_chart = ChartWidget::new(AccChartType { state: self })
-            .width(Length::Fill)
-            .height(Length::Fill);
+        if profile.supports(SensorStream::Acc) {
+            charts.push(self.chart_with_window(
+                ChartKind::Acc,
+                ChartWidget::new(AccChartType { state: self, channels }).width(Length::Fill).height(Length::Fill),
+            ));
+        }
+        if profile.supports(SensorStream::Gyro) {
+            charts.push(self.chart_with_window(
+                ChartKind::Gyro,
+                ChartWidget::new(GyroChartType { state: self, channels }).width(Length::Fill).height(Length::Fill),
+            ));
+        }
+        if profile.supports(SensorStream::Mag) {
+            charts.push(self.chart_with_window(
+                ChartKind::Mag,
+                ChartWidget::new(MagChartType { state: self, channels }).width(Length::Fill).height(Length::Fill),
+            ));
+        }
 
-        let stats = column![
-            text(format!("Heart Rate: {} bpm", hr)).size(24),
-            text(format!("RMSSD: {:.2} ms", rmssd)).size(20)
-        ]
-            .spacing(10)
-            .width(Length::FillPortion(1));
+        // Stats for every connected device side by side, the active one first
+        let connected = self.sessions.iter().filter(|s| s.is_connected() && s.id != session.id);
+        let stats = column(
+            std::iter::once(session)
+                .chain(connected)
+                .map(|s| Self::create_device_stats(s, s.id == session.id)),
+        )
+        .push(self.create_calibration_controls(session))
+        .spacing(20)
+        .width(Length::FillPortion(1));
 
-        let plots = column![ecg_chart, hr_chart, rr_chart, hrv_chart, acc_chart]
+        let plots = column(charts)
             .width(Length::FillPortion(3))
             .spacing(10);
 
-        let content = row![plots, stats].spacing(20).padding(20);
+        let mut content = column![].spacing(10).padding(20);
+        if let Some(status) = &self.playback {
+            content = content.push(self.create_playback_controls(status));
+        }
+        content = content
+            .push(self.create_annotation_controls())
+            .push(self.create_chart_view_controls())
+            .push_maybe(self.create_device_selector(session))
+            .push(row![plots, stats].spacing(20));
 
         container(content)
             .width(Length::Fill)
             .height(Length::Fill)
             .into()
     }
+
+    /// Chart below pickers for its time window and value axis mode
+    fn chart_with_window<'a>(
+        &self,
+        kind: ChartKind,
+        chart: impl Into<Element<'a, Message>>,
+    ) -> Element<'a, Message> {
+        let window_picker = pick_list(
+            ChartWindow::all(),
+            Some(self.config.chart_windows.get(kind)),
+            move |window| Message::SelectChartWindow(kind, window),
+        )
+        .text_size(12)
+        .padding(2);
+        let scale_picker = pick_list(
+            YScaleMode::all(),
+            Some(self.config.chart_scales.get(kind).mode),
+            move |mode| Message::SelectChartScaleMode(kind, mode),
+        )
+        .text_size(12)
+        .padding(2);
+
+        column![row![horizontal_space(), scale_picker, window_picker].spacing(5), chart.into()]
+            .height(Length::Fill)
+            .into()
+    }
+
+    /// Freeze button, and a slider scrolling the frozen charts back through history
+    fn create_chart_view_controls(&self) -> Element<'_, Message> {
+        let frozen = self.chart_view.is_frozen();
+        let freeze_button = button(text(if frozen { "Back to Live" } else { "Freeze Charts" }).size(14))
+            .on_press(Message::ToggleFreezeCharts)
+            .padding(8);
+
+        if !frozen {
+            return row![
+                freeze_button,
+                text("Mouse wheel over a chart zooms it, Ctrl+wheel zooms its values; scroll sideways to go back in time").size(12),
+            ]
+            .spacing(10)
+            .align_y(iced::alignment::Vertical::Center)
+            .into();
+        }
+
+        // The slider runs from the oldest history on the left to the frozen moment on the right
+        let max_back = self.chart_view.max_scroll_back_ns(self.history_start()) as f64 / 1e9;
+        let back = self.chart_view.scroll_back_ns() as f64 / 1e9;
+        let scroll_slider = slider(-max_back.max(0.001)..=0.0, -back, |value| Message::ScrollChartsTo(-value))
+            .step(0.1)
+            .on_release(Message::LoadChartHistory)
+            .width(Length::Fill);
+        let source = if self.chart_history.is_empty() { "" } else { " (from recording)" };
+
+        row![
+            freeze_button,
+            scroll_slider,
+            text(format!("{} back{}", format_duration(back), source)).size(12),
+        ]
+        .spacing(10)
+        .align_y(iced::alignment::Vertical::Center)
+        .into()
+    }
+
+    /// Marker entry, and the markers of a recording under review for editing
+    fn create_annotation_controls(&self) -> Element<'_, Message> {
+        let entry = row![
+            text_input("Marker label", &self.annotation_label)
+                .on_input(Message::AnnotationLabelChanged)
+                .on_submit(Message::AddAnnotation)
+                .size(14)
+                .width(Length::FillPortion(1)),
+            text_input("Note (optional)", &self.annotation_note)
+                .on_input(Message::AnnotationNoteChanged)
+                .on_submit(Message::AddAnnotation)
+                .size(14)
+                .width(Length::FillPortion(2)),
+            button(text("Add Marker (M)").size(14))
+                .on_press(Message::AddAnnotation)
+                .padding(8),
+        ]
+        .spacing(10)
+        .align_y(iced::alignment::Vertical::Center);
+
+        // Live markers are already recorded; those of a recording can be edited
+        let Some(status) = &self.playback else {
+            return entry.into();
+        };
+
+        let items = self.annotations.iter().enumerate().map(|(index, annotation)| {
+            let offset = annotation.time.saturating_sub(status.start_time_ns) as f64 / 1e9;
+            let span = if annotation.duration_ns > 0 {
+                format!("{} +{:.1} s", format_duration(offset), annotation.duration_ns as f64 / 1e9)
+            } else {
+                format_duration(offset)
+            };
+            row![
+                button(text(span).size(12))
+                    .on_press(Message::SeekToAnnotation(index))
+                    .padding(5),
+                text_input("Label", &annotation.label)
+                    .on_input(move |label| Message::EditAnnotationLabel(index, label))
+                    .size(12)
+                    .width(Length::FillPortion(1)),
+                text_input("Note", &annotation.note)
+                    .on_input(move |note| Message::EditAnnotationNote(index, note))
+                    .size(12)
+                    .width(Length::FillPortion(2)),
+                button(text("End Here").size(12))
+                    .on_press(Message::EndAnnotationHere(index))
+                    .padding(5),
+                button(text("Delete").size(12))
+                    .on_press(Message::DeleteAnnotation(index))
+                    .padding(5)
+                    .style(styles::disconnect_button_style()),
+            ]
+            .spacing(5)
+            .align_y(iced::alignment::Vertical::Center)
+            .into()
+        });
+
+        let save_button = button(text(if self.annotations_modified { "Save Markers" } else { "Markers Saved" }).size(12))
+            .on_press_maybe(self.annotations_modified.then_some(Message::SaveAnnotations))
+            .padding(5);
+
+        column![
+            entry,
+            row![
+                text(format!("Markers in recording: {}", self.annotations.len())).size(14).width(Length::Fill),
+                save_button,
+            ]
+            .align_y(iced::alignment::Vertical::Center),
+            scrollable(column(items).spacing(3)).height(Length::Fixed(100.0)),
+        ]
+        .spacing(5)
+        .into()
+    }
+
+    /// Baseline calibration of the active device: start, progress, or the stored baseline
+    fn create_calibration_controls(&self, active: &DeviceSession) -> Element<'_, Message> {
+        let mut controls = column![text("Stress Baseline").size(16)].spacing(5);
+
+        if let Some(calibration) = &self.calibration {
+            let progress = calibration.progress(self.display_clock.now());
+            let remaining = CALIBRATION_SECONDS as f64 * (1.0 - progress);
+            let device = self.session(&calibration.device_id).map_or(calibration.device_id.as_str(), |s| s.name.as_str());
+            controls = controls
+                .push(text(format!("Sit still and breathe normally ({}), {} left", device, format_duration(remaining))).size(12))
+                .push(progress_bar(0.0..=1.0, progress as f32).height(10))
+                .push(button(text("Cancel").size(12)).on_press(Message::CancelCalibration).padding(5));
+        } else {
+            let baseline = match &self.config.stress_baseline {
+                Some(b) => format!(
+                    "RMSSD {:.1} ms, stress index {:.0}, {:.0} bpm, recorded {}",
+                    b.rmssd,
+                    b.stress_index,
+                    b.mean_hr,
+                    b.recorded_at.get(..10).unwrap_or(&b.recorded_at)
+                ),
+                None => "None recorded".to_string(),
+            };
+            // Replayed beats are not the user at rest now
+            let calibrate = button(text(format!("Calibrate ({} min rest)", CALIBRATION_SECONDS / 60)).size(12))
+                .on_press_maybe(self.playback.is_none().then(|| Message::StartCalibration(active.id.clone())))
+                .padding(5);
+            let clear = button(text("Clear").size(12))
+                .on_press_maybe(self.config.stress_baseline.is_some().then_some(Message::ClearStressBaseline))
+                .padding(5);
+            controls = controls
+                .push(text(baseline).size(12))
+                .push(row![calibrate, clear].spacing(5));
+        }
+
+        controls
+            .push_maybe(self.calibration_status.as_deref().map(|status| text(status).size(12)))
+            .into()
+    }
+
+    /// Buttons choosing which device's charts to show, when more than one is connected
+    fn create_device_selector(&self, active: &DeviceSession) -> Option<Element<'_, Message>> {
+        let connected: Vec<&DeviceSession> = self.sessions.iter().filter(|s| s.is_connected()).collect();
+        if connected.len() < 2 {
+            return None;
+        }
+
+        let buttons = connected.into_iter().map(|session| {
+            button(text(session.name.as_str()).size(14))
+                .on_press(Message::SelectActiveDevice(session.id.clone()))
+                .padding(8)
+                .style(styles::device_button_style(session.id == active.id))
+                .into()
+        });
+        Some(row(buttons).spacing(5).into())
+    }
+
+    /// Activity level, posture, steps and intensity of the latest activity epoch
+    fn activity_summary(channels: &Channels) -> Option<String> {
+        let level = ActivityLevel::from_code(channels.activity.last_point()?.value)?;
+        let posture = channels
+            .posture
+            .last_point()
+            .and_then(|p| Posture::from_code(p.value))
+            .map_or(String::new(), |posture| format!(", {}", posture));
+        Some(format!(
+            "Activity: {}{}, {} steps, ENMO {} mg",
+            level,
+            posture,
+            channels.steps.last_point().map_or(0, |p| p.value),
+            channels.enmo.last_point().map_or(0, |p| p.value),
+        ))
+    }
+
+    /// Heart rate summary of one device
+    fn create_device_stats(session: &DeviceSession, is_active: bool) -> Element<'_, Message> {
+        let channels = &session.channels;
+        let hr = channels.hr.last_points(1).last().map(|point| point.value).unwrap_or(0);
+
+        // HRV over the configured window of beats
+        let rmssd = session.hrv.map(|m| m.rmssd).unwrap_or(0.0);
+        let hrv_details = session.hrv.map(|m| {
+            let optional = |value: Option<f64>, precision: usize| {
+                value.map_or("-".to_string(), |v| format!("{:.*}", precision, v))
+            };
+            column![
+                text(format!("SDNN: {:.1} ms, mean NN: {:.0} ms, pNN50: {:.1}%", m.sdnn, m.mean_nn, m.pnn50)).size(12),
+                text(format!(
+                    "HR max-min: {:.1} bpm, {} beats, artifacts: {:.1}%",
+                    m.hr_max_min,
+                    m.beats,
+                    session.artifact_percentage.unwrap_or(0.0)
+                ))
+                .size(12),
+                text(format!(
+                    "LF: {} ms², HF: {} ms², LF/HF: {}",
+                    optional(m.lf, 0),
+                    optional(m.hf, 0),
+                    optional(m.lf_hf, 2)
+                ))
+                .size(12),
+                text(format!(
+                    "SD1: {:.1} ms, SD2: {:.1} ms, SampEn: {}, DFA α1: {}",
+                    m.sd1,
+                    m.sd2,
+                    optional(m.sample_entropy, 2),
+                    optional(m.dfa_alpha1, 2)
+                ))
+                .size(12),
+            ]
+            .spacing(2)
+        });
+
+        // One line per periodic stream that delivered data
+        let quality = channels.profile.streams().iter().filter_map(|&stream| {
+            let report = channels.quality_report(stream)?;
+            Some(
+                text(format!(
+                    "{}: {:.1}% ({}/{}), longest gap {:.1} s, jitter {:.0} ms",
+                    stream.display_name(),
+                    report.completeness() * 100.0,
+                    report.samples_received,
+                    report.samples_expected,
+                    report.longest_gap_ns as f64 / 1e9,
+                    report.jitter_ms,
+                ))
+                .size(12)
+                .into(),
+            )
+        });
+
+        // Stress index, and the relaxation gauge once a baseline is recorded
+        let stress = session.stress.map(|stress| {
+            let relaxation: Element<'_, Message> = match stress.relaxation {
+                Some(score) => column![
+                    text(format!("Relaxation: {:.0} / 100", score)).size(14),
+                    progress_bar(0.0..=100.0, score as f32).height(10),
+                ]
+                .spacing(2)
+                .into(),
+                None => text("Relaxation: calibrate a baseline").size(12).into(),
+            };
+            column![
+                text(format!("Stress index: {:.0} ({})", stress.stress_index, stress.level)).size(16),
+                relaxation,
+            ]
+            .spacing(4)
+        });
+
+        let scale = if is_active { 1.0 } else { 0.75 };
+        column![
+            text(format!("{} ({})", session.name, channels.profile.model.display_name())).size(16),
+            text(format!("Heart Rate: {} bpm", hr)).size(24.0 * scale),
+            text(format!("RMSSD: {:.2} ms", rmssd)).size(20.0 * scale)
+        ]
+        .push_maybe(stress)
+        .push_maybe(
+            channels
+                .respiration
+                .last_point()
+                .map(|rate| text(format!("Respiration: {} breaths/min", rate.value)).size(16)),
+        )
+        .push_maybe(Self::activity_summary(channels).map(|summary| text(summary).size(16)))
+        .push_maybe(hrv_details)
+        .push_maybe(
+            channels
+                .energy_expended
+                .map(|kj| text(format!("Energy: {} kJ", kj)).size(16)),
+        )
+        .push_maybe(
+            channels
+                .clock
+                .drift_ppm()
+                .map(|ppm| text(format!("Clock drift: {:+.1} ppm", ppm)).size(14)),
+        )
+        .push(column(quality).spacing(2))
+        .spacing(10)
+        .into()
+    }
+}
+
+/// Format seconds as `m:ss`
+fn format_duration(seconds: f64) -> String {
+    let total = seconds.max(0.0) as u64;
+    format!("{}:{:02}", total / 60, total % 60)
 }
@@ -0,0 +1,308 @@
+//! # RR Artifact Module
+//!
+//! Finds ectopic beats and missed or extra detections in RR/PPI intervals and
+//! replaces them, turning the raw intervals into NN intervals for HRV.
+//!
+//! ## Detection
+//! Threshold-based, as in Kubios' "medium" setting: an interval is an
+//! artifact if it differs from the median of the previous
+//! `REFERENCE_BEATS` intervals by more than `ARTIFACT_THRESHOLD` of that
+//! median (250 ms at 60 bpm), or lies outside the physiological range
+//! `MIN_INTERVAL_MS`..`MAX_INTERVAL_MS`. The median is taken over the raw
+//! intervals, so it follows real heart rate changes within a few beats.
+//!
+//! ## Correction
+//! - **Delete**: Artifacts are left out of the NN series
+//! - **Interpolate**: Replaced by the straight line between the normal beats
+//!   before and after
+//! - **Cubic spline**: Replaced by a natural cubic spline through up to
+//!   `SPLINE_KNOTS_BEFORE` normal beats before and the one after
+//!
+//! Interpolation needs the next normal beat, so a corrected beat is emitted
+//! when that beat arrives. The filter works beat by beat and serves live
+//! data and recordings alike.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+
+// Deviation from the local median, as a fraction of it, that marks an artifact
+const ARTIFACT_THRESHOLD: f64 = 0.25;
+
+// Raw intervals the local median is taken over
+const REFERENCE_BEATS: usize = 11;
+
+// Intervals needed before the median check starts
+const MIN_REFERENCE_BEATS: usize = 5;
+
+// Physiological interval range (200 bpm to 30 bpm)
+const MIN_INTERVAL_MS: f64 = 300.0;
+const MAX_INTERVAL_MS: f64 = 2000.0;
+
+// Normal beats before an artifact used as spline knots
+const SPLINE_KNOTS_BEFORE: usize = 3;
+
+/// How detected artifacts are replaced
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ArtifactCorrection {
+    Delete,
+    Interpolate,
+    #[default]
+    CubicSpline,
+}
+
+impl ArtifactCorrection {
+    pub fn all() -> [ArtifactCorrection; 3] {
+        [ArtifactCorrection::Delete, ArtifactCorrection::Interpolate, ArtifactCorrection::CubicSpline]
+    }
+
+    /// Name used on the command line
+    pub fn cli_name(&self) -> &'static str {
+        match self {
+            ArtifactCorrection::Delete => "delete",
+            ArtifactCorrection::Interpolate => "interpolate",
+            ArtifactCorrection::CubicSpline => "spline",
+        }
+    }
+
+    pub fn from_cli_name(name: &str) -> Option<Self> {
+        Self::all().into_iter().find(|c| c.cli_name() == name)
+    }
+}
+
+impl fmt::Display for ArtifactCorrection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ArtifactCorrection::Delete => "Delete",
+            ArtifactCorrection::Interpolate => "Interpolate",
+            ArtifactCorrection::CubicSpline => "Cubic spline",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// An NN interval ready for HRV
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NnBeat {
+    /// Time of the beat in nanoseconds
+    pub time: u64,
+    /// Interval in ms
+    pub interval: f64,
+    /// Whether the interval replaces an artifact
+    pub corrected: bool,
+}
+
+/// Outcome of adding one interval
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FilterOutput {
+    /// Whether the interval was detected as an artifact
+    pub artifact: bool,
+    /// NN beats completed by this interval, oldest first
+    pub beats: Vec<NnBeat>,
+}
+
+/// Beat-by-beat artifact detection and correction
+#[derive(Debug, Clone)]
+pub struct ArtifactFilter {
+    correction: ArtifactCorrection,
+    /// Latest raw intervals for the local median
+    recent: VecDeque<f64>,
+    /// Latest normal beats as (time, interval), the interpolation knots
+    normal: VecDeque<(u64, f64)>,
+    /// Artifact times waiting for the next normal beat
+    pending: Vec<u64>,
+}
+
+impl ArtifactFilter {
+    pub fn new(correction: ArtifactCorrection) -> Self {
+        Self {
+            correction,
+            recent: VecDeque::with_capacity(REFERENCE_BEATS),
+            normal: VecDeque::with_capacity(SPLINE_KNOTS_BEFORE),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Correction applied from the next artifact on
+    pub fn set_correction(&mut self, correction: ArtifactCorrection) {
+        self.correction = correction;
+        if correction == ArtifactCorrection::Delete {
+            self.pending.clear();
+        }
+    }
+
+    /// Whether an interval is an artifact given the intervals before it
+    fn is_artifact(&self, interval: f64) -> bool {
+        if !(MIN_INTERVAL_MS..=MAX_INTERVAL_MS).contains(&interval) {
+            return true;
+        }
+        if self.recent.len() < MIN_REFERENCE_BEATS {
+            return false;
+        }
+        let mut sorted: Vec<f64> = self.recent.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        let median = (sorted[(sorted.len() - 1) / 2] + sorted[sorted.len() / 2]) / 2.0;
+        (interval - median).abs() > ARTIFACT_THRESHOLD * median
+    }
+
+    /// Add the interval of the beat at `time` (nanoseconds) in ms
+    pub fn push(&mut self, time: u64, interval: f64) -> FilterOutput {
+        let artifact = self.is_artifact(interval);
+        if self.recent.len() == REFERENCE_BEATS {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(interval);
+
+        let mut beats = Vec::new();
+        if artifact {
+            if self.correction != ArtifactCorrection::Delete {
+                self.pending.push(time);
+            }
+            return FilterOutput { artifact, beats };
+        }
+
+        let pending = std::mem::take(&mut self.pending);
+        if !pending.is_empty() {
+            let mut knots: Vec<(f64, f64)> = self.normal.iter().map(|&(t, v)| (t as f64, v)).collect();
+            knots.push((time as f64, interval));
+            beats.extend(pending.into_iter().map(|t| NnBeat {
+                time: t,
+                interval: match self.correction {
+                    ArtifactCorrection::CubicSpline if knots.len() > 2 => natural_spline(&knots, t as f64),
+                    _ => linear(&knots, t as f64),
+                },
+                corrected: true,
+            }));
+        }
+
+        beats.push(NnBeat { time, interval, corrected: false });
+        if self.normal.len() == SPLINE_KNOTS_BEFORE {
+            self.normal.pop_front();
+        }
+        self.normal.push_back((time, interval));
+        FilterOutput { artifact, beats }
+    }
+}
+
+/// Straight line between the last two knots, or the only knot's value
+fn linear(knots: &[(f64, f64)], x: f64) -> f64 {
+    match knots {
+        [.., (x0, y0), (x1, y1)] if x1 > x0 => y0 + (y1 - y0) * (x - x0) / (x1 - x0),
+        _ => knots[knots.len() - 1].1,
+    }
+}
+
+/// Natural cubic spline through knots sorted by x, evaluated at `x`
+fn natural_spline(knots: &[(f64, f64)], x: f64) -> f64 {
+    let n = knots.len();
+    let h: Vec<f64> = knots.windows(2).map(|w| w[1].0 - w[0].0).collect();
+    if h.iter().any(|&step| step <= 0.0) {
+        return linear(knots, x);
+    }
+
+    // Second derivatives m[1..n-1] from the tridiagonal system, m[0] = m[n-1] = 0
+    let mut m = vec![0.0; n];
+    let mut diagonal = vec![0.0; n];
+    let mut rhs = vec![0.0; n];
+    for i in 1..n - 1 {
+        diagonal[i] = 2.0 * (h[i - 1] + h[i]);
+        rhs[i] = 6.0 * ((knots[i + 1].1 - knots[i].1) / h[i] - (knots[i].1 - knots[i - 1].1) / h[i - 1]);
+    }
+    for i in 2..n - 1 {
+        let factor = h[i - 1] / diagonal[i - 1];
+        diagonal[i] -= factor * h[i - 1];
+        rhs[i] -= factor * rhs[i - 1];
+    }
+    for i in (1..n - 1).rev() {
+        m[i] = (rhs[i] - h[i] * m[i + 1]) / diagonal[i];
+    }
+
+    let i = knots.windows(2).position(|w| x <= w[1].0).unwrap_or(n - 2);
+    let (x0, y0) = knots[i];
+    let (x1, y1) = knots[i + 1];
+    let (a, b) = ((x1 - x) / h[i], (x - x0) / h[i]);
+    a * y0 + b * y1 + ((a.powi(3) - a) * m[i] + (b.powi(3) - b) * m[i + 1]) * h[i] * h[i] / 6.0
+}
+
+/// Run the filter over recorded intervals given as (time, interval in ms)
+///
+/// Returns the NN beats and the times of the artifacts found.
+pub fn correct_intervals(intervals: impl IntoIterator<Item = (u64, f64)>, correction: ArtifactCorrection) -> (Vec<NnBeat>, Vec<u64>) {
+    let mut filter = ArtifactFilter::new(correction);
+    let mut beats = Vec::new();
+    let mut artifacts = Vec::new();
+    for (time, interval) in intervals {
+        let output = filter.push(time, interval);
+        if output.artifact {
+            artifacts.push(time);
+        }
+        beats.extend(output.beats);
+    }
+    (beats, artifacts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    /// Steady 1000 ms beats with an ectopic short-long pair at beats 10 and 11
+    fn with_ectopic_beat() -> Vec<(u64, f64)> {
+        (0..20u64)
+            .map(|i| {
+                let interval = match i {
+                    10 => 600.0,
+                    11 => 1400.0,
+                    _ => 1000.0 + (i % 3) as f64 * 10.0,
+                };
+                (i * SECOND, interval)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_detects_ectopic_pair() {
+        let mut filter = ArtifactFilter::new(ArtifactCorrection::Delete);
+        let flagged: Vec<u64> = with_ectopic_beat()
+            .into_iter()
+            .filter(|&(time, interval)| filter.push(time, interval).artifact)
+            .map(|(time, _)| time / SECOND)
+            .collect();
+        assert_eq!(flagged, vec![10, 11]);
+
+        // Out of range intervals are artifacts even without history
+        assert!(ArtifactFilter::new(ArtifactCorrection::Delete).push(0, 3500.0).artifact);
+    }
+
+    #[test]
+    fn test_corrections() {
+        let (deleted, artifacts) = correct_intervals(with_ectopic_beat(), ArtifactCorrection::Delete);
+        assert_eq!(artifacts, vec![10 * SECOND, 11 * SECOND]);
+        assert_eq!(deleted.len(), 18);
+        assert!(deleted.iter().all(|b| !b.corrected));
+
+        let (interpolated, _) = correct_intervals(with_ectopic_beat(), ArtifactCorrection::Interpolate);
+        assert_eq!(interpolated.len(), 20);
+        let corrected: Vec<&NnBeat> = interpolated.iter().filter(|b| b.corrected).collect();
+        assert_eq!(corrected.len(), 2);
+        // Between beat 9 (1000 ms) and beat 12 (1000 ms)
+        assert_eq!(corrected[0].time, 10 * SECOND);
+        assert!((corrected[0].interval - 1000.0).abs() < 1e-9);
+
+        // Beats stay in time order
+        assert!(interpolated.windows(2).all(|w| w[0].time < w[1].time));
+    }
+
+    #[test]
+    fn test_spline_follows_trend() {
+        // A rising trend the spline continues through the artifact
+        let intervals: Vec<(u64, f64)> = (0..10u64)
+            .map(|i| (i * SECOND, if i == 7 { 400.0 } else { 800.0 + i as f64 * 20.0 }))
+            .collect();
+        let (beats, artifacts) = correct_intervals(intervals, ArtifactCorrection::CubicSpline);
+        assert_eq!(artifacts.len(), 1);
+        let corrected = beats.iter().find(|b| b.corrected).unwrap();
+        assert!((corrected.interval - 940.0).abs() < 1.0, "{}", corrected.interval);
+    }
+}
@@ -1,13 +1,33 @@
 use crate::app::{Message, ZenSignal};
-use crate::timeseries::TimeUnit;
-use crate::visualization::{range_from_time_interpolated, current_display_time, ChartWindow};
-use plotters::chart::ChartBuilder;
+use crate::device_profile::SensorStream;
+use crate::polar_data::Channels;
+use crate::recorder::ChannelId;
+use crate::session::DeviceSession;
+use crate::timeseries::{split_at_gaps, Gap, Point, PointSliceExt, TimeUnit};
+use crate::visualization::{auto_fit_range, range_from_time_interpolated, zoom_range, ChartKind, YScaleMode};
+use iced::keyboard;
+use iced::mouse::{self, Cursor, ScrollDelta};
+use iced::widget::canvas::{event, Event};
+use plotters::chart::{ChartBuilder, ChartContext};
+use plotters::coord::types::{RangedCoordf64, RangedCoordi32};
+use plotters::coord::cartesian::Cartesian2d;
+use plotters::element::{Circle, Rectangle, Text};
 use plotters::series::LineSeries;
-use plotters::style::{BLUE, CYAN, GREEN, MAGENTA, RED, RGBColor};
+use plotters::style::{Color, IntoFont, BLUE, CYAN, GREEN, MAGENTA, RED, RGBColor};
 use plotters_iced::{Chart, DrawingBackend};
+use std::cell::Cell;
+use std::ops::Range;
 
-// Chart display constants
-const CHART_TIME_WINDOW_SECONDS: f64 = 10.0;
+// Mouse wheel: pixels of touchpad scrolling per wheel line, and the part of
+// the window one line scrolls through
+const WHEEL_PIXELS_PER_LINE: f32 = 50.0;
+const WHEEL_SCROLL_FRACTION: f32 = 0.1;
+
+// Value axis zoom per wheel line while Ctrl (Cmd on macOS) is held
+const WHEEL_VALUE_ZOOM: f64 = 1.25;
+
+// Auto-fit never shows less than this part of a chart's fixed range
+const AUTO_FIT_MIN_SPAN_DIVISOR: i32 = 20;
 
 // Y-axis ranges for different chart types
 const ECG_MIN_UV: i32 = -2000;
@@ -25,45 +45,111 @@ const HRV_MAX_MS: i32 = 150;
 const ACC_MIN_MG: i32 = -8000;
 const ACC_MAX_MG: i32 = 8000;
 
-// Chart types
+const GYRO_MIN_MDPS: i32 = -5000;
+const GYRO_MAX_MDPS: i32 = 5000;
+
+const MAG_MIN_MG: i32 = -1000;
+const MAG_MAX_MG: i32 = 1000;
+
+// Stress index range; Baevsky's normal range ends at 150, elevated at 500
+const STRESS_MIN_INDEX: i32 = 0;
+const STRESS_MAX_INDEX: i32 = 1000;
+
+// Breathing rate range in breaths/min
+const RESPIRATION_MIN_BRPM: i32 = 0;
+const RESPIRATION_MAX_BRPM: i32 = 40;
+
+// Smallest Y span of the auto-ranged PPG chart
+const PPG_MIN_SPAN: i32 = 200;
+
+// Event markers, drawn on top of every chart
+const ANNOTATION_COLOR: RGBColor = RGBColor(230, 120, 0);
+
+/// State kept by each chart widget between frames
+#[derive(Default)]
+pub struct ChartState {
+    /// Modifier keys held, to tell value zoom from time zoom
+    modifiers: keyboard::Modifiers,
+    /// Value range last drawn: the starting point of auto-fit hysteresis and of zooming
+    y_range: Cell<Option<(i32, i32)>>,
+}
+
+// Chart types, each drawing the channels of one device
 pub struct EcgChartType<'a> {
     pub state: &'a ZenSignal,
+    pub channels: &'a Channels,
 }
 
 pub struct HrChartType<'a> {
     pub state: &'a ZenSignal,
+    pub channels: &'a Channels,
 }
 
 pub struct RrChartType<'a> {
     pub state: &'a ZenSignal,
+    pub channels: &'a Channels,
 }
 
 pub struct HrvChartType<'a> {
     pub state: &'a ZenSignal,
+    pub channels: &'a Channels,
+}
+
+/// Stress index trend, over a longer window than the signal charts
+pub struct StressChartType<'a> {
+    pub state: &'a ZenSignal,
+    pub session: &'a DeviceSession,
+}
+
+/// Breathing rate, over a longer window than the signal charts
+pub struct RespirationChartType<'a> {
+    pub state: &'a ZenSignal,
+    pub channels: &'a Channels,
 }
 
 pub struct AccChartType<'a> {
     pub state: &'a ZenSignal,
+    pub channels: &'a Channels,
+}
+
+pub struct PpgChartType<'a> {
+    pub state: &'a ZenSignal,
+    pub channels: &'a Channels,
+}
+
+pub struct GyroChartType<'a> {
+    pub state: &'a ZenSignal,
+    pub channels: &'a Channels,
+}
+
+pub struct MagChartType<'a> {
+    pub state: &'a ZenSignal,
+    pub channels: &'a Channels,
 }
 
 // ECG Chart
 impl<'a> Chart<Message> for EcgChartType<'a> {
-    type State = ();
-
-    fn build_chart<DB: DrawingBackend>(&self, _state: &Self::State, mut builder: ChartBuilder<DB>) {
-        let ecg_series = &self.state.channels.ecg;
-        // Show last 10 seconds of ECG data
-        let window = ChartWindow::TenSeconds.as_nanos();
-        let smooth_streaming = self.state.config.smooth_data_streaming;
-        let display_time = current_display_time(smooth_streaming);
-        let points = ecg_series.range_from_time(display_time, window);
+    type State = ChartState;
+
+    fn build_chart<DB: DrawingBackend>(&self, chart_state: &Self::State, mut builder: ChartBuilder<DB>) {
+        let (channel, caption) = if show_filtered(self.state, self.channels, SensorStream::Ecg) {
+            (ChannelId::EcgFiltered, "ECG Signal (filtered)")
+        } else {
+            (ChannelId::Ecg, "ECG Signal")
+        };
+        let ecg_series = self.state.chart_series(channel, self.channels);
+        let window = self.state.config.chart_windows.get(ChartKind::Ecg);
+        let display_time = self.state.chart_display_time();
+        let points = ecg_series.range_from_time(display_time, window.as_nanos());
+        let envelope = ecg_series.envelope_from_time(display_time, window.as_nanos());
+        let y_range = value_range(self.state, ChartKind::Ecg, chart_state, ECG_MIN_UV..ECG_MAX_UV, &[points, envelope.as_slice()]);
         
         let mut chart = builder
             .margin(15)
-            .caption("ECG Signal", ("sans-serif", 20))
+            .caption(caption, ("sans-serif", 20))
             .x_label_area_size(30)
             .y_label_area_size(40)
-            .build_cartesian_2d(-CHART_TIME_WINDOW_SECONDS..0.0, ECG_MIN_UV..ECG_MAX_UV)
+            .build_cartesian_2d(-window.as_seconds()..0.0, y_range.clone())
             .expect("Failed to build chart");
 
         chart.plotting_area().fill(&RGBColor(245, 245, 240)).expect("Failed to fill background");
@@ -74,38 +160,41 @@ impl<'a> Chart<Message> for EcgChartType<'a> {
             .axis_style(RGBColor(60, 60, 60))
             .draw().expect("Failed to draw mesh");
 
-        chart
-            .draw_series(LineSeries::new(
-                points.iter().map(|p| {
-                    let time_sec = (p.time as f64 - display_time as f64) / TimeUnit::Seconds.nanos_per_unit();
-                    (time_sec, p.value)
-                }),
-                &RED,
-            ))
-            .expect("Failed to draw series");
+        let gaps = self.channels.gaps_for(SensorStream::Ecg);
+        draw_line(&mut chart, &envelope, display_time, &gaps, &RED);
+        draw_line(&mut chart, points, display_time, &gaps, &RED);
+
+        // R peaks found by the QRS detector
+        let r_peaks = self.state.chart_series(ChannelId::RPeak, self.channels).range_from_time(display_time, window.as_nanos());
+        draw_markers(&mut chart, r_peaks, display_time, y_range.clone(), &BLUE);
+        draw_annotations(&mut chart, self.state, display_time, window.as_nanos(), y_range);
+    }
+
+    fn update(&self, chart_state: &mut Self::State, event: Event, bounds: iced::Rectangle, cursor: Cursor) -> (event::Status, Option<Message>) {
+        handle_input(self.state, ChartKind::Ecg, chart_state, event, bounds, cursor)
     }
 }
 
 // HR Chart
 impl<'a> Chart<Message> for HrChartType<'a> {
-    type State = ();
+    type State = ChartState;
 
-    fn build_chart<DB: DrawingBackend>(&self, _state: &Self::State, mut builder: ChartBuilder<DB>) {
-        let hr_series = &self.state.channels.hr;
-        // Show last 10 seconds of HR data
-        let window = ChartWindow::TenSeconds.as_nanos();
+    fn build_chart<DB: DrawingBackend>(&self, chart_state: &Self::State, mut builder: ChartBuilder<DB>) {
+        let hr_series = self.state.chart_series(ChannelId::Hr, self.channels);
+        let window = self.state.config.chart_windows.get(ChartKind::Hr);
         let smooth_streaming = self.state.config.smooth_data_streaming;
-        let display_time = current_display_time(smooth_streaming);
+        let display_time = self.state.chart_display_time();
         
         // Always use interpolation, but only interpolate at the end when smooth streaming is enabled
-        let points = range_from_time_interpolated(hr_series, display_time, window, 100_000_000, smooth_streaming);
+        let points = range_from_time_interpolated(hr_series, display_time, window.as_nanos(), 100_000_000, smooth_streaming);
+        let y_range = value_range(self.state, ChartKind::Hr, chart_state, HR_MIN_BPM..HR_MAX_BPM, &[points.as_slice()]);
 
         let mut chart = builder
             .margin(15)
             .caption("Heart Rate", ("sans-serif", 20))
             .x_label_area_size(30)
             .y_label_area_size(40)
-            .build_cartesian_2d(-CHART_TIME_WINDOW_SECONDS..0.0, HR_MIN_BPM..HR_MAX_BPM)
+            .build_cartesian_2d(-window.as_seconds()..0.0, y_range.clone())
             .expect("Failed to build chart");
 
         chart.plotting_area().fill(&RGBColor(245, 245, 240)).expect("Failed to fill background");
@@ -116,80 +205,100 @@ impl<'a> Chart<Message> for HrChartType<'a> {
             .axis_style(RGBColor(60, 60, 60))
             .draw().expect("Failed to draw mesh");
 
-        chart
-            .draw_series(LineSeries::new(
-                points.iter().map(|p| {
-                    let time_sec = (p.time as f64 - display_time as f64) / TimeUnit::Seconds.nanos_per_unit();
-                    (time_sec, p.value)
-                }),
-                &RED,
-            ))
-            .expect("Failed to draw series");
+        draw_line(&mut chart, &points, display_time, &self.channels.gaps, &RED);
+        draw_annotations(&mut chart, self.state, display_time, window.as_nanos(), y_range);
+    }
+
+    fn update(&self, chart_state: &mut Self::State, event: Event, bounds: iced::Rectangle, cursor: Cursor) -> (event::Status, Option<Message>) {
+        handle_input(self.state, ChartKind::Hr, chart_state, event, bounds, cursor)
     }
 }
 
 // RR Chart
 impl<'a> Chart<Message> for RrChartType<'a> {
-    type State = ();
+    type State = ChartState;
 
-    fn build_chart<DB: DrawingBackend>(&self, _state: &Self::State, mut builder: ChartBuilder<DB>) {
-        let rr_series = &self.state.channels.rr;
-        // Show last 10 seconds of RR data
-        let window = ChartWindow::TenSeconds.as_nanos();
+    fn build_chart<DB: DrawingBackend>(&self, chart_state: &Self::State, mut builder: ChartBuilder<DB>) {
+        // Optical sensors measure peak-to-peak intervals instead of RR
+        let is_ppi = self.channels.profile.supports(SensorStream::Ppi);
+        let rr_series = self.state.chart_series(if is_ppi { ChannelId::Ppi } else { ChannelId::Rr }, self.channels);
+        let window = self.state.config.chart_windows.get(ChartKind::Rr);
         let smooth_streaming = self.state.config.smooth_data_streaming;
-        let display_time = current_display_time(smooth_streaming);
+        let display_time = self.state.chart_display_time();
         
         // Always use interpolation, but only interpolate at the end when smooth streaming is enabled
-        let points = range_from_time_interpolated(rr_series, display_time, window, 100_000_000, smooth_streaming);
+        let points = range_from_time_interpolated(rr_series, display_time, window.as_nanos(), 100_000_000, smooth_streaming);
+
+        // RR from the detected R peaks, to compare with the device's
+        let has_ecg = self.channels.profile.supports(SensorStream::Ecg);
+        let ecg_rr = if has_ecg {
+            self.state.chart_series(ChannelId::EcgRr, self.channels).range_from_time(display_time, window.as_nanos())
+        } else {
+            &[]
+        };
+        let y_range = value_range(self.state, ChartKind::Rr, chart_state, RR_MIN_MS..RR_MAX_MS, &[points.as_slice(), ecg_rr]);
+
+        let (caption, y_desc) = if is_ppi {
+            ("PP Interval", "PPI (ms)")
+        } else if has_ecg {
+            ("RR Interval (blue: device, green: ECG)", "RR (ms)")
+        } else {
+            ("RR Interval", "RR (ms)")
+        };
 
         let mut chart = builder
             .margin(15)
-            .caption("RR Interval", ("sans-serif", 20))
+            .caption(caption, ("sans-serif", 20))
             .x_label_area_size(30)
             .y_label_area_size(40)
-            .build_cartesian_2d(-CHART_TIME_WINDOW_SECONDS..0.0, RR_MIN_MS..RR_MAX_MS)
+            .build_cartesian_2d(-window.as_seconds()..0.0, y_range.clone())
             .expect("Failed to build chart");
 
         chart.plotting_area().fill(&RGBColor(245, 245, 240)).expect("Failed to fill background");
 
         chart.configure_mesh()
             .x_desc("Time (s)")
-            .y_desc("RR (ms)")
+            .y_desc(y_desc)
             .axis_style(RGBColor(60, 60, 60))
             .draw().expect("Failed to draw mesh");
 
-        chart
-            .draw_series(LineSeries::new(
-                points.iter().map(|p| {
-                    let time_sec = (p.time as f64 - display_time as f64) / TimeUnit::Seconds.nanos_per_unit();
-                    (time_sec, p.value)
-                }),
-                &BLUE,
-            ))
-            .expect("Failed to draw series");
+        draw_line(&mut chart, &points, display_time, &self.channels.gaps, &BLUE);
+
+        if has_ecg {
+            draw_line(&mut chart, ecg_rr, display_time, &self.channels.gaps_for(SensorStream::Ecg), &GREEN);
+        }
+
+        // Intervals the artifact filter replaced or dropped
+        let artifacts = self.state.chart_series(ChannelId::RrArtifact, self.channels).range_from_time(display_time, window.as_nanos());
+        draw_markers(&mut chart, artifacts, display_time, y_range.clone(), &RED);
+        draw_annotations(&mut chart, self.state, display_time, window.as_nanos(), y_range);
+    }
+
+    fn update(&self, chart_state: &mut Self::State, event: Event, bounds: iced::Rectangle, cursor: Cursor) -> (event::Status, Option<Message>) {
+        handle_input(self.state, ChartKind::Rr, chart_state, event, bounds, cursor)
     }
 }
 
 // HRV Chart
 impl<'a> Chart<Message> for HrvChartType<'a> {
-    type State = ();
+    type State = ChartState;
 
-    fn build_chart<DB: DrawingBackend>(&self, _state: &Self::State, mut builder: ChartBuilder<DB>) {
-        let hrv_series = &self.state.channels.hrv;
-        // Show last 10 seconds of HRV (RMSSD) data
-        let window = ChartWindow::TenSeconds.as_nanos();
+    fn build_chart<DB: DrawingBackend>(&self, chart_state: &Self::State, mut builder: ChartBuilder<DB>) {
+        let hrv_series = self.state.chart_series(ChannelId::Hrv, self.channels);
+        let window = self.state.config.chart_windows.get(ChartKind::Hrv);
         let smooth_streaming = self.state.config.smooth_data_streaming;
-        let display_time = current_display_time(smooth_streaming);
+        let display_time = self.state.chart_display_time();
         
         // Always use interpolation, but only interpolate at the end when smooth streaming is enabled
-        let points = range_from_time_interpolated(hrv_series, display_time, window, 100_000_000, smooth_streaming);
+        let points = range_from_time_interpolated(hrv_series, display_time, window.as_nanos(), 100_000_000, smooth_streaming);
+        let y_range = value_range(self.state, ChartKind::Hrv, chart_state, HRV_MIN_MS..HRV_MAX_MS, &[points.as_slice()]);
 
         let mut chart = builder
             .margin(15)
             .caption("HRV (RMSSD)", ("sans-serif", 20))
             .x_label_area_size(30)
             .y_label_area_size(40)
-            .build_cartesian_2d(-CHART_TIME_WINDOW_SECONDS..0.0, HRV_MIN_MS..HRV_MAX_MS)
+            .build_cartesian_2d(-window.as_seconds()..0.0, y_range.clone())
             .expect("Failed to build chart");
 
         chart.plotting_area().fill(&RGBColor(245, 245, 240)).expect("Failed to fill background");
@@ -200,80 +309,419 @@ impl<'a> Chart<Message> for HrvChartType<'a> {
             .axis_style(RGBColor(60, 60, 60))
             .draw().expect("Failed to draw mesh");
 
-        chart
-            .draw_series(LineSeries::new(
-                points.iter().map(|p| {
-                    let time_sec = (p.time as f64 - display_time as f64) / TimeUnit::Seconds.nanos_per_unit();
-                    (time_sec, p.value)
-                }),
-                &GREEN,
-            ))
-            .expect("Failed to draw series");
+        draw_line(&mut chart, &points, display_time, &self.channels.gaps, &GREEN);
+        draw_annotations(&mut chart, self.state, display_time, window.as_nanos(), y_range);
+    }
+
+    fn update(&self, chart_state: &mut Self::State, event: Event, bounds: iced::Rectangle, cursor: Cursor) -> (event::Status, Option<Message>) {
+        handle_input(self.state, ChartKind::Hrv, chart_state, event, bounds, cursor)
     }
 }
 
-// Acceleration Chart
-impl<'a> Chart<Message> for AccChartType<'a> {
-    type State = ();
+// Stress Index Chart
+impl<'a> Chart<Message> for StressChartType<'a> {
+    type State = ChartState;
 
-    fn build_chart<DB: DrawingBackend>(&self, _state: &Self::State, mut builder: ChartBuilder<DB>) {
-        let acc_x_series = &self.state.channels.acc_x;
-        // Show last 10 seconds of accelerometer data
-        let window = ChartWindow::TenSeconds.as_nanos();
-        let smooth_streaming = self.state.config.smooth_data_streaming;
-        let display_time = current_display_time(smooth_streaming);
-        let x_points = acc_x_series.range_from_time(display_time, window);
+    fn build_chart<DB: DrawingBackend>(&self, chart_state: &Self::State, mut builder: ChartBuilder<DB>) {
+        // The stress trend is not recorded, so it only reaches back as far as the session
+        let window = self.state.config.chart_windows.get(ChartKind::Stress);
+        let display_time = self.state.chart_display_time();
+        let points = self.session.stress_trend.range_from_time(display_time, window.as_nanos());
+        let y_range = value_range(self.state, ChartKind::Stress, chart_state, STRESS_MIN_INDEX..STRESS_MAX_INDEX, &[points]);
 
-        let acc_y_series = &self.state.channels.acc_y;
-        let y_points = acc_y_series.range_from_time(display_time, window);
+        let mut chart = builder
+            .margin(15)
+            .caption("Stress Index", ("sans-serif", 20))
+            .x_label_area_size(30)
+            .y_label_area_size(40)
+            .build_cartesian_2d(-window.as_seconds()..0.0, y_range.clone())
+            .expect("Failed to build chart");
 
-        let acc_z_series = &self.state.channels.acc_z;
-        let z_points = acc_z_series.range_from_time(display_time, window);
+        chart.plotting_area().fill(&RGBColor(245, 245, 240)).expect("Failed to fill background");
+
+        chart.configure_mesh()
+            .x_desc("Time (s)")
+            .y_desc("Stress index")
+            .axis_style(RGBColor(60, 60, 60))
+            .draw().expect("Failed to draw mesh");
+
+        draw_line(&mut chart, points, display_time, &self.session.channels.gaps, &MAGENTA);
+        draw_annotations(&mut chart, self.state, display_time, window.as_nanos(), y_range);
+    }
+
+    fn update(&self, chart_state: &mut Self::State, event: Event, bounds: iced::Rectangle, cursor: Cursor) -> (event::Status, Option<Message>) {
+        handle_input(self.state, ChartKind::Stress, chart_state, event, bounds, cursor)
+    }
+}
+
+// Respiration Chart
+impl<'a> Chart<Message> for RespirationChartType<'a> {
+    type State = ChartState;
+
+    fn build_chart<DB: DrawingBackend>(&self, chart_state: &Self::State, mut builder: ChartBuilder<DB>) {
+        let window = self.state.config.chart_windows.get(ChartKind::Respiration);
+        let display_time = self.state.chart_display_time();
+        let respiration = self.state.chart_series(ChannelId::Respiration, self.channels);
+        let points = respiration.range_from_time(display_time, window.as_nanos());
+        let y_range = value_range(
+            self.state,
+            ChartKind::Respiration,
+            chart_state,
+            RESPIRATION_MIN_BRPM..RESPIRATION_MAX_BRPM,
+            &[points],
+        );
 
         let mut chart = builder
             .margin(15)
-            .caption("Acceleration", ("sans-serif", 20))
+            .caption("Respiration", ("sans-serif", 20))
             .x_label_area_size(30)
             .y_label_area_size(40)
-            .build_cartesian_2d(-CHART_TIME_WINDOW_SECONDS..0.0, ACC_MIN_MG..ACC_MAX_MG)
+            .build_cartesian_2d(-window.as_seconds()..0.0, y_range.clone())
             .expect("Failed to build chart");
 
         chart.plotting_area().fill(&RGBColor(245, 245, 240)).expect("Failed to fill background");
 
         chart.configure_mesh()
             .x_desc("Time (s)")
-            .y_desc("Acc (mg)")
+            .y_desc("Breaths/min")
             .axis_style(RGBColor(60, 60, 60))
             .draw().expect("Failed to draw mesh");
 
-        chart
-            .draw_series(LineSeries::new(
-                x_points.iter().map(|p| {
-                    let time_sec = (p.time as f64 - display_time as f64) / TimeUnit::Seconds.nanos_per_unit();
-                    (time_sec, p.value)
-                }),
-                &GREEN,
-            ))
-            .expect("Failed to draw X series");
+        draw_line(&mut chart, points, display_time, &self.channels.gaps, &CYAN);
+        draw_annotations(&mut chart, self.state, display_time, window.as_nanos(), y_range);
+    }
 
-        chart
-            .draw_series(LineSeries::new(
-                y_points.iter().map(|p| {
-                    let time_sec = (p.time as f64 - display_time as f64) / TimeUnit::Seconds.nanos_per_unit();
-                    (time_sec, p.value)
-                }),
-                &MAGENTA,
-            ))
-            .expect("Failed to draw Y series");
+    fn update(&self, chart_state: &mut Self::State, event: Event, bounds: iced::Rectangle, cursor: Cursor) -> (event::Status, Option<Message>) {
+        handle_input(self.state, ChartKind::Respiration, chart_state, event, bounds, cursor)
+    }
+}
+
+// PPG Chart
+impl<'a> Chart<Message> for PpgChartType<'a> {
+    type State = ChartState;
+
+    fn build_chart<DB: DrawingBackend>(&self, chart_state: &Self::State, mut builder: ChartBuilder<DB>) {
+        let (channel, caption) = if show_filtered(self.state, self.channels, SensorStream::Ppg) {
+            (ChannelId::PpgFiltered, "PPG Signal (filtered)")
+        } else {
+            (ChannelId::Ppg, "PPG Signal")
+        };
+        let ppg_series = self.state.chart_series(channel, self.channels);
+        let window = self.state.config.chart_windows.get(ChartKind::Ppg);
+        let display_time = self.state.chart_display_time();
+        let points = ppg_series.range_from_time(display_time, window.as_nanos());
+        let envelope = ppg_series.envelope_from_time(display_time, window.as_nanos());
+
+        // Raw PPG has a large, device-dependent offset, so even its fixed range
+        // follows the visible data, without hysteresis
+        let visible = [points, envelope.as_slice()];
+        let (min, max) = data_range(&visible).unwrap_or((0, 0));
+        let margin = ((max - min) / 10).max(PPG_MIN_SPAN / 2);
+        let y_range = value_range(self.state, ChartKind::Ppg, chart_state, (min - margin)..(max + margin), &visible);
+
+        let mut chart = builder
+            .margin(15)
+            .caption(caption, ("sans-serif", 20))
+            .x_label_area_size(30)
+            .y_label_area_size(60)
+            .build_cartesian_2d(-window.as_seconds()..0.0, y_range.clone())
+            .expect("Failed to build chart");
+
+        chart.plotting_area().fill(&RGBColor(245, 245, 240)).expect("Failed to fill background");
+
+        chart.configure_mesh()
+            .x_desc("Time (s)")
+            .y_desc("PPG")
+            .axis_style(RGBColor(60, 60, 60))
+            .draw().expect("Failed to draw mesh");
+
+        let gaps = self.channels.gaps_for(SensorStream::Ppg);
+        draw_line(&mut chart, &envelope, display_time, &gaps, &RED);
+        draw_line(&mut chart, points, display_time, &gaps, &RED);
+        draw_annotations(&mut chart, self.state, display_time, window.as_nanos(), y_range);
+    }
+
+    fn update(&self, chart_state: &mut Self::State, event: Event, bounds: iced::Rectangle, cursor: Cursor) -> (event::Status, Option<Message>) {
+        handle_input(self.state, ChartKind::Ppg, chart_state, event, bounds, cursor)
+    }
+}
+
+// Acceleration Chart
+impl<'a> Chart<Message> for AccChartType<'a> {
+    type State = ChartState;
+
+    fn build_chart<DB: DrawingBackend>(&self, chart_state: &Self::State, builder: ChartBuilder<DB>) {
+        build_axes_chart(
+            self.state,
+            chart_state,
+            builder,
+            ChartKind::Acc,
+            "Acceleration",
+            "Acc (mg)",
+            ACC_MIN_MG..ACC_MAX_MG,
+            self.channels,
+            [ChannelId::AccX, ChannelId::AccY, ChannelId::AccZ],
+            &self.channels.gaps_for(SensorStream::Acc),
+        );
+    }
+
+    fn update(&self, chart_state: &mut Self::State, event: Event, bounds: iced::Rectangle, cursor: Cursor) -> (event::Status, Option<Message>) {
+        handle_input(self.state, ChartKind::Acc, chart_state, event, bounds, cursor)
+    }
+}
+
+// Gyroscope Chart
+impl<'a> Chart<Message> for GyroChartType<'a> {
+    type State = ChartState;
+
+    fn build_chart<DB: DrawingBackend>(&self, chart_state: &Self::State, builder: ChartBuilder<DB>) {
+        build_axes_chart(
+            self.state,
+            chart_state,
+            builder,
+            ChartKind::Gyro,
+            "Gyroscope",
+            "Rate (mdps)",
+            GYRO_MIN_MDPS..GYRO_MAX_MDPS,
+            self.channels,
+            [ChannelId::GyroX, ChannelId::GyroY, ChannelId::GyroZ],
+            &self.channels.gaps_for(SensorStream::Gyro),
+        );
+    }
 
+    fn update(&self, chart_state: &mut Self::State, event: Event, bounds: iced::Rectangle, cursor: Cursor) -> (event::Status, Option<Message>) {
+        handle_input(self.state, ChartKind::Gyro, chart_state, event, bounds, cursor)
+    }
+}
+
+// Magnetometer Chart
+impl<'a> Chart<Message> for MagChartType<'a> {
+    type State = ChartState;
+
+    fn build_chart<DB: DrawingBackend>(&self, chart_state: &Self::State, builder: ChartBuilder<DB>) {
+        build_axes_chart(
+            self.state,
+            chart_state,
+            builder,
+            ChartKind::Mag,
+            "Magnetometer",
+            "Field (mG)",
+            MAG_MIN_MG..MAG_MAX_MG,
+            self.channels,
+            [ChannelId::MagX, ChannelId::MagY, ChannelId::MagZ],
+            &self.channels.gaps_for(SensorStream::Mag),
+        );
+    }
+
+    fn update(&self, chart_state: &mut Self::State, event: Event, bounds: iced::Rectangle, cursor: Cursor) -> (event::Status, Option<Message>) {
+        handle_input(self.state, ChartKind::Mag, chart_state, event, bounds, cursor)
+    }
+}
+
+/// Draw the window of a 3-axis stream (X green, Y magenta, Z cyan)
+#[allow(clippy::too_many_arguments)]
+fn build_axes_chart<DB: DrawingBackend>(
+    state: &ZenSignal,
+    chart_state: &ChartState,
+    mut builder: ChartBuilder<DB>,
+    kind: ChartKind,
+    caption: &str,
+    y_desc: &str,
+    fixed_range: Range<i32>,
+    channels: &Channels,
+    axes: [ChannelId; 3],
+    gaps: &[Gap],
+) {
+    let window = state.config.chart_windows.get(kind);
+    let display_time = state.chart_display_time();
+    let series = axes.map(|channel| state.chart_series(channel, channels));
+    let points = series.map(|series| series.range_from_time(display_time, window.as_nanos()));
+    let envelopes = series.map(|series| series.envelope_from_time(display_time, window.as_nanos()));
+    let all_points: Vec<&[Point]> = points.iter().copied().chain(envelopes.iter().map(Vec::as_slice)).collect();
+    let y_range = value_range(state, kind, chart_state, fixed_range, &all_points);
+
+    let mut chart = builder
+        .margin(15)
+        .caption(caption, ("sans-serif", 20))
+        .x_label_area_size(30)
+        .y_label_area_size(40)
+        .build_cartesian_2d(-window.as_seconds()..0.0, y_range.clone())
+        .expect("Failed to build chart");
+
+    chart.plotting_area().fill(&RGBColor(245, 245, 240)).expect("Failed to fill background");
+
+    chart.configure_mesh()
+        .x_desc("Time (s)")
+        .y_desc(y_desc)
+        .axis_style(RGBColor(60, 60, 60))
+        .draw().expect("Failed to draw mesh");
+
+    for ((points, envelope), color) in points.into_iter().zip(&envelopes).zip([&GREEN, &MAGENTA, &CYAN]) {
+        draw_line(&mut chart, envelope, display_time, gaps, color);
+        draw_line(&mut chart, points, display_time, gaps, color);
+    }
+    draw_annotations(&mut chart, state, display_time, window.as_nanos(), y_range);
+}
+
+/// Value range of a chart this frame, by its Y scale mode: the chart's fixed
+/// range, a fit of the visible `series`, or the range the user zoomed to
+fn value_range(
+    state: &ZenSignal,
+    kind: ChartKind,
+    chart_state: &ChartState,
+    fixed: Range<i32>,
+    series: &[&[Point]],
+) -> Range<i32> {
+    let scale = state.config.chart_scales.get(kind);
+    let (lo, hi) = match scale.mode {
+        YScaleMode::Fixed => (fixed.start, fixed.end),
+        YScaleMode::Auto => {
+            let min_span = ((fixed.end - fixed.start) / AUTO_FIT_MIN_SPAN_DIVISOR).max(1);
+            auto_fit_range(chart_state.y_range.get(), data_range(series), min_span).unwrap_or((fixed.start, fixed.end))
+        }
+        YScaleMode::Manual => scale.manual_range.unwrap_or((fixed.start, fixed.end)),
+    };
+    chart_state.y_range.set(Some((lo, hi)));
+    lo..hi
+}
+
+/// Lowest and highest value across several point slices
+fn data_range(series: &[&[Point]]) -> Option<(i32, i32)> {
+    series
+        .iter()
+        .filter_map(|points| points.min_max_value())
+        .reduce(|(lo, hi), (min, max)| (lo.min(min), hi.max(max)))
+}
+
+/// Mouse wheel over a chart: turning it zooms the chart's window, tilting it
+/// (or shift-scrolling on most systems) scrolls all charts through time, and
+/// turning it with Ctrl (Cmd on macOS) held zooms the value axis
+fn handle_input(
+    state: &ZenSignal,
+    kind: ChartKind,
+    chart_state: &mut ChartState,
+    event: Event,
+    bounds: iced::Rectangle,
+    cursor: Cursor,
+) -> (event::Status, Option<Message>) {
+    let delta = match event {
+        Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+            chart_state.modifiers = modifiers;
+            return (event::Status::Ignored, None);
+        }
+        Event::Mouse(mouse::Event::WheelScrolled { delta }) => delta,
+        _ => return (event::Status::Ignored, None),
+    };
+    if !cursor.is_over(bounds) {
+        return (event::Status::Ignored, None);
+    }
+
+    let (x, y) = match delta {
+        ScrollDelta::Lines { x, y } => (x, y),
+        ScrollDelta::Pixels { x, y } => (x / WHEEL_PIXELS_PER_LINE, y / WHEEL_PIXELS_PER_LINE),
+    };
+    if chart_state.modifiers.command() {
+        let Some(range) = chart_state.y_range.get().filter(|_| y != 0.0) else {
+            return (event::Status::Ignored, None);
+        };
+        let factor = if y > 0.0 { 1.0 / WHEEL_VALUE_ZOOM } else { WHEEL_VALUE_ZOOM };
+        let (lo, hi) = zoom_range(range, factor);
+        return (event::Status::Captured, Some(Message::SetChartValueRange(kind, lo, hi)));
+    }
+
+    let window = state.config.chart_windows.get(kind);
+    let message = if x.abs() > y.abs() {
+        // Scrolling left goes back in time
+        let step = window.as_nanos() as f32 * WHEEL_SCROLL_FRACTION * x;
+        Message::ScrollCharts(step as i64)
+    } else if y > 0.0 {
+        Message::SelectChartWindow(kind, window.zoomed_in())
+    } else if y < 0.0 {
+        Message::SelectChartWindow(kind, window.zoomed_out())
+    } else {
+        return (event::Status::Ignored, None);
+    };
+    (event::Status::Captured, Some(message))
+}
+
+/// Draw points as a line relative to `display_time`, leaving gaps empty
+fn draw_line<DB: DrawingBackend>(
+    chart: &mut ChartContext<'_, DB, Cartesian2d<RangedCoordf64, RangedCoordi32>>,
+    points: &[Point],
+    display_time: u64,
+    gaps: &[Gap],
+    color: &RGBColor,
+) {
+    for run in split_at_gaps(points, gaps) {
         chart
             .draw_series(LineSeries::new(
-                z_points.iter().map(|p| {
+                run.iter().map(|p| {
                     let time_sec = (p.time as f64 - display_time as f64) / TimeUnit::Seconds.nanos_per_unit();
                     (time_sec, p.value)
                 }),
-                &CYAN,
+                color,
             ))
-            .expect("Failed to draw Z series");
+            .expect("Failed to draw series");
+    }
+}
+
+/// Whether to chart the filtered version of a stream instead of the raw one
+pub fn show_filtered(state: &ZenSignal, channels: &Channels, stream: SensorStream) -> bool {
+    state.config.show_filtered_signals && channels.is_filtered(stream)
+}
+
+/// Mark points with circles, clamped into the visible value range
+fn draw_markers<DB: DrawingBackend>(
+    chart: &mut ChartContext<'_, DB, Cartesian2d<RangedCoordf64, RangedCoordi32>>,
+    points: &[Point],
+    display_time: u64,
+    range: Range<i32>,
+    color: &RGBColor,
+) {
+    chart
+        .draw_series(points.iter().map(|p| {
+            let time_sec = (p.time as f64 - display_time as f64) / TimeUnit::Seconds.nanos_per_unit();
+            Circle::new((time_sec, p.value.clamp(range.start, range.end)), 4, color.filled())
+        }))
+        .expect("Failed to draw markers");
+}
+
+/// Draw the annotations within the window as labelled vertical lines, shading spans
+fn draw_annotations<DB: DrawingBackend>(
+    chart: &mut ChartContext<'_, DB, Cartesian2d<RangedCoordf64, RangedCoordi32>>,
+    state: &ZenSignal,
+    display_time: u64,
+    window: u64,
+    range: Range<i32>,
+) {
+    let window_start = display_time.saturating_sub(window);
+    let seconds = |time: u64| (time as f64 - display_time as f64) / TimeUnit::Seconds.nanos_per_unit();
+
+    for (span, annotation) in state.chart_annotations() {
+        if span.end < window_start || span.start > display_time {
+            continue;
+        }
+        if span.end > span.start {
+            let (left, right) = (seconds(span.start.max(window_start)), seconds(span.end.min(display_time)));
+            chart
+                .draw_series(std::iter::once(Rectangle::new(
+                    [(left, range.start), (right, range.end)],
+                    ANNOTATION_COLOR.mix(0.15).filled(),
+                )))
+                .expect("Failed to draw annotation");
+        }
+        if span.start >= window_start {
+            let x = seconds(span.start);
+            chart
+                .draw_series(LineSeries::new([(x, range.start), (x, range.end)], ANNOTATION_COLOR.stroke_width(2)))
+                .expect("Failed to draw annotation");
+            chart
+                .draw_series(std::iter::once(Text::new(
+                    annotation.label.as_str(),
+                    (x, range.end),
+                    ("sans-serif", 12).into_font().color(&ANNOTATION_COLOR),
+                )))
+                .expect("Failed to draw annotation");
+        }
     }
 }
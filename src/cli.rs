@@ -0,0 +1,401 @@
+//! # Command-Line Interface Module
+//!
+//! Subcommands that run without opening the window, for scripts and data
+//! pipelines. `main` hands the arguments over before starting the UI; without
+//! a known subcommand the app starts as usual.
+//!
+//! ## Commands
+//! - `record [--device <id>]... [--autoconnect] [--simulated] [--duration <time>]
+//!   [--out <dir>] [--subject <id>] [--description <text>] [--status-interval <time>]
+//!   [--scan-timeout <time>]`: Record without the window (see `headless`).
+//!   Times are seconds or a number with `s`, `m` or `h`, e.g. `8h`. Output
+//!   directory, subject and description default to the configured ones.
+//! - `export <recording.h5> [--format <format>] [--out <dir>]`: Convert a
+//!   recording to `csv` (default), `csv-merged`, `edf` or `parquet`. Files go
+//!   to `exports/` next to the recording unless `--out` is given.
+//! - `info <recording.h5>`: Metadata, devices and channels with point counts
+//!   and time ranges
+//! - `validate <recording.h5> [--max-gap <time>]`: Check timestamps, dataset
+//!   lengths and gaps (see `inspect`). Gaps longer than `--max-gap` fail.
+//! - `summary <recording.h5>`: Heart rate and RMSSD statistics
+//! - `hrv <recording.h5> [--window <time>] [--step <time>] [--correction <method>]`:
+//!   HRV metrics per window as CSV, after correcting artifacts with `delete`,
+//!   `interpolate` or `spline`. The window and correction default to the
+//!   configured ones, the step to the window length.
+//! - `help`: Print usage
+//!
+//! ## Windows Console
+//! Release builds use the Windows GUI subsystem and start without a console.
+//! Subcommands attach to the console of the shell that started them, so their
+//! output and Ctrl+C reach the terminal.
+//!
+//! ## Exit Codes
+//! - `0`: Success
+//! - `1`: The command failed, or `validate` found errors
+//! - `2`: Invalid arguments
+
+use crate::artifacts::ArtifactCorrection;
+use crate::config::Config;
+use crate::export::{self, ExportFormat};
+use crate::headless::{self, RecordOptions};
+use crate::inspect;
+use crate::recorder::RecordingInfo;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const EXIT_SUCCESS: i32 = 0;
+const EXIT_FAILURE: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+
+const DEFAULT_STATUS_INTERVAL: Duration = Duration::from_secs(10);
+const DEFAULT_SCAN_TIMEOUT: Duration = Duration::from_secs(60);
+
+const USAGE: &str = "\
+Usage: zen-signal [command]
+
+Without a command the viewer window opens.
+
+Commands:
+  record [--device <id>]... [--autoconnect] [--simulated] [--duration <time>]
+         [--out <dir>] [--subject <id>] [--description <text>]
+         [--status-interval <time>] [--scan-timeout <time>]
+      Record to HDF5 without the window until the duration passes or Ctrl+C.
+      --device may be repeated; --autoconnect picks the first Polar device.
+      Times are seconds or a number followed by s, m or h (e.g. 8h).
+  export <recording.h5> [--format <format>] [--out <dir>]
+      Convert a recording. Formats: csv (default), csv-merged, edf, parquet.
+      Files are written to exports/ next to the recording unless --out is given.
+  info <recording.h5>
+      List metadata, devices and channels with point counts and time ranges.
+  validate <recording.h5> [--max-gap <time>]
+      Check for timestamps going backwards, times/values length mismatches
+      and gaps. Exits with 1 on errors; gaps longer than --max-gap are errors.
+  summary <recording.h5>
+      Print heart rate and RMSSD statistics.
+  hrv <recording.h5> [--window <time>] [--step <time>] [--correction <method>]
+      Print time-domain, frequency-domain and non-linear HRV metrics of
+      consecutive windows as CSV. Artifacts are corrected first; methods are
+      delete, interpolate and spline. Defaults: the configured window and
+      correction, step = window.
+  help
+      Show this message";
+
+/// Run the subcommand named by `args` (without the program name)
+///
+/// Returns the process exit code, or `None` if `args` name no subcommand
+/// and the UI should start.
+pub fn run(args: &[String]) -> Option<i32> {
+    let (command, rest) = args.split_first()?;
+    let command: fn(&[String]) -> Result<(), CliError> = match command.as_str() {
+        "record" => record_command,
+        "export" => export_command,
+        "info" => info_command,
+        "validate" => validate_command,
+        "summary" => summary_command,
+        "hrv" => hrv_command,
+        "help" | "--help" | "-h" => help_command,
+        _ => return None,
+    };
+
+    attach_console();
+    let result = command(rest);
+
+    Some(match result {
+        Ok(()) => EXIT_SUCCESS,
+        Err(CliError::Usage(message)) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            EXIT_USAGE
+        }
+        Err(CliError::Failed(message)) => {
+            eprintln!("Error: {}", message);
+            EXIT_FAILURE
+        }
+    })
+}
+
+/// Attach to the console of the parent process, e.g. the shell running the command
+///
+/// A GUI subsystem process has none of its own; without one, output and Ctrl+C are lost.
+#[cfg(target_os = "windows")]
+fn attach_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+
+    // Fails if the parent has no console or this process already has one (debug builds)
+    // SAFETY: AttachConsole takes a process ID by value and has no other preconditions
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn attach_console() {}
+
+fn help_command(_args: &[String]) -> Result<(), CliError> {
+    println!("{}", USAGE);
+    Ok(())
+}
+
+/// Why a command did not complete
+#[derive(Debug, PartialEq)]
+enum CliError {
+    Usage(String),
+    Failed(String),
+}
+
+/// Positional arguments, `--name value` options and `--name` flags of a command
+#[derive(Debug, Default)]
+struct ParsedArgs {
+    positional: Vec<String>,
+    options: Vec<(String, String)>,
+    flags: Vec<String>,
+}
+
+impl ParsedArgs {
+    /// Split arguments, accepting only the named options and flags
+    fn parse(args: &[String], allowed: &[&str], allowed_flags: &[&str]) -> Result<Self, CliError> {
+        let mut parsed = Self::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(name) if allowed_flags.contains(&name) => parsed.flags.push(name.to_string()),
+                Some(name) if allowed.contains(&name) => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| CliError::Usage(format!("Missing value for --{}", name)))?;
+                    parsed.options.push((name.to_string(), value.clone()));
+                }
+                Some(name) => return Err(CliError::Usage(format!("Unknown option --{}", name))),
+                None => parsed.positional.push(arg.clone()),
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Last value given for an option
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.iter().rev().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    /// All values given for a repeatable option
+    fn option_values(&self, name: &str) -> Vec<String> {
+        self.options.iter().filter(|(n, _)| n == name).map(|(_, v)| v.clone()).collect()
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|f| f == name)
+    }
+
+    /// Duration option, `None` if not given
+    fn duration(&self, name: &str) -> Result<Option<Duration>, CliError> {
+        self.option(name)
+            .map(|value| parse_duration(value).ok_or_else(|| CliError::Usage(format!("Invalid time for --{}: {}", name, value))))
+            .transpose()
+    }
+
+    /// The single positional argument a command expects
+    fn single_path(&self, what: &str) -> Result<PathBuf, CliError> {
+        match self.positional.as_slice() {
+            [path] => Ok(PathBuf::from(path)),
+            [] => Err(CliError::Usage(format!("Missing {}", what))),
+            _ => Err(CliError::Usage(format!("Expected one {}", what))),
+        }
+    }
+}
+
+/// Parse `90`, `90s`, `30m` or `8h`
+fn parse_duration(value: &str) -> Option<Duration> {
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit() && c != '.') {
+        Some(index) => value.split_at(index),
+        None => (value, "s"),
+    };
+    let number: f64 = number.parse().ok()?;
+    let seconds = match unit {
+        "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => return None,
+    };
+    (seconds.is_finite() && seconds > 0.0).then(|| Duration::from_secs_f64(seconds))
+}
+
+fn record_command(args: &[String]) -> Result<(), CliError> {
+    let args = ParsedArgs::parse(
+        args,
+        &["device", "duration", "out", "subject", "description", "status-interval", "scan-timeout"],
+        &["autoconnect", "simulated"],
+    )?;
+    if !args.positional.is_empty() {
+        return Err(CliError::Usage(format!("Unexpected argument {}", args.positional[0])));
+    }
+
+    let config = Config::load().unwrap_or_else(|e| {
+        log::error!("Failed to load config: {}, using defaults", e);
+        Config::default()
+    });
+    let options = RecordOptions {
+        device_ids: args.option_values("device"),
+        autoconnect: args.flag("autoconnect"),
+        simulated: args.flag("simulated").then_some(config.simulated_device_model),
+        duration: args.duration("duration")?,
+        output_dir: args
+            .option("out")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| config.recording_directory.clone()),
+        info: RecordingInfo {
+            subject_id: args.option("subject").unwrap_or(&config.subject_id).to_string(),
+            description: args.option("description").unwrap_or(&config.recording_description).to_string(),
+        },
+        status_interval: args.duration("status-interval")?.unwrap_or(DEFAULT_STATUS_INTERVAL),
+        scan_timeout: args.duration("scan-timeout")?.unwrap_or(DEFAULT_SCAN_TIMEOUT),
+    };
+    if options.device_ids.is_empty() && !options.autoconnect && options.simulated.is_none() {
+        return Err(CliError::Usage("Give --device <id>, --autoconnect or --simulated".to_string()));
+    }
+
+    match headless::record(&options, &config).map_err(CliError::Failed)? {
+        Some(_) => Ok(()),
+        None => Err(CliError::Failed("No device streamed, nothing recorded".to_string())),
+    }
+}
+
+fn export_command(args: &[String]) -> Result<(), CliError> {
+    let args = ParsedArgs::parse(args, &["format", "out"], &[])?;
+    let recording = args.single_path("recording file")?;
+    let format = match args.option("format") {
+        Some(name) => ExportFormat::from_cli_name(name)
+            .ok_or_else(|| CliError::Usage(format!("Unknown format {}", name)))?,
+        None => ExportFormat::default(),
+    };
+    let output_dir = args
+        .option("out")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| export::default_output_dir(&recording));
+
+    let files = export::export_recording(&recording, format, &output_dir).map_err(CliError::Failed)?;
+    for file in files {
+        println!("{}", file.display());
+    }
+    Ok(())
+}
+
+fn info_command(args: &[String]) -> Result<(), CliError> {
+    let args = ParsedArgs::parse(args, &[], &[])?;
+    let recording = args.single_path("recording file")?;
+    let overview = inspect::recording_info(&recording).map_err(CliError::Failed)?;
+    print!("{}", overview);
+    Ok(())
+}
+
+fn validate_command(args: &[String]) -> Result<(), CliError> {
+    let args = ParsedArgs::parse(args, &["max-gap"], &[])?;
+    let recording = args.single_path("recording file")?;
+    let report = inspect::validate_recording(&recording, args.duration("max-gap")?).map_err(CliError::Failed)?;
+    println!("{}", report);
+    if report.is_valid() {
+        Ok(())
+    } else {
+        Err(CliError::Failed(format!("{} is invalid", recording.display())))
+    }
+}
+
+fn summary_command(args: &[String]) -> Result<(), CliError> {
+    let args = ParsedArgs::parse(args, &[], &[])?;
+    let recording = args.single_path("recording file")?;
+    let summary = inspect::summarize_recording(&recording).map_err(CliError::Failed)?;
+    print!("{}", summary);
+    Ok(())
+}
+
+fn hrv_command(args: &[String]) -> Result<(), CliError> {
+    let args = ParsedArgs::parse(args, &["window", "step", "correction"], &[])?;
+    let recording = args.single_path("recording file")?;
+    let correction = args
+        .option("correction")
+        .map(|name| {
+            ArtifactCorrection::from_cli_name(name)
+                .ok_or_else(|| CliError::Usage(format!("Unknown correction '{}'; use delete, interpolate or spline", name)))
+        })
+        .transpose()?;
+
+    let config = Config::load().unwrap_or_else(|e| {
+        log::error!("Failed to load config: {}, using defaults", e);
+        Config::default()
+    });
+    let window = args
+        .duration("window")?
+        .unwrap_or(Duration::from_secs(config.hrv_window_seconds));
+    let step = args.duration("step")?.unwrap_or(window);
+    let correction = correction.unwrap_or(config.rr_artifact_correction);
+
+    let report = inspect::recording_hrv(&recording, window, step, correction).map_err(CliError::Failed)?;
+    print!("{}", report);
+    // Keep stdout plain CSV
+    for device in &report.devices {
+        eprintln!(
+            "{}: {} of {} beats were artifacts ({:.1}%), corrected by {}",
+            device.id.as_deref().unwrap_or("recording"),
+            device.artifacts,
+            device.beats,
+            device.artifacts as f64 / device.beats.max(1) as f64 * 100.0,
+            correction.cli_name()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_unknown_command_starts_ui() {
+        assert_eq!(run(&[]), None);
+        assert_eq!(run(&args(&["-psn_0_12345"])), None);
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let parsed = ParsedArgs::parse(&args(&["a.h5", "--format", "edf", "--out", "x", "--all"]), &["format", "out"], &["all"]).unwrap();
+        assert_eq!(parsed.single_path("file").unwrap(), PathBuf::from("a.h5"));
+        assert_eq!(parsed.option("format"), Some("edf"));
+        assert_eq!(parsed.option("out"), Some("x"));
+        assert!(parsed.flag("all"));
+
+        assert!(matches!(ParsedArgs::parse(&args(&["--bogus", "1"]), &["out"], &[]), Err(CliError::Usage(_))));
+        assert!(matches!(ParsedArgs::parse(&args(&["--out"]), &["out"], &[]), Err(CliError::Usage(_))));
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("30m"), Some(Duration::from_secs(1800)));
+        assert_eq!(parse_duration("1.5h"), Some(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("0"), None);
+        assert_eq!(parse_duration("5d"), None);
+        assert_eq!(run(&args(&["record"])), Some(EXIT_USAGE));
+    }
+
+    #[test]
+    fn test_export_usage_errors() {
+        assert_eq!(run(&args(&["export"])), Some(EXIT_USAGE));
+        assert_eq!(run(&args(&["export", "a.h5", "--format", "xls"])), Some(EXIT_USAGE));
+        assert_eq!(run(&args(&["export", "/nonexistent/a.h5"])), Some(EXIT_FAILURE));
+    }
+
+    #[test]
+    fn test_inspect_usage_errors() {
+        assert_eq!(run(&args(&["info"])), Some(EXIT_USAGE));
+        assert_eq!(run(&args(&["validate", "a.h5", "--max-gap", "soon"])), Some(EXIT_USAGE));
+        assert_eq!(run(&args(&["summary", "/nonexistent/a.h5"])), Some(EXIT_FAILURE));
+        assert_eq!(run(&args(&["hrv", "a.h5", "--correction", "median"])), Some(EXIT_USAGE));
+    }
+}
@@ -8,6 +8,19 @@
 //! - `smooth_data_streaming`: Enable display delay for smoother low-rate data
 //! - `recording_max_memory_mb`: Maximum memory buffer size before flushing to disk
 //! - `recording_directory`: Directory where recording files are saved
+//! - `enable_simulated_device`: List a simulated Polar device in the device scanner
+//! - `simulated_heart_rate_bpm`: Mean heart rate produced by the simulated device
+//! - `simulated_device_model`: Polar model (and thus streams) the simulated device mimics
+//! - `subject_id`: Subject identifier stored in new recordings
+//! - `recording_description`: Free-text description stored in new recordings
+//! - `hrv_window_seconds`: Length of the beat window HRV metrics are computed over
+//! - `rr_artifact_correction`: How RR/PPI artifacts are replaced before HRV
+//! - `hrv_still_only`: Leave beats from periods of movement out of HRV and stress
+//! - `show_filtered_signals`: Chart the filtered ECG and PPG instead of the raw signals
+//! - `signal_filters`: ECG and PPG filter chains, and whether to record their output
+//! - `stress_baseline`: Resting HRV recorded by calibration, the reference for the relaxation score
+//! - `chart_windows`: Time window shown by each chart
+//! - `chart_scales`: Value axis mode of each chart, and its range when set manually
 //!
 //! ## Storage Location
 //! - macOS: ~/Library/Application Support/zen-signal/config.toml
@@ -21,7 +34,12 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
+use crate::artifacts::ArtifactCorrection;
+use crate::device_profile::DeviceModel;
 use crate::error::ConfigError;
+use crate::filters::SignalFilters;
+use crate::stress::StressBaseline;
+use crate::visualization::{ChartScales, ChartWindows};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -29,6 +47,44 @@ pub struct Config {
     pub smooth_data_streaming: bool,
     pub recording_max_memory_mb: usize,
     pub recording_directory: PathBuf,
+    #[serde(default)]
+    pub enable_simulated_device: bool,
+    #[serde(default = "default_simulated_heart_rate_bpm")]
+    pub simulated_heart_rate_bpm: u32,
+    #[serde(default)]
+    pub simulated_device_model: DeviceModel,
+    #[serde(default)]
+    pub subject_id: String,
+    #[serde(default)]
+    pub recording_description: String,
+    #[serde(default = "default_hrv_window_seconds")]
+    pub hrv_window_seconds: u64,
+    #[serde(default)]
+    pub rr_artifact_correction: ArtifactCorrection,
+    #[serde(default)]
+    pub hrv_still_only: bool,
+    #[serde(default = "default_show_filtered_signals")]
+    pub show_filtered_signals: bool,
+    #[serde(default)]
+    pub signal_filters: SignalFilters,
+    #[serde(default)]
+    pub stress_baseline: Option<StressBaseline>,
+    #[serde(default)]
+    pub chart_windows: ChartWindows,
+    #[serde(default)]
+    pub chart_scales: ChartScales,
+}
+
+fn default_simulated_heart_rate_bpm() -> u32 {
+    60
+}
+
+fn default_hrv_window_seconds() -> u64 {
+    300
+}
+
+fn default_show_filtered_signals() -> bool {
+    true
 }
 
 impl Default for Config {
@@ -50,6 +106,19 @@ impl Default for Config {
             smooth_data_streaming: true,
             recording_max_memory_mb: 100,
             recording_directory: recording_dir,
+            enable_simulated_device: false,
+            simulated_heart_rate_bpm: default_simulated_heart_rate_bpm(),
+            simulated_device_model: DeviceModel::default(),
+            subject_id: String::new(),
+            recording_description: String::new(),
+            hrv_window_seconds: default_hrv_window_seconds(),
+            rr_artifact_correction: ArtifactCorrection::default(),
+            hrv_still_only: false,
+            show_filtered_signals: default_show_filtered_signals(),
+            signal_filters: SignalFilters::default(),
+            stress_baseline: None,
+            chart_windows: ChartWindows::default(),
+            chart_scales: ChartScales::default(),
         }
     }
 }
@@ -110,6 +179,7 @@ impl Config {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::visualization::{ChartWindow, YScale, YScaleMode};
 
     #[test]
     fn test_default_config() {
@@ -127,12 +197,51 @@ mod tests {
             smooth_data_streaming: false,
             recording_max_memory_mb: 50,
             recording_directory: PathBuf::from("/test/path"),
+            enable_simulated_device: true,
+            simulated_heart_rate_bpm: 72,
+            simulated_device_model: DeviceModel::VeritySense,
+            subject_id: "S01".to_string(),
+            recording_description: String::new(),
+            hrv_window_seconds: 120,
+            rr_artifact_correction: ArtifactCorrection::Interpolate,
+            hrv_still_only: true,
+            show_filtered_signals: false,
+            signal_filters: SignalFilters { record: true, ..SignalFilters::default() },
+            stress_baseline: Some(StressBaseline {
+                rmssd: 42.5,
+                stress_index: 80.0,
+                mean_hr: 58.0,
+                recorded_at: "2024-01-01T08:00:00+01:00".to_string(),
+            }),
+            chart_windows: ChartWindows { ecg: ChartWindow::ThirtySeconds, ..ChartWindows::default() },
+            chart_scales: ChartScales {
+                hr: YScale { mode: YScaleMode::Manual, manual_range: Some((50, 210)) },
+                ..ChartScales::default()
+            },
         };
         
         let toml_str = toml::to_string(&config).expect("Failed to serialize");
         assert!(toml_str.contains("enable_autoconnect = true"));
         assert!(toml_str.contains("smooth_data_streaming = false"));
         assert!(toml_str.contains("recording_max_memory_mb = 50"));
+        assert!(toml_str.contains("enable_simulated_device = true"));
+        assert!(toml_str.contains("simulated_heart_rate_bpm = 72"));
+        assert!(toml_str.contains("simulated_device_model = \"VeritySense\""));
+        assert!(toml_str.contains("subject_id = \"S01\""));
+        assert!(toml_str.contains("hrv_window_seconds = 120"));
+        assert!(toml_str.contains("rr_artifact_correction = \"Interpolate\""));
+        assert!(toml_str.contains("hrv_still_only = true"));
+        assert!(toml_str.contains("show_filtered_signals = false"));
+        assert!(toml_str.contains("[signal_filters]"));
+        assert!(toml_str.contains("[signal_filters.ecg]"));
+        assert!(toml_str.contains("notch = \"Hz50\""));
+        assert!(toml_str.contains("[stress_baseline]"));
+        assert!(toml_str.contains("rmssd = 42.5"));
+        assert!(toml_str.contains("[chart_windows]"));
+        assert!(toml_str.contains("ecg = \"ThirtySeconds\""));
+        assert!(toml_str.contains("[chart_scales.hr]"));
+        assert!(toml_str.contains("mode = \"Manual\""));
+        assert!(toml_str.contains("manual_range = [50, 210]"));
     }
 
     #[test]
@@ -148,6 +257,18 @@ mod tests {
         assert_eq!(config.enable_autoconnect, true);
         assert_eq!(config.smooth_data_streaming, false);
         assert_eq!(config.recording_max_memory_mb, 50);
+        // Settings added later fall back to defaults for older config files
+        assert!(!config.enable_simulated_device);
+        assert_eq!(config.simulated_heart_rate_bpm, 60);
+        assert_eq!(config.simulated_device_model, DeviceModel::H10);
+        assert_eq!(config.hrv_window_seconds, 300);
+        assert_eq!(config.rr_artifact_correction, ArtifactCorrection::CubicSpline);
+        assert!(!config.hrv_still_only);
+        assert_eq!(config.signal_filters, SignalFilters::default());
+        assert!(config.show_filtered_signals);
+        assert_eq!(config.stress_baseline, None);
+        assert_eq!(config.chart_windows, ChartWindows::default());
+        assert_eq!(config.chart_scales.ppg.mode, YScaleMode::Auto);
     }
 
     #[test]
@@ -7,24 +7,54 @@
 //! ## Key Components
 //! - `ConnectionManager`: Manages connection thread and command processing
 //! - `ConnectionCommand`: Commands sent from UI to connection thread
+//! - `DataSource` backends started per command (Polar, generic heart rate
+//!   strap, synthetic, playback)
 //! - Connection state tracking with atomic stop flags
 //!
+//! ## Multiple Devices
+//! Every running source is keyed by its device ID and has its own stop flag,
+//! so devices connect and disconnect independently. Updates from a source are
+//! forwarded to the UI as `DeviceUpdate`s tagged with that ID.
+//!
+//! A repeated connect replaces the source running for that device. Each start
+//! bumps the device's generation, and forwarders of older generations drop
+//! their updates, so the replaced source's final `Disconnected` cannot end
+//! the new source's session.
+//!
 //! ## Why
 //! Separating connection management improves testability and makes it easier
 //! to add features like recording that need to hook into the connection lifecycle.
 
+use crate::device_profile::DeviceModel;
 use crate::error::ConnectionError;
-use crate::sensor::{start_data_collection, Handler, SensorUpdate};
+use crate::hrs::HrsSource;
+use crate::pmd::PmdSource;
+use crate::replay::{PlaybackCommand, ReplaySource, PLAYBACK_DEVICE_ID};
+use crate::sensor::{DeviceUpdate, PolarSource, SensorUpdate};
+use crate::source::DataSource;
+use crate::synthetic::{SyntheticConfig, SyntheticSource, SIMULATED_DEVICE_ID};
+use std::collections::HashMap;
 use std::sync::mpsc;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::runtime::Runtime;
-use tokio::sync::RwLock;
 
 #[derive(Debug, Clone)]
 pub enum ConnectionCommand {
-    Connect(String),
-    Disconnect,
+    /// Connect to a sensor by device ID; `GenericHrs` devices use the
+    /// standard Heart Rate Service backend and the optical Polar models the
+    /// PMD backend
+    Connect(String, DeviceModel),
+    /// Start the synthetic data source as the simulated device
+    ConnectSimulated(SyntheticConfig),
+    /// Play back one device of a recording file as the playback device;
+    /// `None` plays the first device in the file
+    Replay(PathBuf, Option<String>),
+    /// Control the running playback
+    Playback(PlaybackCommand),
+    /// Disconnect one device by ID, leaving the others streaming
+    Disconnect(String),
 }
 
 /// Manages the connection lifecycle for Polar sensors.
@@ -34,14 +64,14 @@ pub enum ConnectionCommand {
 /// graceful disconnection.
 pub struct ConnectionManager {
     command_receiver: mpsc::Receiver<ConnectionCommand>,
-    sensor_sender: mpsc::Sender<SensorUpdate>,
+    sensor_sender: mpsc::Sender<DeviceUpdate>,
 }
 
 impl ConnectionManager {
     /// Creates a new ConnectionManager.
     ///
     /// Returns the manager and a sender for issuing commands from the UI thread.
-    pub fn new(sensor_sender: mpsc::Sender<SensorUpdate>) -> (Self, mpsc::Sender<ConnectionCommand>) {
+    pub fn new(sensor_sender: mpsc::Sender<DeviceUpdate>) -> (Self, mpsc::Sender<ConnectionCommand>) {
         let (command_sender, command_receiver) = mpsc::channel();
         
         let manager = ConnectionManager {
@@ -66,62 +96,121 @@ impl ConnectionManager {
             Err(e) => {
                 let error = ConnectionError::RuntimeCreation(e.to_string());
                 log::error!("{}", error);
-                let _ = self.sensor_sender.send(SensorUpdate::ConnectionStatus(
-                    crate::sensor::ConnectionStatus::Error(error.to_string())
-                ));
+                let _ = self.sensor_sender.send(DeviceUpdate {
+                    device_id: String::new(),
+                    update: SensorUpdate::ConnectionStatus(crate::sensor::ConnectionStatus::Error(error.to_string())),
+                });
                 return;
             }
         };
         
-        let mut stop_flag: Option<Arc<AtomicBool>> = None;
+        // Stop flags of the running sources, keyed by device ID
+        let mut stop_flags: HashMap<String, Arc<AtomicBool>> = HashMap::new();
+        // Generation of the latest source started for each device ID
+        let mut generations: HashMap<String, Arc<Mutex<u64>>> = HashMap::new();
+        let mut playback_sender: Option<mpsc::Sender<PlaybackCommand>> = None;
         
         // Wait for connection commands
         while let Ok(command) = self.command_receiver.recv() {
-            match command {
-                ConnectionCommand::Connect(device_id) => {
-                    log::info!("Connection manager: Connecting to device: {}", device_id);
-                    
-                    // Create a new stop flag for this connection
-                    // Why: Each connection needs its own cancellation mechanism
-                    let should_stop = Arc::new(AtomicBool::new(false));
-                    stop_flag = Some(should_stop.clone());
-                    
-                    let sender_clone = self.sensor_sender.clone();
-                    
-                    // Spawn the connection task instead of blocking
-                    // Why: Allows processing other commands (like disconnect) while connecting
-                    rt.spawn(async move {
-                        match arctic::PolarSensor::new(device_id.clone()).await {
-                            Ok(sensor) => {
-                                let polar = Arc::new(RwLock::new(sensor));
-                                let handler = Handler::new(sender_clone.clone());
-                                start_data_collection(polar, handler, should_stop).await;
-                            }
-                            Err(e) => {
-                                let error = ConnectionError::DeviceConnection {
-                                    device_id: device_id.clone(),
-                                    reason: format!("{:?}", e),
-                                };
-                                log::error!("{}", error);
-                                let _ = sender_clone.send(SensorUpdate::ConnectionStatus(
-                                    crate::sensor::ConnectionStatus::Error(error.to_string())
-                                ));
-                            }
-                        }
-                    });
+            let (device_id, source): (String, Box<dyn DataSource>) = match command {
+                ConnectionCommand::Connect(device_id, model) => {
+                    let source: Box<dyn DataSource> = match model {
+                        DeviceModel::GenericHrs => Box::new(HrsSource::new(device_id.clone())),
+                        DeviceModel::VeritySense | DeviceModel::Oh1 => Box::new(PmdSource::new(device_id.clone(), model)),
+                        _ => Box::new(PolarSource::new(device_id.clone(), model)),
+                    };
+                    (device_id, source)
+                }
+                ConnectionCommand::ConnectSimulated(config) => {
+                    (SIMULATED_DEVICE_ID.to_string(), Box::new(SyntheticSource::new(config)))
+                }
+                ConnectionCommand::Replay(file_path, device_id) => {
+                    let (sender, receiver) = mpsc::channel();
+                    playback_sender = Some(sender);
+                    (PLAYBACK_DEVICE_ID.to_string(), Box::new(ReplaySource::new(file_path, device_id, receiver)))
+                }
+                ConnectionCommand::Playback(playback_command) => {
+                    match &playback_sender {
+                        Some(sender) if sender.send(playback_command.clone()).is_ok() => {}
+                        _ => log::warn!("Connection manager: No active playback for {:?}", playback_command),
+                    }
+                    continue;
                 }
-                ConnectionCommand::Disconnect => {
-                    log::info!("Connection manager: Disconnect requested");
-                    if let Some(flag) = &stop_flag {
+                ConnectionCommand::Disconnect(device_id) => {
+                    log::info!("Connection manager: Disconnect requested for {}", device_id);
+                    if let Some(flag) = stop_flags.remove(&device_id) {
                         log::debug!("Connection manager: Setting stop flag");
                         flag.store(true, Ordering::Relaxed);
                     }
-                    stop_flag = None;
+                    if device_id == PLAYBACK_DEVICE_ID {
+                        playback_sender = None;
+                    }
+                    continue;
                 }
+            };
+
+            // A repeated connect replaces the source already running for that device
+            if let Some(previous) = stop_flags.remove(&device_id) {
+                previous.store(true, Ordering::Relaxed);
             }
+            let generation = generations.entry(device_id.clone()).or_default().clone();
+            let flag = self.start_source(&rt, device_id.clone(), source, generation);
+            stop_flags.insert(device_id, flag);
         }
         
         log::info!("Connection manager: Command channel closed, shutting down");
+        for flag in stop_flags.values() {
+            flag.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Spawn a data source on the runtime and return its stop flag
+    ///
+    /// Each source gets its own stop flag so a disconnect only cancels the
+    /// source it was issued for. A forwarding thread tags the source's
+    /// updates with its device ID and ends when the source finishes; once a
+    /// newer source for the device started, it drops them instead.
+    fn start_source(
+        &self,
+        rt: &Runtime,
+        device_id: String,
+        source: Box<dyn DataSource>,
+        current_generation: Arc<Mutex<u64>>,
+    ) -> Arc<AtomicBool> {
+        log::info!("Connection manager: Starting {}", source.name());
+
+        let should_stop = Arc::new(AtomicBool::new(false));
+        let (source_sender, source_receiver) = mpsc::channel::<SensorUpdate>();
+        let generation = {
+            let mut current = current_generation.lock().unwrap();
+            *current += 1;
+            *current
+        };
+
+        let sensor_sender = self.sensor_sender.clone();
+        std::thread::spawn(move || {
+            while let Ok(update) = source_receiver.recv() {
+                // Held while sending, so nothing old follows the newer source's first update
+                let current = current_generation.lock().unwrap();
+                if *current != generation {
+                    log::debug!("Dropping update from replaced source for {}", device_id);
+                    continue;
+                }
+                let tagged = DeviceUpdate {
+                    device_id: device_id.clone(),
+                    update,
+                };
+                if sensor_sender.send(tagged).is_err() {
+                    break;
+                }
+            }
+        });
+
+        // Spawn the source task instead of blocking
+        // Why: Allows processing other commands (like disconnect) while connecting
+        rt.spawn(source.start(source_sender, should_stop.clone()));
+
+        should_stop
     }
 }
 
@@ -135,6 +224,67 @@ mod tests {
         let (_manager, command_sender) = ConnectionManager::new(sensor_sender);
         
         // Verify we can send commands
-        assert!(command_sender.send(ConnectionCommand::Disconnect).is_ok());
+        assert!(command_sender.send(ConnectionCommand::Disconnect("12345678".to_string())).is_ok());
+    }
+
+    #[test]
+    fn test_simulated_connection_streams_data() {
+        let (sensor_sender, sensor_receiver) = mpsc::channel();
+        let (manager, command_sender) = ConnectionManager::new(sensor_sender);
+        let handle = std::thread::spawn(move || manager.run());
+
+        command_sender
+            .send(ConnectionCommand::ConnectSimulated(SyntheticConfig::default()))
+            .unwrap();
+
+        // Wait for the first measurement batch to arrive
+        let timeout = std::time::Duration::from_secs(5);
+        let mut got_data = false;
+        while let Ok(tagged) = sensor_receiver.recv_timeout(timeout) {
+            if matches!(tagged.update, SensorUpdate::MeasurementData(_)) {
+                assert_eq!(tagged.device_id, SIMULATED_DEVICE_ID);
+                got_data = true;
+                break;
+            }
+        }
+        assert!(got_data);
+
+        command_sender
+            .send(ConnectionCommand::Disconnect(SIMULATED_DEVICE_ID.to_string()))
+            .unwrap();
+        drop(command_sender);
+        handle.join().unwrap();
+    }
+}
+
+    #[test]
+    fn test_repeated_connect_drops_replaced_source() {
+        use crate::sensor::ConnectionStatus;
+
+        let (sensor_sender, sensor_receiver) = mpsc::channel();
+        let (manager, command_sender) = ConnectionManager::new(sensor_sender);
+        let handle = std::thread::spawn(move || manager.run());
+
+        for _ in 0..2 {
+            command_sender
+                .send(ConnectionCommand::ConnectSimulated(SyntheticConfig::default()))
+                .unwrap();
+        }
+
+        // The first source stops, but its Disconnected must not reach the UI
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(1);
+        let mut statuses = Vec::new();
+        while let Some(remaining) = deadline.checked_duration_since(std::time::Instant::now()) {
+            match sensor_receiver.recv_timeout(remaining) {
+                Ok(DeviceUpdate { update: SensorUpdate::ConnectionStatus(status), .. }) => statuses.push(status),
+                Ok(_) => {}
+                Err(_) => break,
+            }
+        }
+        assert!(statuses.iter().any(|s| matches!(s, ConnectionStatus::Connected)));
+        assert!(!statuses.iter().any(|s| matches!(s, ConnectionStatus::Disconnected)));
+
+        drop(command_sender);
+        handle.join().unwrap();
     }
 }
@@ -0,0 +1,180 @@
+//! # Device Clock Module
+//!
+//! Maps timestamps from a sensor's own clock onto the wall clock.
+//!
+//! ## Why
+//! Polar sensors stamp every PMD frame with the time of its last sample on
+//! the sensor clock. That clock runs slightly fast or slow compared to the
+//! computer, so spacing samples by the nominal rate from their arrival time
+//! makes ECG and ACC drift against each other and against wall time over a
+//! long recording. Mapping the sensor timestamps keeps every stream of a
+//! device on one time base.
+//!
+//! ## Estimation
+//! Each frame yields an observation: its sensor timestamp and its arrival
+//! time. Arrival is the true wall time plus Bluetooth and UI latency, which is
+//! never negative, so the mapping follows the lower edge of the observations:
+//! - **Rate**: Slope between the least delayed observation in the older and
+//!   in the newer half of a sliding window, clamped to a plausible crystal drift
+//! - **Offset**: Chosen so no observation lies below the line
+//!
+//! Until the window spans enough time the rate is assumed to be exact.
+
+use std::collections::VecDeque;
+
+// Sensor time covered by the observations used for the fit
+const WINDOW_NS: u64 = 120_000_000_000;
+
+// Upper bound on stored observations, oldest are dropped first
+const MAX_OBSERVATIONS: usize = 512;
+
+// Shortest window over which a drift estimate is trusted
+const MIN_FIT_SPAN_NS: u64 = 10_000_000_000;
+
+// Largest drift accepted from the fit, in parts per million
+const MAX_DRIFT_PPM: f64 = 500.0;
+
+/// Drift-tracking mapping from sensor clock to wall clock
+#[derive(Debug, Clone, Default)]
+pub struct DeviceClock {
+    /// First observation (sensor, wall); all fits work relative to it
+    anchor: Option<(u64, u64)>,
+    /// Recent observations relative to the anchor (sensor, wall)
+    observations: VecDeque<(i64, i64)>,
+    /// Wall nanoseconds per sensor nanosecond
+    rate: f64,
+    /// Wall offset in nanoseconds at the anchor's sensor time
+    offset: f64,
+}
+
+impl DeviceClock {
+    pub fn new() -> Self {
+        Self {
+            rate: 1.0,
+            ..Default::default()
+        }
+    }
+
+    /// Record that a frame stamped `sensor_ns` arrived at `wall_ns`
+    pub fn observe(&mut self, sensor_ns: u64, wall_ns: u64) {
+        let (anchor_sensor, anchor_wall) = *self.anchor.get_or_insert((sensor_ns, wall_ns));
+        let x = sensor_ns as i64 - anchor_sensor as i64;
+        let y = wall_ns as i64 - anchor_wall as i64;
+
+        self.observations.push_back((x, y));
+        while self.observations.len() > MAX_OBSERVATIONS
+            || self
+                .observations
+                .front()
+                .is_some_and(|&(oldest, _)| x - oldest > WINDOW_NS as i64)
+        {
+            self.observations.pop_front();
+        }
+
+        self.fit();
+    }
+
+    /// Wall time of a sensor timestamp, or `None` before the first observation
+    pub fn to_wall(&self, sensor_ns: u64) -> Option<u64> {
+        let (anchor_sensor, anchor_wall) = self.anchor?;
+        let x = sensor_ns as i64 - anchor_sensor as i64;
+        let wall = anchor_wall as i64 + (self.offset + self.rate * x as f64).round() as i64;
+        Some(wall.max(0) as u64)
+    }
+
+    /// Estimated drift of the sensor clock in parts per million
+    ///
+    /// Positive when the sensor clock runs slow compared to the wall clock.
+    /// `None` until enough observations are collected.
+    pub fn drift_ppm(&self) -> Option<f64> {
+        (self.span_ns() >= MIN_FIT_SPAN_NS).then_some((self.rate - 1.0) * 1e6)
+    }
+
+    /// Sensor time covered by the current observations
+    fn span_ns(&self) -> u64 {
+        match (self.observations.front(), self.observations.back()) {
+            (Some(first), Some(last)) => (last.0 - first.0).max(0) as u64,
+            _ => 0,
+        }
+    }
+
+    /// Refit rate and offset to the current observations
+    fn fit(&mut self) {
+        self.rate = if self.span_ns() >= MIN_FIT_SPAN_NS {
+            // Latency only adds delay, so the least delayed observations sit
+            // closest to the true mapping; compare one from each half
+            let least_delayed = |points: &[(i64, i64)]| {
+                points.iter().copied().min_by_key(|&(x, y)| y - x).unwrap()
+            };
+            let all = self.observations.make_contiguous();
+            let (first_half, second_half) = all.split_at(all.len() / 2);
+            let (x0, y0) = least_delayed(first_half);
+            let (x1, y1) = least_delayed(second_half);
+
+            let max_drift = MAX_DRIFT_PPM / 1e6;
+            if x1 > x0 {
+                ((y1 - y0) as f64 / (x1 - x0) as f64).clamp(1.0 - max_drift, 1.0 + max_drift)
+            } else {
+                self.rate
+            }
+        } else {
+            1.0
+        };
+
+        // Lower edge: the observation with the least latency lies on the line
+        self.offset = self
+            .observations
+            .iter()
+            .map(|&(x, y)| y as f64 - self.rate * x as f64)
+            .fold(f64::INFINITY, f64::min);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    #[test]
+    fn test_maps_onto_earliest_arrival() {
+        let mut clock = DeviceClock::new();
+        assert_eq!(clock.to_wall(5 * SECOND), None);
+
+        // Same sensor spacing, the second frame arrived with less latency
+        clock.observe(100 * SECOND, 1_000 * SECOND + 30_000_000);
+        clock.observe(101 * SECOND, 1_001 * SECOND + 10_000_000);
+
+        assert_eq!(clock.to_wall(101 * SECOND), Some(1_001 * SECOND + 10_000_000));
+        assert_eq!(clock.to_wall(100 * SECOND), Some(1_000 * SECOND + 10_000_000));
+        assert_eq!(clock.drift_ppm(), None);
+    }
+
+    #[test]
+    fn test_tracks_drift() {
+        let mut clock = DeviceClock::new();
+
+        // Sensor clock runs 100 ppm slow: one sensor second is 1.0001 wall seconds
+        for i in 0..60u64 {
+            let sensor = i * SECOND;
+            let latency = (i % 3) * 5_000_000;
+            clock.observe(sensor, 500 * SECOND + sensor + sensor / 10_000 + latency);
+        }
+
+        let drift = clock.drift_ppm().unwrap();
+        assert!((drift - 100.0).abs() < 5.0, "drift {}", drift);
+
+        let expected = 500 * SECOND + 120 * SECOND + 12_000_000;
+        let mapped = clock.to_wall(120 * SECOND).unwrap();
+        assert!(mapped.abs_diff(expected) < 2_000_000, "mapped {} expected {}", mapped, expected);
+    }
+
+    #[test]
+    fn test_implausible_drift_is_clamped() {
+        let mut clock = DeviceClock::new();
+        for i in 0..20u64 {
+            clock.observe(i * SECOND, i * 2 * SECOND);
+        }
+        assert_eq!(clock.drift_ppm().unwrap().round(), MAX_DRIFT_PPM);
+    }
+}
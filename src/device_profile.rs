@@ -0,0 +1,224 @@
+//! # Device Profile Module
+//!
+//! Describes which data streams each supported Polar model provides, so the
+//! rest of the app only creates channels, recordings and charts for streams
+//! the connected device actually has.
+//!
+//! ## Key Types
+//! - `SensorStream`: Kind of data a device can produce (HR, ECG, PPG, ...)
+//! - `DeviceModel`: Polar model, detected from the advertised name
+//! - `DeviceProfile`: Model plus the set of streams it offers
+//!
+//! ## Supported Models
+//! | Model        | Streams                         |
+//! |--------------|---------------------------------|
+//! | H10          | HR/RR, ECG, ACC                 |
+//! | H9           | HR/RR                           |
+//! | Verity Sense | HR, PPG, PPI, ACC, gyro, magnet |
+//! | OH1          | HR, PPG, PPI, ACC               |
+//!
+//! Unrecognised devices fall back to HR only, which every Polar sensor offers
+//! through the standard heart rate service. Straps from other vendors use the
+//! `GenericHrs` model, which is HR/RR only as well.
+//!
+//! The H10 streams through Arctic (`sensor.rs`) and the optical models through
+//! the PMD backend (`pmd.rs`), which leaves out any stream the sensor refuses
+//! to start.
+//!
+//! ## Why Profiles
+//! Stream availability differs per model. Deciding it in one place keeps
+//! sensor setup, `Channels` and the chart layout consistent with each other.
+
+use serde::{Deserialize, Serialize};
+
+/// Kind of data stream a device can provide
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SensorStream {
+    /// Heart rate with RR intervals from the heart rate service
+    HeartRate,
+    /// Electrocardiogram
+    Ecg,
+    /// 3-axis accelerometer
+    Acc,
+    /// Optical photoplethysmogram
+    Ppg,
+    /// Optical peak-to-peak intervals, the optical counterpart of RR
+    Ppi,
+    /// 3-axis gyroscope
+    Gyro,
+    /// 3-axis magnetometer
+    Mag,
+}
+
+impl SensorStream {
+    /// Whether samples of this stream arrive at a fixed sample rate
+    pub fn is_periodic(&self) -> bool {
+        !matches!(self, SensorStream::HeartRate | SensorStream::Ppi)
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            SensorStream::HeartRate => "HR",
+            SensorStream::Ecg => "ECG",
+            SensorStream::Acc => "ACC",
+            SensorStream::Ppg => "PPG",
+            SensorStream::Ppi => "PPI",
+            SensorStream::Gyro => "Gyro",
+            SensorStream::Mag => "Mag",
+        }
+    }
+}
+
+/// Supported Polar sensor models
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DeviceModel {
+    #[default]
+    H10,
+    H9,
+    VeritySense,
+    Oh1,
+    /// Unrecognised model or a recording without device information
+    Other,
+    /// Non-Polar device exposing only the standard Heart Rate Service
+    GenericHrs,
+}
+
+impl DeviceModel {
+    /// Detect the model from an advertised name such as "Polar H10 12345678"
+    ///
+    /// The Verity Sense advertises itself as "Polar Sense".
+    pub fn from_name(name: &str) -> Self {
+        let name = name.to_lowercase();
+        let has_word = |word: &str| name.split_whitespace().any(|w| w == word);
+
+        if has_word("h10") {
+            DeviceModel::H10
+        } else if has_word("h9") {
+            DeviceModel::H9
+        } else if has_word("sense") {
+            DeviceModel::VeritySense
+        } else if has_word("oh1") {
+            DeviceModel::Oh1
+        } else {
+            DeviceModel::Other
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            DeviceModel::H10 => "Polar H10",
+            DeviceModel::H9 => "Polar H9",
+            DeviceModel::VeritySense => "Polar Verity Sense",
+            DeviceModel::Oh1 => "Polar OH1",
+            DeviceModel::Other => "Polar device",
+            DeviceModel::GenericHrs => "Heart rate sensor",
+        }
+    }
+
+    /// Whether the model is worn on the chest, upright while standing
+    pub fn is_chest_strap(&self) -> bool {
+        matches!(self, DeviceModel::H10 | DeviceModel::H9)
+    }
+
+    /// All models that can be selected for the simulated device
+    pub fn all() -> [DeviceModel; 4] {
+        [DeviceModel::H10, DeviceModel::H9, DeviceModel::VeritySense, DeviceModel::Oh1]
+    }
+}
+
+impl std::fmt::Display for DeviceModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.display_name())
+    }
+}
+
+/// Streams offered by a device
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceProfile {
+    pub model: DeviceModel,
+    streams: Vec<SensorStream>,
+}
+
+impl DeviceProfile {
+    /// Profile with the streams a model is known to offer
+    pub fn for_model(model: DeviceModel) -> Self {
+        use SensorStream::*;
+
+        let streams = match model {
+            DeviceModel::H10 => vec![HeartRate, Ecg, Acc],
+            DeviceModel::H9 => vec![HeartRate],
+            DeviceModel::VeritySense => vec![HeartRate, Ppg, Ppi, Acc, Gyro, Mag],
+            DeviceModel::Oh1 => vec![HeartRate, Ppg, Ppi, Acc],
+            DeviceModel::Other | DeviceModel::GenericHrs => vec![HeartRate],
+        };
+        Self { model, streams }
+    }
+
+    /// Profile with an explicit stream set, e.g. detected from a recording
+    pub fn with_streams(model: DeviceModel, streams: Vec<SensorStream>) -> Self {
+        Self { model, streams }
+    }
+
+    pub fn streams(&self) -> &[SensorStream] {
+        &self.streams
+    }
+
+    pub fn supports(&self, stream: SensorStream) -> bool {
+        self.streams.contains(&stream)
+    }
+
+    /// Streams delivered through the PMD measurement service
+    pub fn has_measurement_streams(&self) -> bool {
+        self.streams.iter().any(|s| *s != SensorStream::HeartRate)
+    }
+
+    /// Sample rate in Hz used until the device reports its configured rate
+    pub fn default_sample_rate(&self, stream: SensorStream) -> u64 {
+        match (self.model, stream) {
+            (_, SensorStream::Ecg) => 130,
+            (DeviceModel::VeritySense, SensorStream::Acc) => 52,
+            (DeviceModel::Oh1, SensorStream::Acc) => 50,
+            (_, SensorStream::Acc) => 200,
+            (DeviceModel::Oh1, SensorStream::Ppg) => 130,
+            (_, SensorStream::Ppg) => 55,
+            (_, SensorStream::Gyro) => 52,
+            (_, SensorStream::Mag) => 50,
+            (_, SensorStream::HeartRate | SensorStream::Ppi) => 1,
+        }
+    }
+}
+
+impl Default for DeviceProfile {
+    fn default() -> Self {
+        Self::for_model(DeviceModel::H10)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_from_name() {
+        assert_eq!(DeviceModel::from_name("Polar H10 12345678"), DeviceModel::H10);
+        assert_eq!(DeviceModel::from_name("Polar H9 ABCDEF01"), DeviceModel::H9);
+        assert_eq!(DeviceModel::from_name("Polar Sense B5A7C21F"), DeviceModel::VeritySense);
+        assert_eq!(DeviceModel::from_name("Polar OH1 1A2B3C4D"), DeviceModel::Oh1);
+        assert_eq!(DeviceModel::from_name("Polar H7 12345678"), DeviceModel::Other);
+    }
+
+    #[test]
+    fn test_profile_streams() {
+        let h9 = DeviceProfile::for_model(DeviceModel::H9);
+        assert!(h9.supports(SensorStream::HeartRate));
+        assert!(!h9.supports(SensorStream::Ecg));
+        assert!(!h9.has_measurement_streams());
+
+        let sense = DeviceProfile::for_model(DeviceModel::VeritySense);
+        assert!(sense.supports(SensorStream::Ppg));
+        assert!(sense.supports(SensorStream::Mag));
+        assert!(!sense.supports(SensorStream::Ecg));
+
+        assert!(!DeviceProfile::for_model(DeviceModel::Oh1).supports(SensorStream::Gyro));
+    }
+}
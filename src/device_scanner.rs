@@ -1,44 +1,93 @@
 //! # Bluetooth Device Scanner Module
 //!
-//! Discovers nearby Polar heart rate sensors using btleplug.
-//! Filters for devices with "polar" in their advertised name.
+//! Discovers nearby heart rate sensors using btleplug.
+//! Keeps devices with "polar" in their advertised name, detecting the model
+//! (H10, H9, Verity Sense, OH1) from that name, plus any other device that
+//! advertises the standard Heart Rate Service (0x180D).
 //!
 //! ## Scan Process
 //! 1. Get system Bluetooth adapter
 //! 2. Start BLE scan with default filter
 //! 3. Wait 5 seconds for device discovery
 //! 4. Stop scan and collect peripherals
-//! 5. Filter for Polar devices only
+//! 5. Filter for Polar and Heart Rate Service devices
 //!
 //! ## Device Identification
 //! Polar devices advertise with names like "Polar H10 12345678".
 //! The ID is extracted from the last segment of the name for connection.
+//! Other vendors' straps are identified by their Bluetooth address.
+//!
+//! ## Simulated Device
+//! When enabled, a simulated Polar device is appended to the results. It is listed
+//! even if no Bluetooth adapter is available so the app can run headless.
 //!
 //! ## Why Async
 //! BLE scanning operations are async by nature. Running scan in async context
 //! allows non-blocking discovery while UI remains responsive.
 
+use crate::device_profile::DeviceModel;
 use crate::error::ScanError;
+use crate::hrs::HEART_RATE_SERVICE;
+use crate::synthetic::SIMULATED_DEVICE_ID;
 use std::time::Duration;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BluetoothDevice {
     pub id: String,
     pub name: String,
+    pub model: DeviceModel,
 }
 
 impl BluetoothDevice {
+    /// Create a device entry, detecting the model from its name
     pub fn new(id: String, name: String) -> Self {
-        Self { id, name }
+        let model = DeviceModel::from_name(&name);
+        Self { id, name, model }
+    }
+
+    /// The simulated device entry shown in the scanner list
+    pub fn simulated(model: DeviceModel) -> Self {
+        Self {
+            id: SIMULATED_DEVICE_ID.to_string(),
+            name: format!("Simulated {}", model),
+            model,
+        }
+    }
+
+    /// Non-Polar device exposing the standard Heart Rate Service
+    pub fn generic_hrs(address: String, name: String) -> Self {
+        Self {
+            id: address,
+            name,
+            model: DeviceModel::GenericHrs,
+        }
+    }
+
+    /// Whether this entry refers to the simulated device
+    pub fn is_simulated(&self) -> bool {
+        self.id == SIMULATED_DEVICE_ID
     }
 }
 
 /// Scans for nearby Polar Bluetooth devices
-pub async fn scan_devices() -> Result<Vec<BluetoothDevice>, ScanError> {
-    scan_with_btleplug().await
+///
+/// If `simulated_model` is set, a simulated device of that model is appended
+/// after any real devices, and a failed Bluetooth scan still returns it.
+pub async fn scan_devices(simulated_model: Option<DeviceModel>) -> Result<Vec<BluetoothDevice>, ScanError> {
+    let Some(model) = simulated_model else {
+        return scan_with_btleplug().await;
+    };
+
+    let mut devices = scan_with_btleplug().await.unwrap_or_else(|e| {
+        log::warn!("Bluetooth scan failed ({}), listing simulated device only", e);
+        Vec::new()
+    });
+    devices.push(BluetoothDevice::simulated(model));
+    Ok(devices)
 }
 
 async fn scan_with_btleplug() -> Result<Vec<BluetoothDevice>, ScanError> {
+    use btleplug::api::bleuuid::uuid_from_u16;
     use btleplug::api::{Central, Manager as _, Peripheral as _, ScanFilter};
     use btleplug::platform::Manager;
 
@@ -78,21 +127,28 @@ async fn scan_with_btleplug() -> Result<Vec<BluetoothDevice>, ScanError> {
         .map_err(|e| ScanError::ScanFailed(format!("Failed to get peripherals: {}", e)))?;
 
     let mut devices = Vec::new();
+    let heart_rate_service = uuid_from_u16(HEART_RATE_SERVICE);
 
     for peripheral in peripherals {
         // Get peripheral properties
         if let Ok(Some(props)) = peripheral.properties().await {
-            if let Some(local_name) = props.local_name {
-                if local_name.to_lowercase().contains("polar") {
+            let address = peripheral.address().to_string();
+            match props.local_name {
+                Some(local_name) if local_name.to_lowercase().contains("polar") => {
                     // Try to extract device ID from the local name (e.g., "Polar H10 12345678")
                     let id = local_name
                         .split_whitespace()
                         .last()
-                        .unwrap_or(&peripheral.address().to_string())
+                        .unwrap_or(&address)
                         .to_string();
 
                     devices.push(BluetoothDevice::new(id, local_name));
                 }
+                local_name if props.services.contains(&heart_rate_service) => {
+                    let name = local_name.unwrap_or_else(|| format!("Heart Rate Sensor {}", address));
+                    devices.push(BluetoothDevice::generic_hrs(address, name));
+                }
+                _ => {}
             }
         }
     }
@@ -0,0 +1,799 @@
+//! # Recording Export Module
+//!
+//! Converts `recording_*.h5` files into formats that analysis tools read
+//! without knowing the recorder's HDF5 layout.
+//!
+//! ## Formats
+//! - **CSV**: One file per channel with `time_ns`, `elapsed_s` and the value
+//!   column named after channel and unit (e.g. `ecg_uV`). Kubios imports the
+//!   `rr` file as an RR interval series. Event markers go to an extra
+//!   `..._annotations.csv` with onset, duration, label and note.
+//! - **CSV (merged)**: One file per recording. Rows are the union of all
+//!   timestamps; a cell is empty where a channel has no sample at that time.
+//! - **EDF+**: One EDF+C file per device for EDFbrowser and MNE. Periodic
+//!   channels become signals at their sample rate, HR, HRV, respiration and
+//!   the activity channels are held at 1 Hz. Physical min/max are the
+//!   recorded extremes; gaps and event markers are annotations.
+//!   RR, PPI, NN, artifacts and R peaks are beat-to-beat and have no fixed
+//!   rate, so they are CSV/Parquet only.
+//! - **Parquet**: One long-format table per recording with `device_id`,
+//!   `channel`, `unit`, `time` (UTC nanosecond timestamp) and `value`,
+//!   one row group per channel, ready for `pandas.read_parquet`.
+//!
+//! ## Output Names
+//! Files are named after the recording, e.g. `recording_20240101_100000_ecg.csv`.
+//! Multi-device recordings add the device ID, e.g. `..._<device>_ecg.csv`.
+//!
+//! ## Why Not Through the Live Pipeline
+//! Export reads whole channels with `PolarDataManager::read_all_from_file`,
+//! so it works on any recording, including ones that are playing back.
+
+use crate::annotations::Annotation;
+use crate::recorder::{ChannelId, DeviceMetadata, PolarDataManager, RecordingInfo};
+use crate::timeseries::{Gap, Point};
+use chrono::TimeZone;
+use parquet::data_type::{ByteArray, ByteArrayType, Int32Type, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// Nanoseconds in one second
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+
+// Subdirectory next to the recordings that exports go to by default
+const EXPORT_DIRECTORY: &str = "exports";
+
+// EDF data records are one second long
+const EDF_RECORD_SECONDS: u64 = 1;
+
+// Largest magnitude that fits the 8-character EDF physical min/max fields
+const EDF_PHYSICAL_LIMIT: i32 = 9_999_999;
+
+const EDF_DIGITAL_MIN: i32 = i16::MIN as i32;
+const EDF_DIGITAL_MAX: i32 = i16::MAX as i32;
+
+const PARQUET_SCHEMA: &str = "
+    message recording {
+        REQUIRED BINARY device_id (UTF8);
+        REQUIRED BINARY channel (UTF8);
+        REQUIRED BINARY unit (UTF8);
+        REQUIRED INT64 time (TIMESTAMP(NANOS, true));
+        REQUIRED INT32 value;
+    }
+";
+
+/// File format of an export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    CsvMerged,
+    Edf,
+    Parquet,
+}
+
+impl ExportFormat {
+    pub fn all() -> [ExportFormat; 4] {
+        [ExportFormat::Csv, ExportFormat::CsvMerged, ExportFormat::Edf, ExportFormat::Parquet]
+    }
+
+    /// Name used on the command line
+    pub fn cli_name(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::CsvMerged => "csv-merged",
+            ExportFormat::Edf => "edf",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+
+    pub fn from_cli_name(name: &str) -> Option<Self> {
+        Self::all().into_iter().find(|format| format.cli_name() == name)
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::CsvMerged => "CSV (merged)",
+            ExportFormat::Edf => "EDF+",
+            ExportFormat::Parquet => "Parquet",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Directory exports of a recording are written to unless one is given
+pub fn default_output_dir(recording: &Path) -> PathBuf {
+    recording
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(EXPORT_DIRECTORY)
+}
+
+/// Export a recording, returning the files written
+pub fn export_recording(recording: &Path, format: ExportFormat, output_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let recording = Recording::load(recording)?;
+    std::fs::create_dir_all(output_dir)
+        .map_err(|e| format!("Failed to create {}: {}", output_dir.display(), e))?;
+
+    let files = match format {
+        ExportFormat::Csv => write_csv_per_channel(&recording, output_dir)?,
+        ExportFormat::CsvMerged => vec![write_csv_merged(&recording, output_dir)?],
+        ExportFormat::Edf => recording
+            .devices
+            .iter()
+            .map(|device| write_edf(&recording, device, output_dir))
+            .collect::<Result<Vec<_>, _>>()?,
+        ExportFormat::Parquet => vec![write_parquet(&recording, output_dir)?],
+    };
+    log::info!("Exported {} file(s) as {}", files.len(), format);
+    Ok(files)
+}
+
+/// All channels of one device in a recording
+struct RecordedDevice {
+    /// `None` for recordings in the legacy root layout
+    id: Option<String>,
+    metadata: DeviceMetadata,
+    /// Channels with at least one point, sorted by time
+    channels: Vec<(ChannelId, Vec<Point>)>,
+    /// Spans without data; `None` covers the whole device
+    gaps: Vec<(Option<ChannelId>, Gap)>,
+}
+
+impl RecordedDevice {
+    fn load(file_path: &Path, id: Option<String>) -> Result<Self, String> {
+        let device_id = id.as_deref();
+        let mut channels = Vec::new();
+        for channel in ChannelId::all() {
+            // Channels the device did not stream have no group
+            let Ok(mut points) = PolarDataManager::read_all_from_file(file_path, device_id, channel) else {
+                continue;
+            };
+            if !points.is_empty() {
+                points.sort_by_key(|p| p.time);
+                channels.push((channel, points));
+            }
+        }
+
+        let (metadata, gaps) = match device_id {
+            Some(device_id) => {
+                let mut gaps: Vec<_> = PolarDataManager::read_gaps_from_file(file_path, device_id, None)?
+                    .into_iter()
+                    .map(|gap| (None, gap))
+                    .collect();
+                for &(channel, _) in &channels {
+                    let channel_gaps = PolarDataManager::read_gaps_from_file(file_path, device_id, Some(channel))?;
+                    gaps.extend(channel_gaps.into_iter().map(|gap| (Some(channel), gap)));
+                }
+                gaps.sort_by_key(|(_, gap)| gap.start);
+                (PolarDataManager::read_device_metadata(file_path, device_id)?, gaps)
+            }
+            None => (DeviceMetadata::default(), Vec::new()),
+        };
+
+        Ok(Self {
+            id,
+            metadata,
+            channels,
+            gaps,
+        })
+    }
+
+    fn first_time(&self) -> Option<u64> {
+        self.channels.iter().map(|(_, points)| points[0].time).min()
+    }
+
+    /// Sample rate of a periodic channel, from the file or estimated from its timestamps
+    fn sample_rate(&self, channel: ChannelId, points: &[Point]) -> Option<u64> {
+        if let Some(&(_, rate)) = self.metadata.sample_rates.iter().find(|(c, _)| *c == channel) {
+            return Some(rate);
+        }
+        let mut intervals: Vec<u64> = points.windows(2).map(|w| w[1].time - w[0].time).filter(|&d| d > 0).collect();
+        if intervals.is_empty() {
+            return None;
+        }
+        let mid = intervals.len() / 2;
+        let median = *intervals.select_nth_unstable(mid).1;
+        let rate = (NANOS_PER_SECOND as f64 / median as f64).round() as u64;
+        (rate > 0).then_some(rate)
+    }
+}
+
+/// Contents of a recording file
+struct Recording {
+    /// File name without extension, used as prefix of exported files
+    name: String,
+    info: RecordingInfo,
+    devices: Vec<RecordedDevice>,
+    /// Event markers, sorted by time
+    annotations: Vec<Annotation>,
+}
+
+impl Recording {
+    fn load(file_path: &Path) -> Result<Self, String> {
+        let name = file_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .ok_or_else(|| format!("Not a recording file: {}", file_path.display()))?;
+
+        let device_ids = PolarDataManager::devices_in_file(file_path)?;
+        let devices = if device_ids.is_empty() {
+            vec![RecordedDevice::load(file_path, None)?]
+        } else {
+            device_ids
+                .into_iter()
+                .map(|id| RecordedDevice::load(file_path, Some(id)))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        if devices.iter().all(|device| device.channels.is_empty()) {
+            return Err(format!("No data found in {}", file_path.display()));
+        }
+
+        Ok(Self {
+            name,
+            info: PolarDataManager::read_recording_info(file_path)?,
+            devices,
+            annotations: PolarDataManager::read_annotations_from_file(file_path)?,
+        })
+    }
+
+    fn first_time(&self) -> u64 {
+        self.devices.iter().filter_map(RecordedDevice::first_time).min().unwrap_or(0)
+    }
+
+    /// Output path for a device's file, with the device ID if there are several
+    fn output_path(&self, output_dir: &Path, device: &RecordedDevice, suffix: &str) -> PathBuf {
+        let mut name = self.name.clone();
+        if let (true, Some(id)) = (self.devices.len() > 1, &device.id) {
+            name = format!("{}_{}", name, sanitize_name(id));
+        }
+        output_dir.join(format!("{}{}", name, suffix))
+    }
+}
+
+/// Replace characters that are awkward in file and column names
+fn sanitize_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect()
+}
+
+/// Unit in ASCII for headers of formats that cannot hold "µ"
+fn ascii_unit(channel: ChannelId) -> String {
+    channel.unit().replace('µ', "u")
+}
+
+/// CSV column name of a channel, e.g. `ecg_uV`
+fn column_name(channel: ChannelId) -> String {
+    format!("{}_{}", channel.group_name(), sanitize_name(&ascii_unit(channel)))
+}
+
+fn create_file(path: &Path) -> Result<BufWriter<File>, String> {
+    File::create(path)
+        .map(BufWriter::new)
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))
+}
+
+fn write_error(path: &Path) -> impl Fn(std::io::Error) -> String + '_ {
+    move |e| format!("Failed to write {}: {}", path.display(), e)
+}
+
+fn write_csv_per_channel(recording: &Recording, output_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let start = recording.first_time();
+    let mut files = Vec::new();
+
+    for device in &recording.devices {
+        for (channel, points) in &device.channels {
+            let path = recording.output_path(output_dir, device, &format!("_{}.csv", channel.group_name()));
+            let mut out = create_file(&path)?;
+            let mut write = || -> std::io::Result<()> {
+                writeln!(out, "time_ns,elapsed_s,{}", column_name(*channel))?;
+                for point in points {
+                    let elapsed = point.time.saturating_sub(start) as f64 / NANOS_PER_SECOND as f64;
+                    writeln!(out, "{},{:.6},{}", point.time, elapsed, point.value)?;
+                }
+                out.flush()
+            };
+            write().map_err(write_error(&path))?;
+            files.push(path);
+        }
+    }
+
+    if !recording.annotations.is_empty() {
+        let path = output_dir.join(format!("{}_annotations.csv", recording.name));
+        let mut out = create_file(&path)?;
+        let mut write = || -> std::io::Result<()> {
+            writeln!(out, "time_ns,elapsed_s,duration_s,label,note")?;
+            for annotation in &recording.annotations {
+                let elapsed = annotation.time.saturating_sub(start) as f64 / NANOS_PER_SECOND as f64;
+                let duration = annotation.duration_ns as f64 / NANOS_PER_SECOND as f64;
+                writeln!(
+                    out,
+                    "{},{:.6},{:.6},{},{}",
+                    annotation.time,
+                    elapsed,
+                    duration,
+                    csv_text(&annotation.label),
+                    csv_text(&annotation.note)
+                )?;
+            }
+            out.flush()
+        };
+        write().map_err(write_error(&path))?;
+        files.push(path);
+    }
+    Ok(files)
+}
+
+/// Free text as a CSV field, quoted if it holds separators or quotes
+fn csv_text(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn write_csv_merged(recording: &Recording, output_dir: &Path) -> Result<PathBuf, String> {
+    let multi_device = recording.devices.len() > 1;
+    let columns: Vec<(String, &[Point])> = recording
+        .devices
+        .iter()
+        .flat_map(|device| {
+            device.channels.iter().map(move |(channel, points)| {
+                let name = match (&device.id, multi_device) {
+                    (Some(id), true) => format!("{}_{}", sanitize_name(id), column_name(*channel)),
+                    _ => column_name(*channel),
+                };
+                (name, points.as_slice())
+            })
+        })
+        .collect();
+
+    let mut times: Vec<u64> = columns.iter().flat_map(|(_, points)| points.iter().map(|p| p.time)).collect();
+    times.sort_unstable();
+    times.dedup();
+
+    let start = recording.first_time();
+    let path = output_dir.join(format!("{}_merged.csv", recording.name));
+    let mut out = create_file(&path)?;
+    let mut cursors = vec![0usize; columns.len()];
+
+    let mut write = || -> std::io::Result<()> {
+        write!(out, "time_ns,elapsed_s")?;
+        for (name, _) in &columns {
+            write!(out, ",{}", name)?;
+        }
+        writeln!(out)?;
+
+        for &time in &times {
+            let elapsed = time.saturating_sub(start) as f64 / NANOS_PER_SECOND as f64;
+            write!(out, "{},{:.6}", time, elapsed)?;
+            for ((_, points), cursor) in columns.iter().zip(cursors.iter_mut()) {
+                out.write_all(b",")?;
+                if points.get(*cursor).is_some_and(|p| p.time == time) {
+                    write!(out, "{}", points[*cursor].value)?;
+                }
+                // Duplicate timestamps within a channel keep the first value
+                while points.get(*cursor).is_some_and(|p| p.time == time) {
+                    *cursor += 1;
+                }
+            }
+            writeln!(out)?;
+        }
+        out.flush()
+    };
+    write().map_err(write_error(&path))?;
+    Ok(path)
+}
+
+/// One EDF signal with its samples already laid out on the record grid
+struct EdfSignal {
+    label: String,
+    unit: String,
+    samples_per_record: usize,
+    physical_min: i32,
+    physical_max: i32,
+    digital_min: i32,
+    digital_max: i32,
+    samples: Vec<i32>,
+}
+
+impl EdfSignal {
+    /// Place points on a grid of `rate` samples per second starting at `start`
+    ///
+    /// Grid slots without a point hold the previous value, so held channels
+    /// like HR and the sample slots inside gaps repeat the last known value.
+    fn new(channel: ChannelId, points: &[Point], rate: u64, start: u64, records: usize) -> Self {
+        let samples_per_record = (rate * EDF_RECORD_SECONDS) as usize;
+        let len = samples_per_record * records;
+        let mut slots: Vec<Option<i32>> = vec![None; len];
+        for point in points {
+            let offset = (point.time - start) as f64 * rate as f64 / NANOS_PER_SECOND as f64;
+            let index = (offset.round() as usize).min(len - 1);
+            slots[index] = Some(point.value.clamp(-EDF_PHYSICAL_LIMIT, EDF_PHYSICAL_LIMIT));
+        }
+
+        let mut held = points[0].value.clamp(-EDF_PHYSICAL_LIMIT, EDF_PHYSICAL_LIMIT);
+        let values: Vec<i32> = slots
+            .into_iter()
+            .map(|slot| {
+                if let Some(value) = slot {
+                    held = value;
+                }
+                held
+            })
+            .collect();
+
+        let physical_min = values.iter().copied().min().unwrap_or(0);
+        let mut physical_max = values.iter().copied().max().unwrap_or(0);
+        if physical_max == physical_min {
+            physical_max += 1;
+        }
+
+        // Integer data that fits 16 bits is stored exactly, wider ranges are scaled
+        let range = (physical_max - physical_min) as i64;
+        let digital_min = EDF_DIGITAL_MIN;
+        let digital_max = if range <= (EDF_DIGITAL_MAX - EDF_DIGITAL_MIN) as i64 {
+            EDF_DIGITAL_MIN + range as i32
+        } else {
+            EDF_DIGITAL_MAX
+        };
+        let scale = (digital_max - digital_min) as f64 / range as f64;
+        let samples = values
+            .into_iter()
+            .map(|v| digital_min + ((v - physical_min) as f64 * scale).round() as i32)
+            .collect();
+
+        Self {
+            label: channel.group_name().replace('_', " ").to_uppercase(),
+            unit: ascii_unit(channel),
+            samples_per_record,
+            physical_min,
+            physical_max,
+            digital_min,
+            digital_max,
+            samples,
+        }
+    }
+}
+
+/// Append an EDF header field, ASCII only, space padded to `width`
+fn edf_field(header: &mut Vec<u8>, value: &str, width: usize) {
+    let mut bytes: Vec<u8> = value
+        .chars()
+        .map(|c| if c.is_ascii_graphic() || c == ' ' { c as u8 } else { b'_' })
+        .take(width)
+        .collect();
+    bytes.resize(width, b' ');
+    header.extend(bytes);
+}
+
+/// EDF+ subfield: spaces are not allowed inside, empty fields are `X`
+fn edf_subfield(value: &str) -> String {
+    let value = value.trim();
+    if value.is_empty() {
+        "X".to_string()
+    } else {
+        value.replace(' ', "_")
+    }
+}
+
+/// Time-stamped annotation list entry, see the EDF+ specification section 2.2.2
+fn edf_annotation(onset_s: f64, duration_s: Option<f64>, text: &str) -> Vec<u8> {
+    let mut tal = format!("{:+.3}", onset_s).into_bytes();
+    if let Some(duration) = duration_s {
+        tal.push(0x15);
+        tal.extend(format!("{:.3}", duration).into_bytes());
+    }
+    tal.push(0x14);
+    tal.extend(text.bytes());
+    tal.extend([0x14, 0x00]);
+    tal
+}
+
+fn write_edf(recording: &Recording, device: &RecordedDevice, output_dir: &Path) -> Result<PathBuf, String> {
+    let path = recording.output_path(output_dir, device, ".edf");
+
+    // Periodic channels keep their rate; HR, HRV, respiration and activity are held at 1 Hz
+    let signal_channels: Vec<(ChannelId, &[Point], u64)> = device
+        .channels
+        .iter()
+        .filter_map(|(channel, points)| {
+            let rate = match channel {
+                ChannelId::Rr
+                | ChannelId::Ppi
+                | ChannelId::Nn
+                | ChannelId::RrArtifact
+                | ChannelId::RPeak
+                | ChannelId::EcgRr => return None,
+                ChannelId::Hr
+                | ChannelId::Hrv
+                | ChannelId::Respiration
+                | ChannelId::Posture
+                | ChannelId::Activity
+                | ChannelId::Steps
+                | ChannelId::Enmo => 1,
+                _ => device.sample_rate(*channel, points)?,
+            };
+            Some((*channel, points.as_slice(), rate))
+        })
+        .collect();
+    if signal_channels.is_empty() {
+        return Err(format!("No channels with a fixed sample rate to export to {}", path.display()));
+    }
+
+    // EDF start time has one-second resolution
+    let first = signal_channels.iter().map(|(_, points, _)| points[0].time).min().unwrap_or(0);
+    let last = signal_channels.iter().map(|(_, points, _)| points[points.len() - 1].time).max().unwrap_or(0);
+    let start = first - first % NANOS_PER_SECOND;
+    let record_ns = EDF_RECORD_SECONDS * NANOS_PER_SECOND;
+    let records = ((last - start) / record_ns + 1) as usize;
+
+    let signals: Vec<EdfSignal> = signal_channels
+        .iter()
+        .map(|&(channel, points, rate)| EdfSignal::new(channel, points, rate, start, records))
+        .collect();
+
+    // Each record starts with its time-keeping annotation, followed by the
+    // gaps that begin within it
+    let mut annotations: Vec<Vec<u8>> = (0..records)
+        .map(|record| edf_annotation((record as u64 * EDF_RECORD_SECONDS) as f64, None, ""))
+        .collect();
+    for (channel, gap) in &device.gaps {
+        if gap.end < start {
+            continue;
+        }
+        let record = (((gap.start.max(start) - start) / record_ns) as usize).min(records - 1);
+        let onset = gap.start.saturating_sub(start) as f64 / NANOS_PER_SECOND as f64;
+        let duration = gap.duration_ns() as f64 / NANOS_PER_SECOND as f64;
+        let text = match channel {
+            Some(channel) => format!("{} data lost", channel.group_name().replace('_', " ").to_uppercase()),
+            None => "Signal lost".to_string(),
+        };
+        annotations[record].extend(edf_annotation(onset, Some(duration), &text));
+    }
+    for annotation in &recording.annotations {
+        if annotation.end() < start {
+            continue;
+        }
+        let record = (((annotation.time.max(start) - start) / record_ns) as usize).min(records - 1);
+        let onset = annotation.time.saturating_sub(start) as f64 / NANOS_PER_SECOND as f64;
+        let duration = (annotation.duration_ns > 0).then(|| annotation.duration_ns as f64 / NANOS_PER_SECOND as f64);
+        let text = match annotation.note.as_str() {
+            "" => annotation.label.clone(),
+            note => format!("{}: {}", annotation.label, note),
+        };
+        annotations[record].extend(edf_annotation(onset, duration, &text));
+    }
+    let annotation_bytes = annotations.iter().map(Vec::len).max().unwrap_or(0);
+    let annotation_bytes = annotation_bytes + annotation_bytes % 2;
+
+    let local_start = chrono::Local.timestamp_nanos(start as i64);
+    let signal_count = signals.len() + 1;
+    let mut header = Vec::with_capacity(256 * (signal_count + 1));
+    edf_field(&mut header, "0", 8);
+    edf_field(&mut header, &format!("{} X X X", edf_subfield(&recording.info.subject_id)), 80);
+    let equipment = match (&device.metadata.model, &device.id) {
+        (model, _) if !model.is_empty() => edf_subfield(model),
+        (_, Some(id)) => edf_subfield(id),
+        _ => "X".to_string(),
+    };
+    edf_field(
+        &mut header,
+        &format!(
+            "Startdate {} X X {} {}",
+            local_start.format("%d-%b-%Y").to_string().to_uppercase(),
+            equipment,
+            recording.info.description
+        ),
+        80,
+    );
+    edf_field(&mut header, &local_start.format("%d.%m.%y").to_string(), 8);
+    edf_field(&mut header, &local_start.format("%H.%M.%S").to_string(), 8);
+    edf_field(&mut header, &(256 * (signal_count + 1)).to_string(), 8);
+    edf_field(&mut header, "EDF+C", 44);
+    edf_field(&mut header, &records.to_string(), 8);
+    edf_field(&mut header, &EDF_RECORD_SECONDS.to_string(), 8);
+    edf_field(&mut header, &signal_count.to_string(), 4);
+
+    // Signal headers are stored field by field across all signals
+    let annotation_label = "EDF Annotations";
+    let per_signal = |header: &mut Vec<u8>, width: usize, value: &dyn Fn(&EdfSignal) -> String, annotation: &str| {
+        for signal in &signals {
+            edf_field(header, &value(signal), width);
+        }
+        edf_field(header, annotation, width);
+    };
+    per_signal(&mut header, 16, &|s| s.label.clone(), annotation_label);
+    per_signal(&mut header, 80, &|_| "Polar sensor".to_string(), "");
+    per_signal(&mut header, 8, &|s| s.unit.clone(), "");
+    per_signal(&mut header, 8, &|s| s.physical_min.to_string(), "-1");
+    per_signal(&mut header, 8, &|s| s.physical_max.to_string(), "1");
+    per_signal(&mut header, 8, &|s| s.digital_min.to_string(), &EDF_DIGITAL_MIN.to_string());
+    per_signal(&mut header, 8, &|s| s.digital_max.to_string(), &EDF_DIGITAL_MAX.to_string());
+    per_signal(&mut header, 80, &|_| String::new(), "");
+    per_signal(&mut header, 8, &|s| s.samples_per_record.to_string(), &(annotation_bytes / 2).to_string());
+    per_signal(&mut header, 32, &|_| String::new(), "");
+
+    let mut out = create_file(&path)?;
+    let mut write = || -> std::io::Result<()> {
+        out.write_all(&header)?;
+        for (record, annotation) in annotations.iter_mut().enumerate() {
+            for signal in &signals {
+                let begin = record * signal.samples_per_record;
+                for &sample in &signal.samples[begin..begin + signal.samples_per_record] {
+                    out.write_all(&(sample as i16).to_le_bytes())?;
+                }
+            }
+            annotation.resize(annotation_bytes, 0);
+            out.write_all(annotation)?;
+        }
+        out.flush()
+    };
+    write().map_err(write_error(&path))?;
+    Ok(path)
+}
+
+fn write_parquet(recording: &Recording, output_dir: &Path) -> Result<PathBuf, String> {
+    let path = output_dir.join(format!("{}.parquet", recording.name));
+    let parquet_error = |e: parquet::errors::ParquetError| format!("Failed to write {}: {}", path.display(), e);
+
+    let schema = Arc::new(parse_message_type(PARQUET_SCHEMA).map_err(parquet_error)?);
+    let properties = Arc::new(WriterProperties::builder().build());
+    let file = File::create(&path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut writer = SerializedFileWriter::new(file, schema, properties).map_err(parquet_error)?;
+
+    for device in &recording.devices {
+        let device_id = device.id.clone().unwrap_or_default();
+        for (channel, points) in &device.channels {
+            let repeat = |value: &str| vec![ByteArray::from(value); points.len()];
+            let times: Vec<i64> = points.iter().map(|p| p.time as i64).collect();
+            let values: Vec<i32> = points.iter().map(|p| p.value).collect();
+
+            let mut row_group = writer.next_row_group().map_err(parquet_error)?;
+            let mut column = 0;
+            while let Some(mut column_writer) = row_group.next_column().map_err(parquet_error)? {
+                match column {
+                    0 => column_writer.typed::<ByteArrayType>().write_batch(&repeat(&device_id), None, None),
+                    1 => column_writer.typed::<ByteArrayType>().write_batch(&repeat(channel.group_name()), None, None),
+                    2 => column_writer.typed::<ByteArrayType>().write_batch(&repeat(channel.unit()), None, None),
+                    3 => column_writer.typed::<Int64Type>().write_batch(&times, None, None),
+                    _ => column_writer.typed::<Int32Type>().write_batch(&values, None, None),
+                }
+                .map_err(parquet_error)?;
+                column_writer.close().map_err(parquet_error)?;
+                column += 1;
+            }
+            row_group.close().map_err(parquet_error)?;
+        }
+    }
+
+    writer.close().map_err(parquet_error)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal::test_recordings::{self, START};
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use tempfile::tempdir;
+
+    const MS: u64 = 1_000_000;
+
+    /// 2 s of 130 Hz ECG and 1 Hz HR, with a gap and a marker
+    fn test_recording(dir: &Path) -> PathBuf {
+        test_recordings::build(dir, None, |journal| {
+            for i in 0..260u64 {
+                let time = START + i * NANOS_PER_SECOND / 130;
+                journal.add_point("A", ChannelId::Ecg, time, (i % 50) as i32 * 10 - 200).unwrap();
+            }
+            for i in 0..2u64 {
+                journal.add_point("A", ChannelId::Hr, START + i * NANOS_PER_SECOND, 60 + i as i32).unwrap();
+            }
+            journal.add_gap("A", None, Gap { start: START + 500 * MS, end: START + 700 * MS }).unwrap();
+            journal.add_annotation(&Annotation::new(START + 1_500 * MS, "Stimulus", "Tone, 1 kHz")).unwrap();
+        })
+    }
+
+    #[test]
+    fn test_csv_per_channel() {
+        let dir = tempdir().unwrap();
+        let recording = test_recording(dir.path());
+        let files = export_recording(&recording, ExportFormat::Csv, &dir.path().join("out")).unwrap();
+
+        let names: Vec<_> = files.iter().map(|f| f.file_name().unwrap().to_string_lossy().to_string()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "recording_20240101_100000_ecg.csv",
+                "recording_20240101_100000_hr.csv",
+                "recording_20240101_100000_annotations.csv",
+            ]
+        );
+
+        let hr = std::fs::read_to_string(&files[1]).unwrap();
+        let lines: Vec<_> = hr.lines().collect();
+        assert_eq!(lines[0], "time_ns,elapsed_s,hr_bpm");
+        assert_eq!(lines[2], format!("{},1.000000,61", START + NANOS_PER_SECOND));
+
+        let annotations = std::fs::read_to_string(&files[2]).unwrap();
+        let lines: Vec<_> = annotations.lines().collect();
+        assert_eq!(lines[0], "time_ns,elapsed_s,duration_s,label,note");
+        assert_eq!(lines[1], format!("{},1.500000,0.000000,Stimulus,\"Tone, 1 kHz\"", START + 1_500 * MS));
+    }
+
+    #[test]
+    fn test_csv_merged_uses_common_timeline() {
+        let dir = tempdir().unwrap();
+        let recording = test_recording(dir.path());
+        let files = export_recording(&recording, ExportFormat::CsvMerged, dir.path()).unwrap();
+
+        let csv = std::fs::read_to_string(&files[0]).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines[0], "time_ns,elapsed_s,ecg_uV,hr_bpm");
+        // ECG and HR share the first timestamp, the next row has ECG only
+        assert_eq!(lines[1], format!("{},0.000000,-200,60", START));
+        assert!(lines[2].ends_with(",-190,"));
+        // 260 ECG samples; the HR sample at 1 s coincides with ECG sample 130
+        assert_eq!(lines.len(), 1 + 260);
+    }
+
+    #[test]
+    fn test_edf_layout() {
+        let dir = tempdir().unwrap();
+        let recording = test_recording(dir.path());
+        let files = export_recording(&recording, ExportFormat::Edf, dir.path()).unwrap();
+        let edf = std::fs::read(&files[0]).unwrap();
+
+        let field = |offset: usize, width: usize| String::from_utf8_lossy(&edf[offset..offset + width]).trim().to_string();
+        assert_eq!(field(184, 8), "1024"); // 256 * (ECG, HR, annotations + 1)
+        assert_eq!(field(192, 44), "EDF+C");
+        assert_eq!(field(236, 8), "2"); // records
+        assert_eq!(field(252, 4), "3");
+        assert_eq!(field(256, 16), "ECG");
+        assert_eq!(field(256 + 32, 16), "EDF Annotations");
+
+        // Physical min/max of ECG follow the data, digital range stores it exactly
+        let signal_field = |offset: usize, width: usize, index: usize| field(256 + offset * 3 + index * width, width);
+        assert_eq!(signal_field(16 + 80, 8, 0), "uV");
+        assert_eq!(signal_field(16 + 80 + 8, 8, 0), "-200");
+        assert_eq!(signal_field(16 + 80 + 16, 8, 0), "290");
+        assert_eq!(signal_field(16 + 80 + 40 + 80, 8, 0), "130");
+
+        // The gap is annotated in the first record
+        let annotation_samples: usize = signal_field(16 + 80 + 40 + 80, 8, 2).parse().unwrap();
+        assert_eq!(edf.len(), 1024 + 2 * 2 * (130 + 1 + annotation_samples));
+        let first_record = &edf[1024..1024 + 2 * (130 + 1 + annotation_samples)];
+        let annotation = String::from_utf8_lossy(&first_record[2 * 131..]);
+        assert!(annotation.starts_with("+0.000\u{14}\u{14}\0+0.500\u{15}0.200\u{14}Signal lost"));
+
+        // The marker is annotated in the second record
+        let second_record = &edf[1024 + 2 * (130 + 1 + annotation_samples)..];
+        let annotation = String::from_utf8_lossy(&second_record[2 * 131..]);
+        assert!(annotation.starts_with("+1.000\u{14}\u{14}\0+1.500\u{14}Stimulus: Tone, 1 kHz\u{14}"));
+    }
+
+    #[test]
+    fn test_parquet_rows() {
+        let dir = tempdir().unwrap();
+        let recording = test_recording(dir.path());
+        let files = export_recording(&recording, ExportFormat::Parquet, dir.path()).unwrap();
+
+        let reader = SerializedFileReader::new(File::open(&files[0]).unwrap()).unwrap();
+        let metadata = reader.metadata();
+        assert_eq!(metadata.num_row_groups(), 2);
+        assert_eq!(metadata.file_metadata().num_rows(), 262);
+        assert_eq!(metadata.file_metadata().schema_descr().column(3).name(), "time");
+        assert_eq!(ExportFormat::from_cli_name("csv-merged"), Some(ExportFormat::CsvMerged));
+    }
+}
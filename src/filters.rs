@@ -0,0 +1,360 @@
+//! # Signal Filter Module
+//!
+//! Streaming filters that clean up waveform channels for display, applied
+//! sample by sample as data arrives.
+//!
+//! ## Chain
+//! Each filtered channel (ECG, PPG) has its own `FilterChain`, configured by
+//! its `FilterSettings` in the `SignalFilters` of `Config`. Stages run in this
+//! order, each skipped when disabled or when its frequency is not below the
+//! Nyquist frequency:
+//! 1. **High-pass**: Removes baseline wander from breathing and movement
+//! 2. **Notch**: Removes 50 or 60 Hz mains interference
+//! 3. **Low-pass**: Removes muscle and high-frequency noise
+//! 4. **Median**: Removes single-sample spikes
+//!
+//! The IIR stages are second-order Butterworth (notch: Q = `NOTCH_Q`)
+//! sections. They shift the signal by a few milliseconds, and the median by
+//! half its length; fine for viewing, which is why the raw samples are what
+//! gets recorded and analyzed.
+//!
+//! ## Gaps
+//! A step between samples longer than `MAX_SAMPLE_STEP_NS` restarts the
+//! chain from the next sample, so a gap does not ring through the filters.
+
+use crate::device_profile::SensorStream;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+
+// Quality factor of the mains notch: about 5 Hz wide at 50 Hz
+const NOTCH_Q: f64 = 10.0;
+
+// Longer steps between samples restart the chain
+const MAX_SAMPLE_STEP_NS: u64 = 250_000_000;
+
+/// Second-order IIR filter section (RBJ cookbook designs)
+#[derive(Debug, Clone, Copy)]
+pub struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    fn new(b: [f64; 3], a: [f64; 3]) -> Self {
+        Self {
+            b: [b[0] / a[0], b[1] / a[0], b[2] / a[0]],
+            a: [a[1] / a[0], a[2] / a[0]],
+            x: [0.0; 2],
+            y: [0.0; 2],
+        }
+    }
+
+    /// Butterworth high-pass
+    pub fn highpass(cutoff_hz: f64, sample_rate: f64) -> Self {
+        let (cos, alpha) = Self::design(cutoff_hz, sample_rate, std::f64::consts::FRAC_1_SQRT_2);
+        Self::new(
+            [(1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        )
+    }
+
+    /// Butterworth low-pass
+    pub fn lowpass(cutoff_hz: f64, sample_rate: f64) -> Self {
+        let (cos, alpha) = Self::design(cutoff_hz, sample_rate, std::f64::consts::FRAC_1_SQRT_2);
+        Self::new(
+            [(1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0],
+            [1.0 + alpha, -2.0 * cos, 1.0 - alpha],
+        )
+    }
+
+    /// Band-stop at `center_hz` with quality factor `q`
+    pub fn notch(center_hz: f64, q: f64, sample_rate: f64) -> Self {
+        let (cos, alpha) = Self::design(center_hz, sample_rate, q);
+        Self::new([1.0, -2.0 * cos, 1.0], [1.0 + alpha, -2.0 * cos, 1.0 - alpha])
+    }
+
+    /// Cosine of the normalized frequency and the bandwidth term for quality `q`
+    fn design(frequency_hz: f64, sample_rate: f64, q: f64) -> (f64, f64) {
+        let omega = 2.0 * std::f64::consts::PI * frequency_hz / sample_rate;
+        (omega.cos(), omega.sin() / (2.0 * q))
+    }
+
+    /// Set the state as if `input` had been constant forever, avoiding a
+    /// start-up transient from a signal offset
+    pub fn prime(&mut self, input: f64) {
+        let dc_gain = (self.b[0] + self.b[1] + self.b[2]) / (1.0 + self.a[0] + self.a[1]);
+        self.x = [input; 2];
+        self.y = [dc_gain * input; 2];
+    }
+
+    pub fn process(&mut self, input: f64) -> f64 {
+        let output = self.b[0] * input + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1];
+        self.x = [input, self.x[0]];
+        self.y = [output, self.y[0]];
+        output
+    }
+}
+
+/// Running median over the latest samples
+#[derive(Debug, Clone)]
+struct MedianFilter {
+    length: usize,
+    window: VecDeque<f64>,
+}
+
+impl MedianFilter {
+    fn new(length: usize) -> Self {
+        Self { length, window: VecDeque::with_capacity(length) }
+    }
+
+    fn process(&mut self, input: f64) -> f64 {
+        if self.window.len() == self.length {
+            self.window.pop_front();
+        }
+        self.window.push_back(input);
+        let mut sorted: Vec<f64> = self.window.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        (sorted[(sorted.len() - 1) / 2] + sorted[sorted.len() / 2]) / 2.0
+    }
+}
+
+/// Power line frequency removed by the notch stage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum MainsFrequency {
+    Off,
+    #[default]
+    Hz50,
+    Hz60,
+}
+
+impl MainsFrequency {
+    pub fn all() -> [MainsFrequency; 3] {
+        [MainsFrequency::Off, MainsFrequency::Hz50, MainsFrequency::Hz60]
+    }
+
+    fn hz(&self) -> Option<f64> {
+        match self {
+            MainsFrequency::Off => None,
+            MainsFrequency::Hz50 => Some(50.0),
+            MainsFrequency::Hz60 => Some(60.0),
+        }
+    }
+}
+
+impl fmt::Display for MainsFrequency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.hz() {
+            Some(hz) => write!(f, "{} Hz", hz),
+            None => write!(f, "Off"),
+        }
+    }
+}
+
+/// Stages of one channel's filter chain
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FilterSettings {
+    /// Produce a filtered version of the channel at all
+    pub enabled: bool,
+    /// High-pass cutoff in Hz for baseline removal, 0 disables
+    pub highpass_hz: f64,
+    pub notch: MainsFrequency,
+    /// Low-pass cutoff in Hz, 0 disables
+    pub lowpass_hz: f64,
+    /// Median filter length in samples, below 2 disables
+    pub median_samples: usize,
+}
+
+impl FilterSettings {
+    /// Diagnostic-bandwidth ECG: 0.5-40 Hz without mains
+    pub fn ecg() -> Self {
+        Self {
+            enabled: true,
+            highpass_hz: 0.5,
+            notch: MainsFrequency::Hz50,
+            lowpass_hz: 40.0,
+            median_samples: 0,
+        }
+    }
+
+    /// Pulse wave: drift removed, heart rate harmonics kept
+    pub fn ppg() -> Self {
+        Self {
+            enabled: true,
+            highpass_hz: 0.3,
+            notch: MainsFrequency::Off,
+            lowpass_hz: 8.0,
+            median_samples: 0,
+        }
+    }
+}
+
+impl Default for FilterSettings {
+    fn default() -> Self {
+        Self::ecg()
+    }
+}
+
+/// Filter settings of every filtered stream
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SignalFilters {
+    pub ecg: FilterSettings,
+    pub ppg: FilterSettings,
+    /// Record the filtered signals as derived channels next to the raw ones
+    pub record: bool,
+}
+
+impl SignalFilters {
+    /// Settings for a stream, if it is filtered
+    pub fn for_stream(&self, stream: SensorStream) -> Option<FilterSettings> {
+        match stream {
+            SensorStream::Ecg => Some(self.ecg),
+            SensorStream::Ppg => Some(self.ppg),
+            _ => None,
+        }
+    }
+}
+
+impl Default for SignalFilters {
+    fn default() -> Self {
+        Self { ecg: FilterSettings::ecg(), ppg: FilterSettings::ppg(), record: false }
+    }
+}
+
+/// Configured filters of one channel, with their state
+#[derive(Debug, Clone)]
+pub struct FilterChain {
+    settings: FilterSettings,
+    sample_rate: u64,
+    biquads: Vec<Biquad>,
+    median: Option<MedianFilter>,
+    last_time: Option<u64>,
+}
+
+impl FilterChain {
+    pub fn new(settings: FilterSettings, sample_rate: u64) -> Self {
+        let rate = sample_rate.max(1) as f64;
+        let below_nyquist = |hz: f64| hz > 0.0 && hz < rate / 2.0;
+
+        let mut biquads = Vec::new();
+        if below_nyquist(settings.highpass_hz) {
+            biquads.push(Biquad::highpass(settings.highpass_hz, rate));
+        }
+        if let Some(hz) = settings.notch.hz().filter(|&hz| below_nyquist(hz)) {
+            biquads.push(Biquad::notch(hz, NOTCH_Q, rate));
+        }
+        if below_nyquist(settings.lowpass_hz) {
+            biquads.push(Biquad::lowpass(settings.lowpass_hz, rate));
+        }
+        let median = (settings.median_samples >= 2).then(|| MedianFilter::new(settings.median_samples));
+
+        Self { settings, sample_rate, biquads, median, last_time: None }
+    }
+
+    pub fn settings(&self) -> FilterSettings {
+        self.settings
+    }
+
+    /// Same settings at a new sample rate, with fresh state
+    pub fn with_sample_rate(&self, sample_rate: u64) -> Self {
+        Self::new(self.settings, sample_rate)
+    }
+
+    /// Filter the sample taken at `time` (nanoseconds)
+    pub fn process(&mut self, time: u64, value: i32) -> i32 {
+        let restart = match self.last_time {
+            Some(last) => time <= last || time - last > MAX_SAMPLE_STEP_NS,
+            None => true,
+        };
+        if restart {
+            *self = Self::new(self.settings, self.sample_rate);
+        }
+        self.last_time = Some(time);
+
+        let mut output = value as f64;
+        for biquad in &mut self.biquads {
+            if restart {
+                biquad.prime(output);
+            }
+            output = biquad.process(output);
+        }
+        if let Some(median) = &mut self.median {
+            output = median.process(output);
+        }
+        output.round() as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u64 = 130;
+    const STEP_NS: u64 = 1_000_000_000 / RATE;
+
+    fn sine(hz: f64, amplitude: f64, i: u64) -> f64 {
+        amplitude * (2.0 * std::f64::consts::PI * hz * i as f64 / RATE as f64).sin()
+    }
+
+    /// Largest absolute output after the filters settled
+    fn settled_peak(chain: &mut FilterChain, signal: impl Fn(u64) -> f64) -> i32 {
+        (0..10 * RATE)
+            .map(|i| chain.process(i * STEP_NS, signal(i).round() as i32))
+            .skip(5 * RATE as usize)
+            .map(i32::abs)
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_removes_mains_and_baseline() {
+        let only = |settings: FilterSettings| FilterChain::new(settings, RATE);
+        let off = FilterSettings {
+            highpass_hz: 0.0,
+            notch: MainsFrequency::Off,
+            lowpass_hz: 0.0,
+            ..FilterSettings::ecg()
+        };
+
+        // Mains hum is removed, a 10 Hz QRS component passes
+        let mut notch = only(FilterSettings { notch: MainsFrequency::Hz50, ..off });
+        assert!(settled_peak(&mut notch, |i| sine(50.0, 500.0, i)) < 25);
+        let mut notch = only(FilterSettings { notch: MainsFrequency::Hz50, ..off });
+        assert!(settled_peak(&mut notch, |i| sine(10.0, 500.0, i)) > 450);
+
+        // Offset and breathing wander are removed without a start-up jump
+        let mut highpass = only(FilterSettings { highpass_hz: 0.5, ..off });
+        let first = highpass.process(0, 3000);
+        assert_eq!(first, 0);
+        assert!(settled_peak(&mut highpass, |i| 3000.0 + sine(0.05, 400.0, i)) < 40);
+    }
+
+    #[test]
+    fn test_median_and_stage_limits() {
+        let settings = FilterSettings {
+            enabled: true,
+            highpass_hz: 0.0,
+            notch: MainsFrequency::Off,
+            lowpass_hz: 0.0,
+            median_samples: 3,
+        };
+        let mut median = FilterChain::new(settings, RATE);
+        let output: Vec<i32> = [10, 10, 900, 10, 10]
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| median.process(i as u64 * STEP_NS, v))
+            .collect();
+        assert_eq!(output[2], 10);
+
+        // At 55 Hz neither the 50 Hz notch nor a 40 Hz low-pass can work
+        let slow = FilterChain::new(FilterSettings::ecg(), 55);
+        assert_eq!(slow.biquads.len(), 1);
+        assert!(slow.median.is_none());
+    }
+}
@@ -0,0 +1,384 @@
+//! # Headless Recording Module
+//!
+//! Records sensors to HDF5 without opening the window, for unattended
+//! captures on a lab machine over SSH (`zen-signal record`).
+//!
+//! ## Flow
+//! 1. Scan until every requested device is found, or with autoconnect the
+//!    first Polar device
+//! 2. Connect them through the same `ConnectionManager` the window uses
+//! 3. Start recording once the first device streams. Updates go through
+//!    `DeviceSession` exactly as in the window, so recordings are identical
+//! 4. Print a status line at a fixed interval
+//! 5. Stop after the duration, on Ctrl+C/SIGINT or once no device is left:
+//!    devices are disconnected and the recorder's final flush completes
+//!    before the process exits
+//!
+//! Sources reconnect after link loss on their own, as in the window; the
+//! gaps end up in the recording.
+
+use crate::config::Config;
+use crate::connection::{ConnectionCommand, ConnectionManager};
+use crate::device_profile::DeviceModel;
+use crate::device_scanner::{scan_devices, BluetoothDevice};
+use crate::recorder::{PolarDataManager, RecordingInfo};
+use crate::sensor::{ConnectionStatus, DeviceUpdate, SensorUpdate};
+use crate::session::DeviceSession;
+use crate::synthetic::SyntheticConfig;
+use crate::visualization::system_time_ns;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// Seconds of live data the recorder keeps in memory
+const LIVE_BUFFER_SECONDS: u64 = 30;
+
+// Longest wait for an update before checking the stop conditions
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// How long devices get to disconnect cleanly when stopping
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What to record and for how long
+#[derive(Debug, Clone)]
+pub struct RecordOptions {
+    /// Devices to connect; empty with `autoconnect` picks the first Polar device
+    pub device_ids: Vec<String>,
+    pub autoconnect: bool,
+    /// Record the simulated device of this model instead of scanning
+    pub simulated: Option<DeviceModel>,
+    /// Recording length, or until interrupted
+    pub duration: Option<Duration>,
+    pub output_dir: PathBuf,
+    pub info: RecordingInfo,
+    pub status_interval: Duration,
+    /// How long to keep scanning for the requested devices
+    pub scan_timeout: Duration,
+}
+
+/// Record until the duration passes or the process is interrupted
+///
+/// Returns the recording file, or `None` if no device ever streamed.
+pub fn record(options: &RecordOptions, config: &Config) -> Result<Option<PathBuf>, String> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .enable_all()
+        .build()
+        .map_err(|e| format!("Failed to create runtime: {}", e))?;
+
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let flag = interrupted.clone();
+    runtime.spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            flag.store(true, Ordering::Relaxed);
+        }
+    });
+
+    let devices = match options.simulated {
+        Some(model) => vec![BluetoothDevice::simulated(model)],
+        None => runtime.block_on(find_devices(options, &interrupted))?,
+    };
+    if devices.is_empty() {
+        return Ok(None);
+    }
+
+    let (sender, receiver) = mpsc::channel::<DeviceUpdate>();
+    let (manager, connect_sender) = ConnectionManager::new(sender);
+    std::thread::spawn(move || {
+        manager.run();
+    });
+
+    let mut recorder = HeadlessRecorder {
+        options,
+        recorder: PolarDataManager::new(LIVE_BUFFER_SECONDS, config.recording_max_memory_mb)?,
+        sessions: Vec::new(),
+        started: None,
+    };
+    for device in devices {
+        println!("Connecting to {} ({})", device.name, device.id);
+        let command = if device.is_simulated() {
+            ConnectionCommand::ConnectSimulated(SyntheticConfig {
+                heart_rate_bpm: config.simulated_heart_rate_bpm as f64,
+                model: device.model,
+            })
+        } else {
+            ConnectionCommand::Connect(device.id.clone(), device.model)
+        };
+        connect_sender
+            .send(command)
+            .map_err(|e| format!("Failed to send connection request: {}", e))?;
+        recorder.sessions.push(
+            DeviceSession::new(device.id, device.name)
+                .with_hrv_window(config.hrv_window_seconds)
+                .with_artifact_correction(config.rr_artifact_correction)
+                .with_hrv_still_only(config.hrv_still_only)
+                .with_filters(config.signal_filters),
+        );
+    }
+
+    let mut last_status = Instant::now();
+    loop {
+        if !recorder.receive(&receiver)? {
+            break;
+        }
+        if interrupted.load(Ordering::Relaxed) {
+            println!("Interrupted, finishing recording");
+            break;
+        }
+        if recorder.sessions.is_empty() {
+            println!("No devices left, finishing recording");
+            break;
+        }
+        if let (Some(started), Some(duration)) = (recorder.started, options.duration) {
+            if started.elapsed() >= duration {
+                println!("Duration reached, finishing recording");
+                break;
+            }
+        }
+        if last_status.elapsed() >= options.status_interval {
+            println!("{}", recorder.status_line());
+            last_status = Instant::now();
+        }
+    }
+
+    // Let devices disconnect cleanly; their last updates are still recorded
+    for session in &recorder.sessions {
+        let _ = connect_sender.send(ConnectionCommand::Disconnect(session.id.clone()));
+    }
+    let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+    while !recorder.sessions.is_empty() && Instant::now() < deadline {
+        if !recorder.receive(&receiver)? {
+            break;
+        }
+    }
+
+    recorder.finish()
+}
+
+/// Scan until the requested devices are found
+async fn find_devices(options: &RecordOptions, interrupted: &AtomicBool) -> Result<Vec<BluetoothDevice>, String> {
+    if options.device_ids.is_empty() && !options.autoconnect {
+        return Err("No device given; use --device <id> or --autoconnect".to_string());
+    }
+
+    let device_ids = unique_device_ids(&options.device_ids);
+    let deadline = Instant::now() + options.scan_timeout;
+    loop {
+        println!("Scanning for devices...");
+        let found = scan_devices(None).await.map_err(|e| e.to_string())?;
+        let selected = select_devices(&found, &device_ids);
+
+        let complete = if device_ids.is_empty() {
+            !selected.is_empty()
+        } else {
+            selected.len() == device_ids.len()
+        };
+        if complete {
+            return Ok(selected);
+        }
+        if interrupted.load(Ordering::Relaxed) {
+            return Ok(Vec::new());
+        }
+        if Instant::now() >= deadline {
+            let missing: Vec<&str> = device_ids
+                .iter()
+                .filter(|id| !selected.iter().any(|d| d.id.eq_ignore_ascii_case(id)))
+                .map(String::as_str)
+                .collect();
+            return Err(if missing.is_empty() {
+                "No Polar device found".to_string()
+            } else {
+                format!("Devices not found: {}", missing.join(", "))
+            });
+        }
+    }
+}
+
+/// Requested device IDs without repeats, which match case-insensitively like the scan
+fn unique_device_ids(device_ids: &[String]) -> Vec<String> {
+    let mut unique: Vec<String> = Vec::new();
+    for id in device_ids {
+        if !unique.iter().any(|u| u.eq_ignore_ascii_case(id)) {
+            unique.push(id.clone());
+        }
+    }
+    unique
+}
+
+/// Requested devices among the found ones, or the first Polar device if none are requested
+fn select_devices(found: &[BluetoothDevice], device_ids: &[String]) -> Vec<BluetoothDevice> {
+    if device_ids.is_empty() {
+        return found
+            .iter()
+            .find(|d| d.name.to_lowercase().contains("polar"))
+            .cloned()
+            .into_iter()
+            .collect();
+    }
+    device_ids
+        .iter()
+        .filter_map(|id| found.iter().find(|d| d.id.eq_ignore_ascii_case(id)).cloned())
+        .collect()
+}
+
+/// Sessions and recorder of a headless run
+struct HeadlessRecorder<'a> {
+    options: &'a RecordOptions,
+    recorder: PolarDataManager,
+    sessions: Vec<DeviceSession>,
+    /// When recording started, once the first device streamed
+    started: Option<Instant>,
+}
+
+impl HeadlessRecorder<'_> {
+    /// Apply the updates that arrive within one poll interval
+    ///
+    /// Returns `false` once the connection thread is gone.
+    fn receive(&mut self, receiver: &Receiver<DeviceUpdate>) -> Result<bool, String> {
+        match receiver.recv_timeout(POLL_INTERVAL) {
+            Ok(DeviceUpdate { device_id, update }) => {
+                self.handle_update(device_id, update)?;
+                while let Ok(DeviceUpdate { device_id, update }) = receiver.try_recv() {
+                    self.handle_update(device_id, update)?;
+                }
+                Ok(true)
+            }
+            Err(RecvTimeoutError::Timeout) => Ok(true),
+            Err(RecvTimeoutError::Disconnected) => Ok(false),
+        }
+    }
+
+    fn handle_update(&mut self, device_id: String, update: SensorUpdate) -> Result<(), String> {
+        let now = system_time_ns();
+        let Some(index) = self.sessions.iter().position(|s| s.id == device_id) else {
+            log::debug!("Dropping update for unknown device {}", device_id);
+            return Ok(());
+        };
+
+        match update {
+            SensorUpdate::ConnectionStatus(status) => match status {
+                ConnectionStatus::Connecting => {}
+                ConnectionStatus::Connected => {
+                    let session = &mut self.sessions[index];
+                    println!("{} connected", session.name);
+                    session.mark_connected(now, &self.recorder.for_device(&device_id));
+                    if self.started.is_none() {
+                        self.start_recording()?;
+                    }
+                }
+                ConnectionStatus::Reconnecting { attempt } => {
+                    let session = &mut self.sessions[index];
+                    println!("{} lost its link, reconnecting (attempt {})", session.name, attempt);
+                    session.mark_reconnecting(attempt, now);
+                }
+                ConnectionStatus::Disconnected => {
+                    println!("{} disconnected", self.sessions[index].name);
+                    self.sessions.remove(index);
+                }
+                ConnectionStatus::Error(e) => {
+                    eprintln!("{}: {}", self.sessions[index].name, e);
+                    self.sessions.remove(index);
+                }
+            },
+            update => {
+                let recorder = self.recorder.for_device(&device_id);
+                self.sessions[index].handle_update(update, now, Some(&recorder));
+            }
+        }
+        Ok(())
+    }
+
+    fn start_recording(&mut self) -> Result<(), String> {
+        self.recorder
+            .start_recording(&self.options.output_dir, self.options.info.clone())?;
+        // Quality attributes describe the recorded span only
+        for session in self.sessions.iter_mut() {
+            session.channels.reset_quality();
+        }
+        self.started = Some(Instant::now());
+        if let Some(path) = self.recorder.recording_path() {
+            println!("Recording to {}", path.display());
+        }
+        Ok(())
+    }
+
+    /// One line with elapsed time, file size and the state of every device
+    fn status_line(&self) -> String {
+        let elapsed = self.started.map(|s| s.elapsed().as_secs()).unwrap_or(0);
+        let mut line = format!(
+            "[{:02}:{:02}:{:02}] {:.1} MB, {} points",
+            elapsed / 3600,
+            elapsed / 60 % 60,
+            elapsed % 60,
+            self.recorder.memory_usage_mb(),
+            self.recorder.total_points()
+        );
+        for session in &self.sessions {
+            line.push_str(&format!(" | {}: {}", session.name, session.state.label()));
+            if let Some(hr) = session.channels.hr.last_point() {
+                line.push_str(&format!(", HR {}", hr.value));
+            }
+            for &stream in session.channels.profile.streams() {
+                if let Some(report) = session.channels.quality_report(stream) {
+                    line.push_str(&format!(", {} {:.1}%", stream.display_name(), report.completeness() * 100.0));
+                }
+            }
+        }
+        line
+    }
+
+    /// Stop recording and wait for the final flush
+    fn finish(self) -> Result<Option<PathBuf>, String> {
+        let path = self.recorder.recording_path();
+        self.recorder.stop_recording()?;
+        // Dropping the recorder waits for the writer thread to finish the file
+        drop(self.recorder);
+        if let Some(path) = &path {
+            println!("Saved {}", path.display());
+        }
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_select_devices() {
+        let found = vec![
+            BluetoothDevice::new("AA:BB".to_string(), "Wahoo TICKR".to_string()),
+            BluetoothDevice::new("12345678".to_string(), "Polar H10 12345678".to_string()),
+        ];
+        assert_eq!(select_devices(&found, &[])[0].id, "12345678");
+        assert_eq!(select_devices(&found, &["aa:bb".to_string()])[0].name, "Wahoo TICKR");
+        assert!(select_devices(&found, &["missing".to_string()]).is_empty());
+
+        let ids = unique_device_ids(&["AA:BB".to_string(), "aa:bb".to_string(), "12345678".to_string()]);
+        assert_eq!(ids, ["AA:BB", "12345678"]);
+    }
+
+    #[test]
+    fn test_record_simulated_device() {
+        let dir = tempdir().unwrap();
+        let options = RecordOptions {
+            device_ids: Vec::new(),
+            autoconnect: false,
+            simulated: Some(DeviceModel::H10),
+            duration: Some(Duration::from_secs(2)),
+            output_dir: dir.path().to_path_buf(),
+            info: RecordingInfo::default(),
+            status_interval: Duration::from_secs(1),
+            scan_timeout: Duration::from_secs(1),
+        };
+
+        let path = record(&options, &Config::default()).unwrap().unwrap();
+        assert!(!crate::journal::journal_path(&path).exists());
+        let ecg = PolarDataManager::read_all_from_file(&path, Some(crate::synthetic::SIMULATED_DEVICE_ID), crate::recorder::ChannelId::Ecg)
+            .unwrap();
+        assert!(ecg.len() > 130);
+    }
+}
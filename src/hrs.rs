@@ -0,0 +1,364 @@
+//! # Generic Heart Rate Service Module
+//!
+//! Backend for chest straps from any vendor (Garmin, Wahoo, Coospo, ...) that
+//! expose the standard Bluetooth Heart Rate Service (0x180D) but no Polar
+//! measurement service. Talks to the device directly through btleplug.
+//!
+//! ## Key Components
+//! - `HeartRateMeasurement`: Parsed Heart Rate Measurement characteristic (0x2A37)
+//! - `parse_heart_rate_measurement()`: Decoder for the characteristic value
+//! - `HrsSource`: `DataSource` connecting to a device by its Bluetooth address,
+//!   reconnecting with backoff when the link drops
+//!
+//! ## Heart Rate Measurement Format
+//! ```text
+//! byte 0      flags
+//!   bit 0     heart rate is u16 (otherwise u8)
+//!   bit 1-2   sensor contact status
+//!   bit 3     energy expended (u16, kJ) present
+//!   bit 4     RR intervals (u16, 1/1024 s) present
+//! byte 1..    heart rate, energy expended, RR intervals (little endian)
+//! ```
+//!
+//! ## Why Not Arctic
+//! Arctic speaks the Polar protocol only. The Heart Rate Service is simple
+//! enough to read with btleplug directly, and only feeds HR, RR and HRV.
+
+use crate::device_profile::{DeviceModel, DeviceProfile};
+use crate::error::ConnectionError;
+use crate::samples::HeartRateSample;
+use crate::sensor::{ConnectionStatus, SensorUpdate};
+use crate::source::{run_with_reconnect, DataSource, SessionEnd, SourceTask};
+use btleplug::api::bleuuid::uuid_from_u16;
+use btleplug::api::{Central, Manager as _, Peripheral as _, ScanFilter};
+use btleplug::platform::{Manager, Peripheral};
+use futures::StreamExt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Heart Rate Service UUID
+pub const HEART_RATE_SERVICE: u16 = 0x180D;
+
+/// Heart Rate Measurement characteristic UUID
+pub const HEART_RATE_MEASUREMENT: u16 = 0x2A37;
+
+/// Firmware Revision String characteristic UUID (Device Information service)
+const FIRMWARE_REVISION: u16 = 0x2A26;
+
+// How long to look for the device before giving up
+const FIND_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Interval between stop flag checks and device lookups
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+const FLAG_HR_U16: u8 = 0x01;
+const FLAG_CONTACT_DETECTED: u8 = 0x02;
+const FLAG_CONTACT_SUPPORTED: u8 = 0x04;
+const FLAG_ENERGY_EXPENDED: u8 = 0x08;
+const FLAG_RR_INTERVALS: u8 = 0x10;
+
+/// Decoded Heart Rate Measurement characteristic value
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeartRateMeasurement {
+    pub bpm: u16,
+    /// Whether the strap has skin contact, if the sensor reports it
+    pub sensor_contact: Option<bool>,
+    /// Cumulative energy expended in kilojoules
+    pub energy_expended: Option<u16>,
+    /// RR intervals in units of 1/1024 s
+    pub rr_intervals: Vec<u16>,
+}
+
+impl HeartRateMeasurement {
+    /// RR intervals converted to milliseconds
+    pub fn rr_intervals_ms(&self) -> Vec<u16> {
+        self.rr_intervals
+            .iter()
+            .map(|&rr| ((rr as u32 * 1000 + 512) / 1024) as u16)
+            .collect()
+    }
+}
+
+impl From<&HeartRateMeasurement> for HeartRateSample {
+    fn from(measurement: &HeartRateMeasurement) -> Self {
+        let mut sample = HeartRateSample::new(measurement.bpm, measurement.rr_intervals_ms());
+        sample.energy_expended = measurement.energy_expended;
+        sample
+    }
+}
+
+/// Parse a Heart Rate Measurement value, returning None if it is truncated
+pub fn parse_heart_rate_measurement(data: &[u8]) -> Option<HeartRateMeasurement> {
+    let (&flags, mut rest) = data.split_first()?;
+
+    let read_u16 = |rest: &mut &[u8]| -> Option<u16> {
+        let bytes = rest.get(..2)?;
+        let value = u16::from_le_bytes([bytes[0], bytes[1]]);
+        *rest = &rest[2..];
+        Some(value)
+    };
+
+    let bpm = if flags & FLAG_HR_U16 != 0 {
+        read_u16(&mut rest)?
+    } else {
+        let (&bpm, tail) = rest.split_first()?;
+        rest = tail;
+        bpm as u16
+    };
+
+    let sensor_contact = (flags & FLAG_CONTACT_SUPPORTED != 0).then_some(flags & FLAG_CONTACT_DETECTED != 0);
+
+    let energy_expended = if flags & FLAG_ENERGY_EXPENDED != 0 {
+        Some(read_u16(&mut rest)?)
+    } else {
+        None
+    };
+
+    let mut rr_intervals = Vec::new();
+    if flags & FLAG_RR_INTERVALS != 0 {
+        while let Some(rr) = read_u16(&mut rest) {
+            rr_intervals.push(rr);
+        }
+    }
+
+    Some(HeartRateMeasurement {
+        bpm,
+        sensor_contact,
+        energy_expended,
+        rr_intervals,
+    })
+}
+
+/// Data source for a standard Heart Rate Service device
+pub struct HrsSource {
+    /// Bluetooth address as shown by the scanner
+    device_id: String,
+}
+
+impl HrsSource {
+    pub fn new(device_id: String) -> Self {
+        Self { device_id }
+    }
+}
+
+impl DataSource for HrsSource {
+    fn name(&self) -> String {
+        format!("heart rate sensor {}", self.device_id)
+    }
+
+    fn start(self: Box<Self>, sender: Sender<SensorUpdate>, should_stop: Arc<AtomicBool>) -> SourceTask {
+        Box::pin(async move {
+            log::info!("Attempting connection to heart rate sensor {}", self.device_id);
+            let _ = sender.send(SensorUpdate::ConnectionStatus(ConnectionStatus::Connecting));
+
+            run_with_reconnect(&sender, &should_stop, |reconnecting| {
+                let device_id = self.device_id.clone();
+                let sender = sender.clone();
+                let should_stop = should_stop.clone();
+                async move {
+                    match stream_heart_rate(&device_id, &sender, &should_stop).await {
+                        Ok(end) => end,
+                        Err(ConnectionError::Interrupted) => SessionEnd::Stopped,
+                        // Not finding the device again is expected while it is out of range
+                        Err(e) if reconnecting && !matches!(e, ConnectionError::NoAdapter) => {
+                            log::warn!("{}", e);
+                            SessionEnd::LinkLost { streamed: false }
+                        }
+                        Err(e) => SessionEnd::Failed(e),
+                    }
+                }
+            })
+            .await;
+        })
+    }
+}
+
+/// Scan with `filter` until a peripheral accepted by `matches` shows up
+///
+/// `matches` gets the address and the advertised name of each peripheral.
+pub async fn find_peripheral<F>(
+    device_id: &str,
+    filter: ScanFilter,
+    should_stop: &AtomicBool,
+    matches: F,
+) -> Result<Peripheral, ConnectionError>
+where
+    F: Fn(&str, Option<&str>) -> bool,
+{
+    let manager = Manager::new().await.map_err(|_| ConnectionError::NoAdapter)?;
+    let central = manager
+        .adapters()
+        .await
+        .ok()
+        .and_then(|adapters| adapters.into_iter().next())
+        .ok_or(ConnectionError::NoAdapter)?;
+
+    central.start_scan(filter).await.map_err(|e| ConnectionError::DeviceConnection {
+        device_id: device_id.to_string(),
+        reason: format!("Failed to start scan: {}", e),
+    })?;
+
+    let started = std::time::Instant::now();
+    let found = 'scan: loop {
+        if should_stop.load(Ordering::Relaxed) {
+            break Err(ConnectionError::Interrupted);
+        }
+        if let Ok(peripherals) = central.peripherals().await {
+            for p in peripherals {
+                let name = p.properties().await.ok().flatten().and_then(|props| props.local_name);
+                if matches(&p.address().to_string(), name.as_deref()) {
+                    break 'scan Ok(p);
+                }
+            }
+        }
+        if started.elapsed() > FIND_TIMEOUT {
+            break Err(ConnectionError::DeviceConnection {
+                device_id: device_id.to_string(),
+                reason: "Device not found".to_string(),
+            });
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    };
+
+    let _ = central.stop_scan().await;
+    found
+}
+
+/// Firmware revision from the Device Information service, if the sensor offers it
+pub async fn read_firmware_revision(peripheral: &Peripheral) -> Option<String> {
+    let uuid = uuid_from_u16(FIRMWARE_REVISION);
+    let characteristic = peripheral.characteristics().into_iter().find(|c| c.uuid == uuid)?;
+    let value = peripheral.read(&characteristic).await.ok()?;
+    let firmware = String::from_utf8_lossy(&value).trim_end_matches('\0').trim().to_string();
+    (!firmware.is_empty()).then_some(firmware)
+}
+
+/// Connect, subscribe to heart rate notifications and forward them until
+/// the device disconnects or the stop flag is set
+async fn stream_heart_rate(
+    device_id: &str,
+    sender: &Sender<SensorUpdate>,
+    should_stop: &AtomicBool,
+) -> Result<SessionEnd, ConnectionError> {
+    let connection_error = |reason: String| ConnectionError::DeviceConnection {
+        device_id: device_id.to_string(),
+        reason,
+    };
+
+    let filter = ScanFilter {
+        services: vec![uuid_from_u16(HEART_RATE_SERVICE)],
+    };
+    let peripheral = find_peripheral(device_id, filter, should_stop, |address, _| address == device_id).await?;
+    peripheral
+        .connect()
+        .await
+        .map_err(|e| connection_error(e.to_string()))?;
+    peripheral
+        .discover_services()
+        .await
+        .map_err(|e| connection_error(format!("Service discovery failed: {}", e)))?;
+
+    let measurement_uuid = uuid_from_u16(HEART_RATE_MEASUREMENT);
+    let characteristic = peripheral
+        .characteristics()
+        .into_iter()
+        .find(|c| c.uuid == measurement_uuid)
+        .ok_or_else(|| connection_error("No Heart Rate Measurement characteristic".to_string()))?;
+    peripheral
+        .subscribe(&characteristic)
+        .await
+        .map_err(|e| connection_error(format!("Failed to subscribe: {}", e)))?;
+    let mut notifications = peripheral
+        .notifications()
+        .await
+        .map_err(|e| connection_error(e.to_string()))?;
+
+    log::info!("Connected to heart rate sensor {}", device_id);
+    let _ = sender.send(SensorUpdate::ConnectionStatus(ConnectionStatus::Connected));
+    let _ = sender.send(SensorUpdate::DeviceProfile(DeviceProfile::for_model(DeviceModel::GenericHrs)));
+    if let Some(firmware) = read_firmware_revision(&peripheral).await {
+        let _ = sender.send(SensorUpdate::Firmware(firmware));
+    }
+
+    let end = loop {
+        tokio::select! {
+            notification = notifications.next() => {
+                let Some(notification) = notification else {
+                    log::warn!("Heart rate sensor {} disconnected", device_id);
+                    break SessionEnd::LinkLost { streamed: true };
+                };
+                if notification.uuid != measurement_uuid {
+                    continue;
+                }
+                match parse_heart_rate_measurement(&notification.value) {
+                    Some(measurement) => {
+                        if measurement.sensor_contact == Some(false) {
+                            log::debug!("Heart rate sensor reports no skin contact");
+                        }
+                        if let Err(why) = sender.send(SensorUpdate::HeartRate((&measurement).into())) {
+                            log::error!("Could not send heart rate data: {:?}", why);
+                            break SessionEnd::Stopped;
+                        }
+                    }
+                    None => log::warn!("Malformed heart rate measurement: {:?}", notification.value),
+                }
+            }
+            _ = tokio::time::sleep(POLL_INTERVAL) => {
+                if should_stop.load(Ordering::Relaxed) {
+                    log::info!("Disconnecting...");
+                    break SessionEnd::Stopped;
+                }
+            }
+        }
+    };
+
+    let _ = peripheral.disconnect().await;
+    Ok(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_8bit_heart_rate() {
+        let measurement = parse_heart_rate_measurement(&[0x00, 72]).unwrap();
+
+        assert_eq!(measurement.bpm, 72);
+        assert_eq!(measurement.sensor_contact, None);
+        assert_eq!(measurement.energy_expended, None);
+        assert!(measurement.rr_intervals.is_empty());
+    }
+
+    #[test]
+    fn test_parse_16bit_with_energy_and_rr() {
+        // u16 HR 300, contact detected, energy 0x0102 kJ, RR 1024 and 512
+        let flags = FLAG_HR_U16 | FLAG_CONTACT_SUPPORTED | FLAG_CONTACT_DETECTED | FLAG_ENERGY_EXPENDED | FLAG_RR_INTERVALS;
+        let data = [flags, 0x2C, 0x01, 0x02, 0x01, 0x00, 0x04, 0x00, 0x02];
+        let measurement = parse_heart_rate_measurement(&data).unwrap();
+
+        assert_eq!(measurement.bpm, 300);
+        assert_eq!(measurement.sensor_contact, Some(true));
+        assert_eq!(measurement.energy_expended, Some(0x0102));
+        assert_eq!(measurement.rr_intervals, vec![1024, 512]);
+        assert_eq!(measurement.rr_intervals_ms(), vec![1000, 500]);
+    }
+
+    #[test]
+    fn test_rr_conversion_rounds() {
+        let data = [FLAG_RR_INTERVALS, 60, 0xC5, 0x03]; // 965/1024 s = 942.4 ms
+        let sample: HeartRateSample = (&parse_heart_rate_measurement(&data).unwrap()).into();
+
+        assert_eq!(sample.bpm, 60);
+        assert_eq!(sample.rr_intervals, vec![942]);
+    }
+
+    #[test]
+    fn test_parse_truncated() {
+        assert!(parse_heart_rate_measurement(&[]).is_none());
+        assert!(parse_heart_rate_measurement(&[FLAG_HR_U16, 72]).is_none());
+        assert!(parse_heart_rate_measurement(&[FLAG_ENERGY_EXPENDED, 72, 0x01]).is_none());
+    }
+}
@@ -0,0 +1,480 @@
+//! # HRV Analysis Module
+//!
+//! Heart rate variability metrics from a series of NN intervals (normal
+//! beat-to-beat intervals in milliseconds).
+//!
+//! ## Metrics
+//! - **Time domain**: Mean NN, SDNN, RMSSD, pNN50, HR max - min
+//! - **Frequency domain**: LF (0.04-0.15 Hz) and HF (0.15-0.4 Hz) power and
+//!   LF/HF from a Lomb-Scargle periodogram. The intervals are placed at their
+//!   cumulative beat times, so no resampling is needed.
+//! - **Non-linear**: Poincaré SD1/SD2, sample entropy (m = 2, r = 0.2 SDNN)
+//!   and DFA α1 (box sizes 4-16 beats)
+//!
+//! Metrics that need more data than the window holds are `None`: frequency
+//! domain below `MIN_SPECTRAL_DURATION_MS` of beats, DFA α1 below
+//! `MIN_DFA_BEATS` beats.
+//!
+//! ## Input
+//! Callers pass NN intervals: real beats, without the fill points the `rr`
+//! series repeats for display, and with artifacts corrected (`Channels::nn`,
+//! or `artifacts::correct_intervals` for recordings).
+//!
+//! ## Windows
+//! Live metrics cover the last `hrv_window_seconds` (see `Config`) and are
+//! updated when new beats arrive, at most once a second. `analyze_windows`
+//! splits a recording's intervals into windows of the same kind for the `hrv`
+//! command.
+
+use crate::timeseries::Point;
+use std::fmt;
+
+// Fewest intervals for the time-domain and Poincaré metrics
+const MIN_BEATS: usize = 3;
+
+// Shortest span of beats for LF and HF; the Task Force recommends 2 minutes for LF
+const MIN_SPECTRAL_DURATION_MS: f64 = 120_000.0;
+
+// Fewest intervals for DFA α1: two boxes of the largest size
+const MIN_DFA_BEATS: usize = 2 * DFA_MAX_BOX;
+
+const DFA_MIN_BOX: usize = 4;
+const DFA_MAX_BOX: usize = 16;
+
+const LF_BAND: (f64, f64) = (0.04, 0.15);
+const HF_BAND: (f64, f64) = (0.15, 0.4);
+
+// Frequency resolution of the periodogram in Hz
+const FREQUENCY_STEP: f64 = 0.001;
+
+// Sample entropy template length and tolerance as a fraction of SDNN
+const SAMPEN_M: usize = 2;
+const SAMPEN_R: f64 = 0.2;
+
+/// Analysis window lengths offered in the UI
+pub const WINDOW_CHOICES: [WindowLength; 4] = [
+    WindowLength(60),
+    WindowLength(120),
+    WindowLength(300),
+    WindowLength(600),
+];
+
+/// Length of an analysis window in seconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowLength(pub u64);
+
+impl fmt::Display for WindowLength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.0 / 60, self.0 % 60) {
+            (minutes, 0) => write!(f, "{} min", minutes),
+            _ => write!(f, "{} s", self.0),
+        }
+    }
+}
+
+/// HRV metrics of one window
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct HrvMetrics {
+    /// Number of NN intervals analyzed
+    pub beats: usize,
+    /// Mean NN interval in ms
+    pub mean_nn: f64,
+    /// Standard deviation of NN intervals in ms
+    pub sdnn: f64,
+    /// Root mean square of successive differences in ms
+    pub rmssd: f64,
+    /// Percentage of successive differences over 50 ms
+    pub pnn50: f64,
+    /// Difference between the highest and lowest beat-to-beat heart rate in bpm
+    pub hr_max_min: f64,
+    /// Low-frequency power in ms²
+    pub lf: Option<f64>,
+    /// High-frequency power in ms²
+    pub hf: Option<f64>,
+    pub lf_hf: Option<f64>,
+    /// Poincaré plot width (short-term variability) in ms
+    pub sd1: f64,
+    /// Poincaré plot length (long-term variability) in ms
+    pub sd2: f64,
+    /// Sample entropy, `None` if no templates match
+    pub sample_entropy: Option<f64>,
+    /// Short-term DFA scaling exponent
+    pub dfa_alpha1: Option<f64>,
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Sample variance
+fn variance(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let mean = mean(values);
+    values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64
+}
+
+/// Compute all metrics from consecutive NN intervals in ms
+///
+/// Returns `None` with fewer than `MIN_BEATS` intervals.
+pub fn analyze(nn: &[f64]) -> Option<HrvMetrics> {
+    // Beats placed at their cumulative times in seconds
+    let mut elapsed = 0.0;
+    let times: Vec<f64> = nn
+        .iter()
+        .map(|interval| {
+            elapsed += interval;
+            elapsed / 1000.0
+        })
+        .collect();
+    analyze_beat_runs(&[BeatRun { times: &times, nn }])
+}
+
+/// Compute all metrics from runs of consecutive NN intervals, timed in nanoseconds
+///
+/// Beats left out between runs, e.g. while the wearer moved, break the
+/// sequence: successive differences, sample entropy templates and DFA boxes
+/// stay within a run, and the spectrum places every beat at its own time.
+/// Returns `None` with fewer than `MIN_BEATS` intervals or no two consecutive ones.
+pub fn analyze_runs(runs: &[&[Point]]) -> Option<HrvMetrics> {
+    let runs: Vec<(Vec<f64>, Vec<f64>)> = runs
+        .iter()
+        .map(|run| (run.iter().map(|p| p.time as f64 / 1e9).collect(), run.iter().map(|p| p.value as f64).collect()))
+        .collect();
+    let runs: Vec<BeatRun> = runs.iter().map(|(times, nn)| BeatRun { times, nn }).collect();
+    analyze_beat_runs(&runs)
+}
+
+/// RMSSD in ms over runs of consecutive NN intervals, differencing only within a run
+pub fn rmssd_of_runs(runs: &[&[Point]]) -> Option<f64> {
+    let diffs: Vec<f64> = runs
+        .iter()
+        .flat_map(|run| run.windows(2).map(|w| w[1].value as f64 - w[0].value as f64))
+        .collect();
+    (!diffs.is_empty()).then(|| (diffs.iter().map(|d| d * d).sum::<f64>() / diffs.len() as f64).sqrt())
+}
+
+/// Consecutive NN intervals in ms and their beat times in seconds
+struct BeatRun<'a> {
+    times: &'a [f64],
+    nn: &'a [f64],
+}
+
+fn analyze_beat_runs(runs: &[BeatRun]) -> Option<HrvMetrics> {
+    let nn: Vec<f64> = runs.iter().flat_map(|run| run.nn.iter().copied()).collect();
+    let diffs: Vec<f64> = runs
+        .iter()
+        .flat_map(|run| run.nn.windows(2).map(|w| w[1] - w[0]))
+        .collect();
+    if nn.len() < MIN_BEATS || diffs.is_empty() {
+        return None;
+    }
+
+    let sdnn = variance(&nn).sqrt();
+    let rmssd = (diffs.iter().map(|d| d * d).sum::<f64>() / diffs.len() as f64).sqrt();
+    let pnn50 = diffs.iter().filter(|d| d.abs() > 50.0).count() as f64 / diffs.len() as f64 * 100.0;
+
+    let (min_nn, max_nn) = nn.iter().fold((f64::MAX, f64::MIN), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    let hr_max_min = 60_000.0 / min_nn - 60_000.0 / max_nn;
+
+    // SD1² = Var(ΔNN) / 2, SD2² = 2 SDNN² - SD1²
+    let sd1_squared = variance(&diffs) / 2.0;
+    let sd2_squared = (2.0 * sdnn * sdnn - sd1_squared).max(0.0);
+
+    let times: Vec<f64> = runs.iter().flat_map(|run| run.times.iter().copied()).collect();
+    let (lf, hf) = match band_powers(&times, &nn) {
+        Some((lf, hf)) => (Some(lf), Some(hf)),
+        None => (None, None),
+    };
+
+    Some(HrvMetrics {
+        beats: nn.len(),
+        mean_nn: mean(&nn),
+        sdnn,
+        rmssd,
+        pnn50,
+        hr_max_min,
+        lf,
+        hf,
+        lf_hf: lf.zip(hf).and_then(|(lf, hf)| (hf > 0.0).then(|| lf / hf)),
+        sd1: sd1_squared.sqrt(),
+        sd2: sd2_squared.sqrt(),
+        sample_entropy: sample_entropy(runs, SAMPEN_M, SAMPEN_R * sdnn),
+        dfa_alpha1: dfa_alpha1(runs),
+    })
+}
+
+/// LF and HF power in ms² from a Lomb-Scargle periodogram
+///
+/// `times` are the beat times in seconds. The periodogram is scaled to a
+/// power spectral density, so a sinusoid of amplitude A in the intervals
+/// integrates to A²/2.
+fn band_powers(times: &[f64], nn: &[f64]) -> Option<(f64, f64)> {
+    let duration = times[times.len() - 1] - times[0];
+    if duration * 1000.0 < MIN_SPECTRAL_DURATION_MS {
+        return None;
+    }
+
+    let nn_mean = mean(nn);
+    let centered: Vec<f64> = nn.iter().map(|v| v - nn_mean).collect();
+    let density = |frequency: f64| lomb_scargle(times, &centered, frequency) * 2.0 * duration / nn.len() as f64;
+    let band = |(low, high): (f64, f64)| {
+        let steps = ((high - low) / FREQUENCY_STEP).round() as usize;
+        (0..steps)
+            .map(|i| density(low + (i as f64 + 0.5) * FREQUENCY_STEP) * FREQUENCY_STEP)
+            .sum::<f64>()
+    };
+    Some((band(LF_BAND), band(HF_BAND)))
+}
+
+/// Classic Lomb-Scargle power of mean-free `values` at `frequency` in Hz
+///
+/// `times` are in seconds and need not be evenly spaced.
+pub fn lomb_scargle(times: &[f64], values: &[f64], frequency: f64) -> f64 {
+    let omega = 2.0 * std::f64::consts::PI * frequency;
+
+    // Time offset that makes the sine and cosine terms orthogonal
+    let (sin_sum, cos_sum) = times.iter().fold((0.0, 0.0), |(s, c), t| {
+        let angle = 2.0 * omega * t;
+        (s + angle.sin(), c + angle.cos())
+    });
+    let tau = sin_sum.atan2(cos_sum) / (2.0 * omega);
+
+    let (mut yc, mut ys, mut cc, mut ss) = (0.0, 0.0, 0.0, 0.0);
+    for (t, y) in times.iter().zip(values) {
+        let (sin, cos) = (omega * (t - tau)).sin_cos();
+        yc += y * cos;
+        ys += y * sin;
+        cc += cos * cos;
+        ss += sin * sin;
+    }
+    let term = |num: f64, den: f64| if den > 0.0 { num * num / den } else { 0.0 };
+    0.5 * (term(yc, cc) + term(ys, ss))
+}
+
+/// Sample entropy with template length `m` and tolerance `r`
+///
+/// Templates are taken within runs and compared across all of them.
+fn sample_entropy(runs: &[BeatRun], m: usize, r: f64) -> Option<f64> {
+    // Every m + 1 consecutive intervals of a run
+    let templates: Vec<&[f64]> = runs
+        .iter()
+        .filter(|run| run.nn.len() > m)
+        .flat_map(|run| (0..run.nn.len() - m).map(move |i| &run.nn[i..=i + m]))
+        .collect();
+    if templates.len() < 2 || r <= 0.0 {
+        return None;
+    }
+
+    // Pairs of templates matching over m points (b) and m + 1 points (a)
+    let (mut a, mut b) = (0u64, 0u64);
+    for (i, first) in templates.iter().enumerate() {
+        for second in &templates[i + 1..] {
+            if (0..m).all(|k| (first[k] - second[k]).abs() <= r) {
+                b += 1;
+                if (first[m] - second[m]).abs() <= r {
+                    a += 1;
+                }
+            }
+        }
+    }
+    (a > 0 && b > 0).then(|| -(a as f64 / b as f64).ln())
+}
+
+/// Short-term detrended fluctuation analysis exponent
+///
+/// Each run is integrated on its own and cut into boxes that stay within it.
+fn dfa_alpha1(runs: &[BeatRun]) -> Option<f64> {
+    if runs.iter().map(|run| run.nn.len()).sum::<usize>() < MIN_DFA_BEATS {
+        return None;
+    }
+
+    // Integrated, mean-free profile of each run
+    let profiles: Vec<Vec<f64>> = runs
+        .iter()
+        .map(|run| {
+            let run_mean = mean(run.nn);
+            let mut sum = 0.0;
+            run.nn
+                .iter()
+                .map(|v| {
+                    sum += v - run_mean;
+                    sum
+                })
+                .collect()
+        })
+        .collect();
+
+    let points: Vec<(f64, f64)> = (DFA_MIN_BOX..=DFA_MAX_BOX)
+        .filter_map(|size| {
+            let fluctuation = box_fluctuation(&profiles, size)?;
+            (fluctuation > 0.0).then(|| ((size as f64).ln(), fluctuation.ln()))
+        })
+        .collect();
+    (points.len() >= 2).then(|| linear_fit(&points).0)
+}
+
+/// Root mean square deviation of the profiles from a line fitted to each box,
+/// `None` with fewer than two boxes
+fn box_fluctuation(profiles: &[Vec<f64>], size: usize) -> Option<f64> {
+    let mut boxes = 0;
+    let mut squares = 0.0;
+    for chunk in profiles.iter().flat_map(|profile| profile.chunks_exact(size)) {
+        let points: Vec<(f64, f64)> = chunk.iter().enumerate().map(|(i, &y)| (i as f64, y)).collect();
+        let (slope, intercept) = linear_fit(&points);
+        squares += points.iter().map(|(x, y)| (y - (slope * x + intercept)).powi(2)).sum::<f64>();
+        boxes += 1;
+    }
+    (boxes >= 2).then(|| (squares / (boxes * size) as f64).sqrt())
+}
+
+/// Least-squares line through the points as (slope, intercept)
+fn linear_fit(points: &[(f64, f64)]) -> (f64, f64) {
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let sxy: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let sxx: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    let slope = if sxx > 0.0 { sxy / sxx } else { 0.0 };
+    (slope, mean_y - slope * mean_x)
+}
+
+/// Metrics of one window of a recording
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HrvWindow {
+    /// Start and end of the window in nanoseconds
+    pub start: u64,
+    pub end: u64,
+    pub metrics: HrvMetrics,
+}
+
+/// Metrics over consecutive windows of timestamped intervals
+///
+/// Windows of `window_ns` start every `step_ns` from the first interval;
+/// windows with too few beats are left out.
+pub fn analyze_windows(intervals: &[Point], window_ns: u64, step_ns: u64) -> Vec<HrvWindow> {
+    let (Some(first), Some(last)) = (intervals.first(), intervals.last()) else {
+        return Vec::new();
+    };
+
+    let mut windows = Vec::new();
+    let mut start = first.time;
+    loop {
+        let end = start + window_ns;
+        let nn: Vec<f64> = intervals
+            .iter()
+            .filter(|p| p.time >= start && p.time < end)
+            .map(|p| p.value as f64)
+            .collect();
+        if let Some(metrics) = analyze(&nn) {
+            windows.push(HrvWindow { start, end, metrics });
+        }
+        if end > last.time || step_ns == 0 {
+            break;
+        }
+        start += step_ns;
+    }
+    windows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Intervals around 1000 ms modulated by a sine of the given frequency
+    fn modulated(frequency: f64, amplitude: f64, beats: usize) -> Vec<f64> {
+        let mut elapsed = 0.0;
+        (0..beats)
+            .map(|_| {
+                let interval = 1000.0 + amplitude * (2.0 * std::f64::consts::PI * frequency * elapsed / 1000.0).sin();
+                elapsed += interval;
+                interval
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_time_domain() {
+        let metrics = analyze(&[1000.0, 1100.0, 1000.0, 1100.0]).unwrap();
+        assert_eq!(metrics.beats, 4);
+        assert_eq!(metrics.mean_nn, 1050.0);
+        assert!((metrics.rmssd - 100.0).abs() < 1e-9);
+        assert_eq!(metrics.pnn50, 100.0);
+        assert!((metrics.hr_max_min - (60.0 - 60_000.0 / 1100.0)).abs() < 1e-9);
+        // Alternating intervals spread across the Poincaré identity line
+        assert!(metrics.sd1 > metrics.sd2);
+        assert!(metrics.lf.is_none());
+
+        assert!(analyze(&[1000.0, 1000.0]).is_none());
+    }
+
+    #[test]
+    fn test_frequency_bands() {
+        // Breathing at 15/min shows up as HF, a 10 s rhythm as LF
+        let hf = analyze(&modulated(0.25, 50.0, 300)).unwrap();
+        let lf = analyze(&modulated(0.1, 50.0, 300)).unwrap();
+        assert!(hf.lf_hf.unwrap() < 0.2);
+        assert!(lf.lf_hf.unwrap() > 5.0);
+
+        // A sinusoid of amplitude A carries A²/2 of power
+        let power = hf.hf.unwrap();
+        assert!((power - 1250.0).abs() < 250.0, "HF power {}", power);
+    }
+
+    #[test]
+    fn test_nonlinear() {
+        // A periodic series is regular: low entropy
+        let regular = analyze(&modulated(0.25, 50.0, 200)).unwrap();
+        assert!(regular.sample_entropy.unwrap() < 0.5);
+
+        // Uncorrelated intervals give DFA α1 near 0.5
+        let mut state = 12345u64;
+        let noise: Vec<f64> = (0..400)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                900.0 + (state >> 33) as f64 / (1u64 << 31) as f64 * 200.0
+            })
+            .collect();
+        let alpha = analyze(&noise).unwrap().dfa_alpha1.unwrap();
+        assert!((alpha - 0.5).abs() < 0.2, "DFA α1 {}", alpha);
+        assert!(analyze(&noise[..20]).unwrap().dfa_alpha1.is_none());
+    }
+
+    #[test]
+    fn test_runs_keep_differences_within_a_run() {
+        let second = 1_000_000_000;
+        let run = |start: u64, value: i32| -> Vec<Point> {
+            (0..4u64).map(|i| Point { time: (start + i) * second, value }).collect()
+        };
+        let (before, after) = (run(0, 1000), run(20, 700));
+
+        // Steady runs at different rates have no beat-to-beat variability
+        let metrics = analyze_runs(&[&before, &after]).unwrap();
+        assert_eq!(metrics.beats, 8);
+        assert_eq!(metrics.rmssd, 0.0);
+        assert_eq!(metrics.pnn50, 0.0);
+        assert!(metrics.sdnn > 100.0);
+        assert_eq!(rmssd_of_runs(&[&before, &after]), Some(0.0));
+
+        // Joined, the step between them would count as a difference
+        let joined: Vec<f64> = before.iter().chain(&after).map(|p| p.value as f64).collect();
+        assert!(analyze(&joined).unwrap().rmssd > 100.0);
+
+        // Single beats have nothing to difference
+        assert!(analyze_runs(&[&before[..1], &after[..1], &before[..1]]).is_none());
+        assert_eq!(rmssd_of_runs(&[&before[..1]]), None);
+    }
+
+    #[test]
+    fn test_windows() {
+        let second = 1_000_000_000;
+        let intervals: Vec<Point> = (0..100u64).map(|i| Point { time: i * second, value: 1000 + (i % 2) as i32 * 20 }).collect();
+
+        let windows = analyze_windows(&intervals, 30 * second, 30 * second);
+        assert_eq!(windows.len(), 4);
+        assert_eq!(windows[0].metrics.beats, 30);
+        assert_eq!(windows[3].metrics.beats, 10);
+        assert_eq!(windows[1].start, 30 * second);
+    }
+}
@@ -0,0 +1,748 @@
+//! # Recording Inspection Module
+//!
+//! Reads `recording_*.h5` files for the `info`, `validate` and `summary`
+//! commands, so a recording can be checked without h5py.
+//!
+//! ## Reports
+//! - **Info**: Metadata attributes, event markers, and per device its
+//!   channels with point counts, sample rates and time ranges
+//! - **Validation**: Problems that make a recording unusable (errors) or
+//!   worth a look (warnings). Errors are unreadable channels, `times` and
+//!   `values` of different length and timestamps that go backwards. Gaps are
+//!   warnings unless longer than the allowed maximum.
+//! - **Summary**: Heart rate and RMSSD statistics per device
+//! - **HRV**: Metrics of the `hrv` module over consecutive windows of the
+//!   recorded RR (or PPI) intervals, as CSV. Artifacts are corrected with
+//!   the chosen method first, and each window reports its artifact share.
+//!
+//! ## Gaps
+//! Validation reports the gaps stored in the file (reconnects, dropped frames)
+//! and steps between timestamps the recorder did not mark: more than
+//! `DETECTED_GAP_STEPS` sample periods for periodic channels, or more than
+//! `IRREGULAR_GAP_NS` for HR, RR, NN, HRV and PPI. Artifacts are sparse by
+//! nature and never have gaps.
+
+use crate::annotations::Annotation;
+use crate::artifacts::{self, ArtifactCorrection};
+use crate::hrv::{self, HrvWindow};
+use crate::recorder::{ChannelId, DeviceMetadata, PolarDataManager};
+use crate::timeseries::{Gap, Point};
+use chrono::TimeZone;
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+
+// Steps longer than this many sample periods count as gaps in periodic channels
+const DETECTED_GAP_STEPS: u64 = 3;
+
+// Steps longer than this count as gaps in beat and notification channels
+const IRREGULAR_GAP_NS: u64 = 5 * NANOS_PER_SECOND;
+
+/// Local date and time of a timestamp, with milliseconds
+fn format_time(time: u64) -> String {
+    chrono::Local
+        .timestamp_nanos(time as i64)
+        .format("%Y-%m-%d %H:%M:%S%.3f")
+        .to_string()
+}
+
+fn seconds(duration_ns: u64) -> f64 {
+    duration_ns as f64 / NANOS_PER_SECOND as f64
+}
+
+/// Name of a device in reports
+fn device_label(device_id: Option<&str>) -> &str {
+    device_id.unwrap_or("(legacy layout)")
+}
+
+/// Devices of a recording; a single `None` for the legacy root layout
+fn recorded_devices(file_path: &Path) -> Result<Vec<Option<String>>, String> {
+    let device_ids = PolarDataManager::devices_in_file(file_path)?;
+    Ok(if device_ids.is_empty() {
+        vec![None]
+    } else {
+        device_ids.into_iter().map(Some).collect()
+    })
+}
+
+/// Recorded gaps of a device, or of one of its channels
+fn recorded_gaps(file_path: &Path, device_id: Option<&str>, channel: Option<ChannelId>) -> Result<Vec<Gap>, String> {
+    match device_id {
+        Some(device_id) => PolarDataManager::read_gaps_from_file(file_path, device_id, channel),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Channel overview for `info`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelInfo {
+    pub channel: ChannelId,
+    pub points: usize,
+    /// Configured sample rate, for periodic channels of newer recordings
+    pub sample_rate: Option<u64>,
+    /// First and last timestamp, `None` without points
+    pub time_range: Option<(u64, u64)>,
+}
+
+/// Device overview for `info`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceInfo {
+    /// `None` for recordings in the legacy root layout
+    pub id: Option<String>,
+    pub metadata: DeviceMetadata,
+    pub channels: Vec<ChannelInfo>,
+    /// Number of gaps recorded for the device and its channels
+    pub gap_count: usize,
+}
+
+impl DeviceInfo {
+    fn time_range(&self) -> Option<(u64, u64)> {
+        let ranges = self.channels.iter().filter_map(|c| c.time_range);
+        ranges.reduce(|(start, end), (s, e)| (start.min(s), end.max(e)))
+    }
+}
+
+/// Contents of a recording as listed by `info`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordingOverview {
+    /// String attributes of `/metadata`
+    pub metadata: Vec<(String, String)>,
+    /// Event markers, sorted by time
+    pub annotations: Vec<Annotation>,
+    pub devices: Vec<DeviceInfo>,
+}
+
+/// List the metadata, devices and channels of a recording
+pub fn recording_info(file_path: &Path) -> Result<RecordingOverview, String> {
+    let mut devices = Vec::new();
+    for id in recorded_devices(file_path)? {
+        let device_id = id.as_deref();
+        let metadata = match device_id {
+            Some(device_id) => PolarDataManager::read_device_metadata(file_path, device_id)?,
+            None => DeviceMetadata::default(),
+        };
+
+        let mut channels = Vec::new();
+        let mut gap_count = recorded_gaps(file_path, device_id, None)?.len();
+        for channel in PolarDataManager::channels_in_file(file_path, device_id)? {
+            let (times, _) = PolarDataManager::read_datasets_from_file(file_path, device_id, channel)?;
+            gap_count += recorded_gaps(file_path, device_id, Some(channel))?.len();
+            channels.push(ChannelInfo {
+                channel,
+                points: times.len(),
+                sample_rate: metadata.sample_rates.iter().find(|(c, _)| *c == channel).map(|&(_, rate)| rate),
+                time_range: times.iter().min().zip(times.iter().max()).map(|(&min, &max)| (min, max)),
+            });
+        }
+
+        devices.push(DeviceInfo { id, metadata, channels, gap_count });
+    }
+
+    Ok(RecordingOverview {
+        metadata: PolarDataManager::read_metadata_attrs(file_path)?,
+        annotations: PolarDataManager::read_annotations_from_file(file_path)?,
+        devices,
+    })
+}
+
+impl fmt::Display for RecordingOverview {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Metadata:")?;
+        if self.metadata.is_empty() {
+            writeln!(f, "  (none)")?;
+        }
+        for (name, value) in &self.metadata {
+            writeln!(f, "  {}: {}", name, value)?;
+        }
+
+        if !self.annotations.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Annotations:")?;
+        }
+        for annotation in &self.annotations {
+            write!(f, "  {} {}", format_time(annotation.time), annotation.label)?;
+            if annotation.duration_ns > 0 {
+                write!(f, " ({:.1} s)", seconds(annotation.duration_ns))?;
+            }
+            if !annotation.note.is_empty() {
+                write!(f, ": {}", annotation.note)?;
+            }
+            writeln!(f)?;
+        }
+
+        for device in &self.devices {
+            writeln!(f)?;
+            writeln!(f, "Device {}", device_label(device.id.as_deref()))?;
+            let metadata = &device.metadata;
+            if !metadata.name.is_empty() {
+                writeln!(f, "  Name: {}", metadata.name)?;
+            }
+            if !metadata.model.is_empty() {
+                writeln!(f, "  Model: {}", metadata.model)?;
+            }
+            if let Some(firmware) = &metadata.firmware {
+                writeln!(f, "  Firmware: {}", firmware)?;
+            }
+            if let Some((start, end)) = device.time_range() {
+                writeln!(f, "  Time range: {} to {} ({:.1} s)", format_time(start), format_time(end), seconds(end - start))?;
+            }
+            writeln!(f, "  Gaps: {}", device.gap_count)?;
+            writeln!(f, "  Channels:")?;
+            for channel in &device.channels {
+                let rate = channel.sample_rate.map(|r| format!("{} Hz", r)).unwrap_or_default();
+                write!(f, "    {:<7} {:>10} points  {:<7}", channel.channel.group_name(), channel.points, rate)?;
+                match channel.time_range {
+                    Some((start, end)) => writeln!(f, " {} to {}", format_time(start), format_time(end))?,
+                    None => writeln!(f)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// One problem found by `validate`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub severity: Severity,
+    /// `None` for the legacy root layout
+    pub device_id: Option<String>,
+    /// `None` for problems of the whole device
+    pub channel: Option<ChannelId>,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "WARNING",
+            Severity::Error => "ERROR",
+        };
+        write!(f, "{:<7} {}", severity, device_label(self.device_id.as_deref()))?;
+        if let Some(channel) = self.channel {
+            write!(f, "/{}", channel.group_name())?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Result of `validate`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationReport {
+    pub findings: Vec<Finding>,
+}
+
+impl ValidationReport {
+    /// Whether the recording has no errors; warnings are allowed
+    pub fn is_valid(&self) -> bool {
+        self.count(Severity::Error) == 0
+    }
+
+    fn count(&self, severity: Severity) -> usize {
+        self.findings.iter().filter(|f| f.severity == severity).count()
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for finding in &self.findings {
+            writeln!(f, "{}", finding)?;
+        }
+        let (errors, warnings) = (self.count(Severity::Error), self.count(Severity::Warning));
+        let verdict = if errors == 0 { "Valid" } else { "Invalid" };
+        write!(f, "{}: {} error(s), {} warning(s)", verdict, errors, warnings)
+    }
+}
+
+/// Findings of one device or channel
+struct Findings<'a> {
+    report: &'a mut ValidationReport,
+    device_id: Option<&'a str>,
+    channel: Option<ChannelId>,
+}
+
+impl Findings<'_> {
+    fn add(&mut self, severity: Severity, message: String) {
+        self.report.findings.push(Finding {
+            severity,
+            device_id: self.device_id.map(str::to_string),
+            channel: self.channel,
+            message,
+        });
+    }
+
+    /// Report gaps as one finding, an error if one is longer than `max_gap`
+    fn add_gaps(&mut self, what: &str, gaps: &[Gap], max_gap: Option<Duration>) {
+        let Some(longest) = gaps.iter().max_by_key(|gap| gap.duration_ns()) else {
+            return;
+        };
+        let total: u64 = gaps.iter().map(Gap::duration_ns).sum();
+        let too_long = max_gap.is_some_and(|max| longest.duration_ns() > max.as_nanos() as u64);
+        let severity = if too_long { Severity::Error } else { Severity::Warning };
+        self.add(
+            severity,
+            format!(
+                "{} {} totalling {:.1} s, longest {:.1} s at {}",
+                gaps.len(),
+                what,
+                seconds(total),
+                seconds(longest.duration_ns()),
+                format_time(longest.start)
+            ),
+        );
+    }
+}
+
+/// Longest step between timestamps that is not a gap
+fn gap_threshold(channel: ChannelId, sample_rate: Option<u64>, times: &[u64]) -> Option<u64> {
+    if channel == ChannelId::RrArtifact {
+        return None;
+    }
+    if !channel.is_periodic() {
+        return Some(IRREGULAR_GAP_NS);
+    }
+    let period = match sample_rate {
+        Some(rate) if rate > 0 => NANOS_PER_SECOND / rate,
+        // Older recordings have no rate; use the typical step
+        _ => {
+            let mut steps: Vec<u64> = times.windows(2).map(|w| w[1].saturating_sub(w[0])).filter(|&d| d > 0).collect();
+            if steps.is_empty() {
+                return None;
+            }
+            let mid = steps.len() / 2;
+            *steps.select_nth_unstable(mid).1
+        }
+    };
+    Some(period * DETECTED_GAP_STEPS)
+}
+
+/// Check a recording for broken channels and gaps
+///
+/// Gaps longer than `max_gap` are errors, all others warnings. Fails only if
+/// the file cannot be opened; problems inside it are findings.
+pub fn validate_recording(file_path: &Path, max_gap: Option<Duration>) -> Result<ValidationReport, String> {
+    let mut report = ValidationReport::default();
+    let mut total_points = 0;
+
+    for id in recorded_devices(file_path)? {
+        let device_id = id.as_deref();
+        let device_gaps = recorded_gaps(file_path, device_id, None)?;
+        Findings { report: &mut report, device_id, channel: None }.add_gaps("recorded gap(s)", &device_gaps, max_gap);
+
+        let sample_rates = match device_id {
+            Some(device_id) => PolarDataManager::read_device_metadata(file_path, device_id)?.sample_rates,
+            None => Vec::new(),
+        };
+
+        for channel in PolarDataManager::channels_in_file(file_path, device_id)? {
+            let mut findings = Findings { report: &mut report, device_id, channel: Some(channel) };
+            let (times, values) = match PolarDataManager::read_datasets_from_file(file_path, device_id, channel) {
+                Ok(datasets) => datasets,
+                Err(e) => {
+                    findings.add(Severity::Error, e);
+                    continue;
+                }
+            };
+            total_points += times.len();
+
+            if times.len() != values.len() {
+                findings.add(
+                    Severity::Error,
+                    format!("{} times but {} values", times.len(), values.len()),
+                );
+            }
+            if times.is_empty() {
+                findings.add(Severity::Warning, "No points".to_string());
+                continue;
+            }
+
+            let backwards: Vec<usize> = (1..times.len()).filter(|&i| times[i] < times[i - 1]).collect();
+            if let Some(&first) = backwards.first() {
+                findings.add(
+                    Severity::Error,
+                    format!("Timestamps go backwards {} time(s), first at index {}", backwards.len(), first),
+                );
+            }
+
+            let channel_gaps = recorded_gaps(file_path, device_id, Some(channel))?;
+            let sample_rate = sample_rates.iter().find(|(c, _)| *c == channel).map(|&(_, rate)| rate);
+            let unmarked: Vec<Gap> = match gap_threshold(channel, sample_rate, &times) {
+                Some(threshold) => times
+                    .windows(2)
+                    .filter(|w| w[1] > w[0] && w[1] - w[0] > threshold)
+                    .map(|w| Gap { start: w[0], end: w[1] })
+                    .filter(|gap| {
+                        !device_gaps
+                            .iter()
+                            .chain(&channel_gaps)
+                            .any(|recorded| recorded.start < gap.end && recorded.end > gap.start)
+                    })
+                    .collect(),
+                None => Vec::new(),
+            };
+            findings.add_gaps("recorded gap(s)", &channel_gaps, max_gap);
+            findings.add_gaps("unmarked gap(s)", &unmarked, max_gap);
+        }
+    }
+
+    if total_points == 0 {
+        report.findings.push(Finding {
+            severity: Severity::Error,
+            device_id: None,
+            channel: None,
+            message: "No data in recording".to_string(),
+        });
+    }
+    Ok(report)
+}
+
+/// Count, mean, spread and range of a set of values
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Statistics {
+    pub count: usize,
+    pub mean: f64,
+    /// Sample standard deviation, 0 for a single value
+    pub sd: f64,
+    pub median: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Statistics {
+    /// Statistics of `values`, `None` if there are none
+    pub fn of(values: &[f64]) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        let count = values.len();
+        let mean = values.iter().sum::<f64>() / count as f64;
+        let sd = if count > 1 {
+            (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (count - 1) as f64).sqrt()
+        } else {
+            0.0
+        };
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        // Middle value, or the mean of the two middle values
+        let median = (sorted[(count - 1) / 2] + sorted[count / 2]) / 2.0;
+
+        Some(Self {
+            count,
+            mean,
+            sd,
+            median,
+            min: sorted[0],
+            max: sorted[count - 1],
+        })
+    }
+}
+
+/// Heart rate and HRV of one device for `summary`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceSummary {
+    /// `None` for recordings in the legacy root layout
+    pub id: Option<String>,
+    pub duration_ns: u64,
+    /// Heart rate in bpm
+    pub hr: Option<Statistics>,
+    /// Rolling RMSSD in ms as recorded in the `hrv` channel
+    pub rmssd: Option<Statistics>,
+}
+
+/// Heart rate and RMSSD of every device in a recording
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordingSummary {
+    pub devices: Vec<DeviceSummary>,
+}
+
+/// Summarize heart rate and RMSSD of a recording
+///
+/// Fails if no device recorded heart rate or HRV.
+pub fn summarize_recording(file_path: &Path) -> Result<RecordingSummary, String> {
+    let mut devices = Vec::new();
+    for id in recorded_devices(file_path)? {
+        let device_id = id.as_deref();
+        let channels = PolarDataManager::channels_in_file(file_path, device_id)?;
+
+        let mut time_range: Option<(u64, u64)> = None;
+        let mut statistics = |channel: ChannelId| -> Result<Option<Statistics>, String> {
+            if !channels.contains(&channel) {
+                return Ok(None);
+            }
+            let points = PolarDataManager::read_all_from_file(file_path, device_id, channel)?;
+            for point in &points {
+                let (start, end) = time_range.get_or_insert((point.time, point.time));
+                *start = (*start).min(point.time);
+                *end = (*end).max(point.time);
+            }
+            let values: Vec<f64> = points.iter().map(|p| p.value as f64).collect();
+            Ok(Statistics::of(&values))
+        };
+        let hr = statistics(ChannelId::Hr)?;
+        let rmssd = statistics(ChannelId::Hrv)?;
+
+        if hr.is_some() || rmssd.is_some() {
+            devices.push(DeviceSummary {
+                id,
+                duration_ns: time_range.map_or(0, |(start, end)| end - start),
+                hr,
+                rmssd,
+            });
+        }
+    }
+
+    if devices.is_empty() {
+        return Err(format!("No heart rate data in {}", file_path.display()));
+    }
+    Ok(RecordingSummary { devices })
+}
+
+impl fmt::Display for RecordingSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, device) in self.devices.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "Device {}", device_label(device.id.as_deref()))?;
+            writeln!(f, "  Duration: {:.1} min", seconds(device.duration_ns) / 60.0)?;
+            for (name, unit, statistics) in [("HR", "bpm", &device.hr), ("RMSSD", "ms", &device.rmssd)] {
+                match statistics {
+                    Some(s) => writeln!(
+                        f,
+                        "  {:<6} mean {:.1} {unit}, SD {:.1}, median {:.1}, min {:.0}, max {:.0} ({} values)",
+                        name, s.mean, s.sd, s.median, s.min, s.max, s.count
+                    )?,
+                    None => writeln!(f, "  {:<6} not recorded", name)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// HRV windows of one device
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceHrv {
+    /// `None` for the legacy root layout
+    pub id: Option<String>,
+    /// Each window with the percentage of its beats that were artifacts
+    pub windows: Vec<(HrvWindow, f64)>,
+    pub beats: usize,
+    pub artifacts: usize,
+}
+
+/// HRV windows of every device with beat intervals
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordingHrv {
+    pub devices: Vec<DeviceHrv>,
+}
+
+/// Analyze a recording's RR intervals, or PPI without RR, in windows
+///
+/// The recorded intervals are raw; artifacts are corrected with `correction`
+/// before the analysis. Fails if no device recorded intervals.
+pub fn recording_hrv(
+    file_path: &Path,
+    window: Duration,
+    step: Duration,
+    correction: ArtifactCorrection,
+) -> Result<RecordingHrv, String> {
+    let mut devices = Vec::new();
+    for id in recorded_devices(file_path)? {
+        let device_id = id.as_deref();
+        let channels = PolarDataManager::channels_in_file(file_path, device_id)?;
+        let Some(channel) = [ChannelId::Rr, ChannelId::Ppi].into_iter().find(|c| channels.contains(c)) else {
+            continue;
+        };
+        let mut intervals = PolarDataManager::read_all_from_file(file_path, device_id, channel)?;
+        intervals.sort_by_key(|p| p.time);
+
+        let (nn, artifact_times) =
+            artifacts::correct_intervals(intervals.iter().map(|p| (p.time, p.value as f64)), correction);
+        let nn: Vec<Point> = nn
+            .iter()
+            .map(|beat| Point { time: beat.time, value: beat.interval.round() as i32 })
+            .collect();
+        let windows = hrv::analyze_windows(&nn, window.as_nanos() as u64, step.as_nanos() as u64)
+            .into_iter()
+            .map(|w| {
+                let in_window = |time: &u64| (w.start..w.end).contains(time);
+                let beats = intervals.iter().filter(|p| in_window(&p.time)).count();
+                let artifacts = artifact_times.iter().filter(|t| in_window(t)).count();
+                (w, artifacts as f64 / beats.max(1) as f64 * 100.0)
+            })
+            .collect();
+        devices.push(DeviceHrv {
+            id,
+            windows,
+            beats: intervals.len(),
+            artifacts: artifact_times.len(),
+        });
+    }
+
+    if devices.is_empty() {
+        return Err(format!("No RR or PPI intervals in {}", file_path.display()));
+    }
+    Ok(RecordingHrv { devices })
+}
+
+impl fmt::Display for RecordingHrv {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let optional = |value: Option<f64>| value.map(|v| format!("{:.4}", v)).unwrap_or_default();
+        writeln!(
+            f,
+            "device_id,start_ns,end_ns,beats,mean_nn_ms,sdnn_ms,rmssd_ms,pnn50_pct,hr_max_min_bpm,\
+             lf_ms2,hf_ms2,lf_hf,sd1_ms,sd2_ms,sample_entropy,dfa_alpha1,artifacts_pct"
+        )?;
+        for device in &self.devices {
+            for (window, artifact_percentage) in &device.windows {
+                let m = &window.metrics;
+                writeln!(
+                    f,
+                    "{},{},{},{},{:.2},{:.2},{:.2},{:.2},{:.2},{},{},{},{:.2},{:.2},{},{},{:.2}",
+                    device.id.as_deref().unwrap_or_default(),
+                    window.start,
+                    window.end,
+                    m.beats,
+                    m.mean_nn,
+                    m.sdnn,
+                    m.rmssd,
+                    m.pnn50,
+                    m.hr_max_min,
+                    optional(m.lf),
+                    optional(m.hf),
+                    optional(m.lf_hf),
+                    m.sd1,
+                    m.sd2,
+                    optional(m.sample_entropy),
+                    optional(m.dfa_alpha1),
+                    artifact_percentage,
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal::test_recordings::{self, START};
+    use std::path::PathBuf;
+    use tempfile::tempdir;
+
+    /// 10 s of 130 Hz ECG with a second missing, 1 Hz HR, RR and HRV, and a marker
+    fn test_recording(dir: &Path) -> PathBuf {
+        test_recordings::build(dir, Some("3.1.1"), |journal| {
+            for i in (0..1300u64).filter(|i| !(520..650).contains(i)) {
+                journal.add_point("A", ChannelId::Ecg, START + i * NANOS_PER_SECOND / 130, 0).unwrap();
+            }
+            for i in 0..10u64 {
+                journal.add_point("A", ChannelId::Hr, START + i * NANOS_PER_SECOND, 60 + 2 * (i % 2) as i32).unwrap();
+                journal.add_point("A", ChannelId::Hrv, START + i * NANOS_PER_SECOND, 40).unwrap();
+                journal.add_point("A", ChannelId::Rr, START + i * NANOS_PER_SECOND, 1000 + 40 * (i % 2) as i32).unwrap();
+            }
+            let mut annotation = Annotation::new(START + 2 * NANOS_PER_SECOND, "Stimulus", "");
+            annotation.set_end(START + 5 * NANOS_PER_SECOND);
+            journal.add_annotation(&annotation).unwrap();
+        })
+    }
+
+    #[test]
+    fn test_info_lists_channels() {
+        let dir = tempdir().unwrap();
+        let overview = recording_info(&test_recording(dir.path())).unwrap();
+
+        assert!(overview.metadata.iter().any(|(name, _)| name == "app_version"));
+        let device = &overview.devices[0];
+        assert_eq!(device.id.as_deref(), Some("A"));
+        assert_eq!(device.metadata.firmware.as_deref(), Some("3.1.1"));
+        let ecg = device.channels.iter().find(|c| c.channel == ChannelId::Ecg).unwrap();
+        assert_eq!(ecg.points, 1170);
+        assert_eq!(ecg.sample_rate, Some(130));
+        assert_eq!(ecg.time_range.unwrap().0, START);
+        assert!(overview.to_string().contains("Model: Polar H10"));
+        assert_eq!(overview.annotations[0].label, "Stimulus");
+        assert!(overview.to_string().contains("Stimulus (3.0 s)"));
+    }
+
+    #[test]
+    fn test_validate_reports_unmarked_gap() {
+        let dir = tempdir().unwrap();
+        let recording = test_recording(dir.path());
+
+        let report = validate_recording(&recording, None).unwrap();
+        assert!(report.is_valid());
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(report.findings[0].channel, Some(ChannelId::Ecg));
+        assert!(report.findings[0].message.starts_with("1 unmarked gap(s)"));
+
+        let report = validate_recording(&recording, Some(Duration::from_millis(500))).unwrap();
+        assert!(!report.is_valid());
+    }
+
+    #[test]
+    fn test_validate_detects_broken_datasets() {
+        let dir = tempdir().unwrap();
+        let recording = test_recording(dir.path());
+        {
+            let file = hdf5::File::open_rw(&recording).unwrap();
+            let hr = file.group("devices/A/hr").unwrap();
+            let times = hr.dataset("times").unwrap();
+            times.resize(11).unwrap();
+            times.write_slice(&[START], 10..11).unwrap();
+        }
+
+        let report = validate_recording(&recording, None).unwrap();
+        assert!(!report.is_valid());
+        let hr_errors: Vec<_> = report
+            .findings
+            .iter()
+            .filter(|f| f.channel == Some(ChannelId::Hr) && f.severity == Severity::Error)
+            .collect();
+        assert_eq!(hr_errors.len(), 2);
+        assert_eq!(hr_errors[0].message, "11 times but 10 values");
+        assert!(hr_errors[1].message.contains("first at index 10"));
+    }
+
+    #[test]
+    fn test_summary_statistics() {
+        let dir = tempdir().unwrap();
+        let summary = summarize_recording(&test_recording(dir.path())).unwrap();
+
+        let device = &summary.devices[0];
+        let hr = device.hr.unwrap();
+        assert_eq!(hr.count, 10);
+        assert_eq!(hr.mean, 61.0);
+        assert_eq!((hr.min, hr.max), (60.0, 62.0));
+        assert_eq!(device.rmssd.unwrap().median, 40.0);
+        assert_eq!(device.duration_ns, 9 * NANOS_PER_SECOND);
+    }
+
+    #[test]
+    fn test_hrv_windows_as_csv() {
+        let dir = tempdir().unwrap();
+        let recording = test_recording(dir.path());
+        let report = recording_hrv(&recording, Duration::from_secs(5), Duration::from_secs(5), ArtifactCorrection::default()).unwrap();
+
+        let device = &report.devices[0];
+        assert_eq!(device.id.as_deref(), Some("A"));
+        assert_eq!(device.artifacts, 0);
+        assert_eq!(device.windows.len(), 2);
+        assert_eq!(device.windows[0].0.metrics.rmssd, 40.0);
+        assert_eq!(device.windows[0].1, 0.0);
+
+        let csv = report.to_string();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with(&format!("A,{},", START)));
+    }
+}
@@ -0,0 +1,532 @@
+//! # Recording Journal Module
+//!
+//! Append-only sidecar log that makes disk recordings crash-safe.
+//!
+//! ## Why
+//! The HDF5 file is reopened and appended to on every flush, and points wait
+//! in memory between flushes. A crash in the middle of a write can corrupt
+//! the file, and whatever was still staged is lost. Every point, gap,
+//! quality update and annotation of a recording is therefore also appended to
+//! `recording_*.h5.journal` as it arrives and synced to disk at least once a
+//! second. The journal is removed once the recording is closed cleanly, so a
+//! journal found on launch belongs to an unfinished recording that can be
+//! rebuilt from it (see `PolarDataManager::recover_recording`).
+//!
+//! ## Format
+//! Little-endian binary. A header (`ZSJ1` and the recording start time as
+//! u64 nanoseconds) is followed by records, each starting with a tag byte:
+//! - `0` Device: index u8, id length u16, UTF-8 id
+//! - `1` Point: device u8, channel u8, time u64, value i32
+//! - `2` Gap: device u8, channel u8 (255 for the whole device), start u64, end u64
+//! - `3` Quality: device u8, channel u8, received u64, expected u64,
+//!   longest gap u64, gap count u64, jitter f64
+//! - `4` Recording info: subject ID, description
+//! - `5` Device metadata: device u8, name, model, firmware (empty if unknown),
+//!   count u8, then channel u8 and sample rate u64 per periodic channel
+//! - `6` Annotation: time u64, duration u64, label, note
+//!
+//! Strings are a u16 byte length followed by UTF-8.
+//!
+//! A record cut short by a crash ends the journal; everything before it is kept.
+
+use crate::annotations::Annotation;
+use crate::quality::QualityReport;
+use crate::recorder::{ChannelId, DeviceMetadata, RecordingInfo};
+use crate::timeseries::{Gap, Point};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+// File name suffix appended to the recording's name
+const JOURNAL_EXTENSION: &str = "journal";
+
+const MAGIC: &[u8; 4] = b"ZSJ1";
+
+// Longest time appended records may stay unsynced
+const SYNC_INTERVAL: Duration = Duration::from_secs(1);
+
+const TAG_DEVICE: u8 = 0;
+const TAG_POINT: u8 = 1;
+const TAG_GAP: u8 = 2;
+const TAG_QUALITY: u8 = 3;
+const TAG_RECORDING_INFO: u8 = 4;
+const TAG_DEVICE_METADATA: u8 = 5;
+const TAG_ANNOTATION: u8 = 6;
+
+// Channel code of gaps covering all channels of a device
+const WHOLE_DEVICE: u8 = u8::MAX;
+
+/// Journal path belonging to a recording
+pub fn journal_path(recording: &Path) -> PathBuf {
+    let mut name = recording.as_os_str().to_owned();
+    name.push(".");
+    name.push(JOURNAL_EXTENSION);
+    PathBuf::from(name)
+}
+
+/// Recording path a journal belongs to, if the path is a journal
+pub fn recording_path(journal: &Path) -> Option<PathBuf> {
+    (journal.extension()? == JOURNAL_EXTENSION).then(|| journal.with_extension(""))
+}
+
+fn channel_from_code(code: u8) -> Option<ChannelId> {
+    ChannelId::all().into_iter().find(|&channel| channel as u8 == code)
+}
+
+/// Entry of a journal
+#[derive(Debug, Clone, PartialEq)]
+pub enum JournalRecord {
+    Point {
+        device_id: String,
+        channel: ChannelId,
+        point: Point,
+    },
+    Gap {
+        device_id: String,
+        channel: Option<ChannelId>,
+        gap: Gap,
+    },
+    Quality {
+        device_id: String,
+        channel: ChannelId,
+        report: QualityReport,
+    },
+    RecordingInfo(RecordingInfo),
+    DeviceMetadata {
+        device_id: String,
+        metadata: DeviceMetadata,
+    },
+    Annotation(Annotation),
+}
+
+/// Writer appending to a recording's journal
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    /// Index assigned to each device id written so far
+    devices: HashMap<String, u8>,
+    last_sync: Instant,
+}
+
+impl Journal {
+    /// Create the journal for a recording, replacing any existing one
+    pub fn create(recording: &Path, start_time: u64) -> Result<Self, String> {
+        let path = journal_path(recording);
+        let file = File::create(&path).map_err(|e| format!("Failed to create journal: {}", e))?;
+        let mut journal = Self {
+            path,
+            writer: BufWriter::new(file),
+            devices: HashMap::new(),
+            last_sync: Instant::now(),
+        };
+        journal.write(MAGIC)?;
+        journal.write(&start_time.to_le_bytes())?;
+        journal.sync()?;
+        Ok(journal)
+    }
+
+    pub fn add_point(&mut self, device_id: &str, channel: ChannelId, time: u64, value: i32) -> Result<(), String> {
+        let device = self.device_index(device_id)?;
+        self.write(&[TAG_POINT, device, channel as u8])?;
+        self.write(&time.to_le_bytes())?;
+        self.write(&value.to_le_bytes())?;
+        self.sync_if_due()
+    }
+
+    pub fn add_gap(&mut self, device_id: &str, channel: Option<ChannelId>, gap: Gap) -> Result<(), String> {
+        let device = self.device_index(device_id)?;
+        let channel = channel.map_or(WHOLE_DEVICE, |c| c as u8);
+        self.write(&[TAG_GAP, device, channel])?;
+        self.write(&gap.start.to_le_bytes())?;
+        self.write(&gap.end.to_le_bytes())?;
+        self.sync_if_due()
+    }
+
+    pub fn add_quality(&mut self, device_id: &str, channel: ChannelId, report: &QualityReport) -> Result<(), String> {
+        let device = self.device_index(device_id)?;
+        self.write(&[TAG_QUALITY, device, channel as u8])?;
+        for value in [
+            report.samples_received,
+            report.samples_expected,
+            report.longest_gap_ns,
+            report.gap_count,
+        ] {
+            self.write(&value.to_le_bytes())?;
+        }
+        self.write(&report.jitter_ms.to_le_bytes())?;
+        self.sync_if_due()
+    }
+
+    pub fn add_recording_info(&mut self, info: &RecordingInfo) -> Result<(), String> {
+        self.write(&[TAG_RECORDING_INFO])?;
+        self.write_str(&info.subject_id)?;
+        self.write_str(&info.description)?;
+        self.sync_if_due()
+    }
+
+    pub fn add_device_metadata(&mut self, device_id: &str, metadata: &DeviceMetadata) -> Result<(), String> {
+        let device = self.device_index(device_id)?;
+        let count = u8::try_from(metadata.sample_rates.len()).map_err(|_| "Too many sample rates for journal".to_string())?;
+        self.write(&[TAG_DEVICE_METADATA, device])?;
+        self.write_str(&metadata.name)?;
+        self.write_str(&metadata.model)?;
+        self.write_str(metadata.firmware.as_deref().unwrap_or_default())?;
+        self.write(&[count])?;
+        for &(channel, rate) in &metadata.sample_rates {
+            self.write(&[channel as u8])?;
+            self.write(&rate.to_le_bytes())?;
+        }
+        self.sync_if_due()
+    }
+
+    pub fn add_annotation(&mut self, annotation: &Annotation) -> Result<(), String> {
+        self.write(&[TAG_ANNOTATION])?;
+        self.write(&annotation.time.to_le_bytes())?;
+        self.write(&annotation.duration_ns.to_le_bytes())?;
+        self.write_str(&annotation.label)?;
+        self.write_str(&annotation.note)?;
+        self.sync_if_due()
+    }
+
+    /// Write buffered records and sync them to disk
+    pub fn sync(&mut self) -> Result<(), String> {
+        self.writer
+            .flush()
+            .and_then(|_| self.writer.get_ref().sync_data())
+            .map_err(|e| format!("Failed to sync journal: {}", e))?;
+        self.last_sync = Instant::now();
+        Ok(())
+    }
+
+    /// Delete the journal once the recording was closed cleanly
+    pub fn remove(self) -> Result<(), String> {
+        let Self { path, writer, .. } = self;
+        drop(writer);
+        std::fs::remove_file(&path).map_err(|e| format!("Failed to remove journal: {}", e))
+    }
+
+    fn sync_if_due(&mut self) -> Result<(), String> {
+        if self.last_sync.elapsed() >= SYNC_INTERVAL {
+            self.sync()?;
+        }
+        Ok(())
+    }
+
+    /// Index of a device, declaring it in the journal on first use
+    fn device_index(&mut self, device_id: &str) -> Result<u8, String> {
+        if let Some(&index) = self.devices.get(device_id) {
+            return Ok(index);
+        }
+        let index = u8::try_from(self.devices.len()).map_err(|_| "Too many devices for journal".to_string())?;
+        self.write(&[TAG_DEVICE, index])?;
+        self.write_str(device_id)?;
+        self.devices.insert(device_id.to_string(), index);
+        Ok(index)
+    }
+
+    fn write_str(&mut self, value: &str) -> Result<(), String> {
+        let len = u16::try_from(value.len()).map_err(|_| "String too long for journal".to_string())?;
+        self.write(&len.to_le_bytes())?;
+        self.write(value.as_bytes())
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.writer
+            .write_all(bytes)
+            .map_err(|e| format!("Failed to write journal: {}", e))
+    }
+}
+
+/// Reader iterating over the records of a journal
+pub struct JournalReader {
+    reader: BufReader<File>,
+    devices: Vec<String>,
+    /// Recording start time in nanoseconds
+    pub start_time: u64,
+}
+
+impl JournalReader {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("Failed to open journal: {}", e))?;
+        let mut reader = BufReader::new(file);
+
+        let mut magic = [0u8; 4];
+        reader
+            .read_exact(&mut magic)
+            .map_err(|e| format!("Failed to read journal header: {}", e))?;
+        if &magic != MAGIC {
+            return Err(format!("{} is not a recording journal", path.display()));
+        }
+        let mut start_time = [0u8; 8];
+        reader
+            .read_exact(&mut start_time)
+            .map_err(|e| format!("Failed to read journal header: {}", e))?;
+
+        Ok(Self {
+            reader,
+            devices: Vec::new(),
+            start_time: u64::from_le_bytes(start_time),
+        })
+    }
+
+    fn read_bytes<const N: usize>(&mut self) -> std::io::Result<[u8; N]> {
+        let mut bytes = [0u8; N];
+        self.reader.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn read_u8(&mut self) -> std::io::Result<u8> {
+        Ok(self.read_bytes::<1>()?[0])
+    }
+
+    fn read_u64(&mut self) -> std::io::Result<u64> {
+        Ok(u64::from_le_bytes(self.read_bytes()?))
+    }
+
+    fn read_str(&mut self) -> std::io::Result<String> {
+        let len = u16::from_le_bytes(self.read_bytes()?) as usize;
+        let mut bytes = vec![0u8; len];
+        self.reader.read_exact(&mut bytes)?;
+        String::from_utf8(bytes).map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e))
+    }
+
+    fn read_device(&mut self) -> std::io::Result<String> {
+        let index = self.read_u8()? as usize;
+        self.devices
+            .get(index)
+            .cloned()
+            .ok_or_else(|| std::io::Error::new(ErrorKind::InvalidData, "undeclared device"))
+    }
+
+    fn read_channel(&mut self) -> std::io::Result<Option<ChannelId>> {
+        let code = self.read_u8()?;
+        if code == WHOLE_DEVICE {
+            return Ok(None);
+        }
+        channel_from_code(code)
+            .map(Some)
+            .ok_or_else(|| std::io::Error::new(ErrorKind::InvalidData, "unknown channel"))
+    }
+
+    /// Read the next data record, handling device declarations on the way
+    fn read_record(&mut self) -> std::io::Result<JournalRecord> {
+        let missing_channel = || std::io::Error::new(ErrorKind::InvalidData, "missing channel");
+        loop {
+            match self.read_u8()? {
+                TAG_DEVICE => {
+                    let _index = self.read_u8()?;
+                    let id = self.read_str()?;
+                    self.devices.push(id);
+                }
+                TAG_POINT => {
+                    let device_id = self.read_device()?;
+                    let channel = self.read_channel()?.ok_or_else(missing_channel)?;
+                    let time = self.read_u64()?;
+                    let value = i32::from_le_bytes(self.read_bytes()?);
+                    return Ok(JournalRecord::Point {
+                        device_id,
+                        channel,
+                        point: Point { time, value },
+                    });
+                }
+                TAG_GAP => {
+                    let device_id = self.read_device()?;
+                    let channel = self.read_channel()?;
+                    let gap = Gap {
+                        start: self.read_u64()?,
+                        end: self.read_u64()?,
+                    };
+                    return Ok(JournalRecord::Gap { device_id, channel, gap });
+                }
+                TAG_QUALITY => {
+                    let device_id = self.read_device()?;
+                    let channel = self.read_channel()?.ok_or_else(missing_channel)?;
+                    let report = QualityReport {
+                        samples_received: self.read_u64()?,
+                        samples_expected: self.read_u64()?,
+                        longest_gap_ns: self.read_u64()?,
+                        gap_count: self.read_u64()?,
+                        jitter_ms: f64::from_le_bytes(self.read_bytes()?),
+                    };
+                    return Ok(JournalRecord::Quality { device_id, channel, report });
+                }
+                TAG_RECORDING_INFO => {
+                    let info = RecordingInfo {
+                        subject_id: self.read_str()?,
+                        description: self.read_str()?,
+                    };
+                    return Ok(JournalRecord::RecordingInfo(info));
+                }
+                TAG_DEVICE_METADATA => {
+                    let device_id = self.read_device()?;
+                    let name = self.read_str()?;
+                    let model = self.read_str()?;
+                    let firmware = Some(self.read_str()?).filter(|f| !f.is_empty());
+                    let count = self.read_u8()?;
+                    let mut sample_rates = Vec::with_capacity(count as usize);
+                    for _ in 0..count {
+                        let channel = self.read_channel()?.ok_or_else(missing_channel)?;
+                        sample_rates.push((channel, self.read_u64()?));
+                    }
+                    let metadata = DeviceMetadata {
+                        name,
+                        model,
+                        firmware,
+                        sample_rates,
+                    };
+                    return Ok(JournalRecord::DeviceMetadata { device_id, metadata });
+                }
+                TAG_ANNOTATION => {
+                    let annotation = Annotation {
+                        time: self.read_u64()?,
+                        duration_ns: self.read_u64()?,
+                        label: self.read_str()?,
+                        note: self.read_str()?,
+                    };
+                    return Ok(JournalRecord::Annotation(annotation));
+                }
+                tag => {
+                    return Err(std::io::Error::new(
+                        ErrorKind::InvalidData,
+                        format!("unknown record tag {}", tag),
+                    ))
+                }
+            }
+        }
+    }
+}
+
+impl Iterator for JournalReader {
+    type Item = JournalRecord;
+
+    fn next(&mut self) -> Option<JournalRecord> {
+        match self.read_record() {
+            Ok(record) => Some(record),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => None,
+            Err(e) => {
+                log::warn!("Journal ends in a damaged record: {}", e);
+                None
+            }
+        }
+    }
+}
+
+/// Recordings for tests, written through a journal the way the app writes them
+#[cfg(test)]
+pub mod test_recordings {
+    use super::*;
+    use crate::recorder::PolarDataManager;
+
+    /// Start of test recordings, on a whole second
+    pub const START: u64 = 1_700_000_000 * 1_000_000_000;
+
+    /// Recording of device "A", a Polar H10 with 130 Hz ECG, holding what
+    /// `fill` journals, recovered into `dir` as after a crash
+    pub fn build(dir: &Path, firmware: Option<&str>, fill: impl FnOnce(&mut Journal)) -> PathBuf {
+        let recording = dir.join("recording_20240101_100000.h5");
+        let mut journal = Journal::create(&recording, START).unwrap();
+        let metadata = DeviceMetadata {
+            name: "Polar H10 1234".to_string(),
+            model: "Polar H10".to_string(),
+            firmware: firmware.map(str::to_string),
+            sample_rates: vec![(ChannelId::Ecg, 130)],
+        };
+        journal.add_device_metadata("A", &metadata).unwrap();
+        fill(&mut journal);
+        journal.sync().unwrap();
+        drop(journal);
+        PolarDataManager::recover_recording(&journal_path(&recording)).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_journal_paths() {
+        let recording = Path::new("/data/recording_20240101_100000.h5");
+        let journal = journal_path(recording);
+        assert_eq!(journal, Path::new("/data/recording_20240101_100000.h5.journal"));
+        assert_eq!(recording_path(&journal).as_deref(), Some(recording));
+        assert_eq!(recording_path(recording), None);
+    }
+
+    #[test]
+    fn test_round_trip_with_truncated_tail() {
+        let temp_dir = tempdir().unwrap();
+        let recording = temp_dir.path().join("recording_test.h5");
+
+        let mut journal = Journal::create(&recording, 42).unwrap();
+        journal.add_point("A", ChannelId::Ecg, 1_000, -5).unwrap();
+        journal.add_point("B", ChannelId::MagZ, 2_000, 7).unwrap();
+        journal.add_gap("A", None, Gap { start: 1_000, end: 9_000 }).unwrap();
+        let report = QualityReport {
+            samples_received: 9,
+            samples_expected: 10,
+            longest_gap_ns: 8_000,
+            gap_count: 1,
+            jitter_ms: 1.5,
+        };
+        journal.add_quality("A", ChannelId::Ecg, &report).unwrap();
+        let metadata = DeviceMetadata {
+            name: "Polar H10 1234".to_string(),
+            model: "Polar H10".to_string(),
+            firmware: None,
+            sample_rates: vec![(ChannelId::Ecg, 130)],
+        };
+        journal.add_device_metadata("A", &metadata).unwrap();
+        let annotation = Annotation::new(2_500, "Stimulus", "Tone at 1 kHz");
+        journal.add_annotation(&annotation).unwrap();
+        journal.add_point("A", ChannelId::Hr, 3_000, 60).unwrap();
+        journal.sync().unwrap();
+
+        // Simulate a crash in the middle of the last record
+        let path = journal_path(&recording);
+        let len = std::fs::metadata(&path).unwrap().len();
+        std::fs::OpenOptions::new().write(true).open(&path).unwrap().set_len(len - 3).unwrap();
+
+        let reader = JournalReader::open(&path).unwrap();
+        assert_eq!(reader.start_time, 42);
+        let records: Vec<JournalRecord> = reader.collect();
+        assert_eq!(records.len(), 6);
+        assert_eq!(
+            records[1],
+            JournalRecord::Point {
+                device_id: "B".to_string(),
+                channel: ChannelId::MagZ,
+                point: Point { time: 2_000, value: 7 },
+            }
+        );
+        assert_eq!(
+            records[3],
+            JournalRecord::Quality {
+                device_id: "A".to_string(),
+                channel: ChannelId::Ecg,
+                report,
+            }
+        );
+        assert_eq!(
+            records[4],
+            JournalRecord::DeviceMetadata {
+                device_id: "A".to_string(),
+                metadata,
+            }
+        );
+        assert_eq!(records[5], JournalRecord::Annotation(annotation));
+    }
+
+    #[test]
+    fn test_remove_deletes_journal() {
+        let temp_dir = tempdir().unwrap();
+        let recording = temp_dir.path().join("recording_test.h5");
+        let journal = Journal::create(&recording, 0).unwrap();
+        assert!(journal_path(&recording).exists());
+        journal.remove().unwrap();
+        assert!(!journal_path(&recording).exists());
+    }
+}
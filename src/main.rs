@@ -1,15 +1,38 @@
 // Hide console window on Windows in release builds
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod activity;
+mod annotations;
 mod app;
+mod artifacts;
 mod charts;
+mod cli;
 mod config;
 mod connection;
+mod device_clock;
+mod device_profile;
 mod device_scanner;
 mod error;
+mod export;
+mod filters;
+mod headless;
+mod hrs;
+mod hrv;
+mod inspect;
+mod journal;
+mod pmd;
 mod polar_data;
+mod qrs;
+mod quality;
 mod recorder;
+mod replay;
+mod respiration;
+mod samples;
 mod sensor;
+mod session;
+mod source;
+mod stress;
+mod synthetic;
 mod timeseries;
 mod ui;
 mod visualization;
@@ -17,7 +40,7 @@ mod visualization;
 use app::ZenSignal;
 use connection::ConnectionManager;
 use iced::Theme;
-use sensor::SensorUpdate;
+use sensor::DeviceUpdate;
 use std::sync::mpsc;
 
 fn main() -> iced::Result {
@@ -28,9 +51,15 @@ fn main() -> iced::Result {
     
     // Initialize logging
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    // Command-line subcommands run without the window
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(exit_code) = cli::run(&args) {
+        std::process::exit(exit_code);
+    }
     
     // Create a channel for communication between the data collection thread and the UI thread
-    let (sender, receiver) = mpsc::channel::<SensorUpdate>();
+    let (sender, receiver) = mpsc::channel::<DeviceUpdate>();
     
     // Create the connection manager
     let (manager, connect_sender) = ConnectionManager::new(sender);
@@ -0,0 +1,774 @@
+//! # Polar Measurement Data Module
+//!
+//! Backend for the optical Polar sensors (Verity Sense, OH1). Arctic only
+//! implements the H10 measurement types, so this module talks to the Polar
+//! Measurement Data (PMD) service directly through btleplug, the way `hrs.rs`
+//! reads the standard heart rate service.
+//!
+//! ## Key Components
+//! - `MeasurementType`: PMD measurement type delivering a `SensorStream`
+//! - `StreamSettings`: Settings the sensor offers for one measurement type
+//! - `parse_control_response()`: Decoder for control point responses
+//! - `parse_data_frame()`: Decoder for data notifications, including
+//!   delta-compressed frames
+//! - `PmdSource`: `DataSource` connecting to an optical sensor by device ID,
+//!   reconnecting with backoff when the link drops
+//!
+//! ## Control Point
+//! ```text
+//! request   op (0x01 settings, 0x02 start, 0x03 stop), type, settings
+//! response  0xF0, op, type, status, more, settings
+//! setting   id, count, values (little endian, width depends on id)
+//! ```
+//! Each stream the model offers is started with the highest value the sensor
+//! lists for every setting. Streams the sensor refuses to start are left out
+//! of the `DeviceProfile` sent to the UI.
+//!
+//! ## Data Frames
+//! ```text
+//! byte 0      measurement type
+//! byte 1-8    sensor time of the last sample (u64, ns)
+//! byte 9      frame type, bit 7 set for delta-compressed frames
+//! byte 10..   samples
+//! ```
+//! A delta-compressed frame holds one reference sample followed by blocks of
+//! `[delta bit width, sample count, packed deltas]`. Compressed values are
+//! scaled by the factor the sensor returns when the stream starts.
+
+use crate::device_profile::{DeviceModel, DeviceProfile, SensorStream};
+use crate::error::ConnectionError;
+use crate::hrs::{find_peripheral, parse_heart_rate_measurement, read_firmware_revision, HEART_RATE_MEASUREMENT};
+use crate::samples::{MeasurementBatch, MeasurementSample};
+use crate::sensor::{ConnectionStatus, SensorUpdate};
+use crate::source::{run_with_reconnect, DataSource, SessionEnd, SourceTask};
+use btleplug::api::bleuuid::uuid_from_u16;
+use btleplug::api::{Characteristic, Peripheral as _, ScanFilter, ValueNotification, WriteType};
+use btleplug::platform::Peripheral;
+use futures::{Stream, StreamExt};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// PMD control point characteristic UUID
+const PMD_CONTROL_POINT: &str = "fb005c81-02e7-f387-1cad-8acd2d8df0c8";
+
+/// PMD data characteristic UUID
+const PMD_DATA: &str = "fb005c82-02e7-f387-1cad-8acd2d8df0c8";
+
+// How long to wait for the sensor to answer a control point request
+const CONTROL_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Interval between stop flag checks
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+const OP_GET_SETTINGS: u8 = 0x01;
+const OP_START: u8 = 0x02;
+const OP_STOP: u8 = 0x03;
+const CONTROL_RESPONSE: u8 = 0xF0;
+
+const STATUS_SUCCESS: u8 = 0;
+const STATUS_ALREADY_IN_STATE: u8 = 6;
+
+const SETTING_SAMPLE_RATE: u8 = 0;
+const SETTING_RESOLUTION: u8 = 1;
+const SETTING_RANGE: u8 = 2;
+const SETTING_RANGE_MILLIUNIT: u8 = 3;
+const SETTING_CHANNELS: u8 = 4;
+const SETTING_FACTOR: u8 = 5;
+
+const FRAME_HEADER_LEN: usize = 10;
+const FRAME_COMPRESSED: u8 = 0x80;
+
+// PPI sample: HR (u8), interval (u16 ms), error estimate (u16 ms), flags (u8)
+const PPI_SAMPLE_LEN: usize = 6;
+// Interval disturbed by movement
+const PPI_BLOCKER: u8 = 0x01;
+
+/// PMD measurement type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeasurementType {
+    Ecg = 0,
+    Ppg = 1,
+    Acc = 2,
+    Ppi = 3,
+    Gyro = 5,
+    Mag = 6,
+}
+
+impl MeasurementType {
+    /// Measurement type delivering `stream`; None for the heart rate service
+    pub fn for_stream(stream: SensorStream) -> Option<Self> {
+        match stream {
+            SensorStream::HeartRate => None,
+            SensorStream::Ecg => Some(MeasurementType::Ecg),
+            SensorStream::Ppg => Some(MeasurementType::Ppg),
+            SensorStream::Acc => Some(MeasurementType::Acc),
+            SensorStream::Ppi => Some(MeasurementType::Ppi),
+            SensorStream::Gyro => Some(MeasurementType::Gyro),
+            SensorStream::Mag => Some(MeasurementType::Mag),
+        }
+    }
+
+    /// Multiplier from the sensor's scaled units (deg/s, gauss) to the sample units
+    fn unit_scale(self) -> f32 {
+        match self {
+            MeasurementType::Gyro | MeasurementType::Mag => 1000.0,
+            _ => 1.0,
+        }
+    }
+}
+
+/// Settings offered for, or selected for, one measurement type
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamSettings {
+    /// Sample rates in Hz
+    pub sample_rates: Vec<u16>,
+    /// Bits per channel
+    pub resolutions: Vec<u16>,
+    /// Measurement ranges in the stream's units (e.g. g for ACC)
+    pub ranges: Vec<u16>,
+    pub channels: Vec<u8>,
+    /// Scale of compressed values, only present in start responses
+    pub factor: Option<f32>,
+}
+
+impl StreamSettings {
+    /// Parse setting entries, stopping at the first unknown or truncated one
+    pub fn parse(mut data: &[u8]) -> Self {
+        let mut settings = Self::default();
+        while let [id, count, rest @ ..] = data {
+            let width = match *id {
+                SETTING_SAMPLE_RATE | SETTING_RESOLUTION | SETTING_RANGE => 2,
+                SETTING_RANGE_MILLIUNIT | SETTING_FACTOR => 4,
+                SETTING_CHANNELS => 1,
+                _ => {
+                    log::debug!("Unknown PMD setting {}", id);
+                    break;
+                }
+            };
+            let len = *count as usize * width;
+            let Some(values) = rest.get(..len) else {
+                break;
+            };
+            let values = values.chunks_exact(width);
+            let read_u16 = |v: &[u8]| u16::from_le_bytes([v[0], v[1]]);
+            match *id {
+                SETTING_SAMPLE_RATE => settings.sample_rates = values.map(read_u16).collect(),
+                SETTING_RESOLUTION => settings.resolutions = values.map(read_u16).collect(),
+                SETTING_RANGE => settings.ranges = values.map(read_u16).collect(),
+                SETTING_CHANNELS => settings.channels = values.map(|v| v[0]).collect(),
+                SETTING_FACTOR => {
+                    settings.factor = values.last().map(|v| f32::from_le_bytes([v[0], v[1], v[2], v[3]]))
+                }
+                // Ranges in milliunits duplicate the plain ranges
+                _ => {}
+            }
+            data = &rest[len..];
+        }
+        settings
+    }
+
+    /// The highest value offered for each setting
+    pub fn highest(&self) -> Self {
+        let highest = |values: &[u16]| values.iter().max().map(|&v| vec![v]).unwrap_or_default();
+        Self {
+            sample_rates: highest(&self.sample_rates),
+            resolutions: highest(&self.resolutions),
+            ranges: highest(&self.ranges),
+            channels: self.channels.iter().max().map(|&c| vec![c]).unwrap_or_default(),
+            factor: None,
+        }
+    }
+
+    /// Encode as setting entries for a start request
+    fn encode(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        for (id, values) in [
+            (SETTING_SAMPLE_RATE, &self.sample_rates),
+            (SETTING_RESOLUTION, &self.resolutions),
+            (SETTING_RANGE, &self.ranges),
+        ] {
+            if !values.is_empty() {
+                data.extend([id, values.len() as u8]);
+                data.extend(values.iter().flat_map(|v| v.to_le_bytes()));
+            }
+        }
+        if !self.channels.is_empty() {
+            data.extend([SETTING_CHANNELS, self.channels.len() as u8]);
+            data.extend(&self.channels);
+        }
+        data
+    }
+}
+
+/// Decoded control point response
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlResponse {
+    pub op: u8,
+    pub measurement_type: u8,
+    pub status: u8,
+    pub settings: StreamSettings,
+}
+
+/// Parse a control point response, returning None for anything else
+pub fn parse_control_response(data: &[u8]) -> Option<ControlResponse> {
+    let [CONTROL_RESPONSE, op, measurement_type, status, rest @ ..] = data else {
+        return None;
+    };
+    // Settings follow the "more" byte, and only on success
+    let settings = match rest {
+        [_more, settings @ ..] if *status == STATUS_SUCCESS => StreamSettings::parse(settings),
+        _ => StreamSettings::default(),
+    };
+    Some(ControlResponse {
+        op: *op,
+        measurement_type: *measurement_type,
+        status: *status,
+        settings,
+    })
+}
+
+fn settings_request(measurement_type: MeasurementType) -> Vec<u8> {
+    vec![OP_GET_SETTINGS, measurement_type as u8]
+}
+
+fn start_request(measurement_type: MeasurementType, settings: &StreamSettings) -> Vec<u8> {
+    let mut request = vec![OP_START, measurement_type as u8];
+    request.extend(settings.encode());
+    request
+}
+
+fn stop_request(measurement_type: MeasurementType) -> Vec<u8> {
+    vec![OP_STOP, measurement_type as u8]
+}
+
+/// Sample layout of a started stream's data frames
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameFormat {
+    pub measurement_type: MeasurementType,
+    pub channels: usize,
+    /// Bits per channel of each sample
+    pub resolution: u32,
+    /// Scale of compressed values
+    pub factor: f32,
+}
+
+impl FrameFormat {
+    /// Layout of a stream started with `settings`, falling back to the usual
+    /// layout of the measurement type for settings the sensor did not list
+    pub fn new(measurement_type: MeasurementType, settings: &StreamSettings, factor: Option<f32>) -> Self {
+        let (channels, resolution) = match measurement_type {
+            // Three PPG channels plus ambient light
+            MeasurementType::Ppg => (4, 22),
+            MeasurementType::Ecg => (1, 14),
+            _ => (3, 16),
+        };
+        Self {
+            measurement_type,
+            channels: settings.channels.first().map_or(channels, |&c| c as usize),
+            resolution: settings.resolutions.first().map_or(resolution, |&r| r as u32),
+            factor: factor.unwrap_or(1.0),
+        }
+    }
+
+    /// Convert one decoded sample (a value per channel) to a crate sample
+    fn sample(&self, values: &[i32], scale: f32) -> Option<MeasurementSample> {
+        let scaled = |v: i32| (v as f32 * scale).round() as i32;
+        match (self.measurement_type, values) {
+            (MeasurementType::Ecg, [v, ..]) => Some(MeasurementSample::Ecg(scaled(*v))),
+            // The first PPG channel; the others repeat it from other LEDs
+            (MeasurementType::Ppg, [v, ..]) => Some(MeasurementSample::Ppg(scaled(*v))),
+            (MeasurementType::Acc, [x, y, z, ..]) => Some(MeasurementSample::Acc {
+                x: scaled(*x),
+                y: scaled(*y),
+                z: scaled(*z),
+            }),
+            (MeasurementType::Gyro, [x, y, z, ..]) => Some(MeasurementSample::Gyro {
+                x: scaled(*x),
+                y: scaled(*y),
+                z: scaled(*z),
+            }),
+            (MeasurementType::Mag, [x, y, z, ..]) => Some(MeasurementSample::Mag {
+                x: scaled(*x),
+                y: scaled(*y),
+                z: scaled(*z),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Sign-extend the low `bits` bits of `value`
+fn sign_extend(value: u32, bits: u32) -> i32 {
+    if bits == 0 {
+        return 0;
+    }
+    let shift = 32 - bits;
+    ((value << shift) as i32) >> shift
+}
+
+/// Little endian signed integer as wide as `bytes`
+fn read_signed(bytes: &[u8]) -> i32 {
+    let value = bytes.iter().rev().fold(0u32, |value, &b| (value << 8) | b as u32);
+    sign_extend(value, bytes.len() as u32 * 8)
+}
+
+/// Read `bits` bits starting at `offset`, least significant bit first
+fn read_bits(data: &[u8], offset: usize, bits: u32) -> Option<i32> {
+    if bits > 32 {
+        return None;
+    }
+    let mut value = 0u32;
+    for i in 0..bits as usize {
+        let pos = offset + i;
+        let bit = (data.get(pos / 8)? >> (pos % 8)) & 1;
+        value |= (bit as u32) << i;
+    }
+    Some(sign_extend(value, bits))
+}
+
+/// Decode a delta-compressed payload into one value per channel per sample
+fn decode_delta_frame(payload: &[u8], channels: usize, resolution: u32) -> Option<Vec<Vec<i32>>> {
+    let width = resolution.div_ceil(8).max(1) as usize;
+    let reference = payload.get(..width * channels)?;
+    let mut sample: Vec<i32> = reference.chunks_exact(width).map(read_signed).collect();
+    let mut samples = vec![sample.clone()];
+
+    let mut rest = &payload[width * channels..];
+    while let [delta_bits, count, tail @ ..] = rest {
+        let (delta_bits, count) = (*delta_bits as u32, *count as usize);
+        let len = (delta_bits as usize * count * channels).div_ceil(8);
+        let block = tail.get(..len)?;
+        let mut offset = 0;
+        for _ in 0..count {
+            for value in sample.iter_mut() {
+                *value = value.wrapping_add(read_bits(block, offset, delta_bits)?);
+                offset += delta_bits as usize;
+            }
+            samples.push(sample.clone());
+        }
+        rest = &tail[len..];
+    }
+    Some(samples)
+}
+
+/// Peak-to-peak intervals of a PPI frame, skipping those disturbed by movement
+fn parse_ppi(payload: &[u8]) -> Vec<MeasurementSample> {
+    payload
+        .chunks_exact(PPI_SAMPLE_LEN)
+        .filter(|s| s[5] & PPI_BLOCKER == 0)
+        .map(|s| MeasurementSample::Ppi(u16::from_le_bytes([s[1], s[2]])))
+        .collect()
+}
+
+/// Parse a data notification of a stream with the given format, returning
+/// None if it is truncated or belongs to another measurement type
+pub fn parse_data_frame(data: &[u8], format: &FrameFormat) -> Option<MeasurementBatch> {
+    let header = data.get(..FRAME_HEADER_LEN)?;
+    if header[0] != format.measurement_type as u8 {
+        return None;
+    }
+    let sensor_time = u64::from_le_bytes(header[1..9].try_into().ok()?);
+    let frame_type = header[9];
+    let payload = &data[FRAME_HEADER_LEN..];
+
+    if format.measurement_type == MeasurementType::Ppi {
+        return Some(MeasurementBatch::new(parse_ppi(payload)).with_sensor_time(sensor_time));
+    }
+
+    let (values, scale) = if frame_type & FRAME_COMPRESSED != 0 {
+        let values = decode_delta_frame(payload, format.channels, format.resolution)?;
+        (values, format.factor * format.measurement_type.unit_scale())
+    } else {
+        // Uncompressed ACC frame types 0, 1 and 2 use 1, 2 and 3 bytes per axis
+        let width = match format.measurement_type {
+            MeasurementType::Acc => frame_type as usize + 1,
+            _ => format.resolution.div_ceil(8).max(1) as usize,
+        };
+        let values = payload
+            .chunks_exact(width * format.channels)
+            .map(|sample| sample.chunks_exact(width).map(read_signed).collect())
+            .collect();
+        (values, 1.0)
+    };
+
+    let samples = values.iter().filter_map(|v: &Vec<i32>| format.sample(v, scale)).collect();
+    Some(MeasurementBatch::new(samples).with_sensor_time(sensor_time))
+}
+
+/// Data source for an optical Polar sensor
+pub struct PmdSource {
+    /// Polar device ID as shown by the scanner
+    device_id: String,
+    model: DeviceModel,
+}
+
+impl PmdSource {
+    pub fn new(device_id: String, model: DeviceModel) -> Self {
+        Self { device_id, model }
+    }
+}
+
+impl DataSource for PmdSource {
+    fn name(&self) -> String {
+        format!("{} {}", self.model, self.device_id)
+    }
+
+    fn start(self: Box<Self>, sender: Sender<SensorUpdate>, should_stop: Arc<AtomicBool>) -> SourceTask {
+        Box::pin(async move {
+            log::info!("Attempting connection to {} {}", self.model, self.device_id);
+            let _ = sender.send(SensorUpdate::ConnectionStatus(ConnectionStatus::Connecting));
+
+            run_with_reconnect(&sender, &should_stop, |reconnecting| {
+                let device_id = self.device_id.clone();
+                let model = self.model;
+                let sender = sender.clone();
+                let should_stop = should_stop.clone();
+                async move {
+                    match stream_measurements(&device_id, model, &sender, &should_stop).await {
+                        Ok(end) => end,
+                        Err(ConnectionError::Interrupted) => SessionEnd::Stopped,
+                        // Not finding the device again is expected while it is out of range
+                        Err(e) if reconnecting && !matches!(e, ConnectionError::NoAdapter) => {
+                            log::warn!("{}", e);
+                            SessionEnd::LinkLost { streamed: false }
+                        }
+                        Err(e) => SessionEnd::Failed(e),
+                    }
+                }
+            })
+            .await;
+        })
+    }
+}
+
+/// Write a control point request and wait for the sensor's response to it
+///
+/// Other notifications arriving meanwhile are dropped; streaming has not
+/// started while the control point is in use.
+async fn control_request<S>(
+    peripheral: &Peripheral,
+    control: &Characteristic,
+    notifications: &mut S,
+    request: &[u8],
+) -> Result<ControlResponse, String>
+where
+    S: Stream<Item = ValueNotification> + Unpin,
+{
+    peripheral
+        .write(control, request, WriteType::WithResponse)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let response = async {
+        while let Some(notification) = notifications.next().await {
+            if notification.uuid != control.uuid {
+                continue;
+            }
+            match parse_control_response(&notification.value) {
+                Some(response) if response.op == request[0] && response.measurement_type == request[1] => {
+                    return Ok(response);
+                }
+                _ => log::debug!("Ignoring PMD control response {:?}", notification.value),
+            }
+        }
+        Err("Device disconnected".to_string())
+    };
+    tokio::time::timeout(CONTROL_TIMEOUT, response)
+        .await
+        .map_err(|_| "No response from device".to_string())?
+}
+
+/// Query the settings of a measurement type and start it with the highest
+/// values offered, returning the frame format and the selected settings
+async fn start_measurement<S>(
+    peripheral: &Peripheral,
+    control: &Characteristic,
+    notifications: &mut S,
+    measurement_type: MeasurementType,
+) -> Result<(FrameFormat, StreamSettings), String>
+where
+    S: Stream<Item = ValueNotification> + Unpin,
+{
+    let offered = control_request(peripheral, control, notifications, &settings_request(measurement_type)).await?;
+    if offered.status != STATUS_SUCCESS {
+        return Err(format!("settings query failed with status {}", offered.status));
+    }
+    let settings = offered.settings.highest();
+    log::debug!("{:?} settings offered: {:?}", measurement_type, offered.settings);
+    log::info!("Starting {:?} with {:?}", measurement_type, settings);
+
+    let request = start_request(measurement_type, &settings);
+    let mut started = control_request(peripheral, control, notifications, &request).await?;
+    // Still running from a session that lost its link
+    if started.status == STATUS_ALREADY_IN_STATE {
+        control_request(peripheral, control, notifications, &stop_request(measurement_type)).await?;
+        started = control_request(peripheral, control, notifications, &request).await?;
+    }
+    if started.status != STATUS_SUCCESS {
+        return Err(format!("start failed with status {}", started.status));
+    }
+
+    Ok((FrameFormat::new(measurement_type, &settings, started.settings.factor), settings))
+}
+
+/// Connect, start every measurement stream of the model and forward heart
+/// rate and measurement data until the device disconnects or the stop flag
+/// is set
+async fn stream_measurements(
+    device_id: &str,
+    model: DeviceModel,
+    sender: &Sender<SensorUpdate>,
+    should_stop: &AtomicBool,
+) -> Result<SessionEnd, ConnectionError> {
+    let connection_error = |reason: String| ConnectionError::DeviceConnection {
+        device_id: device_id.to_string(),
+        reason,
+    };
+
+    // Polar sensors advertise their device ID as the last word of the name
+    let peripheral = find_peripheral(device_id, ScanFilter::default(), should_stop, |_, name| {
+        name.and_then(|name| name.split_whitespace().last()) == Some(device_id)
+    })
+    .await?;
+    peripheral
+        .connect()
+        .await
+        .map_err(|e| connection_error(e.to_string()))?;
+    peripheral
+        .discover_services()
+        .await
+        .map_err(|e| connection_error(format!("Service discovery failed: {}", e)))?;
+
+    let characteristics = peripheral.characteristics();
+    let heart_rate_uuid = uuid_from_u16(HEART_RATE_MEASUREMENT);
+    let find = |uuid: &str| characteristics.iter().find(|c| c.uuid.to_string() == uuid).cloned();
+    let heart_rate = characteristics
+        .iter()
+        .find(|c| c.uuid == heart_rate_uuid)
+        .cloned()
+        .ok_or_else(|| connection_error("No Heart Rate Measurement characteristic".to_string()))?;
+    let control = find(PMD_CONTROL_POINT).ok_or_else(|| connection_error("No PMD control point".to_string()))?;
+    let data = find(PMD_DATA).ok_or_else(|| connection_error("No PMD data characteristic".to_string()))?;
+    for characteristic in [&heart_rate, &control, &data] {
+        peripheral
+            .subscribe(characteristic)
+            .await
+            .map_err(|e| connection_error(format!("Failed to subscribe: {}", e)))?;
+    }
+    let mut notifications = peripheral
+        .notifications()
+        .await
+        .map_err(|e| connection_error(e.to_string()))?;
+
+    log::info!("Connected to {} {}", model, device_id);
+    let _ = sender.send(SensorUpdate::ConnectionStatus(ConnectionStatus::Connected));
+
+    let offered = DeviceProfile::for_model(model);
+    let mut streams = vec![SensorStream::HeartRate];
+    let mut formats = Vec::new();
+    let mut rates = Vec::new();
+    for &stream in offered.streams() {
+        let Some(measurement_type) = MeasurementType::for_stream(stream) else {
+            continue;
+        };
+        match start_measurement(&peripheral, &control, &mut notifications, measurement_type).await {
+            Ok((format, settings)) => {
+                if stream.is_periodic() {
+                    let rate = settings
+                        .sample_rates
+                        .first()
+                        .map_or(offered.default_sample_rate(stream), |&rate| rate as u64);
+                    rates.push((stream, rate));
+                }
+                streams.push(stream);
+                formats.push(format);
+            }
+            Err(reason) => log::warn!("Could not start {} stream: {}", stream.display_name(), reason),
+        }
+    }
+
+    let profile = DeviceProfile::with_streams(model, streams);
+    let _ = sender.send(SensorUpdate::DeviceProfile(profile.clone()));
+    if let Some(firmware) = read_firmware_revision(&peripheral).await {
+        let _ = sender.send(SensorUpdate::Firmware(firmware));
+    }
+    if profile.has_measurement_streams() {
+        let _ = sender.send(SensorUpdate::SampleRateConfig { rates });
+    }
+
+    let end = loop {
+        tokio::select! {
+            notification = notifications.next() => {
+                let Some(notification) = notification else {
+                    log::warn!("{} {} disconnected", model, device_id);
+                    break SessionEnd::LinkLost { streamed: true };
+                };
+                let update = if notification.uuid == heart_rate.uuid {
+                    parse_heart_rate_measurement(&notification.value)
+                        .map(|measurement| SensorUpdate::HeartRate((&measurement).into()))
+                } else if notification.uuid == data.uuid {
+                    notification
+                        .value
+                        .first()
+                        .and_then(|&measurement_type| formats.iter().find(|f| f.measurement_type as u8 == measurement_type))
+                        .and_then(|format| parse_data_frame(&notification.value, format))
+                        .map(SensorUpdate::MeasurementData)
+                } else {
+                    continue;
+                };
+                let Some(update) = update else {
+                    log::warn!("Malformed notification: {:?}", notification.value);
+                    continue;
+                };
+                if let Err(why) = sender.send(update) {
+                    log::error!("Could not send sensor data: {:?}", why);
+                    break SessionEnd::Stopped;
+                }
+            }
+            _ = tokio::time::sleep(POLL_INTERVAL) => {
+                if should_stop.load(Ordering::Relaxed) {
+                    log::info!("Disconnecting...");
+                    break SessionEnd::Stopped;
+                }
+            }
+        }
+    };
+
+    // The sensor keeps measurements running across connections otherwise
+    for format in &formats {
+        let _ = peripheral
+            .write(&control, &stop_request(format.measurement_type), WriteType::WithResponse)
+            .await;
+    }
+    let _ = peripheral.disconnect().await;
+    Ok(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_settings_response() {
+        // Settings of the Verity Sense ACC: 52 Hz, 16 bit, 8 g, 3 channels
+        let data = [
+            CONTROL_RESPONSE, OP_GET_SETTINGS, 2, STATUS_SUCCESS, 0,
+            SETTING_SAMPLE_RATE, 1, 52, 0,
+            SETTING_RESOLUTION, 1, 16, 0,
+            SETTING_RANGE, 3, 2, 0, 4, 0, 8, 0,
+            SETTING_CHANNELS, 1, 3,
+        ];
+        let response = parse_control_response(&data).unwrap();
+
+        assert_eq!(response.op, OP_GET_SETTINGS);
+        assert_eq!(response.measurement_type, MeasurementType::Acc as u8);
+        assert_eq!(response.settings.sample_rates, vec![52]);
+        assert_eq!(response.settings.ranges, vec![2, 4, 8]);
+        assert_eq!(response.settings.channels, vec![3]);
+
+        let selected = response.settings.highest();
+        assert_eq!(selected.ranges, vec![8]);
+        assert_eq!(
+            start_request(MeasurementType::Acc, &selected),
+            vec![
+                OP_START, 2,
+                SETTING_SAMPLE_RATE, 1, 52, 0,
+                SETTING_RESOLUTION, 1, 16, 0,
+                SETTING_RANGE, 1, 8, 0,
+                SETTING_CHANNELS, 1, 3,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_start_response_factor() {
+        let mut data = vec![CONTROL_RESPONSE, OP_START, 5, STATUS_SUCCESS, 0, SETTING_FACTOR, 1];
+        data.extend(0.5f32.to_le_bytes());
+        let response = parse_control_response(&data).unwrap();
+        assert_eq!(response.settings.factor, Some(0.5));
+
+        let failed = parse_control_response(&[CONTROL_RESPONSE, OP_START, 5, STATUS_ALREADY_IN_STATE]).unwrap();
+        assert_eq!(failed.status, STATUS_ALREADY_IN_STATE);
+        assert_eq!(failed.settings, StreamSettings::default());
+
+        assert!(parse_control_response(&[OP_START, 5]).is_none());
+    }
+
+    fn frame(measurement_type: MeasurementType, frame_type: u8, payload: &[u8]) -> Vec<u8> {
+        let mut data = vec![measurement_type as u8];
+        data.extend(1_000_000_000u64.to_le_bytes());
+        data.push(frame_type);
+        data.extend(payload);
+        data
+    }
+
+    #[test]
+    fn test_parse_uncompressed_acc() {
+        let format = FrameFormat::new(MeasurementType::Acc, &StreamSettings::default(), None);
+        // Frame type 1: 16-bit axes, x = -1, y = 1000, z = 2
+        let data = frame(MeasurementType::Acc, 1, &[0xFF, 0xFF, 0xE8, 0x03, 0x02, 0x00]);
+        let batch = parse_data_frame(&data, &format).unwrap();
+
+        assert_eq!(batch.samples, vec![MeasurementSample::Acc { x: -1, y: 1000, z: 2 }]);
+        assert_eq!(batch.sensor_time, Some(1_000_000_000));
+    }
+
+    #[test]
+    fn test_parse_compressed_gyro() {
+        let format = FrameFormat::new(MeasurementType::Gyro, &StreamSettings::default(), Some(0.5));
+        // Reference (10, -2, 0), then two samples of 4-bit deltas:
+        // (+1, -1, +2) and (-8, +7, 0)
+        let data = frame(
+            MeasurementType::Gyro,
+            FRAME_COMPRESSED,
+            &[10, 0, 0xFE, 0xFF, 0, 0, 4, 2, 0xF1, 0x82, 0x07],
+        );
+        let batch = parse_data_frame(&data, &format).unwrap();
+
+        let gyro = |x, y, z| MeasurementSample::Gyro { x, y, z };
+        assert_eq!(
+            batch.samples,
+            vec![gyro(5000, -1000, 0), gyro(5500, -1500, 1000), gyro(1500, 2000, 1000)]
+        );
+    }
+
+    #[test]
+    fn test_parse_compressed_ppg_uses_first_channel() {
+        let format = FrameFormat::new(MeasurementType::Ppg, &StreamSettings::default(), None);
+        // Four 24-bit reference channels, then one sample of 8-bit deltas
+        let data = frame(
+            MeasurementType::Ppg,
+            FRAME_COMPRESSED,
+            &[0x10, 0x27, 0x00, 1, 0, 0, 2, 0, 0, 3, 0, 0, 8, 1, 0xFB, 1, 1, 1],
+        );
+        let batch = parse_data_frame(&data, &format).unwrap();
+
+        assert_eq!(batch.samples, vec![MeasurementSample::Ppg(10_000), MeasurementSample::Ppg(9_995)]);
+    }
+
+    #[test]
+    fn test_parse_ppi_skips_blocked_intervals() {
+        let format = FrameFormat::new(MeasurementType::Ppi, &StreamSettings::default(), None);
+        let data = frame(
+            MeasurementType::Ppi,
+            0,
+            &[60, 0xE8, 0x03, 10, 0, 0x06, 61, 0xD0, 0x07, 10, 0, PPI_BLOCKER],
+        );
+        let batch = parse_data_frame(&data, &format).unwrap();
+
+        assert_eq!(batch.samples, vec![MeasurementSample::Ppi(1000)]);
+    }
+
+    #[test]
+    fn test_parse_truncated_frames() {
+        let format = FrameFormat::new(MeasurementType::Gyro, &StreamSettings::default(), None);
+        assert!(parse_data_frame(&[5, 0, 0], &format).is_none());
+        // Delta block shorter than its header announces
+        let truncated = [0, 0, 0, 0, 0, 0, 8, 2, 0];
+        assert!(parse_data_frame(&frame(MeasurementType::Gyro, FRAME_COMPRESSED, &truncated), &format).is_none());
+        // Frame of another measurement type
+        assert!(parse_data_frame(&frame(MeasurementType::Acc, 0, &[]), &format).is_none());
+    }
+}
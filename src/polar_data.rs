@@ -1,76 +1,462 @@
 //! # Polar Sensor Data Management Module
 //!
-//! Handles Polar H10 biosensor-specific data streams and protocol logic.
-//! Converts Arctic library's data types into our time series storage format
+//! Handles Polar biosensor-specific data streams and protocol logic.
+//! Converts sensor samples into our time series storage format
 //! with proper timestamp calculation and channel orchestration.
 //!
 //! ## Key Types
-//! - `Channels`: Container managing all Polar H10 data streams (ECG, ACC, HR, RR, HRV)
+//! - `Channels`: Container managing all Polar data streams (ECG, ACC, HR, RR, HRV,
+//!   PPG, gyroscope, magnetometer)
 //!
 //! ## Responsibilities
-//! 1. Parse Arctic's `PmdData`, `PmdRead`, and `HeartRate` types
-//! 2. Calculate evenly-spaced timestamps for batched samples
+//! 1. Consume `HeartRateSample` and `MeasurementBatch` updates from any sensor source
+//! 2. Timestamp batched samples from the sensor clock, or space them evenly
+//!    from arrival when the device sends no frame timestamps
 //! 3. Distribute data to appropriate time series channels
-//! 4. Compute derived metrics (HRV from RR intervals)
+//! 4. Compute derived metrics (HRV from RR or PPI intervals)
+//! 5. Track data quality of each periodic stream (see `quality` module)
+//!
+//! ## Beats
+//! The `rr` series repeats its last value when a heart rate notification has
+//! no intervals, so the chart line continues. Those fill points are not beats;
+//! `beats` holds only the intervals received. They pass through an
+//! `ArtifactFilter` (see `artifacts` module): detected artifacts go to
+//! `rr_artifacts`, the corrected NN intervals to `nn`, and HRV is computed
+//! from `nn`.
+//!
+//! The ECG also runs through a `QrsDetector` (see `qrs` module). Its R peaks
+//! go to `r_peaks` and the intervals between them to `ecg_rr`, timed from
+//! the signal instead of spread over the notification like `rr`.
+//!
+//! ## Filtered Signals
+//! ECG and PPG samples also pass through a `FilterChain` (see `filters`
+//! module) into `ecg_filtered` and `ppg_filtered` for display. The raw series
+//! stay untouched and are what QRS detection uses; the filtered ones are only
+//! recorded when `SignalFilters::record` is set.
+//!
+//! ## Respiration
+//! Every `RESPIRATION_INTERVAL_NS` the breathing rate of the last minute is
+//! estimated from ACC, the R peak amplitudes and the beat intervals (see
+//! `respiration` module) and added to `respiration` in breaths/min.
+//!
+//! ## Activity
+//! ACC samples also feed an `ActivityClassifier` (see `activity` module),
+//! which adds one point per epoch to `activity`, `enmo` and `steps`, and to
+//! `posture` for chest straps. `is_still()` tells whether the wearer was
+//! still at a given time, so HRV can be limited to still periods;
+//! `still_runs()` splits beats into the consecutive runs HRV is computed over.
+//! With `set_hrv_still_only` the rolling RMSSD in `hrv` is gated the same way.
+//!
+//! ## Device Profiles
+//! Every series exists regardless of model; the `DeviceProfile` tells the UI
+//! which ones the connected device fills. Optical PPI intervals go into the
+//! RR series so HRV works the same for chest straps and optical sensors.
+//!
+//! ## Gaps
+//! While a sensor reconnects no data arrives. `begin_gap()` and `end_gap()`
+//! record that span, so batch timestamps restart at the reconnect instead of
+//! continuing from the last point and charts leave the span empty.
+//!
+//! ## Device Clock
+//! Batches with a sensor timestamp are placed by mapping it through the
+//! device's `DeviceClock`, so all streams share the sensor's time base and
+//! its drift against the wall clock is tracked. Sensor time that jumps by
+//! more than the samples in a frame means frames were dropped; the missing
+//! span is kept as a gap for that stream instead of being compressed.
+//! Without sensor timestamps, a notification arriving much later than the
+//! configured sample rate explains is treated the same way.
+//!
+//! ## Retention
+//! Every series drops points older than its retention, so memory stays flat
+//! in long sessions: sampled signals after `SIGNAL_RETENTION`, keeping a
+//! min/max envelope for charts scrolled back further, beats and derived
+//! metrics after `DERIVED_RETENTION`. Gaps are kept for `GAP_RETENTION_NS`,
+//! as long as the envelope reaches back. Complete data is in the recording.
 //!
 //! ## Why Separate from TimeSeries
 //! This keeps sensor-specific protocol logic separate from generic time series
 //! storage, making TimeSeries reusable for other data sources.
 
-use arctic::PmdData;
-use crate::timeseries::{TimeSeries, PointSliceExt};
-use crate::recorder::{PolarDataManager, ChannelId};
-
-// Sample rates configured for Polar H10 device
-// Note: These should match the rates configured via polar.ecg_sample_rate() and polar.acc_sample_rate()
-// The actual rate is queried from the device and set to maximum in sensor.rs
-const ECG_SAMPLE_RATE_HZ: u64 = 130; // Default ECG sampling rate in Hz (can be configured)
-const ACC_SAMPLE_RATE_HZ: u64 = 200; // Default accelerometer sampling rate in Hz
+use crate::activity::{ActivityClassifier, ActivityLevel};
+use crate::artifacts::{ArtifactCorrection, ArtifactFilter};
+use crate::device_clock::DeviceClock;
+use crate::device_profile::{DeviceProfile, SensorStream};
+use crate::filters::{FilterChain, SignalFilters};
+use crate::hrv;
+use crate::qrs::QrsDetector;
+use crate::quality::{QualityReport, StreamQuality};
+use crate::respiration;
+use crate::samples::{HeartRateSample, MeasurementBatch, MeasurementSample};
+use crate::timeseries::{Gap, Point, Retention, SummaryResolution, TimeSeries};
+use crate::recorder::{ChannelId, DeviceRecorder};
+use std::collections::HashMap;
 
 // Nanoseconds in one second
 const NANOS_PER_SECOND: u64 = 1_000_000_000;
 
-/// Container for all Polar H10 data channels
+// Interval between breathing rate estimates
+const RESPIRATION_INTERVAL_NS: u64 = 5_000_000_000;
+
+// Sampled signals keep full resolution somewhat longer than the widest chart
+// window, and a min/max envelope of older data at 1 s and 10 s resolution
+const SIGNAL_RETENTION: Retention = Retention::Duration(15 * 60 * NANOS_PER_SECOND);
+const SIGNAL_SUMMARIES: [SummaryResolution; 2] = [
+    SummaryResolution { bucket_ns: NANOS_PER_SECOND, retention_ns: 2 * 3600 * NANOS_PER_SECOND },
+    SummaryResolution { bucket_ns: 10 * NANOS_PER_SECOND, retention_ns: 24 * 3600 * NANOS_PER_SECOND },
+];
+
+// Gaps are kept as far back as charts can scroll, the coarsest signal summary
+const GAP_RETENTION_NS: u64 = SIGNAL_SUMMARIES[1].retention_ns;
+
+/// Retention of beats and derived metrics, which arrive about once a second;
+/// an hour covers every chart and analysis window, including calibration
+pub const DERIVED_RETENTION: Retention = Retention::Duration(3600 * NANOS_PER_SECOND);
+
+/// Container for all Polar data channels
 ///
 /// Manages synchronized time series for each sensor output:
 /// - ECG: Electrocardiogram signal
 /// - ACC (X/Y/Z): 3-axis accelerometer
 /// - HR: Heart rate in BPM
-/// - RR: RR intervals (time between heartbeats)
+/// - RR: RR intervals (time between heartbeats), or PPI on optical sensors
 /// - HRV: Heart rate variability (RMSSD)
+/// - Respiration: Breathing rate estimated from ACC and the beats
+/// - Activity: Posture, activity level, steps and ENMO from ACC
+/// - PPG: Optical pulse signal
+/// - Gyro/Mag (X/Y/Z): 3-axis gyroscope and magnetometer
 ///
 /// Data is stored in both TimeSeries (for UI) and optionally RecorderManager (for persistence).
 pub struct Channels {
+    pub profile: DeviceProfile,
     pub ecg: TimeSeries,
     pub acc_x: TimeSeries,
     pub acc_y: TimeSeries,
     pub acc_z: TimeSeries,
     pub hr: TimeSeries,
     pub rr: TimeSeries,
+    /// RR or PPI intervals as received, without the fill points of `rr`
+    pub beats: TimeSeries,
+    /// NN intervals: beats after artifact correction
+    pub nn: TimeSeries,
+    /// Raw intervals detected as artifacts
+    pub rr_artifacts: TimeSeries,
+    /// R peaks detected in the ECG, valued with the ECG at the peak
+    pub r_peaks: TimeSeries,
+    /// Intervals between R peaks in ms
+    pub ecg_rr: TimeSeries,
     pub hrv: TimeSeries, // RMSSD over time
+    /// Breathing rate in breaths/min
+    pub respiration: TimeSeries,
+    /// Posture and activity level codes, cumulative steps and ENMO in mg,
+    /// one point per activity epoch
+    pub posture: TimeSeries,
+    pub activity: TimeSeries,
+    pub steps: TimeSeries,
+    pub enmo: TimeSeries,
+    pub ppg: TimeSeries,
+    /// ECG and PPG after their filter chains
+    pub ecg_filtered: TimeSeries,
+    pub ppg_filtered: TimeSeries,
+    pub gyro_x: TimeSeries,
+    pub gyro_y: TimeSeries,
+    pub gyro_z: TimeSeries,
+    pub mag_x: TimeSeries,
+    pub mag_y: TimeSeries,
+    pub mag_z: TimeSeries,
+    /// Latest cumulative energy expended in kJ (generic heart rate straps)
+    pub energy_expended: Option<u16>,
+    /// Closed spans without data, oldest first
+    pub gaps: Vec<Gap>,
+    /// Start of the gap in progress while the device is reconnecting
+    gap_start: Option<u64>,
+    /// Mapping from the sensor clock to wall time
+    pub clock: DeviceClock,
+    /// Sensor time of the last frame per stream, for dropped frame detection
+    last_frames: HashMap<SensorStream, u64>,
+    /// Spans lost to dropped frames, per stream, oldest first
+    stream_gaps: HashMap<SensorStream, Vec<Gap>>,
+    /// Arrival statistics per periodic stream
+    quality: HashMap<SensorStream, StreamQuality>,
+    /// Detects and corrects artifacts in the beats
+    artifact_filter: ArtifactFilter,
+    /// Finds R peaks in the ECG
+    qrs_detector: QrsDetector,
+    /// Classifies posture and activity from ACC
+    activity_classifier: ActivityClassifier,
+    /// Filter chain per filtered stream
+    filters: HashMap<SensorStream, FilterChain>,
+    /// Whether filtered samples are recorded
+    record_filtered: bool,
+    /// Time of the last breathing rate estimate
+    respiration_updated: Option<u64>,
+    /// Whether the rolling RMSSD only uses beats from still epochs
+    hrv_still_only: bool,
 }
 
 impl Channels {
+    /// Channels for a Polar H10
     pub fn new() -> Self {
-        Self {
-            ecg: TimeSeries::new(ECG_SAMPLE_RATE_HZ),
-            acc_x: TimeSeries::new(ACC_SAMPLE_RATE_HZ),
-            acc_y: TimeSeries::new(ACC_SAMPLE_RATE_HZ),
-            acc_z: TimeSeries::new(ACC_SAMPLE_RATE_HZ),
-            hr: TimeSeries::new(1), // HR doesn't use sample rate for time calculations
-            rr: TimeSeries::new(1), // RR doesn't use sample rate for time calculations
-            hrv: TimeSeries::new(1), // HRV (RMSSD) calculated periodically
+        Self::with_profile(DeviceProfile::default())
+    }
+
+    /// Channels using the default sample rates of the given device profile
+    pub fn with_profile(profile: DeviceProfile) -> Self {
+        let rate = |stream| profile.default_sample_rate(stream);
+
+        let signal = |stream| {
+            TimeSeries::new(rate(stream)).with_retention(SIGNAL_RETENTION).with_summaries(&SIGNAL_SUMMARIES)
+        };
+        let derived = || TimeSeries::new(1).with_retention(DERIVED_RETENTION);
+
+        let mut channels = Self {
+            ecg: signal(SensorStream::Ecg),
+            acc_x: signal(SensorStream::Acc),
+            acc_y: signal(SensorStream::Acc),
+            acc_z: signal(SensorStream::Acc),
+            hr: derived(), // HR doesn't use sample rate for time calculations
+            rr: derived(), // RR doesn't use sample rate for time calculations
+            beats: derived(),
+            nn: derived(),
+            rr_artifacts: derived(),
+            r_peaks: derived(),
+            ecg_rr: derived(),
+            hrv: derived(), // HRV (RMSSD) calculated periodically
+            respiration: derived(),
+            posture: derived(),
+            activity: derived(),
+            steps: derived(),
+            enmo: derived(),
+            ppg: signal(SensorStream::Ppg),
+            ecg_filtered: signal(SensorStream::Ecg),
+            ppg_filtered: signal(SensorStream::Ppg),
+            gyro_x: signal(SensorStream::Gyro),
+            gyro_y: signal(SensorStream::Gyro),
+            gyro_z: signal(SensorStream::Gyro),
+            mag_x: signal(SensorStream::Mag),
+            mag_y: signal(SensorStream::Mag),
+            mag_z: signal(SensorStream::Mag),
+            energy_expended: None,
+            gaps: Vec::new(),
+            gap_start: None,
+            clock: DeviceClock::new(),
+            last_frames: HashMap::new(),
+            stream_gaps: HashMap::new(),
+            quality: HashMap::new(),
+            artifact_filter: ArtifactFilter::new(ArtifactCorrection::default()),
+            qrs_detector: QrsDetector::new(rate(SensorStream::Ecg)),
+            activity_classifier: ActivityClassifier::new(profile.model.is_chest_strap()),
+            filters: HashMap::new(),
+            record_filtered: false,
+            respiration_updated: None,
+            hrv_still_only: false,
+            profile,
+        };
+        channels.set_filters(SignalFilters::default());
+        channels
+    }
+
+    /// Time series backing a recorder channel
+    ///
+    /// PPI shares the RR series so HRV is computed from whichever is available.
+    pub fn series(&self, channel: ChannelId) -> &TimeSeries {
+        match channel {
+            ChannelId::Ecg => &self.ecg,
+            ChannelId::AccX => &self.acc_x,
+            ChannelId::AccY => &self.acc_y,
+            ChannelId::AccZ => &self.acc_z,
+            ChannelId::Hr => &self.hr,
+            ChannelId::Rr | ChannelId::Ppi => &self.rr,
+            ChannelId::Hrv => &self.hrv,
+            ChannelId::Ppg => &self.ppg,
+            ChannelId::GyroX => &self.gyro_x,
+            ChannelId::GyroY => &self.gyro_y,
+            ChannelId::GyroZ => &self.gyro_z,
+            ChannelId::MagX => &self.mag_x,
+            ChannelId::MagY => &self.mag_y,
+            ChannelId::MagZ => &self.mag_z,
+            ChannelId::Nn => &self.nn,
+            ChannelId::RrArtifact => &self.rr_artifacts,
+            ChannelId::RPeak => &self.r_peaks,
+            ChannelId::EcgRr => &self.ecg_rr,
+            ChannelId::EcgFiltered => &self.ecg_filtered,
+            ChannelId::PpgFiltered => &self.ppg_filtered,
+            ChannelId::Respiration => &self.respiration,
+            ChannelId::Posture => &self.posture,
+            ChannelId::Activity => &self.activity,
+            ChannelId::Steps => &self.steps,
+            ChannelId::Enmo => &self.enmo,
+        }
+    }
+
+    fn series_mut(&mut self, channel: ChannelId) -> &mut TimeSeries {
+        match channel {
+            ChannelId::Ecg => &mut self.ecg,
+            ChannelId::AccX => &mut self.acc_x,
+            ChannelId::AccY => &mut self.acc_y,
+            ChannelId::AccZ => &mut self.acc_z,
+            ChannelId::Hr => &mut self.hr,
+            ChannelId::Rr | ChannelId::Ppi => &mut self.rr,
+            ChannelId::Hrv => &mut self.hrv,
+            ChannelId::Ppg => &mut self.ppg,
+            ChannelId::GyroX => &mut self.gyro_x,
+            ChannelId::GyroY => &mut self.gyro_y,
+            ChannelId::GyroZ => &mut self.gyro_z,
+            ChannelId::MagX => &mut self.mag_x,
+            ChannelId::MagY => &mut self.mag_y,
+            ChannelId::MagZ => &mut self.mag_z,
+            ChannelId::Nn => &mut self.nn,
+            ChannelId::RrArtifact => &mut self.rr_artifacts,
+            ChannelId::RPeak => &mut self.r_peaks,
+            ChannelId::EcgRr => &mut self.ecg_rr,
+            ChannelId::EcgFiltered => &mut self.ecg_filtered,
+            ChannelId::PpgFiltered => &mut self.ppg_filtered,
+            ChannelId::Respiration => &mut self.respiration,
+            ChannelId::Posture => &mut self.posture,
+            ChannelId::Activity => &mut self.activity,
+            ChannelId::Steps => &mut self.steps,
+            ChannelId::Enmo => &mut self.enmo,
+        }
+    }
+
+    /// Set the sample rate of every series belonging to a periodic stream
+    pub fn set_sample_rate(&mut self, stream: SensorStream, rate: u64) {
+        if !stream.is_periodic() {
+            return;
+        }
+        for channel in self.recorded_channels(stream, true) {
+            self.series_mut(channel).set_sample_rate(rate);
+        }
+        if let Some(filter) = self.filters.get_mut(&stream) {
+            *filter = filter.with_sample_rate(rate);
+        }
+        if let Some(quality) = self.quality.get_mut(&stream) {
+            quality.set_sample_rate(rate);
+        }
+        if stream == SensorStream::Ecg {
+            self.qrs_detector = QrsDetector::new(rate);
+        }
+    }
+
+    /// How artifacts in beats from now on are corrected
+    pub fn set_artifact_correction(&mut self, correction: ArtifactCorrection) {
+        self.artifact_filter.set_correction(correction);
+    }
+
+    /// Compute the rolling RMSSD from still periods only, from now on
+    pub fn set_hrv_still_only(&mut self, still_only: bool) {
+        self.hrv_still_only = still_only;
+    }
+
+    /// Filter ECG and PPG with new settings from now on
+    ///
+    /// Already filtered samples are kept.
+    pub fn set_filters(&mut self, filters: SignalFilters) {
+        for stream in [SensorStream::Ecg, SensorStream::Ppg] {
+            if let Some(settings) = filters.for_stream(stream) {
+                let rate = self.series(ChannelId::for_stream(stream)[0]).sample_rate();
+                self.filters.insert(stream, FilterChain::new(settings, rate));
+            }
+        }
+        self.record_filtered = filters.record;
+    }
+
+    /// Whether a stream is filtered, so its filtered series receives samples
+    pub fn is_filtered(&self, stream: SensorStream) -> bool {
+        self.filters.get(&stream).is_some_and(|filter| filter.settings().enabled)
+    }
+
+    /// Channels of a stream, with its filtered channel if that one is kept
+    ///
+    /// `include_filtered` adds the filtered channel regardless of whether
+    /// filtered samples are recorded.
+    fn recorded_channels(&self, stream: SensorStream, include_filtered: bool) -> Vec<ChannelId> {
+        let channels = ChannelId::for_stream(stream);
+        let filtered = channels
+            .first()
+            .and_then(ChannelId::filtered)
+            .filter(|_| include_filtered || (self.record_filtered && self.is_filtered(stream)));
+        channels.iter().copied().chain(filtered).collect()
+    }
+
+    /// Configured sample rate of each recorded channel of the profile's periodic streams
+    pub fn sample_rates(&self) -> Vec<(ChannelId, u64)> {
+        self.profile
+            .streams()
+            .iter()
+            .filter(|stream| stream.is_periodic())
+            .flat_map(|&stream| self.recorded_channels(stream, false))
+            .map(|channel| (channel, self.series(channel).sample_rate()))
+            .collect()
+    }
+
+    /// Data-quality statistics of a stream, once it delivered data
+    pub fn quality_report(&self, stream: SensorStream) -> Option<QualityReport> {
+        self.quality.get(&stream).map(StreamQuality::report)
+    }
+
+    /// Restart data-quality statistics, e.g. when a recording starts
+    pub fn reset_quality(&mut self) {
+        self.quality.clear();
+    }
+
+    /// Mark the start of a span without data after the link was lost
+    ///
+    /// The gap starts at the newest data point, since data usually stops a
+    /// while before the loss is detected at `now`.
+    pub fn begin_gap(&mut self, now: u64) {
+        if self.gap_start.is_some() {
+            return;
         }
+        let last_data = ChannelId::all()
+            .into_iter()
+            .filter_map(|channel| self.series(channel).last_point().map(|p| p.time))
+            .max();
+        self.gap_start = Some(last_data.unwrap_or(now).min(now));
+        // Frames after the reconnect are not dropped ones
+        self.last_frames.clear();
     }
 
-    pub fn set_ecg_sample_rate(&mut self, rate: u64) {
-        self.ecg.set_sample_rate(rate);
+    /// Close the gap in progress when data resumes, recording it if recording
+    pub fn end_gap(&mut self, now: u64, recorder: Option<&DeviceRecorder>) {
+        let Some(start) = self.gap_start.take() else {
+            return;
+        };
+        let gap = Gap { start, end: now };
+        log::info!("Data gap of {:.1}s", gap.duration_ns() as f64 / NANOS_PER_SECOND as f64);
+        if let Some(rec) = recorder {
+            let _ = rec.add_gap(gap);
+        }
+        self.gaps.push(gap);
+        Self::prune_gaps(&mut self.gaps, now);
+    }
+
+    /// Drop gaps that ended before the oldest data charts can show
+    fn prune_gaps(gaps: &mut Vec<Gap>, now: u64) {
+        let cutoff = now.saturating_sub(GAP_RETENTION_NS);
+        gaps.retain(|gap| gap.end >= cutoff);
     }
 
-    pub fn set_acc_sample_rate(&mut self, rate: u64) {
-        self.acc_x.set_sample_rate(rate);
-        self.acc_y.set_sample_rate(rate);
-        self.acc_z.set_sample_rate(rate);
+    /// Whether the device is reconnecting and no data is arriving
+    pub fn in_gap(&self) -> bool {
+        self.gap_start.is_some()
+    }
+
+    /// Gaps affecting a stream: device-wide ones and its dropped frames
+    pub fn gaps_for(&self, stream: SensorStream) -> Vec<Gap> {
+        let mut gaps = self.gaps.clone();
+        if let Some(dropped) = self.stream_gaps.get(&stream) {
+            gaps.extend_from_slice(dropped);
+            gaps.sort_by_key(|gap| gap.start);
+        }
+        gaps
+    }
+
+    /// Add a point to a series and record it to file if recording
+    fn add_sample(&mut self, channel: ChannelId, t: u64, value: i32, recorder: Option<&DeviceRecorder>) {
+        self.series_mut(channel).add_point(t, value);
+        if let Some(rec) = recorder {
+            let _ = rec.add_point(channel, t, value);
+        }
     }
 
     /// Process heart rate data from Polar sensor
@@ -78,67 +464,161 @@ impl Channels {
     /// Handles HR (beats per minute) and RR intervals (time between beats).
     /// Calculates rolling HRV (RMSSD) from recent RR intervals.
     /// Records data to file if recorder is provided.
-    pub fn handle_heart_rate(&mut self, hr: arctic::HeartRate, recorder: Option<&PolarDataManager>) {
-        // Use current system time as approximate timestamp for HR
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_nanos() as u64;
-
-        self.hr.add_point(now, (*hr.bpm()).into());
-        
-        // Record HR to file if recording
-        if let Some(rec) = recorder {
-            let _ = rec.add_point(ChannelId::Hr, now, (*hr.bpm()).into());
-        }
+    ///
+    /// `now` is the arrival time in nanoseconds, used as approximate timestamp for HR.
+    pub fn handle_heart_rate(&mut self, hr: HeartRateSample, now: u64, recorder: Option<&DeviceRecorder>) {
+        self.add_sample(ChannelId::Hr, now, hr.bpm.into(), recorder);
 
         log::debug!("Heart rate: {:?}", hr);
 
-        let rr = hr.rr().clone().unwrap_or(vec![]);
-        let rr_len = rr.len();
+        if hr.energy_expended.is_some() {
+            self.energy_expended = hr.energy_expended;
+        }
 
         // Handle RR intervals - each interval is a separate data point
         // RR intervals are the time between beats in milliseconds
-        if rr_len == 0 {
+        if hr.rr_intervals.is_empty() {
             // If no RR data, repeat last value to maintain continuity
             if let Some(last) = self.rr.last_point() {
                 self.rr.add_point(now, last.value);
             }
         } else {
-            // Add each RR interval as a separate point
-            // Space them out evenly within the time since last measurement
-            let time_spacing = if let Some(last) = self.rr.last_point() {
-                (now - last.time) / rr_len as u64
-            } else {
-                1_000_000_000 // 1 second default spacing
-            };
+            self.add_intervals(ChannelId::Rr, &hr.rr_intervals, now, recorder);
+        }
+        self.update_respiration(now, recorder);
+    }
 
-            for (i, &rr_value) in rr.iter().enumerate() {
-                let t = now - ((rr_len - i - 1) as u64 * time_spacing);
-                self.rr.add_point(t, rr_value as i32);
-                
-                // Record RR to file if recording
-                if let Some(rec) = recorder {
-                    let _ = rec.add_point(ChannelId::Rr, t, rr_value as i32);
-                }
+    /// Add beat-to-beat intervals (RR or PPI), correct artifacts and update HRV
+    ///
+    /// Intervals arrive without individual timestamps, so they are spaced out
+    /// evenly within the time since the previous interval.
+    fn add_intervals(
+        &mut self,
+        channel: ChannelId,
+        intervals: &[u16],
+        now: u64,
+        recorder: Option<&DeviceRecorder>,
+    ) {
+        let count = intervals.len();
+        let time_spacing = if let Some(last) = self.rr.last_point() {
+            now.saturating_sub(last.time) / count as u64
+        } else {
+            1_000_000_000 // 1 second default spacing
+        };
+
+        for (i, &interval) in intervals.iter().enumerate() {
+            let t = now - ((count - i - 1) as u64 * time_spacing);
+            self.add_sample(channel, t, interval as i32, recorder);
+            self.beats.add_point(t, interval as i32);
+
+            let output = self.artifact_filter.push(t, interval as f64);
+            if output.artifact {
+                self.add_sample(ChannelId::RrArtifact, t, interval as i32, recorder);
             }
-            
-            // Calculate and store HRV (RMSSD) from recent RR intervals
-            // Use last 30 seconds of data for rolling RMSSD calculation
-            const THIRTY_SECONDS_NS: u64 = 30_000_000_000;
-            let recent_rr = self.rr.last_duration(THIRTY_SECONDS_NS);
-            
-            if recent_rr.len() >= 2 {
-                let rmssd = recent_rr.rmssd();
-                // Store RMSSD value as integer (rounded)
-                self.hrv.add_point(now, rmssd as i32);
-                
-                // Record HRV to file if recording
-                if let Some(rec) = recorder {
-                    let _ = rec.add_point(ChannelId::Hrv, now, rmssd as i32);
-                }
+            for beat in output.beats {
+                self.add_sample(ChannelId::Nn, beat.time, beat.interval.round() as i32, recorder);
             }
         }
+
+        // Calculate and store HRV (RMSSD) from recent NN intervals
+        // Use last 30 seconds of data for rolling RMSSD calculation
+        const THIRTY_SECONDS_NS: u64 = 30_000_000_000;
+        let recent_rr = self.nn.last_duration(THIRTY_SECONDS_NS);
+        let runs = if self.hrv_still_only { self.still_runs(recent_rr) } else { vec![recent_rr] };
+
+        if let Some(rmssd) = hrv::rmssd_of_runs(&runs) {
+            // Store RMSSD value as integer (rounded)
+            self.add_sample(ChannelId::Hrv, now, rmssd as i32, recorder);
+        }
+    }
+
+    /// Feed an ECG sample to the QRS detector and record the beats it finds
+    fn detect_r_peak(&mut self, t: u64, value: i32, recorder: Option<&DeviceRecorder>) {
+        let Some(peak) = self.qrs_detector.push(t, value) else {
+            return;
+        };
+        self.add_sample(ChannelId::RPeak, peak.time, peak.value, recorder);
+        if let Some(interval) = peak.interval_ms {
+            self.add_sample(ChannelId::EcgRr, peak.time, interval.round() as i32, recorder);
+        }
+    }
+
+    /// Feed an ACC sample to the activity classifier and record the epochs it ends
+    fn classify_activity(&mut self, t: u64, x: i32, y: i32, z: i32, recorder: Option<&DeviceRecorder>) {
+        let Some(epoch) = self.activity_classifier.push(t, x, y, z) else {
+            return;
+        };
+        if let Some(posture) = epoch.posture {
+            self.add_sample(ChannelId::Posture, epoch.time, posture.code(), recorder);
+        }
+        self.add_sample(ChannelId::Activity, epoch.time, epoch.level.code(), recorder);
+        self.add_sample(ChannelId::Steps, epoch.time, epoch.steps as i32, recorder);
+        self.add_sample(ChannelId::Enmo, epoch.time, epoch.enmo_mg.round() as i32, recorder);
+    }
+
+    /// Whether the wearer was still at `time`, judged by the epoch covering it
+    ///
+    /// Without activity epochs, e.g. for devices without ACC, everything
+    /// counts as still. Times after the last epoch take its level.
+    pub fn is_still(&self, time: u64) -> bool {
+        let epochs = self.activity.slice(0, self.activity.len());
+        let epoch = epochs.get(self.activity.partition_point_time(time)).or(epochs.last());
+        !epoch.is_some_and(|e| ActivityLevel::from_code(e.value) != Some(ActivityLevel::Still))
+    }
+
+    /// Split beats into runs of consecutive beats while the wearer was still
+    ///
+    /// Beats while moving end a run, so successive differences are never
+    /// taken across the beats left out.
+    pub fn still_runs<'a>(&self, beats: &'a [Point]) -> Vec<&'a [Point]> {
+        beats
+            .split(|beat| !self.is_still(beat.time))
+            .filter(|run| !run.is_empty())
+            .collect()
+    }
+
+    /// Estimate the breathing rate of the last minute, if one is due
+    ///
+    /// Intervals from detected R peaks are preferred over the device's, which
+    /// are spread over each notification.
+    fn update_respiration(&mut self, now: u64, recorder: Option<&DeviceRecorder>) {
+        if self.respiration_updated.is_some_and(|t| now < t + RESPIRATION_INTERVAL_NS) {
+            return;
+        }
+        self.respiration_updated = Some(now);
+
+        let window = |series: &TimeSeries| series.range_from_time(now, respiration::WINDOW_NS);
+        let intervals = if self.ecg_rr.is_empty() { &self.nn } else { &self.ecg_rr };
+        let estimates: Vec<_> = [
+            respiration::acc_rate([window(&self.acc_x), window(&self.acc_y), window(&self.acc_z)]),
+            respiration::edr_rate(window(&self.r_peaks)),
+            respiration::rsa_rate(window(intervals)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if let Some(rate) = respiration::fuse(&estimates) {
+            self.add_sample(ChannelId::Respiration, now, rate.round() as i32, recorder);
+        }
+    }
+
+    /// Feed a sample to its stream's filter chain, if the stream is filtered
+    ///
+    /// The filtered series always receives the output for display; the
+    /// recording only when recording filtered signals is enabled.
+    fn filter_sample(&mut self, stream: SensorStream, t: u64, value: i32, recorder: Option<&DeviceRecorder>) {
+        if !self.is_filtered(stream) {
+            return;
+        }
+        let Some(channel) = ChannelId::for_stream(stream)[0].filtered() else {
+            return;
+        };
+        let Some(filter) = self.filters.get_mut(&stream) else {
+            return;
+        };
+        let filtered = filter.process(t, value);
+        let recorder = recorder.filter(|_| self.record_filtered);
+        self.add_sample(channel, t, filtered, recorder);
     }
 
     /// Calculate the starting timestamp for a data stream based on last known point
@@ -164,103 +644,209 @@ impl Channels {
         }
     }
 
-    /// Count samples by type in measurement data
+    /// Number of samples missing before a frame, judged from sensor timestamps
+    ///
+    /// `previous` and `current` are the sensor times of the last sample in
+    /// consecutive frames of one stream. Differences below half a frame are
+    /// attributed to the sensor rate not matching the nominal one exactly.
+    fn missing_samples(previous: u64, current: u64, sample_count: u64, timestep_ns: u64) -> u64 {
+        let elapsed_samples = (current.saturating_sub(previous) + timestep_ns / 2) / timestep_ns;
+        let missing = elapsed_samples.saturating_sub(sample_count);
+        if missing >= (sample_count / 2).max(1) {
+            missing
+        } else {
+            0
+        }
+    }
+
+    /// Wall time of the first sample in a frame stamped by the sensor clock
+    ///
+    /// Records a gap for the stream if frames were dropped since the previous one.
+    fn frame_start_time(
+        &mut self,
+        stream: SensorStream,
+        sensor_time: u64,
+        sample_count: u64,
+        timestep_ns: u64,
+        now: u64,
+        recorder: Option<&DeviceRecorder>,
+    ) -> u64 {
+        let first_sample = sensor_time.saturating_sub(sample_count.saturating_sub(1) * timestep_ns);
+        let start = self.clock.to_wall(first_sample).unwrap_or(now);
+
+        let resumed_at = self.gaps.last().map_or(0, |gap| gap.end);
+        let last_time = self
+            .series(ChannelId::for_stream(stream)[0])
+            .last_point()
+            .map(|p| p.time)
+            .filter(|&t| t >= resumed_at);
+
+        if let (Some(previous), Some(last)) = (self.last_frames.insert(stream, sensor_time), last_time) {
+            let missing = Self::missing_samples(previous, sensor_time, sample_count, timestep_ns);
+            if missing > 0 && start > last {
+                log::warn!("Dropped {} {:?} samples", missing, stream);
+                self.add_stream_gap(stream, Gap { start: last, end: start }, recorder);
+            }
+        }
+
+        // Estimate corrections must not move samples before stored ones
+        match last_time {
+            Some(last) if start <= last => last + timestep_ns,
+            _ => start,
+        }
+    }
+
+    /// Keep a span without data of one stream, recording it if recording
+    fn add_stream_gap(&mut self, stream: SensorStream, gap: Gap, recorder: Option<&DeviceRecorder>) {
+        if let Some(rec) = recorder {
+            for channel in self.recorded_channels(stream, false) {
+                let _ = rec.add_channel_gap(channel, gap);
+            }
+        }
+        let gaps = self.stream_gaps.entry(stream).or_default();
+        gaps.push(gap);
+        Self::prune_gaps(gaps, gap.end);
+    }
+
+    /// Update a stream's data-quality statistics with a batch arriving at `now`
+    ///
+    /// Returns true if the batch arrived late enough that data was lost before it.
+    fn record_arrival(
+        &mut self,
+        stream: SensorStream,
+        count: u64,
+        now: u64,
+        recorder: Option<&DeviceRecorder>,
+    ) -> bool {
+        let sample_rate = self.series(ChannelId::for_stream(stream)[0]).sample_rate();
+        let quality = self
+            .quality
+            .entry(stream)
+            .or_insert_with(|| StreamQuality::new(sample_rate));
+        let late = quality.record_batch(now, count).is_some();
+        let report = quality.report();
+
+        if let Some(rec) = recorder {
+            for channel in self.recorded_channels(stream, false) {
+                let _ = rec.set_quality(channel, report);
+            }
+        }
+        late
+    }
+
+    /// Count samples by stream in measurement data
     ///
     /// We need counts to calculate proper timestamp spacing before processing.
     /// Single pass is more efficient than counting during processing.
-    fn count_samples(data: &[PmdData]) -> (u64, u64) {
-        let mut ecg_count = 0u64;
-        let mut acc_count = 0u64;
-        
+    fn count_samples(data: &[MeasurementSample]) -> HashMap<SensorStream, u64> {
+        let mut counts = HashMap::new();
+
         for d in data.iter() {
-            match d {
-                PmdData::Ecg(_) => ecg_count += 1,
-                PmdData::Acc(_) => acc_count += 1,
-            }
+            *counts.entry(d.stream()).or_insert(0) += 1;
         }
-        
-        (ecg_count, acc_count)
+
+        counts
     }
 
     /// Process measurement data batch from Polar sensor
     ///
-    /// Handles mixed ECG and accelerometer samples, calculating proper timestamps
-    /// for each channel based on sample rates and maintaining continuity.
+    /// Handles mixed samples from any measurement stream, calculating proper
+    /// timestamps for each channel based on sample rates and maintaining continuity.
     /// Records data to file if recorder is provided.
-    pub fn handle_measurement_data(&mut self, data: arctic::PmdRead, recorder: Option<&PolarDataManager>) {
-        // Use system time as the reference point for this batch
-        // Why: Sensor doesn't provide absolute timestamps, only sample batches
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_nanos() as u64;
-        
-        let data_vec = data.data();
-        
-        // Count samples by type to properly timestamp each channel
-        let (ecg_count, acc_count) = Self::count_samples(&data_vec);
-        
-        // Calculate timesteps for each data type based on sample rates
-        let ecg_timestep = NANOS_PER_SECOND / self.ecg.sample_rate();
-        let acc_timestep = NANOS_PER_SECOND / self.acc_x.sample_rate();
-        
-        // Determine starting timestamps for each channel
-        let ecg_start_time = Self::calculate_start_time(
-            self.ecg.last_point().map(|p| p.time),
-            now,
-            ecg_count,
-            ecg_timestep,
-        );
-        
-        let acc_start_time = Self::calculate_start_time(
-            self.acc_x.last_point().map(|p| p.time),
-            now,
-            acc_count,
-            acc_timestep,
-        );
-        
-        // Track indices per data type for timestamp calculation
-        let mut ecg_idx = 0u64;
-        let mut acc_idx = 0u64;
+    ///
+    /// `now` is the arrival time in nanoseconds. Batches with a sensor timestamp
+    /// are placed through the device clock; without one `now` is the reference
+    /// point and samples continue evenly from the previous batch.
+    pub fn handle_measurement_data(&mut self, data: MeasurementBatch, now: u64, recorder: Option<&DeviceRecorder>) {
+        let counts = Self::count_samples(&data.samples);
 
-        // Process each sample with calculated timestamps
-        for d in data_vec.iter() {
-            match d {
-                PmdData::Acc(acc) => {
-                    let t = acc_start_time + (acc_idx * acc_timestep);
-                    let acc = acc.data();
-                    self.acc_x.add_point(t, acc.0);
-                    self.acc_y.add_point(t, acc.1);
-                    self.acc_z.add_point(t, acc.2);
-                    
-                    // Record ACC to file if recording
-                    if let Some(rec) = recorder {
-                        let _ = rec.add_point(ChannelId::AccX, t, acc.0);
-                        let _ = rec.add_point(ChannelId::AccY, t, acc.1);
-                        let _ = rec.add_point(ChannelId::AccZ, t, acc.2);
+        if let Some(sensor_time) = data.sensor_time {
+            let went_back = counts
+                .keys()
+                .any(|stream| self.last_frames.get(stream).is_some_and(|&t| t >= sensor_time));
+            if went_back {
+                log::warn!("Sensor clock went backwards, restarting clock estimate");
+                self.clock = DeviceClock::new();
+                self.last_frames.clear();
+            }
+            self.clock.observe(sensor_time, now);
+        }
+
+        // Next timestamp and timestep for each periodic stream in this batch
+        let mut timing: HashMap<SensorStream, (u64, u64)> = HashMap::new();
+        for (stream, count) in counts.into_iter().filter(|(stream, _)| stream.is_periodic()) {
+            let late = self.record_arrival(stream, count, now, recorder);
+            let series = self.series(ChannelId::for_stream(stream)[0]);
+            let timestep = NANOS_PER_SECOND / series.sample_rate();
+            let start_time = match data.sensor_time {
+                Some(sensor_time) => self.frame_start_time(stream, sensor_time, count, timestep, now, recorder),
+                None => {
+                    // Points from before a gap must not stretch timestamps across it
+                    let resumed_at = self.gaps.last().map_or(0, |gap| gap.end);
+                    let last_time = series.last_point().map(|p| p.time).filter(|&t| t >= resumed_at);
+                    match last_time {
+                        // Notifications were lost: restart from arrival and keep the hole
+                        Some(last) if late => {
+                            let start = Self::calculate_start_time(None, now, count, timestep).max(last + timestep);
+                            log::warn!("{:?} notification late by {:.0} ms", stream, (start - last) as f64 / 1e6);
+                            self.add_stream_gap(stream, Gap { start: last, end: start }, recorder);
+                            start
+                        }
+                        _ => Self::calculate_start_time(last_time, now, count, timestep),
                     }
-                    
-                    acc_idx += 1;
                 }
-                PmdData::Ecg(ecg) => {
-                    let t = ecg_start_time + (ecg_idx * ecg_timestep);
-                    self.ecg.add_point(t, *ecg.val());
-                    
-                    // Record ECG to file if recording
-                    if let Some(rec) = recorder {
-                        let _ = rec.add_point(ChannelId::Ecg, t, *ecg.val());
-                    }
-                    
-                    ecg_idx += 1;
+            };
+            timing.insert(stream, (start_time, timestep));
+        }
+
+        let mut ppi_intervals = Vec::new();
+
+        // Process each sample with calculated timestamps
+        for d in data.samples.iter() {
+            let (x, y, z) = match *d {
+                MeasurementSample::Ppi(interval) => {
+                    ppi_intervals.push(interval);
+                    continue;
                 }
+                MeasurementSample::Ecg(value) | MeasurementSample::Ppg(value) => (value, 0, 0),
+                MeasurementSample::Acc { x, y, z }
+                | MeasurementSample::Gyro { x, y, z }
+                | MeasurementSample::Mag { x, y, z } => (x, y, z),
+            };
+
+            let stream = d.stream();
+            let Some((next_time, timestep)) = timing.get_mut(&stream) else {
+                continue;
+            };
+            let t = *next_time;
+            *next_time += *timestep;
+
+            // Single-value streams have one channel, 3-axis streams have three
+            for (&channel, value) in ChannelId::for_stream(stream).iter().zip([x, y, z]) {
+                self.add_sample(channel, t, value, recorder);
+            }
+            match stream {
+                SensorStream::Ecg => self.detect_r_peak(t, x, recorder),
+                SensorStream::Acc => self.classify_activity(t, x, y, z, recorder),
+                _ => {}
             }
+            self.filter_sample(stream, t, x, recorder);
         }
+
+        if !ppi_intervals.is_empty() {
+            self.add_intervals(ChannelId::Ppi, &ppi_intervals, now, recorder);
+        }
+        self.update_respiration(now, recorder);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::activity::Posture;
+    use crate::device_profile::DeviceModel;
+    use crate::filters::FilterSettings;
+    use crate::synthetic::{SyntheticConfig, SyntheticSensor};
 
     #[test]
     fn test_channels_creation() {
@@ -275,10 +861,10 @@ mod tests {
     fn test_set_sample_rates() {
         let mut channels = Channels::new();
         
-        channels.set_ecg_sample_rate(256);
+        channels.set_sample_rate(SensorStream::Ecg, 256);
         assert_eq!(channels.ecg.sample_rate(), 256);
         
-        channels.set_acc_sample_rate(100);
+        channels.set_sample_rate(SensorStream::Acc, 100);
         assert_eq!(channels.acc_x.sample_rate(), 100);
         assert_eq!(channels.acc_y.sample_rate(), 100);
         assert_eq!(channels.acc_z.sample_rate(), 100);
@@ -309,6 +895,325 @@ mod tests {
         assert_eq!(start, last_time + timestep);
     }
 
-    // Note: Cannot test count_samples because arctic::Ecg and arctic::Acc
-    // constructors are private. This is tested indirectly through integration tests.
+    #[test]
+    fn test_count_samples() {
+        let samples = vec![
+            MeasurementSample::Ecg(10),
+            MeasurementSample::Acc { x: 1, y: 2, z: 3 },
+            MeasurementSample::Ecg(20),
+            MeasurementSample::Ecg(30),
+        ];
+
+        let counts = Channels::count_samples(&samples);
+        assert_eq!(counts[&SensorStream::Ecg], 3);
+        assert_eq!(counts[&SensorStream::Acc], 1);
+        assert!(Channels::count_samples(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_measurement_data_timestamps() {
+        let mut channels = Channels::new();
+        channels.set_sample_rate(SensorStream::Ecg, 100); // 10ms per sample
+        let now = 10_000_000_000;
+
+        let batch = MeasurementBatch::new(vec![
+            MeasurementSample::Ecg(1),
+            MeasurementSample::Acc { x: 1, y: 2, z: 3 },
+            MeasurementSample::Ecg(2),
+            MeasurementSample::Ecg(3),
+        ]);
+        channels.handle_measurement_data(batch, now, None);
+
+        // First batch ends at 'now'
+        let ecg = channels.ecg.last_duration(u64::MAX);
+        assert_eq!(ecg.len(), 3);
+        assert_eq!(ecg[0].time, now - 20_000_000);
+        assert_eq!(ecg[2].time, now);
+        assert_eq!(ecg[2].value, 3);
+        assert_eq!(channels.acc_z.last_point().unwrap().value, 3);
+
+        // Next batch continues one interval after the last sample
+        let batch = MeasurementBatch::new(vec![MeasurementSample::Ecg(4)]);
+        channels.handle_measurement_data(batch, now + 50_000_000, None);
+        assert_eq!(channels.ecg.last_point().unwrap().time, now + 10_000_000);
+    }
+
+    #[test]
+    fn test_gap_restarts_timestamps() {
+        let mut channels = Channels::new();
+        channels.set_sample_rate(SensorStream::Ecg, 100);
+        let now = 10_000_000_000;
+
+        channels.handle_measurement_data(MeasurementBatch::new(vec![MeasurementSample::Ecg(1)]), now, None);
+        channels.begin_gap(now + 3_000_000_000);
+        assert!(channels.in_gap());
+
+        let resumed = now + 8_000_000_000;
+        channels.end_gap(resumed, None);
+        assert!(!channels.in_gap());
+        assert_eq!(channels.gaps, vec![Gap { start: now, end: resumed }]);
+
+        // Data after the gap is stamped from arrival, not from the old last point
+        let batch = MeasurementBatch::new(vec![MeasurementSample::Ecg(2), MeasurementSample::Ecg(3)]);
+        channels.handle_measurement_data(batch, resumed + 20_000_000, None);
+        assert_eq!(channels.ecg.last_point().unwrap().time, resumed + 20_000_000);
+        assert_eq!(channels.ecg.len(), 3);
+
+        // Gaps older than charts can show are dropped
+        let later = resumed + GAP_RETENTION_NS + 1;
+        channels.begin_gap(later);
+        channels.end_gap(later + 1_000_000_000, None);
+        assert_eq!(channels.gaps, vec![Gap { start: resumed + 20_000_000, end: later + 1_000_000_000 }]);
+    }
+
+    #[test]
+    fn test_missing_samples() {
+        let timestep = 10_000_000;
+        // Consecutive frames of 10 samples, with and without rate mismatch
+        assert_eq!(Channels::missing_samples(0, 100_000_000, 10, timestep), 0);
+        assert_eq!(Channels::missing_samples(0, 103_000_000, 10, timestep), 0);
+        // One frame of 10 samples lost in between
+        assert_eq!(Channels::missing_samples(0, 200_000_000, 10, timestep), 10);
+    }
+
+    #[test]
+    fn test_sensor_timestamps_and_dropped_frames() {
+        let mut channels = Channels::new();
+        channels.set_sample_rate(SensorStream::Ecg, 100);
+        let now = 10_000_000_000;
+        let sensor = 500_000_000_000;
+        let frame = |values: &[i32], sensor_time| {
+            MeasurementBatch::new(values.iter().map(|&v| MeasurementSample::Ecg(v)).collect())
+                .with_sensor_time(sensor_time)
+        };
+
+        channels.handle_measurement_data(frame(&[1, 2], sensor), now, None);
+        assert_eq!(channels.ecg.last_point().unwrap().time, now);
+
+        // Late arrival does not delay the samples, the sensor time places them
+        channels.handle_measurement_data(frame(&[3, 4], sensor + 20_000_000), now + 45_000_000, None);
+        assert_eq!(channels.ecg.last_point().unwrap().time, now + 20_000_000);
+        assert!(channels.gaps_for(SensorStream::Ecg).is_empty());
+
+        // Frame with sensor time 20 samples later: the two samples between are missing
+        channels.handle_measurement_data(frame(&[7, 8], sensor + 60_000_000), now + 60_000_000, None);
+        assert_eq!(channels.ecg.last_point().unwrap().time, now + 60_000_000);
+        assert_eq!(
+            channels.gaps_for(SensorStream::Ecg),
+            vec![Gap { start: now + 20_000_000, end: now + 50_000_000 }]
+        );
+        assert!(channels.gaps_for(SensorStream::Acc).is_empty());
+    }
+
+    #[test]
+    fn test_late_notification_without_sensor_time() {
+        let mut channels = Channels::new();
+        channels.set_sample_rate(SensorStream::Ecg, 100);
+        let now = 10_000_000_000;
+        let batch = || MeasurementBatch::new(vec![MeasurementSample::Ecg(1), MeasurementSample::Ecg(2)]);
+
+        channels.handle_measurement_data(batch(), now, None);
+        channels.handle_measurement_data(batch(), now + 20_000_000, None);
+        assert!(channels.gaps_for(SensorStream::Ecg).is_empty());
+
+        // Nothing for a second: the samples are placed at arrival, not right after the last ones
+        let late = now + 1_020_000_000;
+        channels.handle_measurement_data(batch(), late, None);
+        assert_eq!(channels.ecg.last_point().unwrap().time, late);
+        assert_eq!(
+            channels.gaps_for(SensorStream::Ecg),
+            vec![Gap { start: now + 20_000_000, end: late - 10_000_000 }]
+        );
+
+        let report = channels.quality_report(SensorStream::Ecg).unwrap();
+        assert_eq!(report.samples_received, 6);
+        assert_eq!(report.samples_expected, 104);
+        assert_eq!(report.gap_count, 1);
+        assert_eq!(report.longest_gap_ns, 1_000_000_000);
+        assert!(channels.quality_report(SensorStream::Acc).is_none());
+    }
+
+    #[test]
+    fn test_heart_rate_with_rr_intervals() {
+        let mut channels = Channels::new();
+        let now = 10_000_000_000;
+
+        channels.handle_heart_rate(HeartRateSample::new(60, vec![1000]), now, None);
+        channels.handle_heart_rate(HeartRateSample::new(62, vec![950, 990]), now + 2_000_000_000, None);
+
+        assert_eq!(channels.hr.len(), 2);
+        assert_eq!(channels.hr.last_point().unwrap().value, 62);
+
+        // RR intervals are spread evenly since the previous RR point
+        let rr = channels.rr.last_duration(u64::MAX);
+        assert_eq!(rr.len(), 3);
+        assert_eq!(rr[1].time, now + 1_000_000_000);
+        assert_eq!(rr[2].value, 990);
+
+        // RMSSD needs at least two intervals
+        assert_eq!(channels.hrv.len(), 1);
+        assert!(channels.hrv.last_point().unwrap().value > 0);
+    }
+
+    #[test]
+    fn test_heart_rate_without_rr_repeats_last() {
+        let mut channels = Channels::new();
+        let now = 10_000_000_000;
+
+        channels.handle_heart_rate(HeartRateSample::new(60, Vec::new()), now, None);
+        assert_eq!(channels.rr.len(), 0);
+
+        channels.handle_heart_rate(HeartRateSample::new(60, vec![1000]), now + 1_000_000_000, None);
+        channels.handle_heart_rate(HeartRateSample::new(61, Vec::new()), now + 2_000_000_000, None);
+
+        assert_eq!(channels.rr.len(), 2);
+        assert_eq!(channels.rr.last_point().unwrap().value, 1000);
+
+        // The repeated value is not a beat
+        assert_eq!(channels.beats.len(), 1);
+    }
+
+    #[test]
+    fn test_artifacts_corrected_before_hrv() {
+        let mut channels = Channels::new();
+        channels.set_artifact_correction(ArtifactCorrection::Interpolate);
+        let mut now = 10_000_000_000;
+        for rr in [1000, 1000, 1000, 1000, 1000, 1000, 400, 1000] {
+            now += 1_000_000_000;
+            channels.handle_heart_rate(HeartRateSample::new(60, vec![rr]), now, None);
+        }
+
+        assert_eq!(channels.beats.len(), 8);
+        assert_eq!(channels.rr_artifacts.len(), 1);
+        assert_eq!(channels.rr_artifacts.last_point().unwrap().value, 400);
+        // The short beat is replaced, so the NN intervals do not vary
+        assert_eq!(channels.nn.len(), 8);
+        assert_eq!(channels.hrv.last_point().unwrap().value, 0);
+    }
+
+    #[test]
+    fn test_r_peaks_from_ecg() {
+        let config = SyntheticConfig { heart_rate_bpm: 60.0, model: DeviceModel::H10 };
+        let mut sensor = SyntheticSensor::with_seed(config, 7);
+        let mut channels = Channels::new();
+        let start = 10_000_000_000;
+        for i in 1..=40 {
+            let elapsed = i as f64 * 0.5;
+            let batch = sensor.batch(SensorStream::Ecg, elapsed);
+            channels.handle_measurement_data(batch, start + (elapsed * 1e9) as u64, None);
+        }
+
+        // Every beat after the learning period, with intervals matching the device's
+        let device_rr = sensor.heart_rate(20.0).rr_intervals;
+        let mean = |values: &[f64]| values.iter().sum::<f64>() / values.len() as f64;
+        let ecg_rr: Vec<f64> = channels.ecg_rr.last_duration(u64::MAX).iter().map(|p| p.value as f64).collect();
+        assert!(ecg_rr.len() >= 15, "{} intervals", ecg_rr.len());
+        assert_eq!(channels.r_peaks.len(), ecg_rr.len() + 1);
+        let device_mean = mean(&device_rr[device_rr.len() - ecg_rr.len()..].iter().map(|&v| v as f64).collect::<Vec<_>>());
+        assert!((mean(&ecg_rr) - device_mean).abs() < 10.0);
+    }
+
+    #[test]
+    fn test_respiration_from_synthetic_sensor() {
+        let config = SyntheticConfig { heart_rate_bpm: 70.0, model: DeviceModel::H10 };
+        let mut sensor = SyntheticSensor::with_seed(config, 3);
+        let mut channels = Channels::new();
+        let start = 10_000_000_000;
+        for i in 1..=360 {
+            let elapsed = i as f64 * 0.25;
+            let now = start + (elapsed * 1e9) as u64;
+            for stream in [SensorStream::Ecg, SensorStream::Acc] {
+                channels.handle_measurement_data(sensor.batch(stream, elapsed), now, None);
+            }
+            if i % 4 == 0 {
+                channels.handle_heart_rate(sensor.heart_rate(elapsed), now, None);
+            }
+        }
+
+        // The sensor breathes 15 times a minute; estimates start after 30 s
+        let rates = channels.respiration.last_duration(u64::MAX);
+        assert!(rates.len() >= 10, "{} estimates", rates.len());
+        assert!(rates.iter().all(|p| (p.value - 15).abs() <= 1), "{:?}", rates);
+    }
+
+    #[test]
+    fn test_activity_from_acc() {
+        let mut channels = Channels::new();
+        let start = 10_000_000_000;
+        // 10 s upright and still, then 10 s walking at 2 steps/s
+        for batch in 0..80u64 {
+            let samples = (0..50u64)
+                .map(|i| {
+                    let t = (batch * 50 + i) as f64 / 200.0;
+                    let bounce = if t < 10.0 { 0.0 } else { 300.0 * (4.0 * std::f64::consts::PI * t).sin() };
+                    MeasurementSample::Acc { x: (-1000.0 - bounce).round() as i32, y: 20, z: 50 }
+                })
+                .collect();
+            channels.handle_measurement_data(MeasurementBatch::new(samples), start + (batch + 1) * 250_000_000, None);
+        }
+
+        let epochs = channels.activity.last_duration(u64::MAX);
+        assert_eq!(epochs.len(), 19);
+        assert!(epochs[..10].iter().all(|e| e.value == ActivityLevel::Still.code()));
+        assert!(epochs[10..].iter().all(|e| e.value == ActivityLevel::Walking.code()));
+        assert_eq!(channels.posture.last_point().unwrap().value, Posture::Standing.code());
+        let steps = channels.steps.last_point().unwrap().value;
+        assert!((18..=20).contains(&steps), "{} steps", steps);
+
+        assert!(channels.is_still(epochs[0].time - 500_000_000));
+        assert!(!channels.is_still(epochs[18].time));
+        assert!(Channels::new().is_still(start));
+
+        // Beats while walking are left out of the still runs
+        let beats: Vec<Point> = epochs.iter().map(|e| Point { time: e.time, value: 1000 }).collect();
+        let runs = channels.still_runs(&beats);
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].len(), 10);
+    }
+
+    #[test]
+    fn test_filtered_ecg() {
+        let mut channels = Channels::new();
+        let now = 10_000_000_000;
+        for i in 0..10u64 {
+            let batch = MeasurementBatch::new(vec![MeasurementSample::Ecg(1500); 13]);
+            channels.handle_measurement_data(batch, now + i * 100_000_000, None);
+        }
+
+        // The offset is gone from the filtered series only
+        assert_eq!(channels.ecg_filtered.len(), channels.ecg.len());
+        assert_eq!(channels.ecg.last_point().unwrap().value, 1500);
+        assert!(channels.ecg_filtered.last_point().unwrap().value.abs() < 5);
+
+        // Recorded, and so described in metadata, only on request
+        assert!(!channels.sample_rates().iter().any(|&(c, _)| c == ChannelId::EcgFiltered));
+        channels.set_filters(SignalFilters { record: true, ..SignalFilters::default() });
+        assert!(channels.sample_rates().contains(&(ChannelId::EcgFiltered, 130)));
+        let disabled = FilterSettings { enabled: false, ..FilterSettings::ecg() };
+        channels.set_filters(SignalFilters { ecg: disabled, record: true, ..SignalFilters::default() });
+        assert!(!channels.is_filtered(SensorStream::Ecg));
+    }
+
+    #[test]
+    fn test_optical_streams() {
+        let mut channels = Channels::with_profile(DeviceProfile::for_model(DeviceModel::VeritySense));
+        assert_eq!(channels.ppg.sample_rate(), 55);
+        let now = 10_000_000_000;
+
+        let batch = MeasurementBatch::new(vec![
+            MeasurementSample::Ppg(5000),
+            MeasurementSample::Gyro { x: 10, y: 20, z: 30 },
+            MeasurementSample::Ppi(1000),
+            MeasurementSample::Ppi(980),
+        ]);
+        channels.handle_measurement_data(batch, now, None);
+
+        assert_eq!(channels.ppg.len(), 1);
+        assert_eq!(channels.gyro_y.last_point().unwrap().value, 20);
+        assert_eq!(channels.mag_x.len(), 0);
+
+        // PPI feeds the RR series and HRV
+        assert_eq!(channels.rr.len(), 2);
+        assert_eq!(channels.hrv.len(), 1);
+    }
 }
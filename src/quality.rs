@@ -0,0 +1,182 @@
+//! # Data Quality Module
+//!
+//! Tracks how completely and how regularly a periodic stream arrives.
+//! BLE notifications can be dropped or delayed without any error surfacing,
+//! so the arrival cadence is compared with the configured sample rate.
+//!
+//! ## Metrics
+//! - **Received vs expected**: Samples that arrived against samples the
+//!   configured rate produces over the same time
+//! - **Longest gap**: Longest time between two notifications
+//! - **Jitter**: Standard deviation of the time between notifications
+//! - **Gaps**: Notifications arriving much later than the previous batch's
+//!   duration explains
+//!
+//! ## Usage
+//! `Channels` keeps one `StreamQuality` per periodic stream and feeds it
+//! every batch; `QualityReport` is the snapshot shown in the sidebar and
+//! stored as channel attributes in recordings.
+
+use crate::timeseries::Gap;
+
+// Nanoseconds in one second
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+
+// A notification later than this many batch durations after the previous one is a gap
+const GAP_FACTOR: f64 = 1.5;
+
+// Allowance for connection interval and UI tick delays before declaring a gap
+const GAP_TOLERANCE_NS: u64 = 100_000_000;
+
+/// Snapshot of a stream's data-quality statistics
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct QualityReport {
+    pub samples_received: u64,
+    pub samples_expected: u64,
+    pub longest_gap_ns: u64,
+    pub gap_count: u64,
+    /// Standard deviation of the notification interval in milliseconds
+    pub jitter_ms: f64,
+}
+
+impl QualityReport {
+    /// Received samples as a fraction of expected ones, capped at 1
+    pub fn completeness(&self) -> f64 {
+        if self.samples_expected == 0 {
+            return 1.0;
+        }
+        (self.samples_received as f64 / self.samples_expected as f64).min(1.0)
+    }
+}
+
+/// Running data-quality statistics of one periodic stream
+#[derive(Debug, Clone)]
+pub struct StreamQuality {
+    sample_rate: u64,
+    last_arrival: Option<u64>,
+    received: u64,
+    expected: f64,
+    longest_gap_ns: u64,
+    gap_count: u64,
+    /// Welford running mean and squared deviations of notification intervals
+    interval_count: u64,
+    interval_mean: f64,
+    interval_m2: f64,
+}
+
+impl StreamQuality {
+    pub fn new(sample_rate: u64) -> Self {
+        Self {
+            sample_rate: sample_rate.max(1),
+            last_arrival: None,
+            received: 0,
+            expected: 0.0,
+            longest_gap_ns: 0,
+            gap_count: 0,
+            interval_count: 0,
+            interval_mean: 0.0,
+            interval_m2: 0.0,
+        }
+    }
+
+    /// Use a new configured sample rate for samples expected from now on
+    pub fn set_sample_rate(&mut self, sample_rate: u64) {
+        self.sample_rate = sample_rate.max(1);
+    }
+
+    /// Account for a batch of `count` samples arriving at `now`
+    ///
+    /// Returns the span between the previous and this notification if it
+    /// was late enough to mean notifications were lost.
+    pub fn record_batch(&mut self, now: u64, count: u64) -> Option<Gap> {
+        self.received += count;
+        let Some(previous) = self.last_arrival.replace(now) else {
+            // Nothing to compare the first batch against
+            self.expected += count as f64;
+            return None;
+        };
+
+        let interval = now.saturating_sub(previous);
+        self.expected += interval as f64 * self.sample_rate as f64 / NANOS_PER_SECOND as f64;
+        self.longest_gap_ns = self.longest_gap_ns.max(interval);
+
+        self.interval_count += 1;
+        let delta = interval as f64 - self.interval_mean;
+        self.interval_mean += delta / self.interval_count as f64;
+        self.interval_m2 += delta * (interval as f64 - self.interval_mean);
+
+        let batch_duration = count * NANOS_PER_SECOND / self.sample_rate;
+        let late_after = (batch_duration as f64 * GAP_FACTOR) as u64 + GAP_TOLERANCE_NS;
+        if interval > late_after {
+            self.gap_count += 1;
+            Some(Gap { start: previous, end: now })
+        } else {
+            None
+        }
+    }
+
+    pub fn report(&self) -> QualityReport {
+        let jitter_ns = if self.interval_count >= 2 {
+            (self.interval_m2 / (self.interval_count - 1) as f64).sqrt()
+        } else {
+            0.0
+        };
+        QualityReport {
+            samples_received: self.received,
+            samples_expected: self.expected.round() as u64,
+            longest_gap_ns: self.longest_gap_ns,
+            gap_count: self.gap_count,
+            jitter_ms: jitter_ns / 1_000_000.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: u64 = 1_000_000;
+
+    #[test]
+    fn test_regular_stream_is_complete() {
+        let mut quality = StreamQuality::new(100);
+        for i in 0..11 {
+            // 10 samples every 100ms, every other notification 10ms late
+            let delay = if i % 2 == 0 { 0 } else { 10 * MS };
+            assert_eq!(quality.record_batch(i * 100 * MS + delay, 10), None);
+        }
+
+        let report = quality.report();
+        assert_eq!(report.samples_received, 110);
+        assert_eq!(report.samples_expected, 110);
+        assert_eq!(report.gap_count, 0);
+        assert_eq!(report.longest_gap_ns, 110 * MS);
+        assert!(report.jitter_ms > 9.0 && report.jitter_ms < 11.0);
+    }
+
+    #[test]
+    fn test_lost_notifications_are_gaps() {
+        let mut quality = StreamQuality::new(100);
+        quality.record_batch(0, 10);
+        quality.record_batch(100 * MS, 10);
+        // Two notifications lost
+        let gap = quality.record_batch(400 * MS, 10);
+
+        assert_eq!(gap, Some(Gap { start: 100 * MS, end: 400 * MS }));
+        let report = quality.report();
+        assert_eq!(report.samples_expected, 50);
+        assert_eq!(report.samples_received, 30);
+        assert_eq!(report.gap_count, 1);
+        assert!((report.completeness() - 0.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rate_change_applies_to_later_batches() {
+        let mut quality = StreamQuality::new(100);
+        quality.record_batch(0, 10);
+        quality.set_sample_rate(200);
+        quality.record_batch(100 * MS, 20);
+        assert_eq!(quality.report().samples_expected, 30);
+        assert_eq!(QualityReport::default().completeness(), 1.0);
+    }
+}
@@ -14,30 +14,67 @@
 //! 2. Memory limits: Configurable max memory with automatic flushing
 //! 3. HDF5 format: Industry standard, excellent Python support (h5py)
 //! 4. Transparent reads: Automatic fallback from cache to file
+//! 5. Crash safety: Everything recorded is also appended to a journal
+//!    (`recording_*.h5.journal`, see `journal` module) that rebuilds the file
+//!    if the app dies before the recording is stopped
 //!
 //! ## HDF5 File Structure
 //! ```text
 //! recording_YYYYMMDD_HHMMSS.h5
-//! ├── /ecg
+//! ├── /devices
+//! │   ├── /<device_id> (attributes: device_id, name, model, firmware if known)
+//! │   │   ├── /gaps (spans without data while reconnecting)
+//! │   │   │   ├── start (dataset: u64 nanoseconds)
+//! │   │   │   └── end (dataset: u64 nanoseconds)
+//! │   │   ├── /ecg (attributes: unit, sample_rate_hz for periodic channels,
+//! │   │   │   │     samples_received, samples_expected, longest_gap_ns,
+//! │   │   │   │     gap_count, jitter_ms)
+//! │   │   │   ├── times (dataset: u64 nanoseconds)
+//! │   │   │   ├── values (dataset: i32)
+//! │   │   │   └── /gaps (dropped frames and late notifications, same layout as above)
+//! │   │   ├── /acc_x
+//! │   │   │   ├── times
+//! │   │   │   └── values
+//! │   │   ... (similar for acc_y, acc_z, hr, rr, hrv)
+//! │   │   ... (nn: intervals after artifact correction; rr_artifacts: raw
+//! │   │   ...  intervals detected as artifacts. An nn beat at the time of an
+//! │   │   ...  artifact is a corrected one, none means it was deleted)
+//! │   │   ... (r_peaks: R peaks found in the ECG, value is the ECG at the peak;
+//! │   │   ...  ecg_rr: intervals between them, to compare with rr)
+//! │   │   ... (ecg_filtered, ppg_filtered: the filtered signal, only when
+//! │   │   ...  recording filtered signals is enabled)
+//! │   │   ... (ppg, ppi, gyro_*, mag_* for optical sensors)
+//! │   └── /<other_device_id> (one group per connected device)
+//! ├── /annotations (event markers, sorted by time)
 //! │   ├── times (dataset: u64 nanoseconds)
-//! │   └── values (dataset: i32)
-//! ├── /acc_x
-//! │   ├── times
-//! │   └── values
-//! ... (similar for acc_y, acc_z, hr, rr, hrv)
-//! └── /metadata
-//!     ├── start_time (attribute)
-//!     ├── sample_rates (group with attributes)
-//!     └── description (attribute)
+//! │   ├── durations (dataset: u64 nanoseconds, 0 for a single moment)
+//! │   ├── labels (dataset: UTF-8 strings)
+//! │   └── notes (dataset: UTF-8 strings, empty if none)
+//! └── /metadata (attributes)
+//!     ├── start_time_ns, start_time (RFC 3339 local time), timezone (UTC offset)
+//!     ├── time_unit, app_version
+//!     └── subject_id, description
 //! ```
 //!
+//! Text attributes are variable-length UTF-8 strings, so h5py and MATLAB read
+//! them as ordinary strings.
+//!
+//! Recordings made before multi-device support keep the channel groups at
+//! the file root. Readers take `None` as device to read that layout.
+//!
 //! ## Memory Management
 //! - Each channel maintains a rolling buffer
 //! - When total memory exceeds limit, oldest data is flushed to disk
 //! - LRU eviction policy for multi-channel fairness
 
-use crate::timeseries::Point;
+use crate::device_profile::SensorStream;
+use crate::journal::{self, Journal, JournalReader, JournalRecord};
+use crate::quality::QualityReport;
+use crate::annotations::Annotation;
+use crate::timeseries::{Gap, Point};
+use chrono::TimeZone;
 use crossbeam_channel::{unbounded, Receiver, Sender};
+use hdf5::types::VarLenUnicode;
 use hdf5::{File, Group};
 use lru::LruCache;
 use std::collections::HashMap;
@@ -57,6 +94,36 @@ pub enum ChannelId {
     Hr,
     Rr,
     Hrv,
+    Ppg,
+    Ppi,
+    GyroX,
+    GyroY,
+    GyroZ,
+    MagX,
+    MagY,
+    MagZ,
+    /// NN intervals: RR or PPI after artifact correction
+    Nn,
+    /// Raw intervals detected as artifacts
+    RrArtifact,
+    /// R peaks detected in the ECG, with the ECG value at the peak
+    RPeak,
+    /// RR intervals between detected R peaks
+    EcgRr,
+    /// ECG after the display filter chain
+    EcgFiltered,
+    /// PPG after the display filter chain
+    PpgFiltered,
+    /// Breathing rate estimated from ACC and the beats
+    Respiration,
+    /// Posture per activity epoch (see `activity::Posture::code`)
+    Posture,
+    /// Activity level per activity epoch (see `activity::ActivityLevel::code`)
+    Activity,
+    /// Steps counted since the device connected
+    Steps,
+    /// Movement intensity per activity epoch
+    Enmo,
 }
 
 impl ChannelId {
@@ -70,6 +137,46 @@ impl ChannelId {
             ChannelId::Hr => "hr",
             ChannelId::Rr => "rr",
             ChannelId::Hrv => "hrv",
+            ChannelId::Ppg => "ppg",
+            ChannelId::Ppi => "ppi",
+            ChannelId::GyroX => "gyro_x",
+            ChannelId::GyroY => "gyro_y",
+            ChannelId::GyroZ => "gyro_z",
+            ChannelId::MagX => "mag_x",
+            ChannelId::MagY => "mag_y",
+            ChannelId::MagZ => "mag_z",
+            ChannelId::Nn => "nn",
+            ChannelId::RrArtifact => "rr_artifacts",
+            ChannelId::RPeak => "r_peaks",
+            ChannelId::EcgRr => "ecg_rr",
+            ChannelId::EcgFiltered => "ecg_filtered",
+            ChannelId::PpgFiltered => "ppg_filtered",
+            ChannelId::Respiration => "respiration",
+            ChannelId::Posture => "posture",
+            ChannelId::Activity => "activity",
+            ChannelId::Steps => "steps",
+            ChannelId::Enmo => "enmo",
+        }
+    }
+
+    /// Unit of the channel's values
+    pub fn unit(&self) -> &'static str {
+        match self {
+            ChannelId::Ecg | ChannelId::RPeak | ChannelId::EcgFiltered => "µV",
+            ChannelId::AccX | ChannelId::AccY | ChannelId::AccZ | ChannelId::Enmo => "mg",
+            ChannelId::Hr => "bpm",
+            ChannelId::Rr
+            | ChannelId::Hrv
+            | ChannelId::Ppi
+            | ChannelId::Nn
+            | ChannelId::RrArtifact
+            | ChannelId::EcgRr => "ms",
+            ChannelId::Ppg | ChannelId::PpgFiltered => "a.u.",
+            ChannelId::GyroX | ChannelId::GyroY | ChannelId::GyroZ => "mdps",
+            ChannelId::MagX | ChannelId::MagY | ChannelId::MagZ => "mG",
+            ChannelId::Respiration => "brpm",
+            ChannelId::Posture | ChannelId::Activity => "class",
+            ChannelId::Steps => "steps",
         }
     }
 
@@ -83,8 +190,129 @@ impl ChannelId {
             ChannelId::Hr,
             ChannelId::Rr,
             ChannelId::Hrv,
+            ChannelId::Ppg,
+            ChannelId::Ppi,
+            ChannelId::GyroX,
+            ChannelId::GyroY,
+            ChannelId::GyroZ,
+            ChannelId::MagX,
+            ChannelId::MagY,
+            ChannelId::MagZ,
+            ChannelId::Nn,
+            ChannelId::RrArtifact,
+            ChannelId::RPeak,
+            ChannelId::EcgRr,
+            ChannelId::EcgFiltered,
+            ChannelId::PpgFiltered,
+            ChannelId::Respiration,
+            ChannelId::Posture,
+            ChannelId::Activity,
+            ChannelId::Steps,
+            ChannelId::Enmo,
         ]
     }
+
+    /// Derived channel holding the filtered version of this one, if any
+    pub fn filtered(&self) -> Option<ChannelId> {
+        match self {
+            ChannelId::Ecg => Some(ChannelId::EcgFiltered),
+            ChannelId::Ppg => Some(ChannelId::PpgFiltered),
+            _ => None,
+        }
+    }
+
+    /// Whether samples arrive at a fixed sample rate
+    ///
+    /// HR and HRV follow the heart rate notifications, RR, PPI, NN and the
+    /// ECG beat channels the beats, and artifacts occur at random. The
+    /// breathing rate is estimated every few seconds as data arrives, and
+    /// the activity channels once per epoch while ACC streams without gaps.
+    pub fn is_periodic(&self) -> bool {
+        !matches!(
+            self,
+            ChannelId::Hr
+                | ChannelId::Rr
+                | ChannelId::Hrv
+                | ChannelId::Ppi
+                | ChannelId::Nn
+                | ChannelId::RrArtifact
+                | ChannelId::RPeak
+                | ChannelId::EcgRr
+                | ChannelId::Respiration
+                | ChannelId::Posture
+                | ChannelId::Activity
+                | ChannelId::Steps
+                | ChannelId::Enmo
+        )
+    }
+
+    /// Channels a stream is recorded to
+    pub fn for_stream(stream: SensorStream) -> &'static [ChannelId] {
+        match stream {
+            SensorStream::HeartRate => &[ChannelId::Hr, ChannelId::Rr, ChannelId::Hrv],
+            SensorStream::Ecg => &[ChannelId::Ecg],
+            SensorStream::Acc => &[ChannelId::AccX, ChannelId::AccY, ChannelId::AccZ],
+            SensorStream::Ppg => &[ChannelId::Ppg],
+            SensorStream::Ppi => &[ChannelId::Ppi],
+            SensorStream::Gyro => &[ChannelId::GyroX, ChannelId::GyroY, ChannelId::GyroZ],
+            SensorStream::Mag => &[ChannelId::MagX, ChannelId::MagY, ChannelId::MagZ],
+        }
+    }
+}
+
+// Group holding one subgroup per device
+const DEVICES_GROUP: &str = "devices";
+
+// Per-device and per-channel group listing spans without data
+const GAPS_GROUP: &str = "gaps";
+
+// Group holding the event markers of the recording
+const ANNOTATIONS_GROUP: &str = "annotations";
+
+/// HDF5 group name for a device, with path separators replaced
+fn device_group_name(device_id: &str) -> String {
+    device_id.replace('/', "_")
+}
+
+/// Path of a channel group, at the file root for legacy recordings
+fn channel_group_path(device_id: Option<&str>, channel: ChannelId) -> String {
+    match device_id {
+        Some(id) => format!("{}/{}/{}", DEVICES_GROUP, device_group_name(id), channel.group_name()),
+        None => channel.group_name().to_string(),
+    }
+}
+
+/// Open a subgroup, creating it if it does not exist
+fn open_or_create_group(parent: &Group, name: &str) -> Result<Group, String> {
+    if let Ok(group) = parent.group(name) {
+        return Ok(group);
+    }
+    parent
+        .create_group(name)
+        .map_err(|e| format!("Failed to create group {}: {}", name, e))
+}
+
+/// Value of a string attribute, if present
+fn read_string_attr(group: &Group, name: &str) -> Option<String> {
+    let value = group.attr(name).ok()?.read_scalar::<VarLenUnicode>().ok()?;
+    Some(value.as_str().to_string())
+}
+
+/// Session information stored in `/metadata`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecordingInfo {
+    pub subject_id: String,
+    pub description: String,
+}
+
+/// Device information stored as attributes of `/devices/<id>` and its channels
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeviceMetadata {
+    pub name: String,
+    pub model: String,
+    pub firmware: Option<String>,
+    /// Configured sample rate in Hz of each periodic channel
+    pub sample_rates: Vec<(ChannelId, u64)>,
 }
 
 /// Recording command sent from main thread to writer thread
@@ -92,25 +320,63 @@ impl ChannelId {
 enum RecorderCommand {
     /// Add data point to channel buffer
     AddPoint {
+        device_id: String,
         channel: ChannelId,
         time: u64,
         value: i32,
     },
-    /// Flush all buffers to disk
-    Flush,
+    /// Mark a span without data for a device, or only for one of its channels
+    AddGap {
+        device_id: String,
+        channel: Option<ChannelId>,
+        gap: Gap,
+    },
+    /// Latest data-quality statistics of a channel
+    SetQuality {
+        device_id: String,
+        channel: ChannelId,
+        report: QualityReport,
+    },
+    /// Latest information about a device
+    SetDeviceMetadata {
+        device_id: String,
+        metadata: DeviceMetadata,
+    },
+    /// Event marker of the recording
+    AddAnnotation(Annotation),
+    /// Flush all buffers to disk and close the finished recording's journal
+    Finish { journal: Option<Journal> },
     /// Stop recording and cleanup
     Stop,
 }
 
-/// Cache key for LRU cache (channel, start_time, end_time)
-type CacheKey = (ChannelId, u64, u64);
+/// Staging buffer key (device_id, channel)
+type ChannelKey = (String, ChannelId);
+
+/// Pending gap key (device_id, channel or `None` for the whole device)
+type GapKey = (String, Option<ChannelId>);
+
+/// Cache key for LRU cache (device_id, channel, start_time, end_time)
+type CacheKey = (String, ChannelId, u64, u64);
 
 /// State shared between recorder and writer thread
 struct RecorderState {
     /// Unified LRU cache for both live and file data
     cache: LruCache<CacheKey, Vec<Point>>,
-    /// Per-channel staging buffers for incoming live data (before caching)
-    staging_buffers: HashMap<ChannelId, Vec<Point>>,
+    /// Per-device, per-channel staging buffers for incoming live data (before caching)
+    staging_buffers: HashMap<ChannelKey, Vec<Point>>,
+    /// Gaps not yet written, per device and channel
+    pending_gaps: HashMap<GapKey, Vec<Gap>>,
+    /// Quality statistics not yet written, per device and channel
+    pending_quality: HashMap<ChannelKey, QualityReport>,
+    /// Annotations not yet written
+    pending_annotations: Vec<Annotation>,
+    /// Crash-safety journal of the recording in progress
+    journal: Option<Journal>,
+    /// Session information of the recording in progress
+    info: RecordingInfo,
+    /// Latest information about each device seen
+    devices: HashMap<String, DeviceMetadata>,
     /// Current memory usage in bytes
     memory_usage: usize,
     /// Maximum memory allowed in bytes
@@ -134,18 +400,19 @@ impl RecorderState {
             .unwrap()
             .as_nanos() as u64;
 
-        let mut staging_buffers = HashMap::new();
-        for channel_id in ChannelId::all() {
-            staging_buffers.insert(channel_id, Vec::new());
-        }
-
         // Use full memory budget for unified cache
         let cache_size = max_memory_mb * 100; // Rough estimate: ~1KB per cache entry
         let cache_capacity = NonZeroUsize::new(cache_size.max(100)).unwrap();
 
         Self {
             cache: LruCache::new(cache_capacity),
-            staging_buffers,
+            staging_buffers: HashMap::new(),
+            pending_gaps: HashMap::new(),
+            pending_quality: HashMap::new(),
+            pending_annotations: Vec::new(),
+            journal: None,
+            info: RecordingInfo::default(),
+            devices: HashMap::new(),
             memory_usage: 0,
             max_memory: max_memory_mb * 1024 * 1024,
             file_path: None,
@@ -157,11 +424,42 @@ impl RecorderState {
     }
 
     /// Add point to staging buffer and update memory tracking
-    fn add_point(&mut self, channel: ChannelId, time: u64, value: i32) {
-        if let Some(buffer) = self.staging_buffers.get_mut(&channel) {
-            buffer.push(Point { time, value });
-            // Each Point is 12 bytes (u64 + i32 + padding)
-            self.memory_usage += 16;
+    fn add_point(&mut self, device_id: String, channel: ChannelId, time: u64, value: i32) {
+        self.write_journal(|journal| journal.add_point(&device_id, channel, time, value));
+        let buffer = self.staging_buffers.entry((device_id, channel)).or_default();
+        buffer.push(Point { time, value });
+        // Each Point is 12 bytes (u64 + i32 + padding)
+        self.memory_usage += 16;
+    }
+
+    fn add_gap(&mut self, device_id: String, channel: Option<ChannelId>, gap: Gap) {
+        self.write_journal(|journal| journal.add_gap(&device_id, channel, gap));
+        self.pending_gaps.entry((device_id, channel)).or_default().push(gap);
+    }
+
+    fn set_quality(&mut self, device_id: String, channel: ChannelId, report: QualityReport) {
+        self.write_journal(|journal| journal.add_quality(&device_id, channel, &report));
+        self.pending_quality.insert((device_id, channel), report);
+    }
+
+    fn set_device_metadata(&mut self, device_id: String, metadata: DeviceMetadata) {
+        self.write_journal(|journal| journal.add_device_metadata(&device_id, &metadata));
+        self.devices.insert(device_id, metadata);
+    }
+
+    fn add_annotation(&mut self, annotation: Annotation) {
+        self.write_journal(|journal| journal.add_annotation(&annotation));
+        self.pending_annotations.push(annotation);
+    }
+
+    /// Append to the journal, giving it up after a write error
+    fn write_journal(&mut self, write: impl FnOnce(&mut Journal) -> Result<(), String>) {
+        let Some(journal) = self.journal.as_mut() else {
+            return;
+        };
+        if let Err(e) = write(journal) {
+            log::error!("{}; continuing without crash recovery", e);
+            self.journal = None;
         }
     }
 
@@ -202,11 +500,11 @@ impl RecorderState {
         let _cutoff_time = now.saturating_sub(self.live_buffer_duration_ns);
 
         // Move staging buffers to cache with proper time range keys
-        for (channel, buffer) in self.staging_buffers.iter_mut() {
+        for ((device_id, channel), buffer) in self.staging_buffers.iter_mut() {
             if !buffer.is_empty() {
                 let start_time = buffer.first().unwrap().time;
                 let end_time = buffer.last().unwrap().time;
-                let cache_key = (*channel, start_time, end_time);
+                let cache_key = (device_id.clone(), *channel, start_time, end_time);
                 self.cache.put(cache_key, buffer.clone());
                 buffer.clear();
             }
@@ -220,7 +518,7 @@ impl RecorderState {
     fn flush_to_disk(&mut self) -> Result<(), String> {
         let staging_points: usize = self.staging_buffers.values().map(|v| v.len()).sum();
         
-        if staging_points == 0 {
+        if staging_points == 0 && self.pending_gaps.is_empty() && self.pending_annotations.is_empty() {
             return Ok(());
         }
 
@@ -239,49 +537,72 @@ impl RecorderState {
                 .map_err(|e| format!("Failed to open HDF5 file: {}", e))?;
 
             // Write each channel's staging buffer
-            for (channel_id, buffer) in self.staging_buffers.iter() {
+            let devices = open_or_create_group(&file, DEVICES_GROUP)?;
+            for ((device_id, channel_id), buffer) in self.staging_buffers.iter() {
                 if buffer.is_empty() {
                     continue;
                 }
 
-                let group_name = channel_id.group_name();
-                
-                // Get or create group for this channel
-                let group = if file.group(group_name).is_ok() {
-                    file.group(group_name)
-                        .map_err(|e| format!("Failed to open group {}: {}", group_name, e))?
-                } else {
-                    file.create_group(group_name)
-                        .map_err(|e| format!("Failed to create group {}: {}", group_name, e))?
-                };
+                // Get or create /devices/<id>/<channel>
+                let device = open_or_create_group(&devices, &device_group_name(device_id))?;
+                let group = open_or_create_group(&device, channel_id.group_name())?;
+                Self::write_string_attr(&group, "unit", channel_id.unit())?;
 
                 // Append times and values to datasets
                 Self::append_to_dataset(&group, "times", buffer, |p| p.time)?;
                 Self::append_to_dataset(&group, "values", buffer, |p| p.value)?;
             }
 
+            for ((device_id, channel), gaps) in self.pending_gaps.iter() {
+                let mut parent = open_or_create_group(&devices, &device_group_name(device_id))?;
+                if let Some(channel) = channel {
+                    parent = open_or_create_group(&parent, channel.group_name())?;
+                }
+                let group = open_or_create_group(&parent, GAPS_GROUP)?;
+                Self::append_to_dataset(&group, "start", gaps, |g| g.start)?;
+                Self::append_to_dataset(&group, "end", gaps, |g| g.end)?;
+            }
+
+            for ((device_id, channel), report) in self.pending_quality.iter() {
+                let device = open_or_create_group(&devices, &device_group_name(device_id))?;
+                let group = open_or_create_group(&device, channel.group_name())?;
+                Self::write_quality(&group, report)?;
+            }
+
+            if !self.pending_annotations.is_empty() {
+                let group = open_or_create_group(&file, ANNOTATIONS_GROUP)?;
+                Self::append_annotations(&group, &self.pending_annotations)?;
+            }
+
             // Write metadata
-            Self::write_metadata(&file, self.start_time)?;
+            Self::write_metadata(&file, self.start_time, &self.info)?;
+            Self::write_device_metadata(&devices, &self.devices)?;
+
+            // Everything written so far is in the file; make the journal match
+            self.write_journal(Journal::sync);
 
             log::debug!("Flush complete");
         }
 
         // Move staging to cache (for both recording and live modes)
+        self.pending_gaps.clear();
+        self.pending_quality.clear();
+        self.pending_annotations.clear();
         self.age_out_old_data();
 
         Ok(())
     }
 
     /// Append data to HDF5 dataset, creating if needed
-    fn append_to_dataset<T, F>(
+    fn append_to_dataset<P, T, F>(
         group: &Group,
         dataset_name: &str,
-        buffer: &[Point],
+        buffer: &[P],
         extractor: F,
     ) -> Result<(), String>
     where
         T: hdf5::H5Type,
-        F: Fn(&Point) -> T,
+        F: Fn(&P) -> T,
     {
         let values: Vec<T> = buffer.iter().map(extractor).collect();
 
@@ -318,29 +639,146 @@ impl RecorderState {
         Ok(())
     }
 
-    /// Write recording metadata
-    fn write_metadata(file: &File, start_time: u64) -> Result<(), String> {
-        if file.group("metadata").is_err() {
-            let metadata = file
-                .create_group("metadata")
-                .map_err(|e| format!("Failed to create metadata group: {}", e))?;
+    /// Append annotations to the datasets of an annotations group
+    fn append_annotations(group: &Group, annotations: &[Annotation]) -> Result<(), String> {
+        let text = |value: &str| -> Result<VarLenUnicode, String> {
+            value.parse().map_err(|e| format!("Invalid annotation text {:?}: {}", value, e))
+        };
+        let labels = annotations.iter().map(|a| text(&a.label)).collect::<Result<Vec<_>, _>>()?;
+        let notes = annotations.iter().map(|a| text(&a.note)).collect::<Result<Vec<_>, _>>()?;
+        Self::append_to_dataset(group, "times", annotations, |a| a.time)?;
+        Self::append_to_dataset(group, "durations", annotations, |a| a.duration_ns)?;
+        Self::append_to_dataset(group, "labels", &labels, |label| label.clone())?;
+        Self::append_to_dataset(group, "notes", &notes, |note| note.clone())
+    }
 
-            metadata
-                .new_attr::<u64>()
-                .create("start_time_ns")
-                .map_err(|e| format!("Failed to create start_time attribute: {}", e))?
-                .write_scalar(&start_time)
-                .map_err(|e| format!("Failed to write start_time: {}", e))?;
+    /// Write a scalar attribute, replacing its value if it exists
+    fn write_scalar_attr<T: hdf5::H5Type>(group: &Group, name: &str, value: &T) -> Result<(), String> {
+        let attr = match group.attr(name) {
+            Ok(attr) => attr,
+            Err(_) => group
+                .new_attr::<T>()
+                .create(name)
+                .map_err(|e| format!("Failed to create attribute {}: {}", name, e))?,
+        };
+        attr.write_scalar(value)
+            .map_err(|e| format!("Failed to write attribute {}: {}", name, e))
+    }
 
-            // Note: String attributes require VarLenUnicode type in hdf5 0.8.1
-            // For simplicity, we'll skip the description or use a fixed-size string
-            // Users can add metadata via Python/h5py if needed
-        }
+    /// Write a variable-length UTF-8 string attribute, replacing its value if it exists
+    fn write_string_attr(group: &Group, name: &str, value: &str) -> Result<(), String> {
+        let value: VarLenUnicode = value
+            .parse()
+            .map_err(|e| format!("Invalid value for attribute {}: {}", name, e))?;
+        Self::write_scalar_attr(group, name, &value)
+    }
+
+    /// Store a channel's data-quality statistics as attributes of its group
+    fn write_quality(group: &Group, report: &QualityReport) -> Result<(), String> {
+        Self::write_scalar_attr(group, "samples_received", &report.samples_received)?;
+        Self::write_scalar_attr(group, "samples_expected", &report.samples_expected)?;
+        Self::write_scalar_attr(group, "longest_gap_ns", &report.longest_gap_ns)?;
+        Self::write_scalar_attr(group, "gap_count", &report.gap_count)?;
+        Self::write_scalar_attr(group, "jitter_ms", &report.jitter_ms)
+    }
 
+    /// Write recording metadata
+    fn write_metadata(file: &File, start_time: u64, info: &RecordingInfo) -> Result<(), String> {
+        let metadata = open_or_create_group(file, "metadata")?;
+        let local_start = chrono::Local.timestamp_nanos(start_time as i64);
+
+        Self::write_scalar_attr(&metadata, "start_time_ns", &start_time)?;
+        Self::write_string_attr(&metadata, "start_time", &local_start.to_rfc3339())?;
+        Self::write_string_attr(&metadata, "timezone", &local_start.format("%:z").to_string())?;
+        Self::write_string_attr(&metadata, "time_unit", "ns since Unix epoch")?;
+        Self::write_string_attr(&metadata, "app_version", env!("CARGO_PKG_VERSION"))?;
+        Self::write_string_attr(&metadata, "subject_id", &info.subject_id)?;
+        Self::write_string_attr(&metadata, "description", &info.description)
+    }
+
+    /// Write device attributes and channel sample rates for devices in the file
+    fn write_device_metadata(devices: &Group, metadata: &HashMap<String, DeviceMetadata>) -> Result<(), String> {
+        for (device_id, device) in metadata {
+            // Devices without recorded data get no group
+            let Ok(group) = devices.group(&device_group_name(device_id)) else {
+                continue;
+            };
+            Self::write_string_attr(&group, "device_id", device_id)?;
+            Self::write_string_attr(&group, "name", &device.name)?;
+            Self::write_string_attr(&group, "model", &device.model)?;
+            if let Some(firmware) = &device.firmware {
+                Self::write_string_attr(&group, "firmware", firmware)?;
+            }
+            for (channel, rate) in &device.sample_rates {
+                if let Ok(channel_group) = group.group(channel.group_name()) {
+                    Self::write_scalar_attr(&channel_group, "sample_rate_hz", rate)?;
+                }
+            }
+        }
         Ok(())
     }
 }
 
+/// Recorder handle for the channels of one device
+///
+/// Lets data processing record points without knowing which device it
+/// belongs to.
+pub struct DeviceRecorder<'a> {
+    manager: &'a PolarDataManager,
+    device_id: &'a str,
+}
+
+impl DeviceRecorder<'_> {
+    /// Add a data point to this device's channel
+    pub fn add_point(&self, channel: ChannelId, time: u64, value: i32) -> Result<(), String> {
+        self.manager.add_point(self.device_id, channel, time, value)
+    }
+
+    /// Mark a span without data for this device
+    pub fn add_gap(&self, gap: Gap) -> Result<(), String> {
+        self.send_gap(None, gap)
+    }
+
+    /// Mark a span without data for one channel, e.g. after a dropped frame
+    pub fn add_channel_gap(&self, channel: ChannelId, gap: Gap) -> Result<(), String> {
+        self.send_gap(Some(channel), gap)
+    }
+
+    /// Update the device information stored with the recording
+    pub fn set_metadata(&self, metadata: DeviceMetadata) -> Result<(), String> {
+        self.manager
+            .command_tx
+            .send(RecorderCommand::SetDeviceMetadata {
+                device_id: self.device_id.to_string(),
+                metadata,
+            })
+            .map_err(|e| format!("Failed to send set_metadata command: {}", e))
+    }
+
+    /// Update the data-quality statistics stored with a channel
+    pub fn set_quality(&self, channel: ChannelId, report: QualityReport) -> Result<(), String> {
+        self.manager
+            .command_tx
+            .send(RecorderCommand::SetQuality {
+                device_id: self.device_id.to_string(),
+                channel,
+                report,
+            })
+            .map_err(|e| format!("Failed to send set_quality command: {}", e))
+    }
+
+    fn send_gap(&self, channel: Option<ChannelId>, gap: Gap) -> Result<(), String> {
+        self.manager
+            .command_tx
+            .send(RecorderCommand::AddGap {
+                device_id: self.device_id.to_string(),
+                channel,
+                gap,
+            })
+            .map_err(|e| format!("Failed to send add_gap command: {}", e))
+    }
+}
+
 /// Main data manager for Polar sensor data
 ///
 /// Coordinates recording lifecycle, manages memory buffers, and provides
@@ -386,7 +824,7 @@ impl PolarDataManager {
     /// Start recording to a new file
     ///
     /// Creates a timestamped HDF5 file and enables disk writing.
-    pub fn start_recording(&self, output_dir: impl AsRef<Path>) -> Result<(), String> {
+    pub fn start_recording(&self, output_dir: impl AsRef<Path>, info: RecordingInfo) -> Result<(), String> {
         let mut is_recording = self.is_recording.lock().unwrap();
         if *is_recording {
             return Err("Recording already in progress".to_string());
@@ -407,13 +845,40 @@ impl PolarDataManager {
 
         log::info!("Starting recording to: {}", file_path.display());
 
-        // Enable disk recording
-        self.state.lock().unwrap().enable_disk_recording(file_path);
+        // Enable disk recording, journaling everything until it stops
+        let mut state = self.state.lock().unwrap();
+        state.journal = Journal::create(&file_path, state.start_time)
+            .map_err(|e| log::error!("{}; recording without crash recovery", e))
+            .ok();
+        // Known devices and session info go first so recovery restores them
+        let devices: Vec<(String, DeviceMetadata)> = state.devices.drain().collect();
+        for (device_id, metadata) in devices {
+            state.set_device_metadata(device_id, metadata);
+        }
+        state.write_journal(|journal| journal.add_recording_info(&info));
+        state.info = info;
+        state.enable_disk_recording(file_path);
         *is_recording = true;
 
         Ok(())
     }
 
+    /// Start time of the running recording, as stored in its metadata
+    pub fn recording_start_time(&self) -> Option<u64> {
+        if !self.is_recording() {
+            return None;
+        }
+        Some(self.state.lock().unwrap().start_time)
+    }
+
+    /// File the running recording is written to
+    pub fn recording_path(&self) -> Option<PathBuf> {
+        if !self.is_recording() {
+            return None;
+        }
+        self.state.lock().unwrap().file_path.clone()
+    }
+
     /// Stop recording and flush remaining data
     pub fn stop_recording(&self) -> Result<(), String> {
         let mut is_recording = self.is_recording.lock().unwrap();
@@ -423,15 +888,18 @@ impl PolarDataManager {
 
         log::info!("Stopping recording");
 
-        // Send flush command to ensure data is written
-        self.command_tx
-            .send(RecorderCommand::Flush)
-            .map_err(|e| format!("Failed to send flush command: {}", e))?;
-
-        // Disable disk recording
-        self.state.lock().unwrap().disable_disk_recording();
+        // Disable disk recording; the writer flushes and then drops the journal
+        let journal = {
+            let mut state = self.state.lock().unwrap();
+            state.disable_disk_recording();
+            state.journal.take()
+        };
         *is_recording = false;
 
+        self.command_tx
+            .send(RecorderCommand::Finish { journal })
+            .map_err(|e| format!("Failed to send finish command: {}", e))?;
+
         Ok(())
     }
 
@@ -439,9 +907,10 @@ impl PolarDataManager {
     ///
     /// Non-blocking: queues data for async processing. Safe to call from data collection thread.
     /// Points are always buffered in memory. If disk recording is active, they're also written to file.
-    pub fn add_point(&self, channel: ChannelId, time: u64, value: i32) -> Result<(), String> {
+    pub fn add_point(&self, device_id: &str, channel: ChannelId, time: u64, value: i32) -> Result<(), String> {
         self.command_tx
             .send(RecorderCommand::AddPoint {
+                device_id: device_id.to_string(),
                 channel,
                 time,
                 value,
@@ -451,6 +920,21 @@ impl PolarDataManager {
         Ok(())
     }
 
+    /// Add an event marker to the recording in progress
+    pub fn add_annotation(&self, annotation: Annotation) -> Result<(), String> {
+        self.command_tx
+            .send(RecorderCommand::AddAnnotation(annotation))
+            .map_err(|e| format!("Failed to send add_annotation command: {}", e))
+    }
+
+    /// Handle that records to the channels of one device
+    pub fn for_device<'a>(&'a self, device_id: &'a str) -> DeviceRecorder<'a> {
+        DeviceRecorder {
+            manager: self,
+            device_id,
+        }
+    }
+
     /// Check if currently recording
     pub fn is_recording(&self) -> bool {
         *self.is_recording.lock().unwrap()
@@ -529,21 +1013,38 @@ impl PolarDataManager {
         state.file_time_range
     }
 
+    /// List the devices recorded in an HDF5 file
+    ///
+    /// Returns an empty list for recordings in the legacy root layout.
+    pub fn devices_in_file(file_path: &Path) -> Result<Vec<String>, String> {
+        let file = File::open(file_path)
+            .map_err(|e| format!("Failed to open file: {}", e))?;
+
+        match file.group(DEVICES_GROUP) {
+            Ok(devices) => devices
+                .member_names()
+                .map_err(|e| format!("Failed to list devices: {}", e)),
+            Err(_) => Ok(Vec::new()),
+        }
+    }
+
     /// Read time range from HDF5 file
     ///
-    /// Scans all channels and returns (min_time, max_time) tuple
-    fn read_time_range_from_file(file_path: &Path) -> Result<(u64, u64), String> {
+    /// Scans all channels of all devices and returns (min_time, max_time) tuple
+    pub fn read_time_range_from_file(file_path: &Path) -> Result<(u64, u64), String> {
+        let devices = Self::devices_in_file(file_path)?;
         let file = File::open(file_path)
             .map_err(|e| format!("Failed to open file: {}", e))?;
 
         let mut min_time = u64::MAX;
         let mut max_time = u64::MIN;
 
-        // Check each channel for time range
-        for channel in ChannelId::all() {
-            let group_name = channel.group_name();
-            
-            if let Ok(group) = file.group(group_name) {
+        // Check each channel for time range, including the legacy root layout
+        let device_ids = devices.iter().map(|id| Some(id.as_str())).chain([None]);
+        for (device_id, channel) in device_ids.flat_map(|id| ChannelId::all().into_iter().map(move |c| (id, c))) {
+            let group_name = channel_group_path(device_id, channel);
+
+            if let Ok(group) = file.group(&group_name) {
                 if let Ok(times_ds) = group.dataset("times") {
                     if let Ok(times) = times_ds.read_raw::<u64>() {
                         if !times.is_empty() {
@@ -568,15 +1069,16 @@ impl PolarDataManager {
     /// Checks in order: memory → cache → file
     ///
     /// # Arguments
+    /// * `device_id` - Device the channel belongs to
     /// * `channel` - Channel to read from
     /// * `start_time` - Start of time range in nanoseconds
     /// * `end_time` - End of time range in nanoseconds
     ///
     /// # Returns
     /// Vector of points within the time range, or error message
-    #[allow(dead_code)]
     pub fn read_range(
         &self,
+        device_id: &str,
         channel: ChannelId,
         start_time: u64,
         end_time: u64,
@@ -585,7 +1087,7 @@ impl PolarDataManager {
         let mut result = Vec::new();
 
         // First, check staging buffer for very recent live data
-        if let Some(buffer) = state.staging_buffers.get(&channel) {
+        if let Some(buffer) = state.staging_buffers.get(&(device_id.to_string(), channel)) {
             for point in buffer {
                 if point.time >= start_time && point.time <= end_time {
                     result.push(*point);
@@ -599,7 +1101,7 @@ impl PolarDataManager {
         }
 
         // Check unified cache (contains both historical live data and file chunks)
-        let cache_key = (channel, start_time, end_time);
+        let cache_key = (device_id.to_string(), channel, start_time, end_time);
         if let Some(cached_data) = state.cache.get(&cache_key) {
             return Ok(cached_data.clone());
         }
@@ -609,7 +1111,7 @@ impl PolarDataManager {
             let file_path = file_path.clone();
             drop(state); // Release lock during file I/O
 
-            let data = Self::read_from_file(&file_path, channel, start_time, end_time)?;
+            let data = Self::read_from_file(&file_path, device_id, channel, start_time, end_time)?;
             
             // Cache the result
             let mut state = self.state.lock().unwrap();
@@ -623,17 +1125,17 @@ impl PolarDataManager {
             let next_end = end_time + chunk_duration;
 
             // Prefetch previous chunk if not cached
-            let prev_key = (channel, prev_start, prev_end);
+            let prev_key = (device_id.to_string(), channel, prev_start, prev_end);
             if !state.cache.contains(&prev_key) {
-                if let Ok(prev_data) = Self::read_from_file(&file_path, channel, prev_start, prev_end) {
+                if let Ok(prev_data) = Self::read_from_file(&file_path, device_id, channel, prev_start, prev_end) {
                     state.cache.put(prev_key, prev_data);
                 }
             }
 
             // Prefetch next chunk if not cached
-            let next_key = (channel, next_start, next_end);
+            let next_key = (device_id.to_string(), channel, next_start, next_end);
             if !state.cache.contains(&next_key) {
-                if let Ok(next_data) = Self::read_from_file(&file_path, channel, next_start, next_end) {
+                if let Ok(next_data) = Self::read_from_file(&file_path, device_id, channel, next_start, next_end) {
                     state.cache.put(next_key, next_data);
                 }
             }
@@ -648,6 +1150,7 @@ impl PolarDataManager {
     /// Read data from HDF5 file
     fn read_from_file(
         file_path: &std::path::Path,
+        device_id: &str,
         channel: ChannelId,
         start_time: u64,
         end_time: u64,
@@ -655,9 +1158,9 @@ impl PolarDataManager {
         let file = File::open_rw(file_path)
             .map_err(|e| format!("Failed to open file: {}", e))?;
 
-        let group_name = channel.group_name();
+        let group_name = channel_group_path(Some(device_id), channel);
         let group = file
-            .group(group_name)
+            .group(&group_name)
             .map_err(|e| format!("Failed to open group {}: {}", group_name, e))?;
 
         // Read times dataset
@@ -705,23 +1208,43 @@ impl PolarDataManager {
 
     /// Get last point in a channel from staging buffer
     #[allow(dead_code)]
-    pub fn last_point(&self, channel: ChannelId) -> Option<Point> {
+    pub fn last_point(&self, device_id: &str, channel: ChannelId) -> Option<Point> {
         let state = self.state.lock().unwrap();
-        state.staging_buffers.get(&channel)?.last().copied()
+        state.staging_buffers.get(&(device_id.to_string(), channel))?.last().copied()
     }
 
     /// Read all data from a channel in the file
-    #[allow(dead_code)]
-    fn read_all_from_file(
+    ///
+    /// `device_id` selects the device group; `None` reads a legacy recording
+    /// with channels at the file root.
+    pub fn read_all_from_file(
         file_path: &std::path::Path,
+        device_id: Option<&str>,
         channel: ChannelId,
     ) -> Result<Vec<Point>, String> {
+        let (times, values) = Self::read_datasets_from_file(file_path, device_id, channel)?;
+        Ok(times
+            .into_iter()
+            .zip(values.into_iter())
+            .map(|(time, value)| Point { time, value })
+            .collect())
+    }
+
+    /// Read a channel's `times` and `values` datasets as stored
+    ///
+    /// Unlike `read_all_from_file` the datasets are not paired up, so their
+    /// lengths can be checked.
+    pub fn read_datasets_from_file(
+        file_path: &Path,
+        device_id: Option<&str>,
+        channel: ChannelId,
+    ) -> Result<(Vec<u64>, Vec<i32>), String> {
         let file = File::open_rw(file_path)
             .map_err(|e| format!("Failed to open file: {}", e))?;
 
-        let group_name = channel.group_name();
+        let group_name = channel_group_path(device_id, channel);
         let group = file
-            .group(group_name)
+            .group(&group_name)
             .map_err(|e| format!("Failed to open group {}: {}", group_name, e))?;
 
         let times_ds = group
@@ -738,13 +1261,275 @@ impl PolarDataManager {
             .read_raw()
             .map_err(|e| format!("Failed to read values: {}", e))?;
 
-        Ok(times
+        Ok((times, values))
+    }
+
+    /// List the channels with a group in the file
+    ///
+    /// `device_id` selects the device group; `None` lists the legacy root layout.
+    pub fn channels_in_file(file_path: &Path, device_id: Option<&str>) -> Result<Vec<ChannelId>, String> {
+        let file = File::open(file_path)
+            .map_err(|e| format!("Failed to open file: {}", e))?;
+
+        Ok(ChannelId::all()
             .into_iter()
-            .zip(values.into_iter())
-            .map(|(time, value)| Point { time, value })
+            .filter(|&channel| file.group(&channel_group_path(device_id, channel)).is_ok())
+            .collect())
+    }
+
+    /// Read the string attributes of `/metadata`, sorted by name
+    ///
+    /// Returns an empty list for recordings without metadata.
+    pub fn read_metadata_attrs(file_path: &Path) -> Result<Vec<(String, String)>, String> {
+        let file = File::open(file_path)
+            .map_err(|e| format!("Failed to open file: {}", e))?;
+
+        let Ok(metadata) = file.group("metadata") else {
+            return Ok(Vec::new());
+        };
+        let mut names = metadata
+            .attr_names()
+            .map_err(|e| format!("Failed to list metadata: {}", e))?;
+        names.sort();
+        Ok(names
+            .into_iter()
+            .filter_map(|name| {
+                let value = read_string_attr(&metadata, &name)?;
+                Some((name, value))
+            })
+            .collect())
+    }
+
+    /// Read spans without data of a device from the file
+    ///
+    /// `channel` selects a channel's dropped frames; `None` reads the gaps
+    /// covering the whole device. Returns an empty list if none were recorded.
+    pub fn read_gaps_from_file(
+        file_path: &Path,
+        device_id: &str,
+        channel: Option<ChannelId>,
+    ) -> Result<Vec<Gap>, String> {
+        let file = File::open(file_path)
+            .map_err(|e| format!("Failed to open file: {}", e))?;
+
+        let mut group_name = format!("{}/{}", DEVICES_GROUP, device_group_name(device_id));
+        if let Some(channel) = channel {
+            group_name = format!("{}/{}", group_name, channel.group_name());
+        }
+        let Ok(group) = file.group(&format!("{}/{}", group_name, GAPS_GROUP)) else {
+            return Ok(Vec::new());
+        };
+
+        let read = |name: &str| -> Result<Vec<u64>, String> {
+            group
+                .dataset(name)
+                .and_then(|ds| ds.read_raw())
+                .map_err(|e| format!("Failed to read gap {}: {}", name, e))
+        };
+        let (starts, ends) = (read("start")?, read("end")?);
+        Ok(starts
+            .into_iter()
+            .zip(ends)
+            .map(|(start, end)| Gap { start, end })
             .collect())
     }
 
+    /// Read the event markers of a recording, sorted by time
+    ///
+    /// Returns an empty list for recordings without annotations.
+    pub fn read_annotations_from_file(file_path: &Path) -> Result<Vec<Annotation>, String> {
+        let file = File::open(file_path)
+            .map_err(|e| format!("Failed to open file: {}", e))?;
+
+        let Ok(group) = file.group(ANNOTATIONS_GROUP) else {
+            return Ok(Vec::new());
+        };
+        let read_u64 = |name: &str| -> Result<Vec<u64>, String> {
+            group
+                .dataset(name)
+                .and_then(|ds| ds.read_raw())
+                .map_err(|e| format!("Failed to read annotation {}: {}", name, e))
+        };
+        let read_text = |name: &str| -> Result<Vec<VarLenUnicode>, String> {
+            group
+                .dataset(name)
+                .and_then(|ds| ds.read_raw())
+                .map_err(|e| format!("Failed to read annotation {}: {}", name, e))
+        };
+        let (times, durations) = (read_u64("times")?, read_u64("durations")?);
+        let (labels, notes) = (read_text("labels")?, read_text("notes")?);
+
+        let mut annotations: Vec<Annotation> = times
+            .into_iter()
+            .zip(durations)
+            .zip(labels.into_iter().zip(notes))
+            .map(|((time, duration_ns), (label, note))| Annotation {
+                time,
+                duration_ns,
+                label: label.as_str().to_string(),
+                note: note.as_str().to_string(),
+            })
+            .collect();
+        annotations.sort_by_key(|a| a.time);
+        Ok(annotations)
+    }
+
+    /// Replace the event markers of a finished recording, e.g. after reviewing it
+    pub fn write_annotations_to_file(file_path: &Path, annotations: &[Annotation]) -> Result<(), String> {
+        let file = File::open_rw(file_path)
+            .map_err(|e| format!("Failed to open file: {}", e))?;
+
+        if file.group(ANNOTATIONS_GROUP).is_ok() {
+            file.unlink(ANNOTATIONS_GROUP)
+                .map_err(|e| format!("Failed to remove old annotations: {}", e))?;
+        }
+        if annotations.is_empty() {
+            return Ok(());
+        }
+        let group = open_or_create_group(&file, ANNOTATIONS_GROUP)?;
+        RecorderState::append_annotations(&group, annotations)
+    }
+
+    /// Read the session information stored in `/metadata`
+    ///
+    /// Fields missing in older recordings are left empty.
+    pub fn read_recording_info(file_path: &Path) -> Result<RecordingInfo, String> {
+        let file = File::open(file_path)
+            .map_err(|e| format!("Failed to open file: {}", e))?;
+
+        let Ok(metadata) = file.group("metadata") else {
+            return Ok(RecordingInfo::default());
+        };
+        Ok(RecordingInfo {
+            subject_id: read_string_attr(&metadata, "subject_id").unwrap_or_default(),
+            description: read_string_attr(&metadata, "description").unwrap_or_default(),
+        })
+    }
+
+    /// Read the device attributes and channel sample rates stored for a device
+    ///
+    /// Fields missing in older recordings are left empty.
+    pub fn read_device_metadata(file_path: &Path, device_id: &str) -> Result<DeviceMetadata, String> {
+        let file = File::open(file_path)
+            .map_err(|e| format!("Failed to open file: {}", e))?;
+
+        let group_name = format!("{}/{}", DEVICES_GROUP, device_group_name(device_id));
+        let group = file
+            .group(&group_name)
+            .map_err(|e| format!("Failed to open group {}: {}", group_name, e))?;
+
+        let sample_rates = ChannelId::all()
+            .into_iter()
+            .filter_map(|channel| {
+                let rate = group
+                    .group(channel.group_name())
+                    .and_then(|g| g.attr("sample_rate_hz"))
+                    .and_then(|attr| attr.read_scalar::<u64>())
+                    .ok()?;
+                Some((channel, rate))
+            })
+            .collect();
+
+        Ok(DeviceMetadata {
+            name: read_string_attr(&group, "name").unwrap_or_default(),
+            model: read_string_attr(&group, "model").unwrap_or_default(),
+            firmware: read_string_attr(&group, "firmware"),
+            sample_rates,
+        })
+    }
+
+    /// List recording files in a directory, newest first
+    ///
+    /// Recordings are named `recording_YYYYMMDD_HHMMSS.h5`, so sorting by name
+    /// sorts by start time. Returns an empty list if the directory is missing.
+    pub fn list_recordings(dir: impl AsRef<Path>) -> Vec<PathBuf> {
+        let mut recordings: Vec<PathBuf> = match std::fs::read_dir(dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| name.starts_with("recording_") && name.ends_with(".h5"))
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+
+        recordings.sort();
+        recordings.reverse();
+        recordings
+    }
+
+    /// Journals of recordings that were not stopped cleanly, newest first
+    pub fn unfinished_recordings(dir: impl AsRef<Path>) -> Vec<PathBuf> {
+        let mut journals: Vec<PathBuf> = match std::fs::read_dir(dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| {
+                    journal::recording_path(path)
+                        .and_then(|recording| recording.file_name()?.to_str().map(str::to_string))
+                        .is_some_and(|name| name.starts_with("recording_") && name.ends_with(".h5"))
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+
+        journals.sort();
+        journals.reverse();
+        journals
+    }
+
+    /// Rebuild a recording from the journal of a session that did not finish
+    ///
+    /// The journal holds everything the recording received, so the HDF5 file
+    /// is written again from scratch, replacing a possibly damaged one. The
+    /// journal is removed once the file is complete.
+    pub fn recover_recording(journal_path: &Path) -> Result<PathBuf, String> {
+        let recording = journal::recording_path(journal_path)
+            .ok_or_else(|| format!("{} is not a recording journal", journal_path.display()))?;
+        let reader = JournalReader::open(journal_path)?;
+
+        let mut state = RecorderState::new(0, 0);
+        state.start_time = reader.start_time;
+        if recording.exists() {
+            std::fs::remove_file(&recording)
+                .map_err(|e| format!("Failed to remove damaged recording: {}", e))?;
+        }
+        state.enable_disk_recording(recording.clone());
+
+        let mut points = 0;
+        for record in reader {
+            match record {
+                JournalRecord::Point { device_id, channel, point } => {
+                    state.add_point(device_id, channel, point.time, point.value);
+                    points += 1;
+                }
+                JournalRecord::Gap { device_id, channel, gap } => state.add_gap(device_id, channel, gap),
+                JournalRecord::Quality { device_id, channel, report } => {
+                    state.set_quality(device_id, channel, report)
+                }
+                JournalRecord::RecordingInfo(info) => state.info = info,
+                JournalRecord::DeviceMetadata { device_id, metadata } => {
+                    state.set_device_metadata(device_id, metadata)
+                }
+                JournalRecord::Annotation(annotation) => state.add_annotation(annotation),
+            }
+            if state.should_flush() {
+                state.flush_to_disk()?;
+            }
+        }
+        state.flush_to_disk()?;
+
+        std::fs::remove_file(journal_path).map_err(|e| format!("Failed to remove journal: {}", e))?;
+        log::info!("Recovered {} points into {}", points, recording.display());
+        Ok(recording)
+    }
+
+    /// Delete the journal of an unfinished recording without recovering it
+    pub fn discard_journal(journal_path: &Path) -> Result<(), String> {
+        std::fs::remove_file(journal_path).map_err(|e| format!("Failed to remove journal: {}", e))
+    }
+
     /// Writer thread loop
     ///
     /// Processes commands from main thread and handles file I/O.
@@ -753,12 +1538,13 @@ impl PolarDataManager {
         loop {
             match command_rx.recv() {
                 Ok(RecorderCommand::AddPoint {
+                    device_id,
                     channel,
                     time,
                     value,
                 }) => {
                     let mut recorder_state = state.lock().unwrap();
-                    recorder_state.add_point(channel, time, value);
+                    recorder_state.add_point(device_id, channel, time, value);
 
                     // Auto-flush when memory limit reached (this also ages out data)
                     if recorder_state.should_flush() {
@@ -767,10 +1553,29 @@ impl PolarDataManager {
                         }
                     }
                 }
-                Ok(RecorderCommand::Flush) => {
+                Ok(RecorderCommand::AddGap { device_id, channel, gap }) => {
+                    state.lock().unwrap().add_gap(device_id, channel, gap);
+                }
+                Ok(RecorderCommand::SetQuality { device_id, channel, report }) => {
+                    state.lock().unwrap().set_quality(device_id, channel, report);
+                }
+                Ok(RecorderCommand::SetDeviceMetadata { device_id, metadata }) => {
+                    state.lock().unwrap().set_device_metadata(device_id, metadata);
+                }
+                Ok(RecorderCommand::AddAnnotation(annotation)) => {
+                    state.lock().unwrap().add_annotation(annotation);
+                }
+                Ok(RecorderCommand::Finish { journal }) => {
                     let mut recorder_state = state.lock().unwrap();
-                    if let Err(e) = recorder_state.flush_to_disk() {
-                        log::error!("Failed to flush data: {}", e);
+                    match recorder_state.flush_to_disk() {
+                        // The file is complete, the journal is no longer needed
+                        Ok(()) => {
+                            if let Some(Err(e)) = journal.map(Journal::remove) {
+                                log::error!("{}", e);
+                            }
+                        }
+                        // Keep the journal so the recording can be recovered
+                        Err(e) => log::error!("Failed to flush data: {}", e),
                     }
                 }
                 Ok(RecorderCommand::Stop) => {
@@ -823,16 +1628,179 @@ mod tests {
         let recorder = PolarDataManager::new(30, 10).unwrap();
 
         assert!(!recorder.is_recording());
-        assert!(recorder.start_recording(temp_dir.path()).is_ok());
+        assert!(recorder.start_recording(temp_dir.path(), RecordingInfo::default()).is_ok());
         assert!(recorder.is_recording());
         assert!(recorder.stop_recording().is_ok());
         assert!(!recorder.is_recording());
     }
 
+    #[test]
+    fn test_list_recordings() {
+        let temp_dir = tempdir().unwrap();
+        for name in ["recording_20240101_100000.h5", "recording_20240102_090000.h5", "notes.txt"] {
+            std::fs::write(temp_dir.path().join(name), b"").unwrap();
+        }
+
+        let recordings = PolarDataManager::list_recordings(temp_dir.path());
+
+        assert_eq!(recordings.len(), 2);
+        assert!(recordings[0].ends_with("recording_20240102_090000.h5"));
+        assert!(PolarDataManager::list_recordings(temp_dir.path().join("missing")).is_empty());
+    }
+
     #[test]
     fn test_channel_id_names() {
         assert_eq!(ChannelId::Ecg.group_name(), "ecg");
         assert_eq!(ChannelId::AccX.group_name(), "acc_x");
         assert_eq!(ChannelId::Hr.group_name(), "hr");
+        assert_eq!(ChannelId::GyroZ.group_name(), "gyro_z");
+        assert_eq!(ChannelId::for_stream(SensorStream::Mag)[1], ChannelId::MagY);
+    }
+
+    #[test]
+    fn test_channel_group_paths() {
+        assert_eq!(channel_group_path(Some("12345678"), ChannelId::Ecg), "devices/12345678/ecg");
+        assert_eq!(channel_group_path(Some("A/B"), ChannelId::Hr), "devices/A_B/hr");
+        assert_eq!(channel_group_path(None, ChannelId::AccX), "acc_x");
+    }
+
+    #[test]
+    fn test_live_points_are_kept_per_device() {
+        let recorder = PolarDataManager::new(30, 10).unwrap();
+        recorder.for_device("A").add_point(ChannelId::Hr, 1, 60).unwrap();
+        recorder.add_point("B", ChannelId::Hr, 2, 80).unwrap();
+
+        // The writer thread processes commands asynchronously
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(2);
+        while recorder.total_points() < 2 && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        assert_eq!(recorder.last_point("A", ChannelId::Hr).map(|p| p.value), Some(60));
+        assert_eq!(recorder.last_point("B", ChannelId::Hr).map(|p| p.value), Some(80));
+        assert!(recorder.last_point("A", ChannelId::Rr).is_none());
+    }
+
+    #[test]
+    fn test_quality_written_as_channel_attributes() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("recording_quality.h5");
+        let mut state = RecorderState::new(30, 10);
+        state.enable_disk_recording(path.clone());
+
+        let report = QualityReport {
+            samples_received: 95,
+            samples_expected: 100,
+            longest_gap_ns: 600_000_000,
+            gap_count: 1,
+            jitter_ms: 12.5,
+        };
+        state.add_point("A".to_string(), ChannelId::Ecg, 1, 10);
+        state.pending_quality.insert(("A".to_string(), ChannelId::Ecg), report);
+        state.flush_to_disk().unwrap();
+        // Later statistics replace the stored ones
+        state.add_point("A".to_string(), ChannelId::Ecg, 2, 20);
+        state.pending_quality.insert(("A".to_string(), ChannelId::Ecg), QualityReport { gap_count: 2, ..report });
+        state.flush_to_disk().unwrap();
+
+        let file = File::open(&path).unwrap();
+        let group = file.group(&channel_group_path(Some("A"), ChannelId::Ecg)).unwrap();
+        let read = |name: &str| group.attr(name).unwrap().read_scalar::<u64>().unwrap();
+        assert_eq!(read("samples_received"), 95);
+        assert_eq!(read("gap_count"), 2);
+        assert_eq!(group.attr("jitter_ms").unwrap().read_scalar::<f64>().unwrap(), 12.5);
+    }
+
+    #[test]
+    fn test_metadata_written_as_attributes() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("recording_metadata.h5");
+        let mut state = RecorderState::new(30, 10);
+        state.enable_disk_recording(path.clone());
+        state.info = RecordingInfo {
+            subject_id: "S01".to_string(),
+            description: "Resting baseline".to_string(),
+        };
+        state.set_device_metadata(
+            "A".to_string(),
+            DeviceMetadata {
+                name: "Polar H10 1234".to_string(),
+                model: "Polar H10".to_string(),
+                firmware: Some("3.1.1".to_string()),
+                sample_rates: vec![(ChannelId::Ecg, 130), (ChannelId::AccX, 200)],
+            },
+        );
+        state.add_point("A".to_string(), ChannelId::Ecg, 1, 10);
+        state.flush_to_disk().unwrap();
+
+        let file = File::open(&path).unwrap();
+        let read_str = |group: &Group, name: &str| group.attr(name).unwrap().read_scalar::<VarLenUnicode>().unwrap().to_string();
+        let metadata = file.group("metadata").unwrap();
+        assert_eq!(read_str(&metadata, "subject_id"), "S01");
+        assert_eq!(read_str(&metadata, "description"), "Resting baseline");
+        assert_eq!(read_str(&metadata, "app_version"), env!("CARGO_PKG_VERSION"));
+
+        let device = file.group(&format!("devices/{}", device_group_name("A"))).unwrap();
+        assert_eq!(read_str(&device, "model"), "Polar H10");
+        assert_eq!(read_str(&device, "firmware"), "3.1.1");
+
+        let ecg = file.group(&channel_group_path(Some("A"), ChannelId::Ecg)).unwrap();
+        assert_eq!(read_str(&ecg, "unit"), "µV");
+        assert_eq!(ecg.attr("sample_rate_hz").unwrap().read_scalar::<u64>().unwrap(), 130);
+    }
+
+    #[test]
+    fn test_annotations_appended_and_replaced() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("recording_annotations.h5");
+        let mut state = RecorderState::new(30, 10);
+        state.enable_disk_recording(path.clone());
+
+        state.add_annotation(Annotation::new(5_000, "Stimulus", "Tone"));
+        state.flush_to_disk().unwrap();
+        state.add_point("A".to_string(), ChannelId::Hr, 6_000, 60);
+        state.add_annotation(Annotation::new(7_000, "Cough", ""));
+        state.flush_to_disk().unwrap();
+
+        let annotations = PolarDataManager::read_annotations_from_file(&path).unwrap();
+        assert_eq!(annotations.len(), 2);
+        assert_eq!(annotations[0], Annotation::new(5_000, "Stimulus", "Tone"));
+        assert_eq!(annotations[1].label, "Cough");
+
+        // Edited during review: the stored list is replaced as a whole
+        let mut edited = annotations[1].clone();
+        edited.set_end(9_000);
+        PolarDataManager::write_annotations_to_file(&path, &[edited.clone()]).unwrap();
+        assert_eq!(PolarDataManager::read_annotations_from_file(&path).unwrap(), vec![edited]);
+        PolarDataManager::write_annotations_to_file(&path, &[]).unwrap();
+        assert!(PolarDataManager::read_annotations_from_file(&path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_recover_recording_from_journal() {
+        let temp_dir = tempdir().unwrap();
+        let recording = temp_dir.path().join("recording_20240101_100000.h5");
+
+        // Crashed session: journal written, HDF5 file never completed
+        let mut journal = Journal::create(&recording, 5).unwrap();
+        for i in 0..3 {
+            journal.add_point("A", ChannelId::Ecg, 1_000 + i, i as i32).unwrap();
+        }
+        journal.add_point("B", ChannelId::Hr, 2_000, 61).unwrap();
+        journal.add_gap("A", None, Gap { start: 1_002, end: 9_000 }).unwrap();
+        journal.sync().unwrap();
+        std::fs::write(&recording, b"damaged").unwrap();
+
+        let unfinished = PolarDataManager::unfinished_recordings(temp_dir.path());
+        assert_eq!(unfinished, vec![journal::journal_path(&recording)]);
+
+        let recovered = PolarDataManager::recover_recording(&unfinished[0]).unwrap();
+        assert_eq!(recovered, recording);
+        assert!(PolarDataManager::unfinished_recordings(temp_dir.path()).is_empty());
+
+        let ecg = PolarDataManager::read_all_from_file(&recording, Some("A"), ChannelId::Ecg).unwrap();
+        assert_eq!(ecg.iter().map(|p| p.value).collect::<Vec<_>>(), vec![0, 1, 2]);
+        let hr = PolarDataManager::read_all_from_file(&recording, Some("B"), ChannelId::Hr).unwrap();
+        assert_eq!(hr[0].time, 2_000);
     }
 }
@@ -0,0 +1,739 @@
+//! # Recording Playback Module
+//!
+//! Streams a `recording_*.h5` file back through the live data pipeline as if
+//! it came from a connected sensor, so recorded sessions can be reviewed with
+//! the same channels and charts used for live data.
+//!
+//! ## Key Components
+//! - `ReplayTimeline`: In-memory copy of a recording with a playback cursor
+//! - `PlaybackCommand`: Controls sent from the UI (pause, speed, seek, loop)
+//! - `PlaybackStatus`: Position and state reported back to the UI
+//! - `start_replay()`: Async task that paces the timeline against wall time
+//! - `ReplaySource`: `DataSource` wrapping `start_replay()`
+//! - `RecordedDevice`: Device of a recording, picked for playback
+//!
+//! ## Playback Speed
+//! Speed only sets the pace: at N× speed, N seconds of recording are sent per
+//! second of wall time and the app runs its display clock N× as fast. Batches
+//! carry the recorded time of their last sample as sensor time, which
+//! `Channels` maps through the device clock like a real sensor's, so samples
+//! keep their recorded spacing and rates, and derived metrics stay correct.
+//!
+//! Sensor time counts playback time: it continues across seeks and loops
+//! instead of jumping with the position, so data joins up on the charts.
+//!
+//! ## Streams
+//! Every stream found in the file is replayed, and a `DeviceProfile` listing
+//! them is sent on start so the UI shows the matching charts.
+//!
+//! ## Devices
+//! A replay plays one `/devices/<id>` group. For multi-device recordings the
+//! user picks which from `RecordedDevice::list()`, defaulting to the first;
+//! legacy recordings with channels at the file root are read as a single device.
+//!
+//! ## Heart Rate and RR
+//! The file stores HR and RR as separate channels. RR points are regrouped
+//! with the HR update that followed them; HRV is recomputed by `Channels`.
+
+use crate::device_profile::{DeviceModel, DeviceProfile, SensorStream};
+use crate::recorder::{ChannelId, PolarDataManager};
+use crate::samples::{HeartRateSample, MeasurementBatch, MeasurementSample};
+use crate::sensor::{ConnectionStatus, SensorUpdate};
+use crate::source::{DataSource, SourceTask};
+use crate::timeseries::Point;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// Interval between playback steps
+const REPLAY_TICK_MS: u64 = 50;
+
+// Interval between status reports to the UI
+const STATUS_INTERVAL_MS: u64 = 250;
+
+// Sensor time of the first sample; zero would mean the batch has no sensor time
+const SENSOR_EPOCH_NS: u64 = 1_000_000_000;
+
+// Streams delivered as measurement batches, in the order they are replayed
+const MEASUREMENT_STREAMS: [SensorStream; 6] = [
+    SensorStream::Ecg,
+    SensorStream::Ppg,
+    SensorStream::Ppi,
+    SensorStream::Acc,
+    SensorStream::Gyro,
+    SensorStream::Mag,
+];
+
+/// Device ID under which the playback source reports its data
+pub const PLAYBACK_DEVICE_ID: &str = "PLAYBACK";
+
+/// Playback speeds offered in the UI
+pub const PLAYBACK_SPEEDS: [f64; 4] = [1.0, 2.0, 5.0, 10.0];
+
+/// Commands sent from the UI to a running replay
+#[derive(Debug, Clone)]
+pub enum PlaybackCommand {
+    Play,
+    Pause,
+    SetSpeed(f64),
+    /// Jump to an offset from the start of the recording, in nanoseconds
+    Seek(u64),
+    SetLoop(bool),
+}
+
+/// Playback state reported to the UI
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaybackStatus {
+    pub file_path: PathBuf,
+    /// Recording time at the start of the recording, in nanoseconds since the Unix epoch
+    pub start_time_ns: u64,
+    /// Offset from the start of the recording in nanoseconds
+    pub position_ns: u64,
+    pub duration_ns: u64,
+    pub speed: f64,
+    pub paused: bool,
+    pub looping: bool,
+}
+
+/// A device recorded in a file, offered for playback
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedDevice {
+    pub id: String,
+    /// Advertised name from the device metadata, empty if none was recorded
+    pub name: String,
+}
+
+impl RecordedDevice {
+    /// Devices in a recording; empty for legacy recordings without device groups
+    pub fn list(file_path: &Path) -> Result<Vec<Self>, String> {
+        let devices = PolarDataManager::devices_in_file(file_path)?
+            .into_iter()
+            .map(|id| {
+                let name = PolarDataManager::read_device_metadata(file_path, &id)
+                    .map(|metadata| metadata.name)
+                    .unwrap_or_default();
+                Self { id, name }
+            })
+            .collect();
+        Ok(devices)
+    }
+}
+
+impl fmt::Display for RecordedDevice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.name.is_empty() {
+            f.write_str(&self.id)
+        } else {
+            f.write_str(&self.name)
+        }
+    }
+}
+
+/// Per-channel points with a read cursor
+struct ChannelCursor {
+    points: Vec<Point>,
+    next: usize,
+}
+
+impl ChannelCursor {
+    fn new(points: Vec<Point>) -> Self {
+        Self { points, next: 0 }
+    }
+
+    /// Return all points up to and including `time` not yet returned
+    fn take_until(&mut self, time: u64) -> &[Point] {
+        let start = self.next;
+        let end = start + self.points[start..].partition_point(|p| p.time <= time);
+        self.next = end;
+        &self.points[start..end]
+    }
+
+    /// Move the cursor to the first point at or after `time`
+    fn seek(&mut self, time: u64) {
+        self.next = self.points.partition_point(|p| p.time < time);
+    }
+
+    /// Estimate the sample rate in Hz from point spacing
+    fn estimated_rate(&self) -> Option<u64> {
+        let first = self.points.first()?;
+        let last = self.points.last()?;
+        let span = last.time.checked_sub(first.time).filter(|&s| s > 0)?;
+        let rate = (self.points.len() - 1) as f64 * 1e9 / span as f64;
+        Some(rate.round() as u64).filter(|&r| r > 0)
+    }
+}
+
+/// Cursors for the channels of one measurement stream
+///
+/// Single-value streams have one cursor, 3-axis streams one per axis.
+struct StreamCursors {
+    stream: SensorStream,
+    axes: Vec<ChannelCursor>,
+}
+
+impl StreamCursors {
+    /// Samples up to and including `time` not yet returned, with the time of the last
+    ///
+    /// Axes of one stream share timestamps, so they are zipped by index.
+    fn take_until(&mut self, time: u64) -> (Vec<MeasurementSample>, Option<u64>) {
+        let axes: Vec<&[Point]> = self.axes.iter_mut().map(|c| c.take_until(time)).collect();
+        let count = axes.iter().map(|a| a.len()).min().unwrap_or(0);
+        let last_time = count.checked_sub(1).map(|last| axes[0][last].time);
+        let stream = self.stream;
+
+        let samples = (0..count)
+            .filter_map(|i| {
+                let value = |axis: usize| axes[axis][i].value;
+                let sample = match stream {
+                    SensorStream::Ecg => MeasurementSample::Ecg(value(0)),
+                    SensorStream::Ppg => MeasurementSample::Ppg(value(0)),
+                    SensorStream::Ppi => MeasurementSample::Ppi(value(0).clamp(0, u16::MAX as i32) as u16),
+                    SensorStream::Acc => MeasurementSample::Acc { x: value(0), y: value(1), z: value(2) },
+                    SensorStream::Gyro => MeasurementSample::Gyro { x: value(0), y: value(1), z: value(2) },
+                    SensorStream::Mag => MeasurementSample::Mag { x: value(0), y: value(1), z: value(2) },
+                    SensorStream::HeartRate => return None,
+                };
+                Some(sample)
+            })
+            .collect();
+        (samples, last_time)
+    }
+}
+
+/// A recording loaded into memory with a playback cursor
+pub struct ReplayTimeline {
+    /// Measurement streams present in the recording
+    streams: Vec<StreamCursors>,
+    hr: ChannelCursor,
+    rr: ChannelCursor,
+    /// RR points read but not yet attached to an HR update
+    pending_rr: Vec<Point>,
+    profile: DeviceProfile,
+    start_time: u64,
+    duration_ns: u64,
+    /// Offset from start_time of the last emitted data
+    position_ns: u64,
+    /// Offset where playback last continued after a seek or loop
+    segment_start_ns: u64,
+    /// Sensor time reported for data at segment_start_ns
+    segment_sensor_ns: u64,
+    speed: f64,
+    paused: bool,
+    looping: bool,
+}
+
+impl ReplayTimeline {
+    /// Build a timeline from per-channel points (channels may be empty)
+    pub fn new(mut channels: impl FnMut(ChannelId) -> Vec<Point>) -> Result<Self, String> {
+        let streams: Vec<StreamCursors> = MEASUREMENT_STREAMS
+            .iter()
+            .map(|&stream| StreamCursors {
+                stream,
+                axes: ChannelId::for_stream(stream)
+                    .iter()
+                    .map(|&channel| ChannelCursor::new(channels(channel)))
+                    .collect(),
+            })
+            .filter(|s| s.axes.iter().any(|c| !c.points.is_empty()))
+            .collect();
+        let hr = ChannelCursor::new(channels(ChannelId::Hr));
+        let rr = ChannelCursor::new(channels(ChannelId::Rr));
+
+        // The recording doesn't say which model made it, so offer what it contains
+        let mut available: Vec<SensorStream> = streams.iter().map(|s| s.stream).collect();
+        if !hr.points.is_empty() || !rr.points.is_empty() {
+            available.insert(0, SensorStream::HeartRate);
+        }
+
+        let mut timeline = Self {
+            streams,
+            hr,
+            rr,
+            pending_rr: Vec::new(),
+            profile: DeviceProfile::with_streams(DeviceModel::Other, available),
+            start_time: 0,
+            duration_ns: 0,
+            position_ns: 0,
+            segment_start_ns: 0,
+            segment_sensor_ns: SENSOR_EPOCH_NS,
+            speed: 1.0,
+            paused: false,
+            looping: false,
+        };
+
+        let (min_time, max_time) = timeline
+            .cursors()
+            .flat_map(|c| [c.points.first(), c.points.last()])
+            .flatten()
+            .fold((u64::MAX, u64::MIN), |(min, max), p| (min.min(p.time), max.max(p.time)));
+
+        if min_time > max_time {
+            return Err("Recording contains no data".to_string());
+        }
+
+        timeline.start_time = min_time;
+        timeline.duration_ns = max_time - min_time;
+        timeline.seek(0);
+        Ok(timeline)
+    }
+
+    /// Load the timeline of one device from an HDF5 recording
+    ///
+    /// `None` plays the first device, or the root channels of a legacy recording.
+    pub fn load(file_path: &Path, device_id: Option<&str>) -> Result<Self, String> {
+        let devices = PolarDataManager::devices_in_file(file_path)?;
+        let device_id = match device_id {
+            Some(id) if !devices.iter().any(|d| d == id) => {
+                return Err(format!("Recording has no device {}", id));
+            }
+            Some(id) => Some(id),
+            None => devices.first().map(String::as_str),
+        };
+        if devices.len() > 1 {
+            log::info!("Recording has {} devices, playing back {}", devices.len(), device_id.unwrap_or_default());
+        }
+
+        Self::new(|channel| {
+            PolarDataManager::read_all_from_file(file_path, device_id, channel).unwrap_or_else(|e| {
+                log::debug!("Channel {} not available: {}", channel.group_name(), e);
+                Vec::new()
+            })
+        })
+    }
+
+    fn cursors(&self) -> impl Iterator<Item = &ChannelCursor> {
+        self.streams
+            .iter()
+            .flat_map(|s| s.axes.iter())
+            .chain([&self.hr, &self.rr])
+    }
+
+    /// Streams found in the recording
+    pub fn profile(&self) -> &DeviceProfile {
+        &self.profile
+    }
+
+    /// Recording time of the first data, where offsets count from
+    pub fn start_time(&self) -> u64 {
+        self.start_time
+    }
+
+    pub fn duration_ns(&self) -> u64 {
+        self.duration_ns
+    }
+
+    pub fn position_ns(&self) -> u64 {
+        self.position_ns
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn is_looping(&self) -> bool {
+        self.looping
+    }
+
+    /// Sample rates of the periodic streams as recorded
+    pub fn sample_rates(&self) -> Vec<(SensorStream, u64)> {
+        self.streams
+            .iter()
+            .filter(|s| s.stream.is_periodic())
+            .map(|s| {
+                let rate = s.axes[0]
+                    .estimated_rate()
+                    .unwrap_or_else(|| self.profile.default_sample_rate(s.stream));
+                (s.stream, rate)
+            })
+            .collect()
+    }
+
+    /// Sensor time reported for data recorded at `time`
+    fn sensor_time(&self, time: u64) -> u64 {
+        self.segment_sensor_ns + time.saturating_sub(self.start_time + self.segment_start_ns)
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        // Restart from the beginning when playing after reaching the end
+        if !paused && self.position_ns >= self.duration_ns {
+            self.seek(0);
+        }
+        self.paused = paused;
+    }
+
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = speed.max(0.1);
+    }
+
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+    }
+
+    /// Jump to an offset from the start of the recording
+    pub fn seek(&mut self, position_ns: u64) {
+        // Sensor time moves on from the data already played, never back
+        if position_ns != self.position_ns {
+            self.segment_sensor_ns = self.sensor_time(self.start_time + self.position_ns) + 1;
+        }
+        self.position_ns = position_ns.min(self.duration_ns);
+        self.segment_start_ns = self.position_ns;
+        let time = self.start_time + self.position_ns;
+        for cursor in self
+            .streams
+            .iter_mut()
+            .flat_map(|s| s.axes.iter_mut())
+            .chain([&mut self.hr, &mut self.rr])
+        {
+            cursor.seek(time);
+        }
+        self.pending_rr.clear();
+    }
+
+    /// Advance playback by `wall_elapsed_ns` of wall time
+    ///
+    /// Returns the updates for all data passed, in the order a sensor would
+    /// have sent them. Pauses at the end unless looping is enabled.
+    pub fn advance(&mut self, wall_elapsed_ns: u64) -> Vec<SensorUpdate> {
+        let mut updates = Vec::new();
+        if self.paused {
+            return updates;
+        }
+
+        let step = (wall_elapsed_ns as f64 * self.speed) as u64;
+        let target = self.position_ns + step;
+
+        if target <= self.duration_ns {
+            self.emit_until(target, &mut updates);
+        } else {
+            self.emit_until(self.duration_ns, &mut updates);
+            if self.looping && self.duration_ns > 0 {
+                self.seek(0);
+                let remaining = (target - self.duration_ns) % self.duration_ns;
+                self.emit_until(remaining, &mut updates);
+            } else {
+                self.paused = true;
+            }
+        }
+
+        updates
+    }
+
+    /// Emit updates for data up to `position_ns` and move the cursor there
+    fn emit_until(&mut self, position_ns: u64, updates: &mut Vec<SensorUpdate>) {
+        let until = self.start_time + position_ns;
+        self.position_ns = position_ns;
+
+        let taken: Vec<_> = self.streams.iter_mut().map(|stream| stream.take_until(until)).collect();
+        for (samples, last_time) in taken {
+            if let Some(last_time) = last_time {
+                let batch = MeasurementBatch::new(samples).with_sensor_time(self.sensor_time(last_time));
+                updates.push(SensorUpdate::MeasurementData(batch));
+            }
+        }
+
+        self.pending_rr.extend_from_slice(self.rr.take_until(until));
+        for hr in self.hr.take_until(until) {
+            let split = self.pending_rr.partition_point(|p| p.time <= hr.time);
+            let rr_intervals = self
+                .pending_rr
+                .drain(..split)
+                .map(|p| p.value.clamp(0, u16::MAX as i32) as u16)
+                .collect();
+            let bpm = hr.value.clamp(0, u16::MAX as i32) as u16;
+            updates.push(SensorUpdate::HeartRate(HeartRateSample::new(bpm, rr_intervals)));
+        }
+    }
+}
+
+/// Run playback of one device of a recording until the stop flag is set
+///
+/// Mirrors `sensor::start_data_collection`: reports connection status and
+/// sample rates, then streams data. Playback controls arrive on `commands`.
+pub async fn start_replay(
+    file_path: PathBuf,
+    device_id: Option<String>,
+    sender: Sender<SensorUpdate>,
+    commands: Receiver<PlaybackCommand>,
+    should_stop: Arc<AtomicBool>,
+) {
+    log::info!("Starting playback of {}", file_path.display());
+    let _ = sender.send(SensorUpdate::ConnectionStatus(ConnectionStatus::Connecting));
+
+    let path = file_path.clone();
+    let loaded = tokio::task::spawn_blocking(move || ReplayTimeline::load(&path, device_id.as_deref())).await;
+    let mut timeline = match loaded {
+        Ok(Ok(timeline)) => timeline,
+        Ok(Err(e)) => {
+            log::error!("Failed to load recording: {}", e);
+            let _ = sender.send(SensorUpdate::ConnectionStatus(ConnectionStatus::Error(e)));
+            return;
+        }
+        Err(e) => {
+            log::error!("Failed to load recording: {}", e);
+            let _ = sender.send(SensorUpdate::ConnectionStatus(ConnectionStatus::Error(e.to_string())));
+            return;
+        }
+    };
+
+    let _ = sender.send(SensorUpdate::ConnectionStatus(ConnectionStatus::Connected));
+    let _ = sender.send(SensorUpdate::DeviceProfile(timeline.profile().clone()));
+    let _ = sender.send(SensorUpdate::SampleRateConfig { rates: timeline.sample_rates() });
+
+    let status = |timeline: &ReplayTimeline| {
+        SensorUpdate::PlaybackStatus(PlaybackStatus {
+            file_path: file_path.clone(),
+            start_time_ns: timeline.start_time(),
+            position_ns: timeline.position_ns(),
+            duration_ns: timeline.duration_ns(),
+            speed: timeline.speed(),
+            paused: timeline.is_paused(),
+            looping: timeline.is_looping(),
+        })
+    };
+    let _ = sender.send(status(&timeline));
+
+    let mut interval = tokio::time::interval(Duration::from_millis(REPLAY_TICK_MS));
+    let mut last_tick = Instant::now();
+    let mut last_status = Instant::now();
+
+    loop {
+        interval.tick().await;
+        if should_stop.load(Ordering::Relaxed) {
+            log::info!("Stopping playback");
+            break;
+        }
+
+        let mut status_changed = false;
+        while let Ok(command) = commands.try_recv() {
+            log::debug!("Playback command: {:?}", command);
+            match command {
+                PlaybackCommand::Play => timeline.set_paused(false),
+                PlaybackCommand::Pause => timeline.set_paused(true),
+                PlaybackCommand::SetSpeed(speed) => timeline.set_speed(speed),
+                PlaybackCommand::Seek(position_ns) => timeline.seek(position_ns),
+                PlaybackCommand::SetLoop(looping) => timeline.set_looping(looping),
+            }
+            status_changed = true;
+        }
+
+        let elapsed = last_tick.elapsed();
+        last_tick = Instant::now();
+
+        for update in timeline.advance(elapsed.as_nanos() as u64) {
+            if let Err(why) = sender.send(update) {
+                log::error!("Could not send playback data: {:?}", why);
+                return;
+            }
+        }
+
+        if status_changed || last_status.elapsed() >= Duration::from_millis(STATUS_INTERVAL_MS) {
+            last_status = Instant::now();
+            let _ = sender.send(status(&timeline));
+        }
+    }
+
+    let _ = sender.send(SensorUpdate::ConnectionStatus(ConnectionStatus::Disconnected));
+}
+
+/// Data source playing back one device of a recording file
+pub struct ReplaySource {
+    file_path: PathBuf,
+    /// Device to play, the first in the file if `None`
+    device_id: Option<String>,
+    commands: Receiver<PlaybackCommand>,
+}
+
+impl ReplaySource {
+    pub fn new(file_path: PathBuf, device_id: Option<String>, commands: Receiver<PlaybackCommand>) -> Self {
+        Self { file_path, device_id, commands }
+    }
+}
+
+impl DataSource for ReplaySource {
+    fn name(&self) -> String {
+        match &self.device_id {
+            Some(device_id) => format!("playback of {} from {}", device_id, self.file_path.display()),
+            None => format!("playback of {}", self.file_path.display()),
+        }
+    }
+
+    fn start(self: Box<Self>, sender: Sender<SensorUpdate>, should_stop: Arc<AtomicBool>) -> SourceTask {
+        Box::pin(start_replay(self.file_path, self.device_id, sender, self.commands, should_stop))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal::test_recordings::{self, START};
+    use crate::recorder::DeviceMetadata;
+    use tempfile::tempdir;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    /// One second of 10 Hz ECG, HR at 0.5 s and 1 s with RR just before each
+    fn test_timeline() -> ReplayTimeline {
+        ReplayTimeline::new(|channel| match channel {
+            ChannelId::Ecg => (0..=10).map(|i| Point { time: SECOND + i * SECOND / 10, value: i as i32 }).collect(),
+            ChannelId::Hr => vec![
+                Point { time: SECOND + SECOND / 2, value: 60 },
+                Point { time: 2 * SECOND, value: 62 },
+            ],
+            ChannelId::Rr => vec![
+                Point { time: SECOND + SECOND / 2, value: 1000 },
+                Point { time: 2 * SECOND, value: 970 },
+            ],
+            _ => Vec::new(),
+        })
+        .unwrap()
+    }
+
+    fn ecg_count(updates: &[SensorUpdate]) -> usize {
+        updates
+            .iter()
+            .map(|u| match u {
+                SensorUpdate::MeasurementData(batch) => batch.samples.len(),
+                _ => 0,
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_timeline_duration() {
+        let timeline = test_timeline();
+        assert_eq!(timeline.start_time(), SECOND);
+        assert_eq!(timeline.duration_ns(), SECOND);
+        assert_eq!(timeline.position_ns(), 0);
+    }
+
+    #[test]
+    fn test_advance_emits_data_in_range() {
+        let mut timeline = test_timeline();
+
+        // The first sample sits exactly at the start
+        let updates = timeline.advance(SECOND / 2);
+        assert_eq!(ecg_count(&updates), 6);
+
+        let hr: Vec<&HeartRateSample> = updates
+            .iter()
+            .filter_map(|u| match u {
+                SensorUpdate::HeartRate(hr) => Some(hr),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(hr.len(), 1);
+        assert_eq!(hr[0].bpm, 60);
+        assert_eq!(hr[0].rr_intervals, vec![1000]);
+    }
+
+    #[test]
+    fn test_speed_and_end_of_recording() {
+        let mut timeline = test_timeline();
+        timeline.set_speed(2.0);
+        assert_eq!(timeline.sample_rates(), vec![(SensorStream::Ecg, 10)]);
+
+        let updates = timeline.advance(SECOND);
+        assert_eq!(ecg_count(&updates), 11);
+        assert!(timeline.is_paused());
+        assert_eq!(timeline.position_ns(), SECOND);
+
+        // Playing again restarts from the beginning
+        timeline.set_paused(false);
+        assert_eq!(timeline.position_ns(), 0);
+    }
+
+    #[test]
+    fn test_batches_carry_recorded_time() {
+        let mut timeline = test_timeline();
+        timeline.set_speed(5.0);
+        let sensor_time = |updates: &[SensorUpdate]| {
+            updates.iter().find_map(|u| match u {
+                SensorUpdate::MeasurementData(batch) => batch.sensor_time,
+                _ => None,
+            })
+        };
+
+        // 0.1 s of wall time covers 0.5 s of recording, the last sample at 0.5 s
+        let updates = timeline.advance(SECOND / 10);
+        assert_eq!(ecg_count(&updates), 6);
+        assert_eq!(sensor_time(&updates), Some(SENSOR_EPOCH_NS + SECOND / 2));
+
+        // After seeking back, sensor time keeps counting forward
+        timeline.seek(0);
+        let updates = timeline.advance(SECOND / 50);
+        assert_eq!(ecg_count(&updates), 2);
+        assert_eq!(sensor_time(&updates), Some(SENSOR_EPOCH_NS + SECOND / 2 + 1 + SECOND / 10));
+    }
+
+    #[test]
+    fn test_seek_and_loop() {
+        let mut timeline = test_timeline();
+        timeline.set_looping(true);
+        timeline.seek(SECOND * 9 / 10);
+
+        let updates = timeline.advance(SECOND / 5);
+        // 0.9 s and 1.0 s, then wrap to 0.0 s and 0.1 s
+        assert_eq!(ecg_count(&updates), 4);
+        assert!(!timeline.is_paused());
+        assert_eq!(timeline.position_ns(), SECOND / 10);
+    }
+
+    #[test]
+    fn test_profile_from_recorded_channels() {
+        let mut timeline = ReplayTimeline::new(|channel| match channel {
+            ChannelId::Ppg => (0..5).map(|i| Point { time: i * SECOND / 5, value: 100 + i as i32 }).collect(),
+            ChannelId::GyroX | ChannelId::GyroY | ChannelId::GyroZ => vec![Point { time: 0, value: 7 }],
+            _ => Vec::new(),
+        })
+        .unwrap();
+
+        let profile = timeline.profile();
+        assert!(profile.supports(SensorStream::Ppg));
+        assert!(profile.supports(SensorStream::Gyro));
+        assert!(!profile.supports(SensorStream::HeartRate));
+        assert!(!profile.supports(SensorStream::Ecg));
+
+        let updates = timeline.advance(SECOND);
+        assert!(updates.iter().any(|u| matches!(
+            u,
+            SensorUpdate::MeasurementData(batch) if batch.samples[0] == MeasurementSample::Gyro { x: 7, y: 7, z: 7 }
+        )));
+    }
+
+    #[test]
+    fn test_load_picks_recorded_device() {
+        let dir = tempdir().unwrap();
+        let recording = test_recordings::build(dir.path(), None, |journal| {
+            let metadata = DeviceMetadata {
+                name: "Polar H10 5678".to_string(),
+                model: "Polar H10".to_string(),
+                firmware: None,
+                sample_rates: Vec::new(),
+            };
+            journal.add_device_metadata("B", &metadata).unwrap();
+            journal.add_point("A", ChannelId::Hr, START, 60).unwrap();
+            journal.add_point("B", ChannelId::Hr, START, 70).unwrap();
+            journal.add_point("B", ChannelId::Hr, START + SECOND, 72).unwrap();
+        });
+
+        let devices = RecordedDevice::list(&recording).unwrap();
+        let names: Vec<String> = devices.iter().map(|d| d.to_string()).collect();
+        assert_eq!(names, ["Polar H10 1234", "Polar H10 5678"]);
+
+        assert_eq!(ReplayTimeline::load(&recording, None).unwrap().duration_ns(), 0);
+        assert_eq!(ReplayTimeline::load(&recording, Some("B")).unwrap().duration_ns(), SECOND);
+        assert!(ReplayTimeline::load(&recording, Some("C")).is_err());
+    }
+
+    #[test]
+    fn test_empty_recording_is_rejected() {
+        assert!(ReplayTimeline::new(|_| Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,217 @@
+//! # Respiration Module
+//!
+//! Breathing rate estimated from signals that breathing modulates, without a
+//! respiration belt.
+//!
+//! ## Surrogates
+//! - **ACC**: The chest rises and falls, tilting the sensor. Each axis is
+//!   averaged into `ACC_BIN_NS` bins; the axis with the clearest breathing
+//!   peak is used.
+//! - **EDR** (ECG-derived respiration): The R peak amplitude changes as the
+//!   heart's electrical axis moves with the lungs.
+//! - **RSA** (respiratory sinus arrhythmia): RR intervals shorten on
+//!   inhalation and lengthen on exhalation.
+//!
+//! ## Estimation
+//! Each surrogate over the last `WINDOW_NS` gets a Lomb-Scargle periodogram
+//! (see `hrv::lomb_scargle`) within `BREATHING_BAND` (6-30 breaths/min), so
+//! the irregular beat times need no resampling. The highest peak is the
+//! surrogate's rate; its quality is the share of the band power within
+//! `PEAK_HALF_WIDTH_HZ` of it. Surrogates below `MIN_QUALITY` are dropped,
+//! and the rest are averaged weighted by quality, keeping only those within
+//! `AGREEMENT_BPM` of the best one, so one confused surrogate cannot drag
+//! the result.
+
+use crate::hrv::lomb_scargle;
+use crate::timeseries::Point;
+
+// Span of data the rate is estimated over
+pub const WINDOW_NS: u64 = 60_000_000_000;
+
+// Shortest span of a surrogate for an estimate: a few slow breaths
+const MIN_SPAN_S: f64 = 30.0;
+
+// Fewest points of a surrogate, e.g. beats
+const MIN_POINTS: usize = 16;
+
+// Bin width ACC is averaged over (4 Hz)
+const ACC_BIN_NS: u64 = 250_000_000;
+
+// Breathing frequencies considered, and the periodogram step, in Hz
+const BREATHING_BAND: (f64, f64) = (0.1, 0.5);
+const FREQUENCY_STEP: f64 = 0.005;
+
+// Half width of the band around the peak counted as the peak's power
+const PEAK_HALF_WIDTH_HZ: f64 = 0.03;
+
+// Least share of band power in the peak for a surrogate to count
+const MIN_QUALITY: f64 = 0.3;
+
+// Largest difference to the best surrogate's rate to be averaged with it
+const AGREEMENT_BPM: f64 = 3.0;
+
+const NANOS_PER_SECOND: f64 = 1_000_000_000.0;
+
+/// Breathing rate found in one surrogate signal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateEstimate {
+    pub breaths_per_min: f64,
+    /// Share of the breathing band's power in the peak, from 0 to 1
+    pub quality: f64,
+}
+
+/// Dominant breathing rate of a surrogate signal
+///
+/// `None` if the points span too little time or carry no power in the band.
+pub fn surrogate_rate(points: &[(f64, f64)]) -> Option<RateEstimate> {
+    let (first, last) = (points.first()?.0, points.last()?.0);
+    if points.len() < MIN_POINTS || last - first < MIN_SPAN_S {
+        return None;
+    }
+
+    let times: Vec<f64> = points.iter().map(|&(t, _)| t - first).collect();
+    let values = detrend(&times, points.iter().map(|&(_, v)| v).collect());
+
+    let steps = ((BREATHING_BAND.1 - BREATHING_BAND.0) / FREQUENCY_STEP).round() as usize;
+    let spectrum: Vec<(f64, f64)> = (0..=steps)
+        .map(|i| {
+            let frequency = BREATHING_BAND.0 + i as f64 * FREQUENCY_STEP;
+            (frequency, lomb_scargle(&times, &values, frequency))
+        })
+        .collect();
+    let total: f64 = spectrum.iter().map(|&(_, power)| power).sum();
+    if total <= 0.0 {
+        return None;
+    }
+
+    let &(peak, _) = spectrum.iter().max_by(|a, b| a.1.total_cmp(&b.1))?;
+    let near_peak: f64 = spectrum
+        .iter()
+        .filter(|&&(frequency, _)| (frequency - peak).abs() <= PEAK_HALF_WIDTH_HZ)
+        .map(|&(_, power)| power)
+        .sum();
+    Some(RateEstimate { breaths_per_min: peak * 60.0, quality: near_peak / total })
+}
+
+/// Values minus their least-squares line over `times`
+fn detrend(times: &[f64], mut values: Vec<f64>) -> Vec<f64> {
+    let n = times.len() as f64;
+    let mean_t = times.iter().sum::<f64>() / n;
+    let mean_v = values.iter().sum::<f64>() / n;
+    let stt: f64 = times.iter().map(|t| (t - mean_t).powi(2)).sum();
+    let stv: f64 = times.iter().zip(&values).map(|(t, v)| (t - mean_t) * (v - mean_v)).sum();
+    let slope = if stt > 0.0 { stv / stt } else { 0.0 };
+    for (v, t) in values.iter_mut().zip(times) {
+        *v -= mean_v + slope * (t - mean_t);
+    }
+    values
+}
+
+/// Points as (seconds, value) pairs
+fn to_seconds(points: &[Point]) -> Vec<(f64, f64)> {
+    points.iter().map(|p| (p.time as f64 / NANOS_PER_SECOND, p.value as f64)).collect()
+}
+
+/// Mean of the points in each `bin_ns` bin, placed at the bin's middle
+fn bin_means(points: &[Point], bin_ns: u64) -> Vec<(f64, f64)> {
+    let mut means = Vec::new();
+    let mut current: Option<(u64, f64, usize)> = None;
+    for p in points {
+        let bin = p.time / bin_ns;
+        match &mut current {
+            Some((b, sum, count)) if *b == bin => {
+                *sum += p.value as f64;
+                *count += 1;
+            }
+            _ => {
+                means.extend(current.map(|(b, sum, count)| (b, sum / count as f64)));
+                current = Some((bin, p.value as f64, 1));
+            }
+        }
+    }
+    means.extend(current.map(|(b, sum, count)| (b, sum / count as f64)));
+    means
+        .into_iter()
+        .map(|(bin, mean)| ((bin as f64 + 0.5) * bin_ns as f64 / NANOS_PER_SECOND, mean))
+        .collect()
+}
+
+/// Rate from the accelerometer axis with the clearest breathing peak
+pub fn acc_rate(axes: [&[Point]; 3]) -> Option<RateEstimate> {
+    axes.iter()
+        .filter_map(|axis| surrogate_rate(&bin_means(axis, ACC_BIN_NS)))
+        .max_by(|a, b| a.quality.total_cmp(&b.quality))
+}
+
+/// Rate from the amplitude of R peaks
+pub fn edr_rate(r_peaks: &[Point]) -> Option<RateEstimate> {
+    surrogate_rate(&to_seconds(r_peaks))
+}
+
+/// Rate from the modulation of beat-to-beat intervals
+pub fn rsa_rate(intervals: &[Point]) -> Option<RateEstimate> {
+    surrogate_rate(&to_seconds(intervals))
+}
+
+/// Breathing rate agreed on by the usable surrogate estimates
+pub fn fuse(estimates: &[RateEstimate]) -> Option<f64> {
+    let usable: Vec<&RateEstimate> = estimates.iter().filter(|e| e.quality >= MIN_QUALITY).collect();
+    let best = usable.iter().max_by(|a, b| a.quality.total_cmp(&b.quality))?;
+    let (sum, weights) = usable
+        .iter()
+        .filter(|e| (e.breaths_per_min - best.breaths_per_min).abs() <= AGREEMENT_BPM)
+        .fold((0.0, 0.0), |(sum, weights), e| (sum + e.breaths_per_min * e.quality, weights + e.quality));
+    Some(sum / weights)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    /// Points every `step_ns` for a minute, modulated at `breaths_per_min`
+    fn modulated(step_ns: u64, breaths_per_min: f64, mean: f64, depth: f64) -> Vec<Point> {
+        (0..60 * SECOND / step_ns)
+            .map(|i| {
+                let t = i * step_ns;
+                let phase = 2.0 * std::f64::consts::PI * breaths_per_min / 60.0 * t as f64 / SECOND as f64;
+                Point { time: t, value: (mean + depth * phase.sin() + (i % 3) as f64).round() as i32 }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_surrogates_find_breathing() {
+        // Beat-to-beat surrogates at 75 bpm, ACC at 50 Hz, with a drift on one axis
+        let rsa = rsa_rate(&modulated(800_000_000, 12.0, 800.0, 40.0)).unwrap();
+        assert!((rsa.breaths_per_min - 12.0).abs() < 0.5, "{:?}", rsa);
+        assert!(rsa.quality > 0.5);
+
+        let edr = edr_rate(&modulated(800_000_000, 12.0, 1500.0, 80.0)).unwrap();
+        assert!((edr.breaths_per_min - 12.0).abs() < 0.5, "{:?}", edr);
+
+        let flat: Vec<Point> = (0..3000).map(|i| Point { time: i * SECOND / 50, value: 30 + (i % 5) as i32 }).collect();
+        let drifting: Vec<Point> = modulated(SECOND / 50, 18.0, 80.0, 25.0)
+            .into_iter()
+            .map(|p| Point { value: p.value + (p.time / SECOND) as i32, ..p })
+            .collect();
+        let acc = acc_rate([&flat, &flat, &drifting]).unwrap();
+        assert!((acc.breaths_per_min - 18.0).abs() < 0.5, "{:?}", acc);
+
+        // Too short to tell
+        assert_eq!(rsa_rate(&modulated(800_000_000, 12.0, 800.0, 40.0)[..20]), None);
+    }
+
+    #[test]
+    fn test_fuse() {
+        let estimate = |breaths_per_min, quality| RateEstimate { breaths_per_min, quality };
+        let rate = fuse(&[estimate(12.0, 0.8), estimate(14.0, 0.4)]).unwrap();
+        assert!((rate - 38.0 / 3.0).abs() < 1e-9, "{}", rate);
+        // Disagreeing and poor estimates are left out
+        let rate = fuse(&[estimate(12.0, 0.8), estimate(24.0, 0.7), estimate(13.0, 0.1)]).unwrap();
+        assert!((rate - 12.0).abs() < 1e-9, "{}", rate);
+        assert_eq!(fuse(&[estimate(12.0, 0.2)]), None);
+        assert_eq!(fuse(&[]), None);
+    }
+}
@@ -0,0 +1,93 @@
+//! # Sensor Sample Types Module
+//!
+//! Crate-owned representations of the data produced by sensor backends.
+//! Every backend (Polar via Arctic, the synthetic generator, ...) converts its
+//! native types into these before sending them to the UI thread.
+//!
+//! ## Key Types
+//! - `HeartRateSample`: Heart rate in BPM with the RR intervals of that update
+//! - `MeasurementSample`: Single raw sample from a measurement stream (ECG, ACC, PPG, ...)
+//! - `MeasurementBatch`: Batch of raw samples as delivered by one notification,
+//!   with the sensor clock timestamp if the device provides one
+//!
+//! ## Why Own Types
+//! Arctic's constructors are private, so nothing but a real Polar sensor can
+//! produce `arctic::HeartRate` or `arctic::PmdRead`. Owning the types lets other
+//! sources feed the same processing pipeline.
+
+use crate::device_profile::SensorStream;
+
+/// Heart rate update with the RR intervals measured since the previous update
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeartRateSample {
+    /// Heart rate in beats per minute
+    pub bpm: u16,
+    /// RR intervals in milliseconds (may be empty)
+    pub rr_intervals: Vec<u16>,
+    /// Cumulative energy expended in kilojoules, if the sensor reports it
+    pub energy_expended: Option<u16>,
+}
+
+impl HeartRateSample {
+    pub fn new(bpm: u16, rr_intervals: Vec<u16>) -> Self {
+        Self {
+            bpm,
+            rr_intervals,
+            energy_expended: None,
+        }
+    }
+}
+
+/// Single raw sample from a measurement stream
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MeasurementSample {
+    /// ECG voltage in microvolts
+    Ecg(i32),
+    /// 3-axis acceleration in milli-g
+    Acc { x: i32, y: i32, z: i32 },
+    /// Optical PPG signal in raw sensor units
+    Ppg(i32),
+    /// Optical peak-to-peak interval in milliseconds
+    Ppi(u16),
+    /// 3-axis angular rate in millidegrees per second
+    Gyro { x: i32, y: i32, z: i32 },
+    /// 3-axis magnetic field in milligauss
+    Mag { x: i32, y: i32, z: i32 },
+}
+
+impl MeasurementSample {
+    /// Stream this sample belongs to
+    pub fn stream(&self) -> SensorStream {
+        match self {
+            MeasurementSample::Ecg(_) => SensorStream::Ecg,
+            MeasurementSample::Acc { .. } => SensorStream::Acc,
+            MeasurementSample::Ppg(_) => SensorStream::Ppg,
+            MeasurementSample::Ppi(_) => SensorStream::Ppi,
+            MeasurementSample::Gyro { .. } => SensorStream::Gyro,
+            MeasurementSample::Mag { .. } => SensorStream::Mag,
+        }
+    }
+}
+
+/// Batch of measurement samples delivered together by the sensor
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MeasurementBatch {
+    pub samples: Vec<MeasurementSample>,
+    /// Sensor clock time of the last sample in nanoseconds, if the device stamps its frames
+    pub sensor_time: Option<u64>,
+}
+
+impl MeasurementBatch {
+    pub fn new(samples: Vec<MeasurementSample>) -> Self {
+        Self {
+            samples,
+            sensor_time: None,
+        }
+    }
+
+    /// Attach the sensor timestamp of the frame; zero means the device sent none
+    pub fn with_sensor_time(mut self, sensor_time: u64) -> Self {
+        self.sensor_time = (sensor_time > 0).then_some(sensor_time);
+        self
+    }
+}
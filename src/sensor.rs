@@ -6,16 +6,24 @@
 //! ## Key Components
 //! - `Handler`: Event handler that forwards sensor data to UI thread
 //! - `SensorUpdate`: Messages sent from sensor to UI
+//! - `DeviceUpdate`: `SensorUpdate` tagged with the device ID
 //! - `ConnectionStatus`: Connection lifecycle events
 //! - `start_data_collection()`: Main async function orchestrating data flow
+//! - `PolarSource`: `DataSource` connecting to a Polar sensor by device ID
 //!
 //! ## Connection Lifecycle
 //! 1. Create PolarSensor instance
 //! 2. Attempt connection with retry logic
-//! 3. Subscribe to notification streams (HR, PMD)
-//! 4. Configure data types (ECG, Accelerometer)
+//! 3. Subscribe to notification streams (HR, and PMD if the model has it)
+//! 4. Configure data types from the device profile (ECG, Accelerometer)
 //! 5. Query and set maximum sample rates
 //! 6. Run event loop with cancellation support
+//! 7. After an unexpected link loss, reconnect with backoff (`run_with_reconnect`)
+//!
+//! ## Model Support
+//! Arctic only implements the H10 measurement types (ECG and ACC), so this
+//! backend serves the H10, the H9 and unrecognised Polar models. The optical
+//! models (Verity Sense, OH1) stream through `pmd.rs` instead.
 //!
 //! ## Why Separate Thread
 //! Sensor operations are async and potentially blocking. Running in a separate
@@ -25,6 +33,11 @@
 //! Uses AtomicBool stop flag with tokio::select! to allow clean cancellation
 //! of the event loop without forcefully terminating the async task.
 
+use crate::device_profile::{DeviceModel, DeviceProfile, SensorStream};
+use crate::error::ConnectionError;
+use crate::replay::PlaybackStatus;
+use crate::samples::{HeartRateSample, MeasurementBatch, MeasurementSample};
+use crate::source::{run_with_reconnect, DataSource, SessionEnd, SourceTask};
 use arctic::{self, PolarSensor};
 use std::sync::mpsc::Sender;
 use std::sync::Arc;
@@ -34,16 +47,34 @@ use tokio::sync::RwLock;
 // Enable clone and debug for the SensorUpdate enum
 #[derive(Debug)]
 pub enum SensorUpdate {
-    HeartRate(arctic::HeartRate),
-    MeasurementData(arctic::PmdRead),
+    HeartRate(HeartRateSample),
+    MeasurementData(MeasurementBatch),
     ConnectionStatus(ConnectionStatus),
-    SampleRateConfig { ecg_rate: u64, acc_rate: u64 },
+    /// Configured sample rate in Hz for each periodic stream
+    SampleRateConfig { rates: Vec<(SensorStream, u64)> },
+    /// Streams offered by the connected device, sent once after connecting
+    DeviceProfile(DeviceProfile),
+    /// Firmware revision reported by the device
+    Firmware(String),
+    PlaybackStatus(PlaybackStatus),
+}
+
+/// A `SensorUpdate` tagged with the device that produced it
+///
+/// Sources send plain `SensorUpdate`s; the connection manager adds the tag so
+/// the UI can route updates when several devices stream at once.
+#[derive(Debug)]
+pub struct DeviceUpdate {
+    pub device_id: String,
+    pub update: SensorUpdate,
 }
 
 #[derive(Debug, Clone)]
 pub enum ConnectionStatus {
     Connecting,
     Connected,
+    /// Link lost unexpectedly; the source is retrying the same device
+    Reconnecting { attempt: u32 },
     Disconnected,
     Error(String),
 }
@@ -59,48 +90,77 @@ impl Handler {
     }
 }
 
-// Send data to UI thread
+impl From<&arctic::HeartRate> for HeartRateSample {
+    fn from(hr: &arctic::HeartRate) -> Self {
+        HeartRateSample::new((*hr.bpm()).into(), hr.rr().clone().unwrap_or_default())
+    }
+}
+
+impl From<&arctic::PmdRead> for MeasurementBatch {
+    fn from(data: &arctic::PmdRead) -> Self {
+        let samples = data
+            .data()
+            .iter()
+            .map(|d| match d {
+                arctic::PmdData::Ecg(ecg) => MeasurementSample::Ecg(*ecg.val()),
+                arctic::PmdData::Acc(acc) => {
+                    let acc = acc.data();
+                    MeasurementSample::Acc { x: acc.0, y: acc.1, z: acc.2 }
+                }
+            })
+            .collect();
+        MeasurementBatch::new(samples).with_sensor_time(*data.time_stamp())
+    }
+}
+
+// Convert Arctic data to crate-owned samples and send to UI thread
 #[arctic::async_trait]
 impl arctic::EventHandler for Handler {
     async fn heart_rate_update(&self, _ctx: &arctic::PolarSensor, heartrate: arctic::HeartRate) {
-        if let Err(why) = self.sender.send(SensorUpdate::HeartRate(heartrate)) {
+        if let Err(why) = self.sender.send(SensorUpdate::HeartRate((&heartrate).into())) {
             log::error!("Could not send heart rate data: {:?}", why);
         }
     }
 
     async fn measurement_update(&self, _ctx: &arctic::PolarSensor, data: arctic::PmdRead) {
-        if let Err(why) = self.sender.send(SensorUpdate::MeasurementData(data)) {
+        if let Err(why) = self.sender.send(SensorUpdate::MeasurementData((&data).into())) {
             log::error!("Could not send measurement data: {:?}", why);
         }
     }
 }
 
+/// Connect to the sensor, retrying until connected or stopped
+///
+/// `max_attempts` bounds the retries for reconnects, where the caller backs
+/// off between attempts; `None` retries until the user gives up. If no
+/// connection is made, the error is how the session ended.
 async fn connect_to_device(
     polar: &mut PolarSensor,
     handler: &Handler,
     should_stop: &Arc<AtomicBool>,
-) -> Result<(), crate::error::ConnectionError> {
+    max_attempts: Option<u32>,
+) -> Result<(), SessionEnd> {
     log::info!("Connecting...");
+    let mut attempts = 0;
     while !polar.is_connected().await {
         if should_stop.load(Ordering::Relaxed) {
-            let _ = handler.sender.send(SensorUpdate::ConnectionStatus(ConnectionStatus::Disconnected));
-            return Err(crate::error::ConnectionError::Interrupted);
+            return Err(SessionEnd::Stopped);
+        }
+        if max_attempts.is_some_and(|max| attempts >= max) {
+            return Err(SessionEnd::LinkLost { streamed: false });
         }
+        attempts += 1;
         
         log::debug!("Connection attempt...");
         match polar.connect().await {
             Err(arctic::Error::NoBleAdaptor) => {
                 log::error!("No bluetooth adapter found");
-                let _ = handler.sender.send(SensorUpdate::ConnectionStatus(
-                    ConnectionStatus::Error("No bluetooth adapter found".to_string())
-                ));
-                return Err(crate::error::ConnectionError::NoAdapter);
+                return Err(SessionEnd::Failed(ConnectionError::NoAdapter));
             }
             Err(why) => {
                 log::warn!("Could not connect: {:?}", why);
                 if should_stop.load(Ordering::Relaxed) {
-                    let _ = handler.sender.send(SensorUpdate::ConnectionStatus(ConnectionStatus::Disconnected));
-                    return Err(crate::error::ConnectionError::Interrupted);
+                    return Err(SessionEnd::Stopped);
                 }
             }
             _ => {}
@@ -111,56 +171,71 @@ async fn connect_to_device(
     Ok(())
 }
 
-async fn subscribe_to_streams(polar: &mut PolarSensor) {
+async fn subscribe_to_streams(polar: &mut PolarSensor, profile: &DeviceProfile) {
     if let Err(why) = polar.subscribe(arctic::NotifyStream::HeartRate).await {
         log::error!("Could not subscribe to heart rate notifications: {:?}", why)
     }
+    // HR-only models like the H9 have no measurement service
+    if !profile.has_measurement_streams() {
+        return;
+    }
     if let Err(why) = polar.subscribe(arctic::NotifyStream::MeasurementData).await {
         log::error!("Could not subscribe to measurement data notifications: {:?}", why)
     }
 }
 
-fn setup_data_types(polar: &mut PolarSensor) {
-    polar.data_type_push(arctic::H10MeasurementType::Acc);
-    polar.data_type_push(arctic::H10MeasurementType::Ecg);
+/// Arctic measurement type delivering a PMD stream
+fn measurement_type(stream: SensorStream) -> Option<arctic::H10MeasurementType> {
+    match stream {
+        SensorStream::Ecg => Some(arctic::H10MeasurementType::Ecg),
+        SensorStream::Acc => Some(arctic::H10MeasurementType::Acc),
+        _ => None,
+    }
+}
+
+fn setup_data_types(polar: &mut PolarSensor, profile: &DeviceProfile) {
+    for measurement_type in profile.streams().iter().filter_map(|&stream| measurement_type(stream)) {
+        polar.data_type_push(measurement_type);
+    }
 }
 
-async fn configure_sample_rates(polar: &mut PolarSensor) -> (u64, u64) {
-    let mut configured_ecg_rate = 130u64; // default
-    let mut configured_acc_rate = 200u64; // default
-    
+/// Set the maximum sample rate the sensor offers for each stream of the
+/// profile, returning the rate of every periodic stream
+///
+/// Streams the sensor lists no settings for keep the model's default rate.
+async fn configure_sample_rates(polar: &mut PolarSensor, profile: &DeviceProfile) -> Vec<(SensorStream, u64)> {
+    let mut rates: Vec<(SensorStream, u64)> = profile
+        .streams()
+        .iter()
+        .filter(|stream| stream.is_periodic())
+        .map(|&stream| (stream, profile.default_sample_rate(stream)))
+        .collect();
+
     match polar.settings().await {
         Ok(settings) => {
             for setting in settings {
-                match setting.measurement_type() {
-                    arctic::H10MeasurementType::Ecg => {
-                        let sample_rates = setting.sample_rate();
-                        if !sample_rates.is_empty() {
-                            if let Some(&max_rate) = sample_rates.iter().max() {
-                                log::debug!("Available ECG sample rates: {:?}", sample_rates);
-                                log::info!("Setting ECG sample rate to maximum: {} Hz", max_rate);
-                                if let Err(e) = polar.ecg_sample_rate(max_rate) {
-                                    log::error!("Failed to set ECG sample rate: {:?}", e);
-                                } else {
-                                    configured_ecg_rate = max_rate as u64;
-                                }
-                            }
-                        }
-                    }
-                    arctic::H10MeasurementType::Acc => {
-                        let sample_rates = setting.sample_rate();
-                        if !sample_rates.is_empty() {
-                            if let Some(&max_rate) = sample_rates.iter().max() {
-                                log::debug!("Available ACC sample rates: {:?}", sample_rates);
-                                log::info!("Setting ACC sample rate to maximum: {} Hz", max_rate);
-                                if let Err(e) = polar.acc_sample_rate(max_rate) {
-                                    log::error!("Failed to set ACC sample rate: {:?}", e);
-                                } else {
-                                    configured_acc_rate = max_rate as u64;
-                                }
-                            }
-                        }
-                    }
+                let stream = match setting.measurement_type() {
+                    arctic::H10MeasurementType::Ecg => SensorStream::Ecg,
+                    arctic::H10MeasurementType::Acc => SensorStream::Acc,
+                };
+                // Only configure what the model offers
+                let Some((_, rate)) = rates.iter_mut().find(|(s, _)| *s == stream) else {
+                    log::debug!("Ignoring {} settings, not offered by the {}", stream.display_name(), profile.model);
+                    continue;
+                };
+                let sample_rates = setting.sample_rate();
+                let Some(&max_rate) = sample_rates.iter().max() else {
+                    continue;
+                };
+                log::debug!("Available {} sample rates: {:?}", stream.display_name(), sample_rates);
+                log::info!("Setting {} sample rate to maximum: {} Hz", stream.display_name(), max_rate);
+                let result = match stream {
+                    SensorStream::Ecg => polar.ecg_sample_rate(max_rate),
+                    _ => polar.acc_sample_rate(max_rate),
+                };
+                match result {
+                    Ok(_) => *rate = max_rate as u64,
+                    Err(e) => log::error!("Failed to set {} sample rate: {:?}", stream.display_name(), e),
                 }
             }
         }
@@ -168,15 +243,15 @@ async fn configure_sample_rates(polar: &mut PolarSensor) -> (u64, u64) {
             log::error!("Could not query device settings: {:?}", e);
         }
     }
-    
-    (configured_ecg_rate, configured_acc_rate)
+
+    rates
 }
 
+/// Stream events until the link drops or the stop flag is set
 async fn run_event_loop(
     polar: &PolarSensor,
-    handler: &Handler,
     should_stop: &Arc<AtomicBool>,
-) {
+) -> SessionEnd {
     tokio::select! {
         result = polar.event_loop() => {
             // Log the error but handle gracefully
@@ -189,7 +264,11 @@ async fn run_event_loop(
                     log::info!("Event loop completed normally");
                 }
             }
-            let _ = handler.sender.send(SensorUpdate::ConnectionStatus(ConnectionStatus::Disconnected));
+            if should_stop.load(Ordering::Relaxed) {
+                SessionEnd::Stopped
+            } else {
+                SessionEnd::LinkLost { streamed: true }
+            }
         }
         _ = async {
             loop {
@@ -199,42 +278,115 @@ async fn run_event_loop(
                 }
                 tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
             }
-        } => {
-            let _ = handler.sender.send(SensorUpdate::ConnectionStatus(ConnectionStatus::Disconnected));
-        }
+        } => SessionEnd::Stopped,
     }
 }
 
+/// Run one connection session: connect, configure streams and forward events
+///
+/// On a reconnect the connection is tried once, since `run_with_reconnect()`
+/// handles the retries and backoff.
 pub async fn start_data_collection(
     polar: Arc<RwLock<PolarSensor>>,
     handler: Handler,
+    profile: DeviceProfile,
     should_stop: Arc<AtomicBool>,
-) {
-    log::info!("Attempting connection");
-    let _ = handler.sender.send(SensorUpdate::ConnectionStatus(ConnectionStatus::Connecting));
+    reconnecting: bool,
+) -> SessionEnd {
+    if !reconnecting {
+        log::info!("Attempting connection");
+        let _ = handler.sender.send(SensorUpdate::ConnectionStatus(ConnectionStatus::Connecting));
+    }
 
     {
         let mut polar = polar.write().await;
 
-        if connect_to_device(&mut polar, &handler, &should_stop).await.is_err() {
-            return;
+        let max_attempts = reconnecting.then_some(1);
+        if let Err(end) = connect_to_device(&mut polar, &handler, &should_stop, max_attempts).await {
+            return end;
         }
 
-        subscribe_to_streams(&mut polar).await;
-        setup_data_types(&mut polar);
+        let _ = handler.sender.send(SensorUpdate::DeviceProfile(profile.clone()));
 
-        let (ecg_rate, acc_rate) = configure_sample_rates(&mut polar).await;
+        subscribe_to_streams(&mut polar, &profile).await;
+        setup_data_types(&mut polar, &profile);
 
-        // Send the configured sample rates to the UI thread
-        let _ = handler.sender.send(SensorUpdate::SampleRateConfig {
-            ecg_rate,
-            acc_rate,
-        });
+        if profile.has_measurement_streams() {
+            let rates = configure_sample_rates(&mut polar, &profile).await;
+
+            // Send the configured sample rates to the UI thread
+            let _ = handler.sender.send(SensorUpdate::SampleRateConfig { rates });
+        }
 
         polar.event_handler(handler.clone());
     }
 
     // Run event loop with cancellation check
     let polar_guard = polar.read().await;
-    run_event_loop(&polar_guard, &handler, &should_stop).await;
+    run_event_loop(&polar_guard, &should_stop).await
+}
+
+/// Data source streaming from a Polar sensor over Bluetooth
+pub struct PolarSource {
+    device_id: String,
+    model: DeviceModel,
+}
+
+impl PolarSource {
+    pub fn new(device_id: String, model: DeviceModel) -> Self {
+        Self { device_id, model }
+    }
+}
+
+impl DataSource for PolarSource {
+    fn name(&self) -> String {
+        format!("{} {}", self.model, self.device_id)
+    }
+
+    fn start(self: Box<Self>, sender: Sender<SensorUpdate>, should_stop: Arc<AtomicBool>) -> SourceTask {
+        Box::pin(async move {
+            let profile = DeviceProfile::for_model(self.model);
+
+            // A fresh sensor per session, so a dropped link leaves no stale state behind
+            run_with_reconnect(&sender, &should_stop, |reconnecting| {
+                let device_id = self.device_id.clone();
+                let handler = Handler::new(sender.clone());
+                let profile = profile.clone();
+                let should_stop = should_stop.clone();
+                async move {
+                    match PolarSensor::new(device_id.clone()).await {
+                        Ok(sensor) => {
+                            let polar = Arc::new(RwLock::new(sensor));
+                            start_data_collection(polar, handler, profile, should_stop, reconnecting).await
+                        }
+                        Err(e) if reconnecting => {
+                            log::warn!("Could not recreate sensor {}: {:?}", device_id, e);
+                            SessionEnd::LinkLost { streamed: false }
+                        }
+                        Err(e) => SessionEnd::Failed(ConnectionError::DeviceConnection {
+                            device_id,
+                            reason: format!("{:?}", e),
+                        }),
+                    }
+                }
+            })
+            .await;
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measurement_types_cover_arctic_models() {
+        // Every measurement stream of a model using this backend maps to an Arctic type
+        for model in [DeviceModel::H10, DeviceModel::H9, DeviceModel::Other] {
+            let profile = DeviceProfile::for_model(model);
+            for &stream in profile.streams().iter().filter(|&&s| s != SensorStream::HeartRate) {
+                assert!(measurement_type(stream).is_some(), "{:?} of the {}", stream, model);
+            }
+        }
+    }
 }
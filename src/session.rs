@@ -0,0 +1,352 @@
+//! # Device Session Module
+//!
+//! State of one connecting or connected device: its connection state, the
+//! data channels filled from its updates, HRV metrics, stress estimate and the
+//! artifact share of its latest beats, and the device information stored with
+//! recordings.
+//!
+//! ## Why
+//! The window and the headless recorder receive the same `SensorUpdate`s.
+//! Applying them in one place keeps gaps, sample rates and recording metadata
+//! identical no matter which front end made a recording.
+
+use crate::artifacts::ArtifactCorrection;
+use crate::filters::SignalFilters;
+use crate::hrv::{self, HrvMetrics};
+use crate::polar_data::{Channels, DERIVED_RETENTION};
+use crate::recorder::{DeviceMetadata, DeviceRecorder};
+use crate::sensor::SensorUpdate;
+use crate::stress::{StressBaseline, StressEstimate};
+use crate::timeseries::TimeSeries;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionState {
+    Connecting,
+    Connected,
+    /// Link lost; retrying with the data received so far kept
+    Reconnecting(u32),
+}
+
+impl ConnectionState {
+    pub fn label(&self) -> String {
+        match self {
+            ConnectionState::Connecting => "Connecting...".to_string(),
+            ConnectionState::Connected => "Connected".to_string(),
+            ConnectionState::Reconnecting(attempt) => format!("Reconnecting (attempt {})", attempt),
+        }
+    }
+}
+
+/// A connecting or connected device and the data received from it
+pub struct DeviceSession {
+    pub id: String,
+    pub name: String,
+    pub state: ConnectionState,
+    pub channels: Channels,
+    pub firmware: Option<String>,
+    /// HRV of the NN intervals within the analysis window, once there are enough
+    pub hrv: Option<HrvMetrics>,
+    /// Percentage of beats within the analysis window that were artifacts
+    pub artifact_percentage: Option<f64>,
+    /// Stress of the NN intervals within the analysis window
+    pub stress: Option<StressEstimate>,
+    /// Stress index after each beat, for the trend chart
+    pub stress_trend: TimeSeries,
+    hrv_window_ns: u64,
+    /// Whether HRV and stress only use beats from still epochs
+    hrv_still_only: bool,
+    artifact_correction: ArtifactCorrection,
+    filters: SignalFilters,
+    stress_baseline: Option<StressBaseline>,
+    /// Time of the last HRV analysis, and whether beats arrived since
+    hrv_updated: Option<u64>,
+    hrv_pending: bool,
+}
+
+// Analysis window until the configured one is applied
+const DEFAULT_HRV_WINDOW_SECONDS: u64 = 300;
+
+// Shortest time between HRV analyses of new beats; sample entropy and the
+// spectrum of a long window are too costly to recompute on every beat
+const HRV_UPDATE_INTERVAL_NS: u64 = 1_000_000_000;
+
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+
+impl DeviceSession {
+    pub fn new(id: String, name: String) -> Self {
+        Self {
+            id,
+            name,
+            state: ConnectionState::Connecting,
+            channels: Channels::new(),
+            firmware: None,
+            hrv: None,
+            artifact_percentage: None,
+            stress: None,
+            stress_trend: TimeSeries::new(1).with_retention(DERIVED_RETENTION),
+            hrv_window_ns: DEFAULT_HRV_WINDOW_SECONDS * NANOS_PER_SECOND,
+            hrv_still_only: false,
+            artifact_correction: ArtifactCorrection::default(),
+            filters: SignalFilters::default(),
+            stress_baseline: None,
+            hrv_updated: None,
+            hrv_pending: false,
+        }
+    }
+
+    /// Score relaxation against a resting baseline
+    pub fn with_stress_baseline(mut self, baseline: Option<StressBaseline>) -> Self {
+        self.set_stress_baseline(baseline);
+        self
+    }
+
+    /// Change the resting baseline and recompute the stress estimate
+    pub fn set_stress_baseline(&mut self, baseline: Option<StressBaseline>) {
+        self.stress_baseline = baseline;
+        self.update_hrv();
+    }
+
+    /// Filter ECG and PPG with the given settings
+    pub fn with_filters(mut self, filters: SignalFilters) -> Self {
+        self.set_filters(filters);
+        self
+    }
+
+    /// Change the ECG and PPG filters for new samples
+    pub fn set_filters(&mut self, filters: SignalFilters) {
+        self.filters = filters;
+        self.channels.set_filters(filters);
+    }
+
+    /// Drop all data received so far, keeping the device profile and settings
+    pub fn clear_data(&mut self) {
+        self.channels = Channels::with_profile(self.channels.profile.clone());
+        self.channels.set_artifact_correction(self.artifact_correction);
+        self.channels.set_filters(self.filters);
+        self.channels.set_hrv_still_only(self.hrv_still_only);
+        self.stress_trend = TimeSeries::new(1).with_retention(DERIVED_RETENTION);
+        self.update_hrv();
+    }
+
+    /// Correct beat artifacts with the given method
+    pub fn with_artifact_correction(mut self, correction: ArtifactCorrection) -> Self {
+        self.set_artifact_correction(correction);
+        self
+    }
+
+    /// Change how artifacts in new beats are corrected
+    pub fn set_artifact_correction(&mut self, correction: ArtifactCorrection) {
+        self.artifact_correction = correction;
+        self.channels.set_artifact_correction(correction);
+    }
+
+    /// Use an HRV analysis window of the given length
+    pub fn with_hrv_window(mut self, seconds: u64) -> Self {
+        self.set_hrv_window(seconds);
+        self
+    }
+
+    /// Change the HRV analysis window and recompute the metrics
+    pub fn set_hrv_window(&mut self, seconds: u64) {
+        self.hrv_window_ns = seconds * NANOS_PER_SECOND;
+        self.update_hrv();
+    }
+
+    /// Only analyse beats while the wearer is still
+    pub fn with_hrv_still_only(mut self, still_only: bool) -> Self {
+        self.set_hrv_still_only(still_only);
+        self
+    }
+
+    /// Change whether beats while moving are left out and recompute the metrics
+    pub fn set_hrv_still_only(&mut self, still_only: bool) {
+        self.hrv_still_only = still_only;
+        self.channels.set_hrv_still_only(still_only);
+        self.update_hrv();
+    }
+
+    fn update_hrv(&mut self) {
+        let channels = &self.channels;
+        let window = channels.nn.last_duration(self.hrv_window_ns);
+        let runs = if self.hrv_still_only { channels.still_runs(window) } else { vec![window] };
+        let nn: Vec<f64> = runs.iter().flat_map(|run| run.iter().map(|p| p.value as f64)).collect();
+        self.hrv = hrv::analyze_runs(&runs);
+        self.stress = StressEstimate::from_nn(&nn, self.stress_baseline.as_ref());
+
+        // At most one trend point per beat
+        let last_beat = channels.nn.last_point().map(|p| p.time);
+        let last_trend = self.stress_trend.last_point().map(|p| p.time);
+        if let (Some(stress), Some(time)) = (self.stress, last_beat) {
+            if !last_trend.is_some_and(|t| t >= time) {
+                self.stress_trend.add_point(time, stress.stress_index.round() as i32);
+            }
+        }
+
+        let beats = channels.beats.last_duration(self.hrv_window_ns);
+        self.artifact_percentage = beats.first().map(|first| {
+            let start = channels.rr_artifacts.partition_point_time(first.time);
+            let artifacts = channels.rr_artifacts.len() - start;
+            artifacts as f64 / beats.len() as f64 * 100.0
+        });
+    }
+
+    /// Device description stored in recordings
+    pub fn metadata(&self) -> DeviceMetadata {
+        DeviceMetadata {
+            name: self.name.clone(),
+            model: self.channels.profile.model.display_name().to_string(),
+            firmware: self.firmware.clone(),
+            sample_rates: self.channels.sample_rates(),
+        }
+    }
+
+    /// Whether the device has streamed data, including while it reconnects
+    pub fn is_connected(&self) -> bool {
+        self.state != ConnectionState::Connecting
+    }
+
+    /// The link is up; after a link loss the data is kept and the gap closed
+    pub fn mark_connected(&mut self, now: u64, recorder: &DeviceRecorder) {
+        self.state = ConnectionState::Connected;
+        if self.channels.in_gap() {
+            self.channels.end_gap(now, Some(recorder));
+        }
+    }
+
+    /// The link was lost and the source is retrying
+    pub fn mark_reconnecting(&mut self, attempt: u32, now: u64) {
+        self.state = ConnectionState::Reconnecting(attempt);
+        self.channels.begin_gap(now);
+    }
+
+    /// Apply a data update from the device
+    ///
+    /// `recorder` is `None` for data that must not end up in a recording,
+    /// such as replayed data. Connection and playback status are left to the
+    /// caller.
+    pub fn handle_update(&mut self, update: SensorUpdate, now: u64, recorder: Option<&DeviceRecorder>) {
+        let metadata_changed = matches!(
+            update,
+            SensorUpdate::SampleRateConfig { .. } | SensorUpdate::DeviceProfile(_) | SensorUpdate::Firmware(_)
+        );
+        let beats = self.channels.beats.len();
+        let channels = &mut self.channels;
+
+        match update {
+            SensorUpdate::HeartRate(hr) => {
+                channels.handle_heart_rate(hr, now, recorder);
+            }
+            SensorUpdate::MeasurementData(data) => {
+                channels.handle_measurement_data(data, now, recorder);
+            }
+            SensorUpdate::SampleRateConfig { rates } => {
+                log::info!("Updating sample rates for {}: {:?}", self.id, rates);
+                for (stream, rate) in rates {
+                    channels.set_sample_rate(stream, rate);
+                }
+            }
+            SensorUpdate::DeviceProfile(profile) => {
+                log::info!("Device profile for {}: {} {:?}", self.id, profile.model, profile.streams());
+                // Sent again after a reconnect, when the existing data must stay
+                if channels.profile != profile {
+                    *channels = Channels::with_profile(profile);
+                    channels.set_artifact_correction(self.artifact_correction);
+                    channels.set_filters(self.filters);
+                    channels.set_hrv_still_only(self.hrv_still_only);
+                }
+            }
+            SensorUpdate::Firmware(firmware) => {
+                log::info!("Firmware of {}: {}", self.id, firmware);
+                self.firmware = Some(firmware);
+            }
+            SensorUpdate::ConnectionStatus(_) | SensorUpdate::PlaybackStatus(_) => {}
+        }
+
+        if self.channels.beats.len() != beats {
+            self.hrv_pending = true;
+        }
+        if self.hrv_pending && !self.hrv_updated.is_some_and(|t| now < t + HRV_UPDATE_INTERVAL_NS) {
+            self.hrv_pending = false;
+            self.hrv_updated = Some(now);
+            self.update_hrv();
+        }
+
+        if metadata_changed {
+            if let Some(recorder) = recorder {
+                if let Err(e) = recorder.set_metadata(self.metadata()) {
+                    log::error!("Failed to update device metadata: {}", e);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device_profile::{DeviceModel, DeviceProfile, SensorStream};
+    use crate::samples::HeartRateSample;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    #[test]
+    fn test_profile_and_rates_shape_metadata() {
+        let mut session = DeviceSession::new("A".to_string(), "Polar Sense 1".to_string());
+        let profile = DeviceProfile::for_model(DeviceModel::VeritySense);
+        session.handle_update(SensorUpdate::DeviceProfile(profile), 0, None);
+        session.handle_update(SensorUpdate::SampleRateConfig { rates: vec![(SensorStream::Acc, 52)] }, 0, None);
+        session.handle_update(SensorUpdate::Firmware("2.1.0".to_string()), 0, None);
+
+        let metadata = session.metadata();
+        assert_eq!(metadata.model, DeviceModel::VeritySense.display_name());
+        assert_eq!(metadata.firmware.as_deref(), Some("2.1.0"));
+        assert!(metadata.sample_rates.contains(&(crate::recorder::ChannelId::AccX, 52)));
+    }
+
+    #[test]
+    fn test_reconnect_keeps_data() {
+        let recorder = crate::recorder::PolarDataManager::new(30, 10).unwrap();
+        let mut session = DeviceSession::new("A".to_string(), "Polar H10 1".to_string());
+        session.mark_connected(0, &recorder.for_device("A"));
+        session.handle_update(SensorUpdate::HeartRate(HeartRateSample::new(60, Vec::new())), SECOND, None);
+
+        session.mark_reconnecting(1, 2 * SECOND);
+        assert_eq!(session.state.label(), "Reconnecting (attempt 1)");
+        assert!(session.is_connected());
+
+        session.mark_connected(5 * SECOND, &recorder.for_device("A"));
+        assert_eq!(session.state, ConnectionState::Connected);
+        assert_eq!(session.channels.hr.len(), 1);
+        assert_eq!(session.channels.gaps.len(), 1);
+    }
+
+    #[test]
+    fn test_hrv_follows_beats() {
+        let mut session = DeviceSession::new("A".to_string(), "Polar H10 1".to_string()).with_hrv_window(60);
+        for i in 0..5u64 {
+            let rr = if i % 2 == 0 { 1000 } else { 1100 };
+            session.handle_update(SensorUpdate::HeartRate(HeartRateSample::new(57, vec![rr])), (i + 1) * SECOND, None);
+        }
+        assert_eq!(session.hrv.unwrap().beats, 5);
+
+        // Notifications without intervals repeat the last RR for display only
+        session.handle_update(SensorUpdate::HeartRate(HeartRateSample::new(57, Vec::new())), 6 * SECOND, None);
+        assert_eq!(session.hrv.unwrap().beats, 5);
+        assert_eq!(session.hrv.unwrap().rmssd, 100.0);
+    }
+
+    #[test]
+    fn test_hrv_analysis_throttled() {
+        let mut session = DeviceSession::new("A".to_string(), "Polar H10 1".to_string()).with_hrv_window(60);
+        let beat = |rr| SensorUpdate::HeartRate(HeartRateSample::new(60, vec![rr]));
+        session.handle_update(beat(1000), SECOND, None);
+        assert_eq!(session.hrv.map(|m| m.beats), None);
+
+        // Beats within a second of the last analysis wait for the next update after it
+        session.handle_update(beat(1000), SECOND + SECOND / 4, None);
+        session.handle_update(beat(1100), SECOND + SECOND / 2, None);
+        assert_eq!(session.hrv.map(|m| m.beats), None);
+        session.handle_update(SensorUpdate::HeartRate(HeartRateSample::new(60, Vec::new())), 2 * SECOND, None);
+        assert_eq!(session.hrv.unwrap().beats, 3);
+    }
+}
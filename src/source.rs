@@ -0,0 +1,256 @@
+//! # Data Source Module
+//!
+//! Common interface for everything that can feed sensor data to the UI.
+//!
+//! ## Key Types
+//! - `DataSource`: Trait implemented by each backend (Polar sensor, synthetic
+//!   generator, recording playback)
+//! - `SourceTask`: Boxed future driving a source until it stops
+//! - `SessionEnd`: How one connection of a Bluetooth source ended
+//! - `run_with_reconnect()`: Reconnect loop with exponential `Backoff`
+//!
+//! ## Contract
+//! A source reports its lifecycle through `SensorUpdate::ConnectionStatus`
+//! (Connecting, Connected, then Disconnected or Error), announces its sample
+//! rates with `SensorUpdate::SampleRateConfig`, and streams crate-owned
+//! samples (`HeartRateSample`, `MeasurementBatch`) until the stop flag is set.
+//!
+//! ## Reconnection
+//! Only the stop flag means the user wants the device gone. Bluetooth sources
+//! run their sessions through `run_with_reconnect()`, which reports
+//! `Reconnecting { attempt }` after an unexpected link loss and retries the
+//! same device with exponential backoff until it is back or the user stops it.
+//!
+//! ## Why a Trait
+//! The connection thread only needs to start a source and stop it later. Hiding
+//! backend details behind one trait keeps `ConnectionManager` independent of
+//! Arctic and lets tests drive `Channels` with sources of their own.
+
+use crate::error::ConnectionError;
+use crate::sensor::{ConnectionStatus, SensorUpdate};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::time::Duration;
+
+// Delay before the first reconnect attempt
+const BACKOFF_INITIAL: Duration = Duration::from_secs(1);
+
+// Upper bound for the delay between reconnect attempts
+const BACKOFF_MAX: Duration = Duration::from_secs(30);
+
+// Interval between stop flag checks while waiting to reconnect
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Future running a data source on the connection thread's runtime
+pub type SourceTask = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+/// Backend producing sensor updates
+pub trait DataSource: Send {
+    /// Short description for logging
+    fn name(&self) -> String;
+
+    /// Build the task streaming updates over `sender` until `should_stop` is set
+    fn start(self: Box<Self>, sender: Sender<SensorUpdate>, should_stop: Arc<AtomicBool>) -> SourceTask;
+}
+
+/// How one connection session of a Bluetooth source ended
+#[derive(Debug)]
+pub enum SessionEnd {
+    /// The stop flag was set, i.e. the user disconnected
+    Stopped,
+    /// The link dropped or the device could not be reached
+    LinkLost {
+        /// Whether data was streaming before the loss
+        streamed: bool,
+    },
+    /// Failure that retrying cannot fix, e.g. no Bluetooth adapter
+    Failed(ConnectionError),
+}
+
+/// Exponential backoff between reconnect attempts (1 s, 2 s, 4 s, ... 30 s)
+#[derive(Debug, Default)]
+pub struct Backoff {
+    attempt: u32,
+}
+
+impl Backoff {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of attempts handed out since the last reset
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// Start the next attempt and return how long to wait before it
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = BACKOFF_INITIAL
+            .checked_mul(1 << self.attempt.min(16))
+            .map_or(BACKOFF_MAX, |d| d.min(BACKOFF_MAX));
+        self.attempt += 1;
+        delay
+    }
+
+    /// Start over after a successful connection
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+}
+
+/// Sleep for `delay`, returning false early if the stop flag is set
+async fn sleep_unless_stopped(delay: Duration, should_stop: &AtomicBool) -> bool {
+    let deadline = tokio::time::Instant::now() + delay;
+    while tokio::time::Instant::now() < deadline {
+        if should_stop.load(Ordering::Relaxed) {
+            return false;
+        }
+        tokio::time::sleep(STOP_POLL_INTERVAL.min(deadline - tokio::time::Instant::now())).await;
+    }
+    !should_stop.load(Ordering::Relaxed)
+}
+
+/// Run connection sessions until the user stops the source
+///
+/// `session` is called with `false` for the first connection and `true` for
+/// reconnects. After a link loss the device is retried with exponential
+/// backoff, reporting `Reconnecting { attempt }` before each wait. Sends the
+/// final `Disconnected` or `Error` status.
+pub async fn run_with_reconnect<F, Fut>(sender: &Sender<SensorUpdate>, should_stop: &AtomicBool, mut session: F)
+where
+    F: FnMut(bool) -> Fut,
+    Fut: Future<Output = SessionEnd>,
+{
+    let mut backoff = Backoff::new();
+    let mut reconnecting = false;
+
+    let status = loop {
+        match session(reconnecting).await {
+            SessionEnd::Stopped => break ConnectionStatus::Disconnected,
+            SessionEnd::Failed(ConnectionError::Interrupted) => break ConnectionStatus::Disconnected,
+            SessionEnd::Failed(e) => {
+                log::error!("{}", e);
+                break ConnectionStatus::Error(e.to_string());
+            }
+            SessionEnd::LinkLost { streamed } => {
+                if streamed {
+                    backoff.reset();
+                }
+                let delay = backoff.next_delay();
+                log::warn!(
+                    "Link lost, reconnect attempt {} in {:.0}s",
+                    backoff.attempt(),
+                    delay.as_secs_f64()
+                );
+                let _ = sender.send(SensorUpdate::ConnectionStatus(ConnectionStatus::Reconnecting {
+                    attempt: backoff.attempt(),
+                }));
+
+                if !sleep_unless_stopped(delay, should_stop).await {
+                    break ConnectionStatus::Disconnected;
+                }
+                reconnecting = true;
+            }
+        }
+    };
+
+    let _ = sender.send(SensorUpdate::ConnectionStatus(status));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::polar_data::Channels;
+    use crate::samples::{HeartRateSample, MeasurementBatch, MeasurementSample};
+    use crate::sensor::ConnectionStatus;
+    use std::sync::mpsc;
+
+    /// Source that emits a fixed list of updates and then disconnects
+    struct ScriptedSource {
+        updates: Vec<SensorUpdate>,
+    }
+
+    impl DataSource for ScriptedSource {
+        fn name(&self) -> String {
+            "scripted".to_string()
+        }
+
+        fn start(self: Box<Self>, sender: Sender<SensorUpdate>, _should_stop: Arc<AtomicBool>) -> SourceTask {
+            Box::pin(async move {
+                for update in self.updates {
+                    let _ = sender.send(update);
+                }
+                let _ = sender.send(SensorUpdate::ConnectionStatus(ConnectionStatus::Disconnected));
+            })
+        }
+    }
+
+    #[test]
+    fn test_custom_source_feeds_channels() {
+        let source: Box<dyn DataSource> = Box::new(ScriptedSource {
+            updates: vec![
+                SensorUpdate::HeartRate(HeartRateSample::new(72, vec![830])),
+                SensorUpdate::MeasurementData(MeasurementBatch::new(vec![
+                    MeasurementSample::Ecg(100),
+                    MeasurementSample::Ecg(200),
+                ])),
+            ],
+        });
+        assert_eq!(source.name(), "scripted");
+
+        let (sender, receiver) = mpsc::channel();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(source.start(sender, Arc::new(AtomicBool::new(false))));
+
+        let mut channels = Channels::new();
+        let now = 10_000_000_000;
+        for update in receiver.try_iter() {
+            match update {
+                SensorUpdate::HeartRate(hr) => channels.handle_heart_rate(hr, now, None),
+                SensorUpdate::MeasurementData(data) => channels.handle_measurement_data(data, now, None),
+                _ => {}
+            }
+        }
+
+        assert_eq!(channels.hr.len(), 1);
+        assert_eq!(channels.rr.len(), 1);
+        assert_eq!(channels.ecg.len(), 2);
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let mut backoff = Backoff::new();
+        let delays: Vec<u64> = (0..7).map(|_| backoff.next_delay().as_secs()).collect();
+
+        assert_eq!(delays, vec![1, 2, 4, 8, 16, 30, 30]);
+        assert_eq!(backoff.attempt(), 7);
+
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), BACKOFF_INITIAL);
+    }
+
+    #[test]
+    fn test_reconnect_after_link_loss() {
+        let (sender, receiver) = mpsc::channel();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let should_stop = AtomicBool::new(false);
+
+        // Lose the link once, then end as if the user disconnected
+        let mut sessions = vec![SessionEnd::Stopped, SessionEnd::LinkLost { streamed: true }];
+        let mut reconnect_flags = Vec::new();
+        runtime.block_on(run_with_reconnect(&sender, &should_stop, |reconnecting| {
+            reconnect_flags.push(reconnecting);
+            let end = sessions.pop().unwrap();
+            async move { end }
+        }));
+
+        assert_eq!(reconnect_flags, vec![false, true]);
+        let statuses: Vec<String> = receiver.try_iter().map(|u| format!("{:?}", u)).collect();
+        assert_eq!(statuses.len(), 2);
+        assert!(statuses[0].contains("Reconnecting { attempt: 1 }"));
+        assert!(statuses[1].contains("Disconnected"));
+    }
+}
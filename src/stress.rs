@@ -0,0 +1,265 @@
+//! # Stress Module
+//!
+//! Stress and relaxation estimates from NN intervals, and the resting
+//! baseline they are judged against.
+//!
+//! ## Stress Index
+//! Baevsky's stress index from the histogram of NN intervals in
+//! `BIN_WIDTH_MS` bins: SI = AMo / (2 · Mo · MxDMn), with the mode Mo and the
+//! range MxDMn in seconds and the mode amplitude AMo in percent. A narrow
+//! histogram (little variability, sympathetic dominance) gives a high index.
+//! Below `ELEVATED_STRESS_INDEX` counts as normal, above `HIGH_STRESS_INDEX`
+//! as high, following Baevsky's ranges.
+//!
+//! ## Relaxation Score
+//! Compares the current RMSSD with the RMSSD of a resting baseline on a log
+//! scale, since RMSSD is roughly log-normal: the baseline itself scores
+//! `BASELINE_SCORE`, and each halving or doubling of RMSSD moves the score
+//! by `SCORE_PER_DOUBLING`, clamped to 0-100. Without a baseline there is no
+//! score, as RMSSD differs too much between people to judge it alone.
+//!
+//! ## Calibration
+//! A `Calibration` collects `CALIBRATION_SECONDS` of beats while the user
+//! rests and turns their NN intervals into a `StressBaseline`, which is
+//! stored in `Config`.
+
+use crate::hrv;
+use crate::timeseries::Point;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Histogram bin width for the stress index
+const BIN_WIDTH_MS: f64 = 50.0;
+
+// Fewest intervals for a meaningful histogram
+const MIN_STRESS_BEATS: usize = 20;
+
+// Baevsky's upper limits of normal and elevated stress
+const ELEVATED_STRESS_INDEX: f64 = 150.0;
+const HIGH_STRESS_INDEX: f64 = 500.0;
+
+// Relaxation score of the resting baseline, and its change per RMSSD doubling
+const BASELINE_SCORE: f64 = 75.0;
+const SCORE_PER_DOUBLING: f64 = 35.0;
+
+/// Length of a baseline calibration
+pub const CALIBRATION_SECONDS: u64 = 180;
+
+// Fewest beats a calibration must collect
+const MIN_CALIBRATION_BEATS: usize = 60;
+
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+
+/// Baevsky's stress index of consecutive NN intervals in ms
+///
+/// `None` with fewer than `MIN_STRESS_BEATS` intervals or without any
+/// variability.
+pub fn stress_index(nn: &[f64]) -> Option<f64> {
+    if nn.len() < MIN_STRESS_BEATS {
+        return None;
+    }
+    let (min, max) = nn.iter().fold((f64::MAX, f64::MIN), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    if max <= min {
+        return None;
+    }
+
+    // Count per bin; bins start at multiples of the bin width
+    let first_bin = (min / BIN_WIDTH_MS).floor() as i64;
+    let mut counts = vec![0usize; ((max / BIN_WIDTH_MS).floor() as i64 - first_bin + 1) as usize];
+    for &interval in nn {
+        counts[((interval / BIN_WIDTH_MS).floor() as i64 - first_bin) as usize] += 1;
+    }
+    // The shortest of equally common intervals is the mode
+    let (modal_bin, &modal_count) = counts
+        .iter()
+        .enumerate()
+        .max_by_key(|&(i, count)| (count, std::cmp::Reverse(i)))?;
+
+    let mode_s = ((first_bin + modal_bin as i64) as f64 + 0.5) * BIN_WIDTH_MS / 1000.0;
+    let amplitude_pct = modal_count as f64 / nn.len() as f64 * 100.0;
+    let range_s = (max - min) / 1000.0;
+    Some(amplitude_pct / (2.0 * mode_s * range_s))
+}
+
+/// How stressed a stress index indicates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StressLevel {
+    Normal,
+    Elevated,
+    High,
+}
+
+impl StressLevel {
+    pub fn from_index(index: f64) -> Self {
+        if index < ELEVATED_STRESS_INDEX {
+            StressLevel::Normal
+        } else if index < HIGH_STRESS_INDEX {
+            StressLevel::Elevated
+        } else {
+            StressLevel::High
+        }
+    }
+}
+
+impl fmt::Display for StressLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            StressLevel::Normal => "normal",
+            StressLevel::Elevated => "elevated",
+            StressLevel::High => "high",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// HRV of a resting period, the reference for the relaxation score
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StressBaseline {
+    /// RMSSD at rest in ms
+    pub rmssd: f64,
+    /// Stress index at rest
+    pub stress_index: f64,
+    /// Mean heart rate at rest in bpm
+    pub mean_hr: f64,
+    /// When the baseline was recorded (RFC 3339)
+    pub recorded_at: String,
+}
+
+impl StressBaseline {
+    /// Relaxation score from 0 (far less variability than at rest) to 100
+    pub fn relaxation_score(&self, rmssd: f64) -> f64 {
+        if rmssd <= 0.0 || self.rmssd <= 0.0 {
+            return 0.0;
+        }
+        (BASELINE_SCORE + SCORE_PER_DOUBLING * (rmssd / self.rmssd).log2()).clamp(0.0, 100.0)
+    }
+}
+
+/// Stress estimate of the current analysis window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StressEstimate {
+    pub stress_index: f64,
+    pub level: StressLevel,
+    /// Relaxation score, if a baseline was recorded
+    pub relaxation: Option<f64>,
+}
+
+impl StressEstimate {
+    /// Estimate from NN intervals in ms, scoring relaxation against `baseline`
+    pub fn from_nn(nn: &[f64], baseline: Option<&StressBaseline>) -> Option<Self> {
+        let stress_index = stress_index(nn)?;
+        let rmssd = hrv::analyze(nn)?.rmssd;
+        Some(Self {
+            stress_index,
+            level: StressLevel::from_index(stress_index),
+            relaxation: baseline.map(|b| b.relaxation_score(rmssd)),
+        })
+    }
+}
+
+/// A resting baseline being recorded from one device
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calibration {
+    pub device_id: String,
+    /// Time the calibration started in nanoseconds
+    start: u64,
+}
+
+impl Calibration {
+    pub fn new(device_id: String, now: u64) -> Self {
+        Self { device_id, start: now }
+    }
+
+    /// Fraction of the calibration period elapsed, from 0 to 1
+    pub fn progress(&self, now: u64) -> f64 {
+        let elapsed = now.saturating_sub(self.start) as f64;
+        (elapsed / (CALIBRATION_SECONDS * NANOS_PER_SECOND) as f64).min(1.0)
+    }
+
+    pub fn is_complete(&self, now: u64) -> bool {
+        self.progress(now) >= 1.0
+    }
+
+    /// Baseline from the NN intervals received since the start
+    ///
+    /// `nn` may hold earlier intervals too; they are left out.
+    pub fn finish(&self, nn: &[Point], recorded_at: String) -> Result<StressBaseline, String> {
+        let start = nn.partition_point(|p| p.time < self.start);
+        let intervals: Vec<f64> = nn[start..].iter().map(|p| p.value as f64).collect();
+        if intervals.len() < MIN_CALIBRATION_BEATS {
+            return Err(format!(
+                "Only {} beats during calibration, at least {} are needed",
+                intervals.len(),
+                MIN_CALIBRATION_BEATS
+            ));
+        }
+
+        let metrics = hrv::analyze(&intervals).ok_or("Not enough beats for HRV")?;
+        let stress_index = stress_index(&intervals).ok_or("No heart rate variability during calibration")?;
+        Ok(StressBaseline {
+            rmssd: metrics.rmssd,
+            stress_index,
+            mean_hr: 60_000.0 / metrics.mean_nn,
+            recorded_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Intervals alternating around `mean` by ±`spread` ms
+    fn alternating(mean: f64, spread: f64, count: usize) -> Vec<f64> {
+        (0..count).map(|i| if i % 2 == 0 { mean - spread } else { mean + spread }).collect()
+    }
+
+    #[test]
+    fn test_stress_index() {
+        // 21 of 40 beats in the 1000-1050 ms bin, 980-1030 ms range:
+        // 52.5% / (2 * 1.025 s * 0.05 s)
+        let mut nn = alternating(1000.0, 20.0, 40);
+        nn[0] = 1030.0;
+        let index = stress_index(&nn).unwrap();
+        assert!((index - 52.5 / (2.0 * 1.025 * 0.05)).abs() < 1e-6, "{}", index);
+
+        // Less variability, more stress
+        let varied: Vec<f64> = (0..40).map(|i| 1000.0 + 100.0 * (i as f64 * 0.7).sin()).collect();
+        let calm = stress_index(&varied).unwrap();
+        let tense = stress_index(&alternating(700.0, 10.0, 40)).unwrap();
+        assert!(calm < tense);
+        assert_eq!(StressLevel::from_index(calm), StressLevel::Normal);
+        assert_eq!(StressLevel::from_index(tense), StressLevel::High);
+
+        assert_eq!(stress_index(&[1000.0; 40]), None);
+        assert_eq!(stress_index(&alternating(1000.0, 20.0, 10)), None);
+    }
+
+    #[test]
+    fn test_relaxation_score() {
+        let baseline = StressBaseline { rmssd: 40.0, stress_index: 100.0, mean_hr: 60.0, recorded_at: String::new() };
+        assert_eq!(baseline.relaxation_score(40.0), BASELINE_SCORE);
+        assert_eq!(baseline.relaxation_score(20.0), BASELINE_SCORE - SCORE_PER_DOUBLING);
+        assert_eq!(baseline.relaxation_score(400.0), 100.0);
+        assert_eq!(baseline.relaxation_score(0.0), 0.0);
+    }
+
+    #[test]
+    fn test_calibration() {
+        let second = NANOS_PER_SECOND;
+        let calibration = Calibration::new("A".to_string(), 10 * second);
+        assert_eq!(calibration.progress(10 * second + CALIBRATION_SECONDS * second / 2), 0.5);
+        assert!(calibration.is_complete((10 + CALIBRATION_SECONDS) * second));
+
+        // Beats before the start are left out
+        let nn: Vec<Point> = alternating(1000.0, 20.0, 200)
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| Point { time: i as u64 * second, value: value as i32 })
+            .collect();
+        let baseline = calibration.finish(&nn, "now".to_string()).unwrap();
+        assert_eq!(baseline.rmssd, 40.0);
+        assert_eq!(baseline.mean_hr, 60.0);
+        assert!(calibration.finish(&nn[..50], String::new()).is_err());
+    }
+}
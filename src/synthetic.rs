@@ -0,0 +1,474 @@
+//! # Synthetic Sensor Module
+//!
+//! Simulated Polar data source for running the app without Bluetooth.
+//! Produces the same `SensorUpdate` messages as a real sensor so the whole
+//! pipeline (channels, recorder, charts) can be exercised on any machine.
+//!
+//! ## Generated Streams
+//! Only the streams of the simulated model's `DeviceProfile` are sent.
+//! - ECG: PQRST morphology built from Gaussian waves around each R peak
+//! - PPG: Systolic and dicrotic pulse waves following each R peak
+//! - ACC: Gravity vector with breathing-induced chest movement
+//! - Gyro/Mag: Slow breathing rotation and a constant magnetic field
+//! - HR/RR: Beat-to-beat intervals with respiratory sinus arrhythmia, sent as
+//!   PPI instead of RR for optical models
+//!
+//! ## Beat Model
+//! RR intervals are drawn around the configured mean heart rate, modulated by
+//! a 0.25 Hz breathing cycle (RSA) plus a little random jitter. The ECG is
+//! the sum of P, Q, R, S and T waves placed relative to each R peak.
+//!
+//! ## Why No `rand`
+//! A tiny xorshift generator is sufficient for plausible noise and keeps the
+//! dependency tree unchanged.
+
+use crate::device_profile::{DeviceModel, DeviceProfile, SensorStream};
+use crate::samples::{HeartRateSample, MeasurementBatch, MeasurementSample};
+use std::collections::HashMap;
+use crate::sensor::{ConnectionStatus, SensorUpdate};
+use crate::source::{DataSource, SourceTask};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Device ID advertised by the simulated sensor in the scanner list
+pub const SIMULATED_DEVICE_ID: &str = "SIMULATED";
+
+// Interval between measurement batches (Polar H10 sends frames a few times per second)
+const BATCH_INTERVAL_MS: u64 = 250;
+
+// Interval between heart rate notifications
+const HR_INTERVAL_MS: u64 = 1000;
+
+// Breathing frequency used for RSA and chest movement (15 breaths/min)
+const BREATHING_HZ: f64 = 0.25;
+
+/// PQRST wave definition: (offset from R peak in s, amplitude in µV, width in s)
+const PQRST_WAVES: [(f64, f64, f64); 5] = [
+    (-0.20, 150.0, 0.025),  // P
+    (-0.035, -120.0, 0.010), // Q
+    (0.0, 1200.0, 0.012),   // R
+    (0.035, -250.0, 0.010), // S
+    (0.28, 300.0, 0.060),   // T
+];
+
+/// PPG wave definition: (delay after R peak in s, amplitude, width in s)
+const PPG_WAVES: [(f64, f64, f64); 2] = [
+    (0.25, 4000.0, 0.08), // Systolic peak
+    (0.50, 1200.0, 0.06), // Dicrotic wave
+];
+
+/// Settings for the synthetic data source
+#[derive(Debug, Clone)]
+pub struct SyntheticConfig {
+    /// Mean heart rate in beats per minute
+    pub heart_rate_bpm: f64,
+    /// Polar model whose streams and sample rates are simulated
+    pub model: DeviceModel,
+}
+
+impl Default for SyntheticConfig {
+    fn default() -> Self {
+        Self {
+            heart_rate_bpm: 60.0,
+            model: DeviceModel::H10,
+        }
+    }
+}
+
+/// Minimal xorshift64* pseudo-random generator
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    /// Uniform value in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        let v = self.0.wrapping_mul(0x2545_F491_4F6C_DD1D);
+        (v >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Standard normal value (Box-Muller)
+    fn next_gaussian(&mut self) -> f64 {
+        let u1 = self.next_f64().max(f64::MIN_POSITIVE);
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+}
+
+/// Stateful generator for synthetic Polar signals
+///
+/// All signals are functions of the elapsed simulation time in seconds, so
+/// callers can request any number of samples without drift between streams.
+pub struct SyntheticSensor {
+    config: SyntheticConfig,
+    profile: DeviceProfile,
+    rng: Rng,
+    /// R peak times in seconds (previous, current and upcoming beats)
+    r_peaks: Vec<f64>,
+    /// Index of the first R peak not yet reported in an HR update
+    next_unreported_beat: usize,
+    /// Number of samples generated so far per stream
+    samples_sent: HashMap<SensorStream, u64>,
+}
+
+impl SyntheticSensor {
+    pub fn new(config: SyntheticConfig) -> Self {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(1);
+        Self::with_seed(config, seed)
+    }
+
+    /// Create a generator with a fixed seed for reproducible output
+    pub fn with_seed(config: SyntheticConfig, seed: u64) -> Self {
+        Self {
+            profile: DeviceProfile::for_model(config.model),
+            config,
+            rng: Rng::new(seed),
+            r_peaks: vec![0.0],
+            next_unreported_beat: 1,
+            samples_sent: HashMap::new(),
+        }
+    }
+
+    pub fn profile(&self) -> &DeviceProfile {
+        &self.profile
+    }
+
+    /// Next RR interval in seconds, modulated by breathing (RSA)
+    fn next_rr_seconds(&mut self, at_time: f64) -> f64 {
+        let mean_rr = 60.0 / self.config.heart_rate_bpm.clamp(20.0, 240.0);
+        let rsa = 0.04 * mean_rr * (2.0 * std::f64::consts::PI * BREATHING_HZ * at_time).sin();
+        let jitter = 0.01 * mean_rr * self.rng.next_gaussian();
+        (mean_rr + rsa + jitter).max(0.25)
+    }
+
+    /// Extend the R peak schedule so that it covers `time` plus one beat
+    fn ensure_beats_until(&mut self, time: f64) {
+        while *self.r_peaks.last().unwrap() <= time + 1.0 {
+            let last = *self.r_peaks.last().unwrap();
+            let rr = self.next_rr_seconds(last);
+            self.r_peaks.push(last + rr);
+        }
+    }
+
+    /// Drop R peaks older than `before` that are no longer needed
+    fn prune_beats(&mut self, before: f64) {
+        // Keep the last reported beat so the next RR interval can be computed
+        let keep_from = self.next_unreported_beat.saturating_sub(1);
+        let stale = self.r_peaks[..keep_from].iter().take_while(|&&r| r < before).count();
+        if stale > 0 {
+            self.r_peaks.drain(..stale);
+            self.next_unreported_beat -= stale;
+        }
+    }
+
+    /// ECG value in µV at simulation time `t` (seconds)
+    fn ecg_value(&mut self, t: f64) -> i32 {
+        self.ensure_beats_until(t);
+
+        // Only beats near `t` contribute; T waves reach ~0.5 s after the R peak
+        let value: f64 = self
+            .r_peaks
+            .iter()
+            .filter(|&&r| (t - r).abs() < 1.0)
+            .map(|&r| {
+                PQRST_WAVES
+                    .iter()
+                    .map(|&(offset, amplitude, width)| {
+                        let dt = t - r - offset;
+                        amplitude * (-(dt * dt) / (2.0 * width * width)).exp()
+                    })
+                    .sum::<f64>()
+            })
+            .sum();
+
+        let wander = 60.0 * (2.0 * std::f64::consts::PI * BREATHING_HZ * t).sin();
+        let noise = 8.0 * self.rng.next_gaussian();
+        (value + wander + noise).round() as i32
+    }
+
+    /// PPG value in raw units at simulation time `t` (seconds)
+    fn ppg_value(&mut self, t: f64) -> i32 {
+        self.ensure_beats_until(t);
+
+        let value: f64 = self
+            .r_peaks
+            .iter()
+            .filter(|&&r| (t - r) > -0.5 && (t - r) < 1.5)
+            .map(|&r| {
+                PPG_WAVES
+                    .iter()
+                    .map(|&(delay, amplitude, width)| {
+                        let dt = t - r - delay;
+                        amplitude * (-(dt * dt) / (2.0 * width * width)).exp()
+                    })
+                    .sum::<f64>()
+            })
+            .sum();
+
+        let breath = 500.0 * (2.0 * std::f64::consts::PI * BREATHING_HZ * t).sin();
+        let noise = 40.0 * self.rng.next_gaussian();
+        (100_000.0 + value + breath + noise).round() as i32
+    }
+
+    /// Accelerometer reading in mg at simulation time `t` (seconds)
+    fn acc_value(&mut self, t: f64) -> (i32, i32, i32) {
+        let breath = (2.0 * std::f64::consts::PI * BREATHING_HZ * t).sin();
+        let x = -1000.0 + 15.0 * breath + 4.0 * self.rng.next_gaussian();
+        let y = 30.0 + 4.0 * self.rng.next_gaussian();
+        let z = 80.0 + 25.0 * breath + 4.0 * self.rng.next_gaussian();
+        (x.round() as i32, y.round() as i32, z.round() as i32)
+    }
+
+    /// Gyroscope reading in millidegrees/s at simulation time `t` (seconds)
+    fn gyro_value(&mut self, t: f64) -> (i32, i32, i32) {
+        let breath = (2.0 * std::f64::consts::PI * BREATHING_HZ * t).cos();
+        let x = 800.0 * breath + 150.0 * self.rng.next_gaussian();
+        let y = 150.0 * self.rng.next_gaussian();
+        let z = -300.0 * breath + 150.0 * self.rng.next_gaussian();
+        (x.round() as i32, y.round() as i32, z.round() as i32)
+    }
+
+    /// Magnetometer reading in milligauss (roughly Earth's field)
+    fn mag_value(&mut self) -> (i32, i32, i32) {
+        let x = 200.0 + 5.0 * self.rng.next_gaussian();
+        let y = -150.0 + 5.0 * self.rng.next_gaussian();
+        let z = 400.0 + 5.0 * self.rng.next_gaussian();
+        (x.round() as i32, y.round() as i32, z.round() as i32)
+    }
+
+    /// Single sample of a periodic stream at simulation time `t` (seconds)
+    fn sample(&mut self, stream: SensorStream, t: f64) -> Option<MeasurementSample> {
+        let sample = match stream {
+            SensorStream::Ecg => MeasurementSample::Ecg(self.ecg_value(t)),
+            SensorStream::Ppg => MeasurementSample::Ppg(self.ppg_value(t)),
+            SensorStream::Acc => {
+                let (x, y, z) = self.acc_value(t);
+                MeasurementSample::Acc { x, y, z }
+            }
+            SensorStream::Gyro => {
+                let (x, y, z) = self.gyro_value(t);
+                MeasurementSample::Gyro { x, y, z }
+            }
+            SensorStream::Mag => {
+                let (x, y, z) = self.mag_value();
+                MeasurementSample::Mag { x, y, z }
+            }
+            SensorStream::HeartRate | SensorStream::Ppi => return None,
+        };
+        Some(sample)
+    }
+
+    /// Generate all samples of a periodic stream due up to `elapsed` seconds
+    pub fn batch(&mut self, stream: SensorStream, elapsed: f64) -> MeasurementBatch {
+        let rate = self.profile.default_sample_rate(stream) as f64;
+        let due = (elapsed * rate) as u64;
+        let sent = self.samples_sent.get(&stream).copied().unwrap_or(0);
+        let samples = (sent..due)
+            .filter_map(|i| self.sample(stream, i as f64 / rate))
+            .collect();
+        self.samples_sent.insert(stream, due.max(sent));
+        self.prune_beats(elapsed - 2.0);
+        MeasurementBatch::new(samples)
+    }
+
+    /// Heart rate update covering all beats completed up to `elapsed` seconds
+    pub fn heart_rate(&mut self, elapsed: f64) -> HeartRateSample {
+        self.ensure_beats_until(elapsed);
+
+        let mut rr_intervals = Vec::new();
+        while self.next_unreported_beat < self.r_peaks.len()
+            && self.r_peaks[self.next_unreported_beat] <= elapsed
+        {
+            let i = self.next_unreported_beat;
+            let rr_ms = (self.r_peaks[i] - self.r_peaks[i - 1]) * 1000.0;
+            rr_intervals.push(rr_ms.round() as u16);
+            self.next_unreported_beat += 1;
+        }
+
+        // Like the H10, report the average rate of the beats in this update
+        let bpm = if rr_intervals.is_empty() {
+            self.config.heart_rate_bpm
+        } else {
+            let mean_rr_ms =
+                rr_intervals.iter().map(|&rr| rr as f64).sum::<f64>() / rr_intervals.len() as f64;
+            60_000.0 / mean_rr_ms
+        };
+
+        HeartRateSample::new(bpm.round() as u16, rr_intervals)
+    }
+}
+
+/// Run the synthetic data source until the stop flag is set
+///
+/// Mirrors `sensor::start_data_collection`: reports connection status, the
+/// configured sample rates, then streams data over the update channel.
+pub async fn start_synthetic_collection(
+    config: SyntheticConfig,
+    sender: Sender<SensorUpdate>,
+    should_stop: Arc<AtomicBool>,
+) {
+    log::info!("Starting simulated sensor at {:.0} bpm", config.heart_rate_bpm);
+    let _ = sender.send(SensorUpdate::ConnectionStatus(ConnectionStatus::Connecting));
+    let _ = sender.send(SensorUpdate::ConnectionStatus(ConnectionStatus::Connected));
+
+    let mut sensor = SyntheticSensor::new(config);
+    let profile = sensor.profile().clone();
+    let periodic_streams: Vec<SensorStream> =
+        profile.streams().iter().copied().filter(|s| s.is_periodic()).collect();
+    let optical = profile.supports(SensorStream::Ppi);
+
+    let _ = sender.send(SensorUpdate::DeviceProfile(profile.clone()));
+    let _ = sender.send(SensorUpdate::SampleRateConfig {
+        rates: periodic_streams
+            .iter()
+            .map(|&stream| (stream, profile.default_sample_rate(stream)))
+            .collect(),
+    });
+
+    let start = Instant::now();
+    let mut next_hr = Duration::from_millis(HR_INTERVAL_MS);
+    let mut interval = tokio::time::interval(Duration::from_millis(BATCH_INTERVAL_MS));
+
+    loop {
+        interval.tick().await;
+        if should_stop.load(Ordering::Relaxed) {
+            log::info!("Stopping simulated sensor");
+            break;
+        }
+
+        let elapsed = start.elapsed();
+        let elapsed_secs = elapsed.as_secs_f64();
+
+        for &stream in &periodic_streams {
+            let batch = sensor.batch(stream, elapsed_secs);
+            if batch.samples.is_empty() {
+                continue;
+            }
+            if let Err(why) = sender.send(SensorUpdate::MeasurementData(batch)) {
+                log::error!("Could not send simulated measurement data: {:?}", why);
+                return;
+            }
+        }
+
+        if elapsed >= next_hr {
+            next_hr += Duration::from_millis(HR_INTERVAL_MS);
+            let mut hr = sensor.heart_rate(elapsed_secs);
+
+            // Optical sensors report beat intervals as PPI, not with HR
+            if optical && !hr.rr_intervals.is_empty() {
+                let ppi = hr.rr_intervals.drain(..).map(MeasurementSample::Ppi).collect();
+                let _ = sender.send(SensorUpdate::MeasurementData(MeasurementBatch::new(ppi)));
+            }
+
+            if let Err(why) = sender.send(SensorUpdate::HeartRate(hr)) {
+                log::error!("Could not send simulated heart rate data: {:?}", why);
+                return;
+            }
+        }
+    }
+
+    let _ = sender.send(SensorUpdate::ConnectionStatus(ConnectionStatus::Disconnected));
+}
+
+/// Data source producing simulated Polar H10 data
+pub struct SyntheticSource {
+    config: SyntheticConfig,
+}
+
+impl SyntheticSource {
+    pub fn new(config: SyntheticConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl DataSource for SyntheticSource {
+    fn name(&self) -> String {
+        format!("simulated {}", self.config.model)
+    }
+
+    fn start(self: Box<Self>, sender: Sender<SensorUpdate>, should_stop: Arc<AtomicBool>) -> SourceTask {
+        Box::pin(start_synthetic_collection(self.config, sender, should_stop))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ecg_batch_sample_count() {
+        let mut sensor = SyntheticSensor::with_seed(SyntheticConfig::default(), 42);
+
+        assert_eq!(sensor.batch(SensorStream::Ecg, 1.0).samples.len(), 130);
+        // Second call only returns the newly due samples
+        assert_eq!(sensor.batch(SensorStream::Ecg, 1.5).samples.len(), 65);
+    }
+
+    #[test]
+    fn test_ecg_has_r_peaks() {
+        let mut sensor = SyntheticSensor::with_seed(SyntheticConfig::default(), 42);
+        let batch = sensor.batch(SensorStream::Ecg, 5.0);
+
+        let max = batch
+            .samples
+            .iter()
+            .filter_map(|s| match s {
+                MeasurementSample::Ecg(v) => Some(*v),
+                _ => None,
+            })
+            .max()
+            .unwrap();
+
+        // R waves dominate the signal
+        assert!(max > 800, "max ECG value {} too small", max);
+    }
+
+    #[test]
+    fn test_heart_rate_matches_config() {
+        let config = SyntheticConfig {
+            heart_rate_bpm: 75.0,
+            ..SyntheticConfig::default()
+        };
+        let mut sensor = SyntheticSensor::with_seed(config, 7);
+
+        let hr = sensor.heart_rate(30.0);
+
+        // 75 bpm over 30 seconds is roughly 37 beats
+        assert!(hr.rr_intervals.len() >= 35 && hr.rr_intervals.len() <= 40);
+        assert!((70..=80).contains(&hr.bpm), "bpm {} out of range", hr.bpm);
+        assert!(hr.rr_intervals.iter().all(|&rr| (600..=1000).contains(&rr)));
+    }
+
+    #[test]
+    fn test_acc_batch_gravity() {
+        let mut sensor = SyntheticSensor::with_seed(SyntheticConfig::default(), 1);
+        let batch = sensor.batch(SensorStream::Acc, 1.0);
+
+        assert_eq!(batch.samples.len(), 200);
+        assert!(batch.samples.iter().all(|s| matches!(s, MeasurementSample::Acc { x, .. } if (-1100..=-900).contains(x))));
+    }
+
+    #[test]
+    fn test_verity_sense_streams() {
+        let config = SyntheticConfig {
+            model: DeviceModel::VeritySense,
+            ..SyntheticConfig::default()
+        };
+        let mut sensor = SyntheticSensor::with_seed(config, 3);
+
+        let ppg = sensor.batch(SensorStream::Ppg, 2.0);
+        assert_eq!(ppg.samples.len(), 110);
+        assert!(ppg.samples.iter().all(|s| matches!(s, MeasurementSample::Ppg(_))));
+        assert_eq!(sensor.batch(SensorStream::Mag, 1.0).samples.len(), 50);
+    }
+}
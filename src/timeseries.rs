@@ -6,12 +6,17 @@
 //!
 //! ## Key Types
 //! - `Point`: Individual timestamped data point (time in nanoseconds, integer value)
-//! - `TimeSeries`: Vector-based storage with query methods
+//! - `TimeSeries`: Ring-buffer storage with a retention policy and query methods
+//! - `Retention`, `SummaryResolution`: How long points, and min/max summaries
+//!   of older data, are kept
 //! - `PointSliceExt`: Statistical operations on point slices (min/max, RMSSD)
+//! - `Gap`: Time span without data, used to break lines when drawing
 //!
 //! ## Architecture
 //! TimeSeries maintains sorted points and provides efficient time-windowed queries
 //! using binary search. Query methods return slices when possible to avoid allocation.
+//! Live series drop points older than their retention, optionally keeping a
+//! coarser min/max envelope of them, so memory stays flat in long sessions.
 //!
 //! ## Usage
 //! For sensor-specific data handling, see `polar_data` module.
@@ -40,12 +45,46 @@ impl TimeUnit {
 }
 
 /// Individual timestamped data point
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Point {
     pub time: u64,   // Timestamp in nanoseconds
     pub value: i32,  // Integer value
 }
 
+/// Time span without data, e.g. while a sensor was reconnecting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gap {
+    /// Time of the last data before the gap in nanoseconds
+    pub start: u64,
+    /// Time data resumed in nanoseconds
+    pub end: u64,
+}
+
+impl Gap {
+    pub fn duration_ns(&self) -> u64 {
+        self.end.saturating_sub(self.start)
+    }
+}
+
+/// Split points into runs that do not cross any gap
+///
+/// Lines drawn per run leave the gaps empty instead of bridging them.
+pub fn split_at_gaps<'a>(points: &'a [Point], gaps: &[Gap]) -> Vec<&'a [Point]> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    for i in 1..points.len() {
+        let (before, after) = (points[i - 1].time, points[i].time);
+        if gaps.iter().any(|gap| gap.start >= before && gap.end <= after) {
+            runs.push(&points[start..i]);
+            start = i;
+        }
+    }
+    if start < points.len() {
+        runs.push(&points[start..]);
+    }
+    runs
+}
+
 /// Trait for statistical operations on point slices
 #[allow(dead_code)]
 pub trait PointSliceExt {
@@ -83,22 +122,138 @@ impl PointSliceExt for &[Point] {
     }
 }
 
+/// How long a `TimeSeries` keeps its points
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Retention {
+    /// Keep every point
+    Unbounded,
+    /// Keep points up to this many nanoseconds older than the newest
+    Duration(u64),
+}
+
+/// Bucket width and lifetime of one summary resolution
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SummaryResolution {
+    pub bucket_ns: u64,
+    pub retention_ns: u64,
+}
+
+/// Lowest and highest value within one time bucket
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Summary {
+    /// Start of the bucket in nanoseconds
+    start: u64,
+    min: i32,
+    max: i32,
+}
+
+// Expired items are only dropped once at least this many have piled up
+const MIN_COMPACTION: usize = 1024;
+
+/// Contiguous ring buffer: items leave at the front and arrive at the back
+///
+/// Expired items are skipped over and dropped in batches once they outnumber
+/// the live ones, so storage stays within twice the retained items while
+/// queries keep returning plain slices.
+struct RingBuffer<T> {
+    items: Vec<T>,
+    head: usize,
+}
+
+impl<T> RingBuffer<T> {
+    fn new() -> Self {
+        Self { items: Vec::new(), head: 0 }
+    }
+
+    fn as_slice(&self) -> &[T] {
+        &self.items[self.head..]
+    }
+
+    fn push(&mut self, item: T) {
+        self.items.push(item);
+    }
+
+    fn last_mut(&mut self) -> Option<&mut T> {
+        self.items[self.head..].last_mut()
+    }
+
+    /// Drop the oldest `count` items
+    fn expire(&mut self, count: usize) {
+        self.head = (self.head + count).min(self.items.len());
+        if self.head >= MIN_COMPACTION && self.head * 2 >= self.items.len() {
+            self.items.drain(..self.head);
+            self.head = 0;
+        }
+    }
+}
+
+/// Min/max summaries at one resolution
+struct SummaryLevel {
+    resolution: SummaryResolution,
+    buckets: RingBuffer<Summary>,
+}
+
+impl SummaryLevel {
+    fn add(&mut self, time: u64, value: i32) {
+        let start = time - time % self.resolution.bucket_ns;
+        match self.buckets.last_mut() {
+            // Late points join the newest bucket to keep the buckets sorted
+            Some(bucket) if bucket.start >= start => {
+                bucket.min = bucket.min.min(value);
+                bucket.max = bucket.max.max(value);
+            }
+            _ => self.buckets.push(Summary { start, min: value, max: value }),
+        }
+
+        let cutoff = time.saturating_sub(self.resolution.retention_ns);
+        let expired = self.buckets.as_slice().partition_point(|b| b.start + self.resolution.bucket_ns <= cutoff);
+        self.buckets.expire(expired);
+    }
+}
+
 /// Time series storage with efficient queries
+///
+/// Points older than the `Retention` are dropped as new ones arrive, so a
+/// long session's memory stays flat. Summary resolutions, if any, keep the
+/// min/max of older data at a coarser scale for longer. Indices (`len`,
+/// `slice`, `partition_point_time`) refer to the retained points.
 pub struct TimeSeries {
-    data: Vec<Point>,
+    data: RingBuffer<Point>,
     sample_rate: u64, // Sample rate in Hz (nominal, for calculating expected intervals)
     start_time: Option<u64>, // First timestamp in nanoseconds
+    retention: Retention,
+    summaries: Vec<SummaryLevel>, // Finest resolution first
 }
 
 impl TimeSeries {
     pub fn new(sample_rate: u64) -> Self {
         Self { 
-            data: Vec::new(),
+            data: RingBuffer::new(),
             sample_rate,
             start_time: None,
+            retention: Retention::Unbounded,
+            summaries: Vec::new(),
         }
     }
 
+    /// Keep points for `retention` only
+    pub fn with_retention(mut self, retention: Retention) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Keep min/max summaries at the given resolutions
+    pub fn with_summaries(mut self, resolutions: &[SummaryResolution]) -> Self {
+        let mut resolutions = resolutions.to_vec();
+        resolutions.sort_by_key(|r| r.bucket_ns);
+        self.summaries = resolutions
+            .into_iter()
+            .filter(|r| r.bucket_ns > 0)
+            .map(|resolution| SummaryLevel { resolution, buckets: RingBuffer::new() })
+            .collect();
+        self
+    }
+
     pub fn set_sample_rate(&mut self, rate: u64) {
         self.sample_rate = rate;
     }
@@ -114,99 +269,159 @@ impl TimeSeries {
         }
 
         self.data.push(Point { time, value });
+        for level in &mut self.summaries {
+            level.add(time, value);
+        }
+
+        if let Retention::Duration(keep) = self.retention {
+            let cutoff = time.saturating_sub(keep);
+            let points = self.data.as_slice();
+            if points.first().is_some_and(|p| p.time < cutoff) {
+                let expired = points.partition_point(|p| p.time < cutoff);
+                self.data.expire(expired);
+            }
+        }
     }
 
     /// Check if time series is empty
     pub fn is_empty(&self) -> bool {
-        self.data.is_empty()
+        self.data.as_slice().is_empty()
     }
 
     /// Get number of points
     pub fn len(&self) -> usize {
-        self.data.len()
+        self.data.as_slice().len()
+    }
+
+    /// Get the first point if it exists
+    pub fn first_point(&self) -> Option<&Point> {
+        self.data.as_slice().first()
     }
 
     /// Get the last point if it exists
     pub fn last_point(&self) -> Option<&Point> {
-        self.data.last()
+        self.data.as_slice().last()
+    }
+
+    /// Earliest time with data, in points or summaries
+    pub fn oldest_time(&self) -> Option<u64> {
+        let summarized = self.summaries.iter().filter_map(|level| level.buckets.as_slice().first());
+        summarized.map(|bucket| bucket.start).chain(self.first_point().map(|p| p.time)).min()
     }
 
     /// Binary search for first point >= target time
     pub fn partition_point_time(&self, target_time: u64) -> usize {
-        self.data.partition_point(|p| p.time < target_time)
+        self.data.as_slice().partition_point(|p| p.time < target_time)
     }
 
     /// Binary search for first point > target time
     pub fn partition_point_time_inclusive(&self, target_time: u64) -> usize {
-        self.data.partition_point(|p| p.time <= target_time)
+        self.data.as_slice().partition_point(|p| p.time <= target_time)
     }
 
     /// Get a slice of the internal data
     pub fn slice(&self, start_idx: usize, end_idx: usize) -> &[Point] {
-        &self.data[start_idx..end_idx]
+        &self.data.as_slice()[start_idx..end_idx]
     }
 
     pub fn last_points(&self, n: usize) -> &[Point] {
-        &self.data[self.data.len().saturating_sub(n)..]
+        let data = self.data.as_slice();
+        &data[data.len().saturating_sub(n)..]
     }
 
     /// Get points from the last `duration_ns` nanoseconds
     /// Returns all points whose timestamp is >= (latest_timestamp - duration_ns)
     pub fn last_duration(&self, duration_ns: u64) -> &[Point] {
-        if self.data.is_empty() {
+        let data = self.data.as_slice();
+        let Some(latest) = data.last() else {
             return &[];
-        }
+        };
 
-        let latest_time = self.data.last().unwrap().time;
-        let cutoff_time = latest_time.saturating_sub(duration_ns);
+        let cutoff_time = latest.time.saturating_sub(duration_ns);
 
         // Binary search for the first point >= cutoff_time
-        let start_idx = self.data.partition_point(|p| p.time < cutoff_time);
+        let start_idx = data.partition_point(|p| p.time < cutoff_time);
         
-        &self.data[start_idx..]
+        &data[start_idx..]
     }
 
     /// Get points within a specific time range [end_time - duration_ns, end_time]
     /// This is used for rendering with a fixed reference time for smooth scrolling
     pub fn range_from_time(&self, end_time: u64, duration_ns: u64) -> &[Point] {
-        if self.data.is_empty() {
+        let data = self.data.as_slice();
+        if data.is_empty() {
             return &[];
         }
 
         let start_time = end_time.saturating_sub(duration_ns);
         
         // Find first point >= start_time
-        let start_idx = self.data.partition_point(|p| p.time < start_time);
+        let start_idx = data.partition_point(|p| p.time < start_time);
         
         // Find first point > end_time
-        let end_idx = self.data.partition_point(|p| p.time <= end_time);
+        let end_idx = data.partition_point(|p| p.time <= end_time);
         
-        &self.data[start_idx..end_idx]
+        &data[start_idx..end_idx]
+    }
+
+    /// Min/max envelope of the part of [end_time - duration_ns, end_time] older
+    /// than the points, as a zigzag line
+    ///
+    /// Taken from the finest summary resolution reaching back to the start of
+    /// the range. Each bucket becomes its minimum at its start and its maximum
+    /// halfway through, which drawn as a line fills the range the signal covered.
+    pub fn envelope_from_time(&self, end_time: u64, duration_ns: u64) -> Vec<Point> {
+        let Some((buckets, bucket_ns)) = self.summarized_range(end_time, duration_ns) else {
+            return Vec::new();
+        };
+        buckets
+            .iter()
+            .flat_map(|b| [Point { time: b.start, value: b.min }, Point { time: b.start + bucket_ns / 2, value: b.max }])
+            .collect()
+    }
+
+    /// Summary buckets covering a range before the points, and their width
+    fn summarized_range(&self, end_time: u64, duration_ns: u64) -> Option<(&[Summary], u64)> {
+        let start_time = end_time.saturating_sub(duration_ns);
+        let level = self
+            .summaries
+            .iter()
+            .find(|level| level.buckets.as_slice().first().is_some_and(|b| b.start <= start_time))
+            .or(self.summaries.last())?;
+
+        // Buckets overlapping the points are left to the points
+        let points_from = self.first_point().map_or(u64::MAX, |p| p.time).min(end_time.saturating_add(1));
+        let buckets = level.buckets.as_slice();
+        let bucket_ns = level.resolution.bucket_ns;
+        let start_idx = buckets.partition_point(|b| b.start + bucket_ns <= start_time);
+        let end_idx = buckets.partition_point(|b| b.start + bucket_ns <= points_from).max(start_idx);
+        Some((&buckets[start_idx..end_idx], bucket_ns))
     }
 
     /// Get points for rendering with forward-fill to handle gaps in low-rate data
     /// If there's a gap at the start of the window, adds a synthetic point with the last known value
     pub fn range_from_time_with_fill(&self, end_time: u64, duration_ns: u64) -> Vec<Point> {
-        if self.data.is_empty() {
+        let data = self.data.as_slice();
+        if data.is_empty() {
             return Vec::new();
         }
 
         let start_time = end_time.saturating_sub(duration_ns);
         
         // Find first point >= start_time
-        let start_idx = self.data.partition_point(|p| p.time < start_time);
+        let start_idx = data.partition_point(|p| p.time < start_time);
         
         // Find first point > end_time
-        let end_idx = self.data.partition_point(|p| p.time <= end_time);
+        let end_idx = data.partition_point(|p| p.time <= end_time);
         
-        let points_in_range = &self.data[start_idx..end_idx];
+        let points_in_range = &data[start_idx..end_idx];
         
         // If we have points in range but the first one is after start_time,
         // look for the last point before the window to forward-fill
         if !points_in_range.is_empty() && points_in_range[0].time > start_time {
             // Find the last point before start_time
             if start_idx > 0 {
-                let last_before = &self.data[start_idx - 1];
+                let last_before = &data[start_idx - 1];
                 // Create a synthetic point at the start of the window with the last known value
                 let mut result = Vec::with_capacity(points_in_range.len() + 1);
                 result.push(Point {
@@ -317,4 +532,64 @@ mod tests {
         assert_eq!(range[1].value, 3);
         assert_eq!(range[2].value, 4);
     }
+
+    #[test]
+    fn test_retention_drops_old_points() {
+        let mut ts = TimeSeries::new(1000).with_retention(Retention::Duration(2_000_000_000));
+        for i in 0..10_000u64 {
+            ts.add_point(i * 1_000_000, i as i32);
+        }
+
+        // Only the last 2 seconds are kept, and storage doesn't grow past twice that
+        assert_eq!(ts.len(), 2001);
+        assert_eq!(ts.first_point().unwrap().time, 7_999_000_000);
+        assert!(ts.data.items.len() <= 2 * ts.len() + MIN_COMPACTION);
+
+        // Queries see the retained points only
+        assert_eq!(ts.last_duration(u64::MAX).len(), 2001);
+        let range = ts.range_from_time(9_000_000_000, 500_000_000);
+        assert_eq!(range.len(), 501);
+        assert_eq!(range[0].value, 8500);
+        assert_eq!(ts.partition_point_time(9_000_000_000), 1001);
+        assert!(ts.range_from_time(5_000_000_000, 1_000_000_000).is_empty());
+    }
+
+    #[test]
+    fn test_summaries_envelope() {
+        let mut ts = TimeSeries::new(100)
+            .with_retention(Retention::Duration(1_000_000_000))
+            .with_summaries(&[SummaryResolution { bucket_ns: 100_000_000, retention_ns: 60_000_000_000 }]);
+        let start = 10_000_000_000;
+        for i in 0..500u64 {
+            ts.add_point(start + i * 10_000_000, (i % 10) as i32);
+        }
+        assert_eq!(ts.oldest_time(), Some(start));
+        let first_point = ts.first_point().unwrap().time;
+        assert_eq!(first_point, start + 3_990_000_000);
+
+        // One min and one max per bucket ending before the retained points
+        let envelope = ts.envelope_from_time(start + 5_000_000_000, 5_000_000_000);
+        assert_eq!(envelope.len(), 2 * 39);
+        assert_eq!(envelope[0], Point { time: start, value: 0 });
+        assert_eq!(envelope[1], Point { time: start + 50_000_000, value: 9 });
+        assert!(envelope.last().unwrap().time < first_point);
+
+        // Windows within the retained points need no envelope
+        assert!(ts.envelope_from_time(start + 5_000_000_000, 500_000_000).is_empty());
+        assert!(TimeSeries::new(100).envelope_from_time(start, start).is_empty());
+    }
+
+    #[test]
+    fn test_split_at_gaps() {
+        let points: Vec<Point> = [1, 2, 6, 7, 8].iter().map(|&t| Point { time: t, value: 0 }).collect();
+        let gaps = [Gap { start: 2, end: 6 }];
+
+        let runs = split_at_gaps(&points, &gaps);
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].len(), 2);
+        assert_eq!(runs[1].len(), 3);
+
+        assert_eq!(split_at_gaps(&points, &[]).len(), 1);
+        assert!(split_at_gaps(&[], &gaps).is_empty());
+    }
 }
@@ -11,10 +11,22 @@
 //! - `add_end_boundary_point`: Ensure data at window right edge
 //! - `range_from_time_interpolated`: Complete interpolation pipeline
 //! - `current_display_time`: Calculate reference time for smooth scrolling
+//! - `DisplayClock`: Pausable clock that drives data timestamps and chart scrolling
+//! - `ChartWindows`: Time window of each chart, zoomed with the mouse wheel
+//! - `ChartView`: Charts following live data, or frozen and scrolled back
+//! - `ChartScales`: Value axis of each chart: fixed, auto-fit or manual
+//! - `auto_fit_range`: Auto-fit value range with hysteresis
 //!
 //! ## Display Timing
 //! The `current_display_time` function provides a reference time for chart rendering
-//! with optional delay for smooth streaming of low-rate data.
+//! with optional delay for smooth streaming of low-rate data. A frozen `ChartView`
+//! holds that reference time still while data keeps arriving underneath.
+//!
+//! ## Why a Pausable Clock
+//! Recording playback can pause and run faster than real time. Timestamps and
+//! the chart reference time both come from `DisplayClock`, which excludes time
+//! spent paused, so resumed data continues exactly where the chart stopped, and
+//! runs at the playback speed, so replayed data keeps its recorded spacing.
 //!
 //! ## Why Interpolation
 //! Low sample rate data (HR, RR at ~1Hz) appears steppy without interpolation.
@@ -22,14 +34,15 @@
 //! raw data when interpolation is disabled.
 
 use crate::timeseries::{Point, TimeSeries};
+use serde::{Deserialize, Serialize};
+use std::fmt;
 
 // Display delay for smooth scrolling (1.5 seconds in nanoseconds)
 // This prevents gaps when low-rate data (HR, RR, HRV at ~1Hz) hasn't arrived yet
 const DISPLAY_DELAY_NS: u64 = 1_500_000_000;
 
 /// Time window duration for chart display
-#[allow(dead_code)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ChartWindow {
     /// 10 seconds
     TenSeconds,
@@ -39,6 +52,8 @@ pub enum ChartWindow {
     OneMinute,
     /// 120 seconds
     TwoMinutes,
+    /// 600 seconds
+    TenMinutes,
 }
 
 impl ChartWindow {
@@ -49,6 +64,432 @@ impl ChartWindow {
             ChartWindow::ThirtySeconds => 30_000_000_000,
             ChartWindow::OneMinute => 60_000_000_000,
             ChartWindow::TwoMinutes => 120_000_000_000,
+            ChartWindow::TenMinutes => 600_000_000_000,
+        }
+    }
+
+    pub fn as_seconds(&self) -> f64 {
+        self.as_nanos() as f64 / 1e9
+    }
+
+    pub fn all() -> [ChartWindow; 5] {
+        [
+            ChartWindow::TenSeconds,
+            ChartWindow::ThirtySeconds,
+            ChartWindow::OneMinute,
+            ChartWindow::TwoMinutes,
+            ChartWindow::TenMinutes,
+        ]
+    }
+
+    /// Next shorter window, or this one if it is the shortest
+    pub fn zoomed_in(&self) -> Self {
+        let all = Self::all();
+        let index = all.iter().position(|w| w == self).unwrap_or(0);
+        all[index.saturating_sub(1)]
+    }
+
+    /// Next longer window, or this one if it is the longest
+    pub fn zoomed_out(&self) -> Self {
+        let all = Self::all();
+        let index = all.iter().position(|w| w == self).unwrap_or(0);
+        all[(index + 1).min(all.len() - 1)]
+    }
+}
+
+impl fmt::Display for ChartWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChartWindow::TenSeconds => write!(f, "10 s"),
+            ChartWindow::ThirtySeconds => write!(f, "30 s"),
+            ChartWindow::OneMinute => write!(f, "1 min"),
+            ChartWindow::TwoMinutes => write!(f, "2 min"),
+            ChartWindow::TenMinutes => write!(f, "10 min"),
+        }
+    }
+}
+
+/// Charts with their own time window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChartKind {
+    Ecg,
+    Ppg,
+    Hr,
+    Rr,
+    Hrv,
+    Stress,
+    Respiration,
+    Acc,
+    Gyro,
+    Mag,
+}
+
+impl ChartKind {
+    pub fn all() -> [ChartKind; 10] {
+        [
+            ChartKind::Ecg,
+            ChartKind::Ppg,
+            ChartKind::Hr,
+            ChartKind::Rr,
+            ChartKind::Hrv,
+            ChartKind::Stress,
+            ChartKind::Respiration,
+            ChartKind::Acc,
+            ChartKind::Gyro,
+            ChartKind::Mag,
+        ]
+    }
+}
+
+/// Time window of each chart
+///
+/// Signals default to 10 seconds; the slower trends get longer windows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChartWindows {
+    pub ecg: ChartWindow,
+    pub ppg: ChartWindow,
+    pub hr: ChartWindow,
+    pub rr: ChartWindow,
+    pub hrv: ChartWindow,
+    pub stress: ChartWindow,
+    pub respiration: ChartWindow,
+    pub acc: ChartWindow,
+    pub gyro: ChartWindow,
+    pub mag: ChartWindow,
+}
+
+impl ChartWindows {
+    pub fn get(&self, kind: ChartKind) -> ChartWindow {
+        match kind {
+            ChartKind::Ecg => self.ecg,
+            ChartKind::Ppg => self.ppg,
+            ChartKind::Hr => self.hr,
+            ChartKind::Rr => self.rr,
+            ChartKind::Hrv => self.hrv,
+            ChartKind::Stress => self.stress,
+            ChartKind::Respiration => self.respiration,
+            ChartKind::Acc => self.acc,
+            ChartKind::Gyro => self.gyro,
+            ChartKind::Mag => self.mag,
+        }
+    }
+
+    pub fn set(&mut self, kind: ChartKind, window: ChartWindow) {
+        let slot = match kind {
+            ChartKind::Ecg => &mut self.ecg,
+            ChartKind::Ppg => &mut self.ppg,
+            ChartKind::Hr => &mut self.hr,
+            ChartKind::Rr => &mut self.rr,
+            ChartKind::Hrv => &mut self.hrv,
+            ChartKind::Stress => &mut self.stress,
+            ChartKind::Respiration => &mut self.respiration,
+            ChartKind::Acc => &mut self.acc,
+            ChartKind::Gyro => &mut self.gyro,
+            ChartKind::Mag => &mut self.mag,
+        };
+        *slot = window;
+    }
+}
+
+impl Default for ChartWindows {
+    fn default() -> Self {
+        Self {
+            ecg: ChartWindow::TenSeconds,
+            ppg: ChartWindow::TenSeconds,
+            hr: ChartWindow::TenSeconds,
+            rr: ChartWindow::TenSeconds,
+            hrv: ChartWindow::TenSeconds,
+            stress: ChartWindow::TenMinutes,
+            respiration: ChartWindow::TwoMinutes,
+            acc: ChartWindow::TenSeconds,
+            gyro: ChartWindow::TenSeconds,
+            mag: ChartWindow::TenSeconds,
+        }
+    }
+}
+
+/// How a chart's value axis is scaled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum YScaleMode {
+    /// The chart's built-in range
+    #[default]
+    Fixed,
+    /// Fit the visible data
+    Auto,
+    /// Range set by zooming
+    Manual,
+}
+
+impl YScaleMode {
+    pub fn all() -> [YScaleMode; 3] {
+        [YScaleMode::Fixed, YScaleMode::Auto, YScaleMode::Manual]
+    }
+}
+
+impl fmt::Display for YScaleMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            YScaleMode::Fixed => write!(f, "Fixed"),
+            YScaleMode::Auto => write!(f, "Auto"),
+            YScaleMode::Manual => write!(f, "Manual"),
+        }
+    }
+}
+
+/// Value axis of one chart
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct YScale {
+    pub mode: YScaleMode,
+    /// Range used in manual mode, as (min, max); the fixed range until first zoomed
+    pub manual_range: Option<(i32, i32)>,
+}
+
+impl YScale {
+    fn auto() -> Self {
+        Self { mode: YScaleMode::Auto, manual_range: None }
+    }
+}
+
+/// Value axis of each chart
+///
+/// Raw PPG has a large, device-dependent offset, so it fits the data by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChartScales {
+    pub ecg: YScale,
+    pub ppg: YScale,
+    pub hr: YScale,
+    pub rr: YScale,
+    pub hrv: YScale,
+    pub stress: YScale,
+    pub respiration: YScale,
+    pub acc: YScale,
+    pub gyro: YScale,
+    pub mag: YScale,
+}
+
+impl ChartScales {
+    pub fn get(&self, kind: ChartKind) -> YScale {
+        match kind {
+            ChartKind::Ecg => self.ecg,
+            ChartKind::Ppg => self.ppg,
+            ChartKind::Hr => self.hr,
+            ChartKind::Rr => self.rr,
+            ChartKind::Hrv => self.hrv,
+            ChartKind::Stress => self.stress,
+            ChartKind::Respiration => self.respiration,
+            ChartKind::Acc => self.acc,
+            ChartKind::Gyro => self.gyro,
+            ChartKind::Mag => self.mag,
+        }
+    }
+
+    pub fn get_mut(&mut self, kind: ChartKind) -> &mut YScale {
+        match kind {
+            ChartKind::Ecg => &mut self.ecg,
+            ChartKind::Ppg => &mut self.ppg,
+            ChartKind::Hr => &mut self.hr,
+            ChartKind::Rr => &mut self.rr,
+            ChartKind::Hrv => &mut self.hrv,
+            ChartKind::Stress => &mut self.stress,
+            ChartKind::Respiration => &mut self.respiration,
+            ChartKind::Acc => &mut self.acc,
+            ChartKind::Gyro => &mut self.gyro,
+            ChartKind::Mag => &mut self.mag,
+        }
+    }
+}
+
+impl Default for ChartScales {
+    fn default() -> Self {
+        Self {
+            ecg: YScale::default(),
+            ppg: YScale::auto(),
+            hr: YScale::default(),
+            rr: YScale::default(),
+            hrv: YScale::default(),
+            stress: YScale::default(),
+            respiration: YScale::default(),
+            acc: YScale::default(),
+            gyro: YScale::default(),
+            mag: YScale::default(),
+        }
+    }
+}
+
+/// Value range fitting the data's `(min, max)` with a 10% margin, changed only when needed
+///
+/// Grows as soon as the data leaves `current`, and shrinks only once the fitted
+/// range would be less than half as wide, so the axis holds still instead of
+/// following every beat. The fitted range is at least `min_span` wide, keeping
+/// a flat signal from being blown up to its noise. Without data, `current` is kept.
+pub fn auto_fit_range(current: Option<(i32, i32)>, data: Option<(i32, i32)>, min_span: i32) -> Option<(i32, i32)> {
+    let Some((low, high)) = data else {
+        return current;
+    };
+    let span = (high - low).max(min_span);
+    let center = low + (high - low) / 2;
+    let half = span / 2 + span / 10;
+    let fitted = (center - half, center + half);
+
+    match current {
+        Some((current_low, current_high))
+            if low >= current_low
+                && high <= current_high
+                && (fitted.1 - fitted.0) * 2 >= current_high - current_low =>
+        {
+            current
+        }
+        _ => Some(fitted),
+    }
+}
+
+/// Range scaled around its center; factors below 1 zoom in
+pub fn zoom_range((low, high): (i32, i32), factor: f64) -> (i32, i32) {
+    let center = (low as f64 + high as f64) / 2.0;
+    let half = ((high as f64 - low as f64) / 2.0 * factor).max(1.0);
+    ((center - half).round() as i32, (center + half).round() as i32)
+}
+
+/// Moment the charts end at: the live edge, or a frozen moment scrolled back from
+///
+/// Freezing stops the charts only; the display clock keeps timestamping new data.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ChartView {
+    /// Display time at which the charts were frozen
+    frozen_at: Option<u64>,
+    /// How far before the frozen moment the charts end
+    scroll_back_ns: u64,
+}
+
+impl ChartView {
+    pub fn is_frozen(&self) -> bool {
+        self.frozen_at.is_some()
+    }
+
+    /// Stop the charts at `live_time`; does nothing if already frozen
+    pub fn freeze(&mut self, live_time: u64) {
+        if self.frozen_at.is_none() {
+            self.frozen_at = Some(live_time);
+        }
+    }
+
+    /// Follow live data again
+    pub fn follow_live(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn scroll_back_ns(&self) -> u64 {
+        self.scroll_back_ns
+    }
+
+    /// Longest scroll back that stays within data starting at `history_start`
+    pub fn max_scroll_back_ns(&self, history_start: u64) -> u64 {
+        self.frozen_at.map_or(0, |frozen_at| frozen_at.saturating_sub(history_start))
+    }
+
+    /// End the frozen charts `back_ns` before the frozen moment, no earlier than `history_start`
+    pub fn scroll_to(&mut self, back_ns: u64, history_start: u64) {
+        self.scroll_back_ns = back_ns.min(self.max_scroll_back_ns(history_start));
+    }
+
+    /// Time the charts end at, given the live display time
+    pub fn end_time(&self, live_time: u64) -> u64 {
+        self.frozen_at.unwrap_or(live_time).saturating_sub(self.scroll_back_ns)
+    }
+}
+
+/// Current system time in nanoseconds since the Unix epoch
+pub fn system_time_ns() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64
+}
+
+/// Pausable clock for timestamping incoming data and scrolling charts
+///
+/// Follows system time until first paused or sped up. After that it advances
+/// from where it was by `rate` clock nanoseconds per system nanosecond, and
+/// while paused `now()` stays at the moment the clock was paused.
+#[derive(Debug, Clone)]
+pub struct DisplayClock {
+    /// System time and clock time of the last pause, resume or rate change
+    anchor: Option<(u64, u64)>,
+    /// Clock nanoseconds per system nanosecond
+    rate: f64,
+    paused: bool,
+}
+
+impl Default for DisplayClock {
+    fn default() -> Self {
+        Self {
+            anchor: None,
+            rate: 1.0,
+            paused: false,
+        }
+    }
+}
+
+impl DisplayClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current clock time in nanoseconds
+    pub fn now(&self) -> u64 {
+        self.now_at(system_time_ns())
+    }
+
+    /// Clock time corresponding to the given system time
+    fn now_at(&self, system_now: u64) -> u64 {
+        match self.anchor {
+            None => system_now,
+            Some((_, clock)) if self.paused => clock,
+            Some((system, clock)) => clock + (system_now.saturating_sub(system) as f64 * self.rate) as u64,
+        }
+    }
+
+    /// Continue from the current clock time at the given system time
+    fn reanchor(&mut self, system_now: u64) {
+        self.anchor = Some((system_now, self.now_at(system_now)));
+    }
+
+    /// Stop the clock; does nothing if already paused
+    pub fn pause(&mut self) {
+        self.pause_at(system_time_ns());
+    }
+
+    fn pause_at(&mut self, system_now: u64) {
+        if !self.paused {
+            self.reanchor(system_now);
+            self.paused = true;
+        }
+    }
+
+    /// Restart the clock from where it was paused
+    pub fn resume(&mut self) {
+        self.resume_at(system_time_ns());
+    }
+
+    fn resume_at(&mut self, system_now: u64) {
+        if self.paused {
+            self.reanchor(system_now);
+            self.paused = false;
+        }
+    }
+
+    /// Run the clock `rate` times as fast as system time, e.g. the playback speed
+    pub fn set_rate(&mut self, rate: f64) {
+        self.set_rate_at(system_time_ns(), rate);
+    }
+
+    fn set_rate_at(&mut self, system_now: u64, rate: f64) {
+        if rate != self.rate {
+            self.reanchor(system_now);
+            self.rate = rate;
         }
     }
 }
@@ -60,15 +501,13 @@ impl ChartWindow {
 /// When smooth_streaming is false, returns current time for immediate rendering.
 ///
 /// ## Parameters
+/// - `clock`: Clock providing the current time (system time unless paused)
 /// - `smooth_streaming`: Whether to apply delay for smooth scrolling
 ///
 /// ## Returns
-/// Current system time in nanoseconds, optionally adjusted by DISPLAY_DELAY_NS
-pub fn current_display_time(smooth_streaming: bool) -> u64 {
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_nanos() as u64;
+/// Current clock time in nanoseconds, optionally adjusted by DISPLAY_DELAY_NS
+pub fn current_display_time(clock: &DisplayClock, smooth_streaming: bool) -> u64 {
+    let now = clock.now();
     if smooth_streaming {
         now.saturating_sub(DISPLAY_DELAY_NS)
     } else {
@@ -298,6 +737,88 @@ mod tests {
         assert_eq!(interpolated.len(), 4);
     }
 
+    #[test]
+    fn test_display_clock_pause_excludes_paused_time() {
+        let mut clock = DisplayClock::new();
+        assert_eq!(clock.now_at(1_000), 1_000);
+        clock.pause_at(1_000);
+
+        // While paused, time stands still
+        assert_eq!(clock.now_at(5_000), 1_000);
+
+        // After resuming, the paused duration is subtracted
+        clock.resume_at(5_000);
+        assert_eq!(clock.now_at(6_000), 2_000);
+    }
+
+    #[test]
+    fn test_display_clock_rate() {
+        let mut clock = DisplayClock::new();
+        clock.set_rate_at(1_000, 2.0);
+        assert_eq!(clock.now_at(1_500), 2_000);
+
+        // Pausing keeps the rate for after resuming
+        clock.pause_at(1_500);
+        clock.resume_at(3_000);
+        assert_eq!(clock.now_at(3_500), 3_000);
+
+        clock.set_rate_at(3_500, 1.0);
+        assert_eq!(clock.now_at(4_000), 3_500);
+    }
+
+    #[test]
+    fn test_chart_window_zoom() {
+        assert_eq!(ChartWindow::ThirtySeconds.zoomed_in(), ChartWindow::TenSeconds);
+        assert_eq!(ChartWindow::TenSeconds.zoomed_in(), ChartWindow::TenSeconds);
+        assert_eq!(ChartWindow::TwoMinutes.zoomed_out(), ChartWindow::TenMinutes);
+        assert_eq!(ChartWindow::TenMinutes.zoomed_out(), ChartWindow::TenMinutes);
+    }
+
+    #[test]
+    fn test_chart_view_freeze_and_scroll() {
+        let mut view = ChartView::default();
+        assert_eq!(view.end_time(50_000), 50_000);
+
+        view.freeze(50_000);
+        view.freeze(60_000);
+        assert_eq!(view.end_time(70_000), 50_000);
+
+        // Scrolling stops at the start of the available history
+        view.scroll_to(20_000, 40_000);
+        assert_eq!(view.scroll_back_ns(), 10_000);
+        assert_eq!(view.end_time(70_000), 40_000);
+
+        view.follow_live();
+        assert!(!view.is_frozen());
+        assert_eq!(view.end_time(70_000), 70_000);
+    }
+
+    #[test]
+    fn test_auto_fit_range_hysteresis() {
+        // First fit: 10% margin around the data
+        let range = auto_fit_range(None, Some((60, 160)), 10);
+        assert_eq!(range, Some((50, 170)));
+
+        // Data moving inside the range leaves it alone
+        assert_eq!(auto_fit_range(range, Some((70, 150)), 10), range);
+
+        // Data leaving the range grows it at once
+        assert_eq!(auto_fit_range(range, Some((60, 200)), 10), Some((46, 214)));
+
+        // Data shrinking to well under half the range refits it
+        assert_eq!(auto_fit_range(range, Some((95, 105)), 10), Some((94, 106)));
+
+        // A flat signal is shown at the minimum span, and no data keeps the range
+        assert_eq!(auto_fit_range(None, Some((100, 100)), 10), Some((94, 106)));
+        assert_eq!(auto_fit_range(range, None, 10), range);
+    }
+
+    #[test]
+    fn test_zoom_range() {
+        assert_eq!(zoom_range((-1000, 1000), 0.5), (-500, 500));
+        assert_eq!(zoom_range((0, 100), 2.0), (-50, 150));
+    }
+
     #[test]
     fn test_add_start_boundary_point() {
         let points = vec![